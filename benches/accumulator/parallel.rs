@@ -0,0 +1,30 @@
+/// See https://bheisler.github.io/criterion.rs/book/getting_started.html to add more benchmarks.
+#[macro_use]
+extern crate criterion;
+
+use accumulator::group::Rsa2048;
+use accumulator::hash::PrimeBits;
+use accumulator::Accumulator;
+use criterion::Criterion;
+
+fn bench_add(elems: &[u64]) {
+  let acc = Accumulator::<Rsa2048, u64>::with_params(PrimeBits::B256);
+  acc.add(elems);
+}
+
+fn bench_add_parallel(elems: &[u64]) {
+  let acc = Accumulator::<Rsa2048, u64>::with_params(PrimeBits::B256);
+  acc.add_parallel(elems);
+}
+
+fn criterion_benchmark(c: &mut Criterion) {
+  let elems: Vec<u64> = (0..4096).collect();
+  let elems_1 = elems.clone();
+  c.bench_function("rsa2048_add_4096", move |b| b.iter(|| bench_add(&elems_1)));
+  c.bench_function("rsa2048_add_parallel_4096", move |b| {
+    b.iter(|| bench_add_parallel(&elems))
+  });
+}
+
+criterion_group!(benches, criterion_benchmark);
+criterion_main!(benches);
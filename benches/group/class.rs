@@ -3,10 +3,12 @@
 extern crate criterion;
 
 use accumulator::group::{ClassGroup, ElemFrom, Group, UnknownOrderGroup};
-use criterion::Criterion;
+use criterion::{Criterion, Fun};
 use rug::Integer;
 use std::str::FromStr;
 
+type ClassElem = <ClassGroup as Group>::Elem;
+
 fn criterion_benchmark(c: &mut Criterion) {
   let left = ClassGroup::elem((
     Integer::from_str("16").unwrap(),
@@ -85,6 +87,19 @@ fn criterion_benchmark(c: &mut Criterion) {
   c.bench_function("group_class_square", move |b| {
     b.iter_with_setup(|| g_sq.clone(), |g| ClassGroup::square(&g))
   });
+
+  // A typical element mid-exponentiation, with coefficients of full size. Squarings dominate
+  // exponentiation, so `square` is specialized instead of composing an element with itself; this
+  // compares the two.
+  let g_mid = ClassGroup::exp(
+    &ClassGroup::unknown_order_elem(),
+    &Integer::from_str("123456789").unwrap(),
+  );
+  let square_vs_op = vec![
+    Fun::new("op", |b, x: &ClassElem| b.iter(|| ClassGroup::op(x, x))),
+    Fun::new("square", |b, x: &ClassElem| b.iter(|| ClassGroup::square(x))),
+  ];
+  c.bench_functions("group_class_square_vs_op", square_vs_op, g_mid);
 }
 
 criterion_group!(benches, criterion_benchmark);
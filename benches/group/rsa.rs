@@ -62,6 +62,11 @@ fn bench_exp<G: Group + ElemFrom<u8>>() {
   );
 }
 
+fn bench_exp_long<G: Group + ElemFrom<u8>>() {
+  let exp = (Integer::from(1) << 4096) - 1;
+  G::exp(&G::elem(2), &exp);
+}
+
 fn bench_inv<G: Group + ElemFrom<u8>>() {
   G::inv(&G::elem(2));
 }
@@ -70,6 +75,7 @@ fn criterion_benchmark(c: &mut Criterion) {
   c.bench_function("group_rsa_op", |b| b.iter(bench_op::<Rsa2048>));
   c.bench_function("group_rsa_op_large", |b| b.iter(bench_op_large::<Rsa2048>));
   c.bench_function("group_rsa_exp", |b| b.iter(bench_exp::<Rsa2048>));
+  c.bench_function("group_rsa_exp_long", |b| b.iter(bench_exp_long::<Rsa2048>));
   c.bench_function("group_rsa_inv", |b| b.iter(bench_inv::<Rsa2048>));
 }
 
@@ -3,6 +3,10 @@
 extern crate criterion;
 
 use criterion::Criterion;
+#[cfg(feature = "blake3")]
+use accumulator::hash::Blake3;
+#[cfg(feature = "sha3")]
+use accumulator::hash::Sha3_256;
 use accumulator::hash::{blake2b, hash_to_prime};
 use rand::Rng;
 
@@ -10,6 +14,16 @@ fn bench_blake2() {
   blake2b("werg");
 }
 
+#[cfg(feature = "blake3")]
+fn bench_blake3() {
+  accumulator::hash::hash(&Blake3::default, "werg");
+}
+
+#[cfg(feature = "sha3")]
+fn bench_sha3() {
+  accumulator::hash::hash(&Sha3_256::default, "werg");
+}
+
 fn bench_hash_to_prime() {
   let random_bytes = rand::thread_rng().gen::<[u8; 32]>();
   hash_to_prime(&random_bytes);
@@ -17,6 +31,10 @@ fn bench_hash_to_prime() {
 
 fn criterion_benchmark(c: &mut Criterion) {
   c.bench_function("blake2", |b| b.iter(bench_blake2));
+  #[cfg(feature = "blake3")]
+  c.bench_function("blake3", |b| b.iter(bench_blake3));
+  #[cfg(feature = "sha3")]
+  c.bench_function("sha3", |b| b.iter(bench_sha3));
   c.bench_function("hash_to_prime", |b| b.iter(bench_hash_to_prime));
 }
 
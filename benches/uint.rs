@@ -3,22 +3,45 @@
 extern crate criterion;
 
 use accumulator::hash::blake2b;
-use accumulator::uint::U256;
+use accumulator::uint::{MontgomeryU256, U256};
 use criterion::{black_box, Criterion};
 use rug::integer::Order;
+use rug::Integer;
 use std::ops::Mul;
 
 fn bench_mul<T: Mul>(a: T, b: T) {
   black_box(a * b);
 }
 
+fn u256_of(n: &Integer) -> U256 {
+  let mut bytes = [0; 32];
+  n.write_digits(&mut bytes, Order::Lsf);
+  U256::from(bytes)
+}
+
 fn criterion_benchmark(c: &mut Criterion) {
   let int = blake2b("data");
   let mut bytes = [0; 4];
   int.write_digits(&mut bytes, Order::LsfBe);
   let u256 = U256::from(bytes);
+
+  // Exponentiation modulo an odd 256-bit modulus, as in the Fermat test of `hash_to_prime`.
+  let modulus = blake2b("modulus") | 1;
+  let exp = Integer::from(&modulus - 1);
+  let (u256_modulus, u256_exp) = (u256_of(&modulus), u256_of(&exp));
+  let base = int.clone();
+
   c.bench_function("mul_rug", move |b| b.iter(|| bench_mul(&int, &int)));
   c.bench_function("mul_u256", move |b| b.iter(|| bench_mul(u256, u256)));
+  c.bench_function("pow_mod_rug", move |b| {
+    b.iter(|| black_box(Integer::from(base.pow_mod_ref(&exp, &modulus).unwrap())))
+  });
+  c.bench_function("pow_mod_u256_gmp", move |b| {
+    b.iter(|| black_box(u256.pow_mod(u256_exp, &u256_modulus)))
+  });
+  c.bench_function("pow_mod_u256_montgomery", move |b| {
+    b.iter(|| black_box(MontgomeryU256::new(u256_modulus).pow_mod(u256, &u256_exp)))
+  });
 }
 
 criterion_group!(benches, criterion_benchmark);
@@ -1,9 +1,16 @@
 //! Accumulator library, built on a generic group interface.
-use crate::group::UnknownOrderGroup;
-use crate::hash::hash_to_prime;
-use crate::proof::{Poe, Poke2};
-use crate::util::{divide_and_conquer, int, prime_hash_product, shamir_trick};
-use rug::Integer;
+use crate::group::{simultaneous_exp, ElemBytes, UnknownOrderGroup, DERIVED_GENERATOR_TAG};
+use crate::hash::{hash_to_prime, PrimeBits, PrimeHash, PrimeParams, StreamDigest};
+use crate::integer::{Integer, IntegerOps};
+use crate::proof::{DecodeError, Decoder, Poe, Poke2, Poke2Eq};
+use crate::security::{Security128, SecurityLevel};
+use crate::snapshot::Snapshot;
+use crate::state;
+use crate::util::{
+  cofactors_mod, int, product, root_factor, shamir_combine_n, Redacted, SecretInteger,
+};
+use std::collections::HashSet;
+use std::fmt;
 use std::hash::Hash;
 use std::marker::PhantomData;
 
@@ -24,25 +31,107 @@ pub enum AccError {
 
   /// Inputs not coprime when they were expected to be coprime.
   InputsNotCoprime,
+
+  /// Bytes that are not a canonical encoding.
+  BadEncoding,
+
+  /// Elements supplied for a migration do not reproduce the accumulator being migrated.
+  BadMigration,
+
+  /// An element was added that is already in the accumulator.
+  DuplicateElement,
+
+  /// A prime supplied directly was not an odd prime of sufficient size. See `add_primes`.
+  BadPrime,
+
+  /// An update was not for the current epoch, or lacked the signatures its policy requires. See
+  /// `GovernedAccumulator`.
+  Unauthorized,
+
+  /// An added element hashes to the same prime as a different accumulated element. See
+  /// `CollisionPolicy`.
+  PrimeCollision,
+
+  /// The accumulated exponent is too large for the claimed bound. See `ExponentBoundProof`.
+  BoundExceeded,
+
+  /// A group element was not a valid accumulator value. See `Accumulator::from_value`.
+  BadValue,
+
+  /// A proof was made under different parameters than the accumulator it was checked against,
+  /// e.g. on another network. See `Accumulator::verify_membership_checked`.
+  ParameterMismatch,
+
+  /// A proof was for an epoch older than a verifier accepts, or one it has not recorded. See
+  /// `EpochVerifier`.
+  StaleProof,
+
+  /// A proof failed against the current accumulator but holds for an earlier one, i.e. it was
+  /// made against a stale state. See `Accumulator::verify_membership_with_history`.
+  StaleState,
+
+  /// A batch had more elements, or an exponent more bits, than the configured `Limits` allow.
+  LimitExceeded,
+
+  /// Verifying a proof would take more operations than a `VerifyBudget` allows.
+  BudgetExceeded,
+
+  /// Prime parameters were weaker than a security level requires. See
+  /// `Accumulator::with_security`.
+  WeakParams,
+}
+
+/// Values that can be hashed into an accumulator or committed to as `VectorCommitment` entries.
+/// Implement it for your own types to use them as `VectorCommitment` values.
+pub trait AccumulatorElement: Clone + Eq + Hash {}
+
+macro_rules! impl_accumulator_element {
+  ($($t:ty),*) => {
+    $(impl AccumulatorElement for $t {})*
+  };
 }
 
+impl_accumulator_element!(
+  bool, char, i8, i16, i32, i64, i128, isize, u8, u16, u32, u64, u128, usize, String, Integer
+);
+impl AccumulatorElement for &str {}
+impl AccumulatorElement for StreamDigest {}
+impl<T: AccumulatorElement> AccumulatorElement for Vec<T> {}
+impl<T: AccumulatorElement> AccumulatorElement for Option<T> {}
+impl<A: AccumulatorElement, B: AccumulatorElement> AccumulatorElement for (A, B) {}
+
 // See https://doc.rust-lang.org/std/marker/struct.PhantomData.html#ownership-and-the-drop-check
 // for recommendations regarding phantom types. Note that we disregard the suggestion to use a
 // const reference in the phantom type parameter, which causes issues for the `Send` trait.
-#[derive(Debug, Eq, Hash, PartialEq)]
+#[derive(Eq, Hash, PartialEq)]
 /// A cryptographic accumulator. Wraps a single unknown-order group element and phantom data
 /// representing the type `T` being hashed-to-prime and accumulated.
+///
+/// Each accumulator is bound to the size of the primes its elements are hashed to. Witnesses and
+/// proofs carry this size too, and never verify against an accumulator of a different size.
 pub struct Accumulator<G: UnknownOrderGroup, T> {
   phantom: PhantomData<T>,
+  params: PrimeParams,
   value: G::Elem,
 }
 
+// Shows only a fingerprint of the value. See `util::Redacted`.
+impl<G: UnknownOrderGroup, T> fmt::Debug for Accumulator<G, T> {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    f.debug_struct("Accumulator")
+      .field("params", &self.params)
+      .field("value", &Redacted(&self.value))
+      .finish()
+  }
+}
+
 // Manual clone impl required because Rust's type inference is not good. See
 // https://github.com/rust-lang/rust/issues/26925.
 impl<G: UnknownOrderGroup, T: Hash> Clone for Accumulator<G, T> {
   fn clone(&self) -> Self {
     Self {
       phantom: PhantomData,
+      params: self.params,
       value: self.value.clone(),
     }
   }
@@ -54,16 +143,92 @@ pub struct Witness<G: UnknownOrderGroup, T: Hash>(pub Accumulator<G, T>);
 
 #[derive(Clone, Debug, Eq, Hash, PartialEq)]
 /// A succinct proof of membership (some element is in some accumulator).
+///
+/// A proof may optionally carry the `hash_to_prime` nonce of each element (see `with_nonces`), which
+/// lets verifiers map elements to primes with one hash each instead of repeating the search.
 pub struct MembershipProof<G: UnknownOrderGroup, T: Hash> {
   /// The witness for the element in question.
   pub witness: Witness<G, T>,
   proof: Poe<G>,
+  nonces: Option<Vec<u64>>,
+}
+
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+/// A reason a membership proof failed to verify. See `VerificationReport`.
+pub enum MembershipFailure {
+  /// The proof was made under different prime parameters than the accumulator's.
+  ParameterMismatch,
+  /// The proof carries a different number of nonces than there are elements.
+  NonceCount,
+  /// The nonce for the element at this index does not map it to a prime.
+  BadNonce(usize),
+  /// The element at this index maps to the same prime as an earlier element of the batch.
+  Duplicate(usize),
+  /// The element at this index is not accumulated: the witness holds for the rest of the batch.
+  Absent(usize),
+  /// The witness is not a witness for the batch, nor for the batch without any one element.
+  BadWitness,
+  /// The witness is a witness for the batch, but the NI-PoE does not verify.
+  BadPoe,
+}
+
+#[derive(Clone, Debug, Default, Eq, Hash, PartialEq)]
+/// The outcome of `Accumulator::verify_membership_detailed`: every reason the proof failed, with
+/// the elements responsible where they can be singled out.
+pub struct VerificationReport {
+  /// The reasons the proof failed, empty if it verified.
+  pub failures: Vec<MembershipFailure>,
+}
+
+impl VerificationReport {
+  /// Returns whether the proof verified.
+  pub fn is_valid(&self) -> bool {
+    self.failures.is_empty()
+  }
+}
+
+#[derive(Debug, Eq, Hash, PartialEq)]
+/// A membership proof whose parts are borrowed rather than owned, e.g. from arenas in which a
+/// witness manager keeps a single copy of each witness and NI-PoE shared by many proofs. Verified
+/// with `Accumulator::verify_membership_ref`, without cloning any group elements.
+pub struct MembershipProofRef<'a, G: UnknownOrderGroup, T: Hash> {
+  witness: &'a Witness<G, T>,
+  proof: &'a Poe<G>,
+  nonces: Option<&'a [u64]>,
+}
+
+// Manual clone and copy impls, since the derived ones would require `G: Copy` and `T: Copy`.
+impl<'a, G: UnknownOrderGroup, T: Hash> Clone for MembershipProofRef<'a, G, T> {
+  fn clone(&self) -> Self {
+    *self
+  }
 }
 
+impl<'a, G: UnknownOrderGroup, T: Hash> Copy for MembershipProofRef<'a, G, T> {}
+
 #[derive(Clone, Debug, Eq, Hash, PartialEq)]
+/// A membership proof w.r.t. an older accumulator, together with an NI-PoKE2 that the current
+/// accumulator is the older one raised to some exponent. See `MembershipProof::compress`.
+///
+/// Knowing an integer `y` with `old^y = new` means `witness^y` is a witness w.r.t. `new`, so the
+/// elements are still members; a deletion would instead need a root of `old`. The proof is
+/// constant-size however many elements were added in between, and since the NI-PoKE2 depends only
+/// on the two accumulators, one published transition proof serves every holder of an old proof.
+pub struct CompressedMembershipProof<G: UnknownOrderGroup, T: Hash> {
+  old_acc: Accumulator<G, T>,
+  proof: MembershipProof<G, T>,
+  transition: Poke2<G>,
+}
+
+#[derive(Clone, Eq, Hash, PartialEq)]
 /// A succinct proof of nonmembership (some element is not in some accumulator).
+///
+/// Instead of the LLX Bezout coefficients, whose size grows with the accumulated set, the proof
+/// carries `d = g^a` and `v = acc^b` with an NI-PoKE2 of `b` and an NI-PoE of `d^x = g * v^-1`, as
+/// in BBF (page 20), so its size is constant. See `VerboseNonmembershipProof` for the explicit form.
 pub struct NonmembershipProof<G: UnknownOrderGroup, T> {
   phantom: PhantomData<*const T>,
+  params: PrimeParams,
   d: G::Elem,
   v: G::Elem,
   gv_inv: G::Elem,
@@ -71,23 +236,405 @@ pub struct NonmembershipProof<G: UnknownOrderGroup, T> {
   poe_proof: Poe<G>,
 }
 
+#[derive(Clone, Eq, Hash, PartialEq)]
+/// A proof that some elements are in none of several accumulators (e.g. the shards of a sharded
+/// set). It carries a Bezout pair and a PoKE2 per accumulator, so its size grows with their number,
+/// but it is checked with one proof of exponentiation and one batch of PoKE2 equations rather than
+/// a nonmembership proof per accumulator.
+pub struct AggregateNonmembershipProof<G: UnknownOrderGroup, T> {
+  phantom: PhantomData<*const T>,
+  params: PrimeParams,
+  d: Vec<G::Elem>,
+  v: Vec<G::Elem>,
+  poke2_proofs: Vec<Poke2<G>>,
+  poe_proof: Poe<G>,
+}
+
+#[cfg(feature = "verbose_proof")]
+#[derive(Clone, Eq, Hash, PartialEq)]
+/// An LLX nonmembership proof with an explicit Bezout coefficient, enabled with the
+/// `verbose_proof` feature: `d = g^a` and `b` such that `d^x * acc^b = g`.
+///
+/// `b` is about the size of the product of the elements proven, so the proof stays small for few
+/// elements, and it needs no proofs of exponentiation to make or check. Verifiers exponentiate by
+/// `x` and `b` themselves, though, so `NonmembershipProof` is cheaper to verify for large batches.
+/// Useful for interoperating with LLX implementations, and as a fallback for verifiers that do not
+/// implement NI-PoKE2.
+pub struct VerboseNonmembershipProof<G: UnknownOrderGroup, T> {
+  phantom: PhantomData<*const T>,
+  params: PrimeParams,
+  d: G::Elem,
+  b: Integer,
+}
+
+#[cfg(feature = "verbose_proof")]
+impl<G: UnknownOrderGroup, T> fmt::Debug for VerboseNonmembershipProof<G, T> {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    f.debug_struct("VerboseNonmembershipProof")
+      .field("params", &self.params)
+      .field("d", &Redacted(&self.d))
+      .field("b", &Redacted(&self.b))
+      .finish()
+  }
+}
+
+impl<G: UnknownOrderGroup, T> fmt::Debug for NonmembershipProof<G, T> {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    f.debug_struct("NonmembershipProof")
+      .field("params", &self.params)
+      .field("d", &Redacted(&self.d))
+      .field("v", &Redacted(&self.v))
+      .field("gv_inv", &Redacted(&self.gv_inv))
+      .field("poke2_proof", &self.poke2_proof)
+      .field("poe_proof", &self.poe_proof)
+      .finish()
+  }
+}
+
+impl<G: UnknownOrderGroup, T> fmt::Debug for AggregateNonmembershipProof<G, T> {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    f.debug_struct("AggregateNonmembershipProof")
+      .field("params", &self.params)
+      .field("d", &Redacted(&self.d))
+      .field("v", &Redacted(&self.v))
+      .field("poke2_proofs", &self.poke2_proofs)
+      .field("poe_proof", &self.poe_proof)
+      .finish()
+  }
+}
+
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+/// A proof that two accumulators, possibly over different groups, hold the same set: that one
+/// exponent takes each group's generator to its accumulator. Lets a deployment moving from an RSA
+/// group to a class group show that the new accumulator matches the old without revealing the set.
+pub struct EqualSetProof<G: UnknownOrderGroup, H: UnknownOrderGroup, T> {
+  phantom: PhantomData<*const T>,
+  proof: Poke2Eq<G, H>,
+}
+
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+/// A non-interactive attestation of how an accumulator's initial value was derived, so that
+/// verifiers bootstrapping from genesis can check the starting point instead of trusting it.
+pub enum InitAttestation<G: UnknownOrderGroup> {
+  /// The initial value is the group's standard unknown-order element.
+  Standard,
+  /// The initial value is the standard element raised to a secret exponent, which the prover
+  /// knows. See `Accumulator::from_initial_exponent`.
+  SecretExponent(Poke2<G>),
+  /// The initial value is the standard element raised to this published exponent.
+  PublishedExponent(Integer),
+  /// The initial value is the standard element, which the group derives by hashing this instance
+  /// digest (the modulus digest, for `ImportedRsa`). Verifiers compare the digest with the one a
+  /// setup ceremony published. See `ImportedRsa::init_attestation`.
+  DerivedGenerator([u8; 32]),
+}
+
+#[derive(Debug, Eq, Hash, PartialEq)]
+/// The product of all primes accumulated so far, for holders of the full accumulated set. With
+/// this product in hand, deletions and witness generation need only a single exponentiation of the
+/// generator instead of a witness for every element involved.
+///
+/// If the holder also knows a multiple of the group order (e.g. the trapdoor of an RSA modulus),
+/// exponents are reduced modulo that multiple before exponentiating, which bounds their size.
+///
+/// States are cheap to clone relative to the accumulated set, so they can be checkpointed
+/// alongside the accumulator they describe.
+pub struct ExponentState<G: UnknownOrderGroup, T> {
+  phantom: PhantomData<(G, T)>,
+  params: PrimeParams,
+  product: Integer,
+  order_multiple: Option<SecretInteger>,
+}
+
+// Manual clone impl for the same reason as `Accumulator`.
+impl<G: UnknownOrderGroup, T> Clone for ExponentState<G, T> {
+  fn clone(&self) -> Self {
+    Self {
+      phantom: PhantomData,
+      params: self.params,
+      product: self.product.clone(),
+      order_multiple: self.order_multiple.clone(),
+    }
+  }
+}
+
+// How `AccumulatorBuilder` adds the initial elements: `Accumulator::add` or `add_parallel`.
+type AddFn<G, T> = fn(Accumulator<G, T>, &[T]) -> Accumulator<G, T>;
+
+#[derive(Debug)]
+/// Configures and creates accumulators in one place. The group and element type are the type
+/// parameters; everything else defaults to what `Accumulator::empty` uses. Besides the accumulator
+/// itself, the builder can produce an `ExponentState`, a versioned `AccumulatorState`, or a
+/// `Snapshot` with witnesses for the initial elements.
+///
+/// ```
+/// use accumulator::group::Rsa2048;
+/// use accumulator::hash::PrimeBits;
+/// use accumulator::AccumulatorBuilder;
+///
+/// let (acc, _state) = AccumulatorBuilder::<Rsa2048, &'static str>::new()
+///   .prime_bits(PrimeBits::B128)
+///   .elems(vec!["dog", "cat"])
+///   .build_with_state();
+/// assert!(acc.prime_bits() == PrimeBits::B128);
+/// ```
+pub struct AccumulatorBuilder<G: UnknownOrderGroup, T> {
+  phantom: PhantomData<G>,
+  params: PrimeParams,
+  elems: Vec<T>,
+  order_multiple: Option<SecretInteger>,
+  elem_version: u8,
+  add: AddFn<G, T>,
+}
+
+impl<G: UnknownOrderGroup, T: Eq + Hash> AccumulatorBuilder<G, T> {
+  /// Returns a builder for an empty accumulator with default parameters.
+  pub fn new() -> Self {
+    Self {
+      phantom: PhantomData,
+      params: PrimeBits::B256.into(),
+      elems: vec![],
+      order_multiple: None,
+      elem_version: 0,
+      add: Accumulator::add,
+    }
+  }
+
+  /// Sets the size of the primes elements are hashed to.
+  pub fn prime_bits(mut self, prime_bits: PrimeBits) -> Self {
+    self.params.bits = prime_bits;
+    self
+  }
+
+  /// Sets the hash function elements are mapped to primes with.
+  pub fn prime_hash(mut self, prime_hash: PrimeHash) -> Self {
+    self.params.hash = prime_hash;
+    self
+  }
+
+  /// Adds `elems` to the initial accumulated set.
+  pub fn elems<I: IntoIterator<Item = T>>(mut self, elems: I) -> Self {
+    self.elems.extend(elems);
+    self
+  }
+
+  /// Sets a known multiple of the group order for the state returned by `build_with_state`.
+  /// Panics if `order_multiple` is not positive.
+  pub fn order_multiple(mut self, order_multiple: Integer) -> Self {
+    assert!(order_multiple > 0, "order multiple must be positive");
+    self.order_multiple = Some(SecretInteger(order_multiple));
+    self
+  }
+
+  /// Sets the version of the application's element encoding recorded by `build_state`. Defaults
+  /// to 0.
+  pub fn elem_version(mut self, elem_version: u8) -> Self {
+    self.elem_version = elem_version;
+    self
+  }
+
+  /// Returns the configured accumulator.
+  pub fn build(self) -> Accumulator<G, T> {
+    (self.add)(Accumulator::with_params(self.params), &self.elems)
+  }
+
+  /// Returns the configured accumulator along with an `ExponentState` tracking its elements.
+  pub fn build_with_state(mut self) -> (Accumulator<G, T>, ExponentState<G, T>) {
+    let mut state = ExponentState::with_params(self.params);
+    if let Some(mut order_multiple) = self.order_multiple.take() {
+      state.set_order_multiple(std::mem::take(&mut order_multiple.0));
+    }
+    state.add(&self.elems);
+    let acc = (self.add)(Accumulator::with_params(self.params), &self.elems);
+    (acc, state)
+  }
+
+  /// Returns the configured accumulator as an `AccumulatorState` under the configured element
+  /// encoding version.
+  pub fn build_state(self) -> state::AccumulatorState<G, T>
+  where
+    G: ElemBytes,
+  {
+    let elem_version = self.elem_version;
+    state::AccumulatorState::new(self.build(), elem_version)
+  }
+
+  /// Returns a `Snapshot` of the configured accumulator with a witness for each initial element,
+  /// in order, ready to be written out with `Snapshot::export_snapshot`.
+  pub fn build_snapshot(self) -> Snapshot<G, T>
+  where
+    G: ElemBytes,
+  {
+    let (acc, _, witnesses) = Accumulator::with_params(self.params).add_with_witnesses(&self.elems);
+    Snapshot::new(acc).with_witnesses(witnesses)
+  }
+}
+
+#[cfg(feature = "parallel")]
+impl<G: UnknownOrderGroup, T: Eq + Hash + Sync> AccumulatorBuilder<G, T> {
+  /// Adds the initial elements with `Accumulator::add_parallel`, which pays off for large sets.
+  /// `build_snapshot` computes witnesses as it adds, and is unaffected.
+  pub fn parallel(mut self) -> Self {
+    self.add = Accumulator::add_parallel;
+    self
+  }
+}
+
+impl<G: UnknownOrderGroup, T: Eq + Hash> Default for AccumulatorBuilder<G, T> {
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
 impl<G: UnknownOrderGroup, T: Eq + Hash> Accumulator<G, T> {
-  /// Returns a new, empty accumulator.
+  /// Returns a new, empty accumulator. Elements are hashed to 256-bit primes with Blake2b.
   pub fn empty() -> Self {
+    Self::with_params(PrimeBits::B256)
+  }
+
+  /// Returns a new, empty accumulator whose elements are mapped to primes according to `params`,
+  /// e.g. `PrimeBits::B128` for the default hash function with 128-bit primes, or a security level
+  /// such as `Security112` for the primes it prescribes.
+  pub fn with_params<P: Into<PrimeParams>>(params: P) -> Self {
     Self {
       phantom: PhantomData,
+      params: params.into(),
       value: G::unknown_order_elem(),
     }
   }
 
+  /// Like `with_params`, but returns `WeakParams` if `params` maps elements to primes shorter than
+  /// the security level `S` requires, e.g. `PrimeBits::B128` at `Security112`. Proofs use
+  /// `Security128` challenges, which are never weaker than those of `S`.
+  pub fn with_security<S: SecurityLevel, P: Into<PrimeParams>>(
+    params: P,
+  ) -> Result<Self, AccError> {
+    let params = params.into();
+    if params.max_bits() < S::PRIME_BITS.bits() {
+      return Err(AccError::WeakParams);
+    }
+    Ok(Self::with_params(params))
+  }
+
+  /// Returns the parameters elements of this accumulator are mapped to primes with.
+  pub fn params(&self) -> PrimeParams {
+    self.params
+  }
+
+  /// Returns the size of the primes elements of this accumulator are hashed to.
+  pub fn prime_bits(&self) -> PrimeBits {
+    self.params.bits
+  }
+
+  /// Returns the group element this accumulator wraps.
+  pub fn value(&self) -> &G::Elem {
+    &self.value
+  }
+
+  /// Returns the accumulator wrapping `value`, e.g. one previously returned by `value`, with
+  /// default parameters. Returns `BadValue` if `value` breaks its group's invariants or is the
+  /// identity, of which every element would have a membership proof.
+  pub fn from_value(value: G::Elem) -> Result<Self, AccError> {
+    Self::from_value_with_params(PrimeBits::B256, value)
+  }
+
+  /// Returns the accumulator wrapping `value`, with elements mapped to primes according to
+  /// `params`. See `from_value`.
+  pub fn from_value_with_params<P: Into<PrimeParams>>(
+    params: P,
+    value: G::Elem,
+  ) -> Result<Self, AccError> {
+    if !G::is_valid_elem(&value) || value == G::id() {
+      return Err(AccError::BadValue);
+    }
+    Ok(Self {
+      phantom: PhantomData,
+      params: params.into(),
+      value,
+    })
+  }
+
+  /// Hashes `t` to a prime according to this accumulator's parameters.
+  fn prime(&self, t: &T) -> Integer {
+    self.params.hash_to_prime(t)
+  }
+
+  /// Hashes `ts` to primes according to this accumulator's parameters and takes their product.
+  fn prime_product(&self, ts: &[T]) -> Integer {
+    self.params.prime_hash_product(ts)
+  }
+
+  /// Returns a new, empty accumulator along with an attestation of its initial value, to be
+  /// published at genesis.
+  pub fn empty_with_attestation() -> (Self, InitAttestation<G>) {
+    (Self::empty(), InitAttestation::Standard)
+  }
+
+  /// Returns a new accumulator holding no elements, initialized at `g^r` instead of the standard
+  /// element `g`, along with an NI-PoKE2 attestation that its creator knows `r`. A secret `r` blinds
+  /// the genesis state, and a structured one can embed a commitment in it; a published `r` can be
+  /// attested with `InitAttestation::PublishedExponent` instead.
+  ///
+  /// NI-PoKE2 is not zero-knowledge: the attestation reveals `r` modulo a 256-bit prime, so a
+  /// secret `r` should be much larger than that. Track the accumulated exponent with
+  /// `ExponentState::with_initial_exponent`.
+  ///
+  /// Any element whose prime divides `r` is a hidden member: it was never added, but membership
+  /// proofs for it can be made and verify. Pick `r` with no prime factor an element could hash to,
+  /// e.g. a prime longer than the accumulator's `max_bits`.
+  ///
+  /// Nonmembership proofs assume the standard element and cannot be made for such accumulators,
+  /// and neither can anything that rebuilds the accumulator or a witness from the standard element:
+  /// `delete_unchecked_with_set`, `prove_membership_chunked`, `prove_equal_set` (unless both
+  /// accumulators started at the same `r`), `ExponentState::compute_all_witnesses` and
+  /// `AccumulatorState::migrate` all fail on them.
+  ///
+  /// Panics if `r` is not positive.
+  pub fn from_initial_exponent(r: &Integer) -> (Self, InitAttestation<G>) {
+    Self::from_initial_exponent_with_params(PrimeBits::B256, r)
+  }
+
+  /// Like `from_initial_exponent`, for an accumulator whose elements are mapped to primes
+  /// according to `params`.
+  pub fn from_initial_exponent_with_params<P: Into<PrimeParams>>(
+    params: P,
+    r: &Integer,
+  ) -> (Self, InitAttestation<G>) {
+    assert!(*r > 0, "initial exponent must be positive");
+    let g = G::unknown_order_elem();
+    let value = G::exp(&g, r);
+    let proof = Poke2::prove(&g, r, &value);
+    let acc = Self {
+      phantom: PhantomData,
+      params: params.into(),
+      value,
+    };
+    (acc, InitAttestation::SecretExponent(proof))
+  }
+
+  /// Verifies that this accumulator is the initial value described by `attestation`. Only
+  /// meaningful for a genesis accumulator, before any elements are added.
+  pub fn verify_initialization(&self, attestation: &InitAttestation<G>) -> bool {
+    let g = G::unknown_order_elem();
+    match attestation {
+      InitAttestation::Standard => self.value == g,
+      InitAttestation::SecretExponent(proof) => Poke2::verify(&g, &self.value, proof),
+      InitAttestation::PublishedExponent(r) => *r > 0 && self.value == G::exp(&g, r),
+      InitAttestation::DerivedGenerator(digest) => {
+        self.value == g && g == G::elem_from_hash(&(DERIVED_GENERATOR_TAG, digest))
+      }
+    }
+  }
+
   /// Internal add method that also returns the prime hash product of added elements, enabling an
   /// efficient `add_with_proof`.
   fn add_(&self, elems: &[T]) -> (Self, Integer) {
-    let x = prime_hash_product(elems);
+    let x = self.prime_product(elems);
     let acc_elem = G::exp(&self.value, &x);
     (
       Self {
         phantom: PhantomData,
+        params: self.params,
         value: acc_elem,
       },
       x,
@@ -105,6 +652,12 @@ impl<G: UnknownOrderGroup, T: Eq + Hash> Accumulator<G, T> {
     self.add_(elems).0
   }
 
+  /// Like `add`, but leaves this accumulator in place, for callers that keep it (e.g. as a
+  /// witness) and would otherwise clone it first.
+  pub fn add_ref(&self, elems: &[T]) -> Self {
+    self.add_(elems).0
+  }
+
   /// A specialized version of `add` that also returns a batch membership proof for added elements.
   pub fn add_with_proof(self, elems: &[T]) -> (Self, MembershipProof<G, T>) {
     let (acc, x) = self.add_(elems);
@@ -114,7 +667,91 @@ impl<G: UnknownOrderGroup, T: Eq + Hash> Accumulator<G, T> {
       MembershipProof {
         witness: Witness(self),
         proof,
+        nonces: None,
+      },
+    )
+  }
+
+  /// Like `add_with_proof`, but also returns a witness for each of `elems`, in order, for issuers
+  /// that hand out witnesses as they add elements. Each element is hashed to a prime once, and the
+  /// witnesses are computed with RootFactor from the old accumulator, after which the new
+  /// accumulator is a single exponentiation of any witness by its element's prime. This saves the
+  /// full exponentiation `add_with_proof` followed by `compute_individual_witnesses` would repeat.
+  pub fn add_with_witnesses(
+    self,
+    elems: &[T],
+  ) -> (Self, MembershipProof<G, T>, Vec<Witness<G, T>>) {
+    let primes = elems.iter().map(|t| self.prime(t)).collect::<Vec<_>>();
+    let roots = root_factor::<G>(&self.value, &primes);
+    let value = match (roots.first(), primes.first()) {
+      (Some(root), Some(p)) => G::exp(root, p),
+      _ => self.value.clone(),
+    };
+    let proof = Poe::<G>::prove(&self.value, &product(primes), &value);
+    let params = self.params;
+    let with_value = |value| Self {
+      phantom: PhantomData,
+      params,
+      value,
+    };
+    let witnesses = roots
+      .into_iter()
+      .map(|root| Witness(with_value(root)))
+      .collect();
+    (
+      with_value(value),
+      MembershipProof {
+        witness: Witness(self),
+        proof,
+        nonces: None,
+      },
+      witnesses,
+    )
+  }
+
+  #[cfg(feature = "parallel")]
+  /// Like `add`, but hashes large batches across threads and exponentiates while hashing. See the
+  /// `parallel` module.
+  pub fn add_parallel(self, elems: &[T]) -> Self
+  where
+    T: Sync,
+  {
+    self.add_parallel_(elems).0
+  }
+
+  #[cfg(feature = "parallel")]
+  /// Like `add_with_proof`, but hashes large batches across threads and exponentiates while
+  /// hashing. See the `parallel` module.
+  pub fn add_parallel_with_proof(self, elems: &[T]) -> (Self, MembershipProof<G, T>)
+  where
+    T: Sync,
+  {
+    let (acc, x) = self.add_parallel_(elems);
+    let proof = Poe::<G>::prove(&self.value, &x, &acc.value);
+    (
+      acc,
+      MembershipProof {
+        witness: Witness(self),
+        proof,
+        nonces: None,
+      },
+    )
+  }
+
+  #[cfg(feature = "parallel")]
+  fn add_parallel_(&self, elems: &[T]) -> (Self, Integer)
+  where
+    T: Sync,
+  {
+    let (value, x) =
+      crate::parallel::exp_prime_hash_product::<G, T>(&self.value, self.params, elems);
+    (
+      Self {
+        phantom: PhantomData,
+        params: self.params,
+        value,
       },
+      x,
     )
   }
 
@@ -123,33 +760,85 @@ impl<G: UnknownOrderGroup, T: Eq + Hash> Accumulator<G, T> {
   ///
   /// Uses a divide-and-conquer approach to running the ShamirTrick, which keeps the average input
   /// smaller: For `[a, b, c, d]` do `S(S(a, b), S(c, d))` instead of `S(S(S(a, b), c), d)`.
-  fn delete_(self, elem_witnesses: &[(T, Witness<G, T>)]) -> Result<(Self, Integer), AccError> {
+  fn delete_(&self, elem_witnesses: &[(T, Witness<G, T>)]) -> Result<(Self, Integer), AccError> {
     let prime_witnesses = elem_witnesses
       .iter()
-      .map(|(elem, witness)| (hash_to_prime(elem), witness.0.value.clone()))
+      .map(|(elem, witness)| (self.prime(elem), witness.0.value.clone()))
       .collect::<Vec<_>>();
 
+    if elem_witnesses
+      .iter()
+      .any(|(_, witness)| witness.0.params != self.params)
+    {
+      return Err(AccError::BadWitness);
+    }
     for (p, witness_elem) in &prime_witnesses {
       if G::exp(&witness_elem, &p) != self.value {
         return Err(AccError::BadWitness);
       }
     }
+    self.delete_primes_(&prime_witnesses)
+  }
 
-    let (prime_product, acc_elem) = divide_and_conquer(
-      |(p1, v1), (p2, v2)| Ok((int(p1 * p2), shamir_trick::<G>(&v1, &v2, p1, p2).unwrap())),
-      (int(1), self.value),
-      &prime_witnesses[..],
-    )?;
-
+  // Removes primes with verified witnesses, returning their product too. Returns
+  // `InputsNotCoprime` if two of them share a factor, which only division-intractable hashing (or
+  // a repeated element) makes possible.
+  fn delete_primes_(
+    &self,
+    prime_witnesses: &[(Integer, G::Elem)],
+  ) -> Result<(Self, Integer), AccError> {
+    let mut exp_roots = vec![(int(1), self.value.clone())];
+    exp_roots.extend_from_slice(prime_witnesses);
+    let (prime_product, acc_elem) =
+      shamir_combine_n::<G>(&exp_roots).ok_or(AccError::InputsNotCoprime)?;
     Ok((
       Self {
         phantom: PhantomData,
-        value: acc_elem.clone(),
+        params: self.params,
+        value: acc_elem,
       },
       prime_product,
     ))
   }
 
+  /// Like `delete`, but skips entries of `elem_witnesses` with bad witnesses instead of failing
+  /// the whole batch, for processing untrusted submissions. Returns the accumulator with the other
+  /// elements removed, and the index of each skipped entry with the reason: `BadWitness`,
+  /// `DuplicateElement` for an element already removed by an earlier entry, or (with
+  /// division-intractable hashing) `InputsNotCoprime` for one sharing a factor with such an
+  /// element.
+  ///
+  /// Uses a move instead of a `&self` reference to prevent accidental use of the old accumulator.
+  pub fn delete_batch_lenient(
+    self,
+    elem_witnesses: &[(T, Witness<G, T>)],
+  ) -> (Self, Vec<(usize, AccError)>) {
+    let mut failures = Vec::new();
+    let mut seen = HashSet::with_capacity(elem_witnesses.len());
+    let mut prime_witnesses = Vec::with_capacity(elem_witnesses.len());
+    let coprime_check = self.params.hash == PrimeHash::DivisionIntractable;
+    let mut accepted_product = int(1);
+    for (i, (elem, witness)) in elem_witnesses.iter().enumerate() {
+      let p = self.prime(elem);
+      if witness.0.params != self.params || G::exp(&witness.0.value, &p) != self.value {
+        failures.push((i, AccError::BadWitness));
+      } else if !seen.insert(p.clone()) {
+        failures.push((i, AccError::DuplicateElement));
+      } else if coprime_check && p.xgcd(&accepted_product).0 != 1 {
+        failures.push((i, AccError::InputsNotCoprime));
+      } else {
+        if coprime_check {
+          accepted_product *= &p;
+        }
+        prime_witnesses.push((p, witness.0.value.clone()));
+      }
+    }
+    let (acc, _) = self
+      .delete_primes_(&prime_witnesses)
+      .expect("accepted primes are pairwise coprime");
+    (acc, failures)
+  }
+
   /// Removes the elements in `elem_witnesses` from the accumulator.
   ///
   /// # Arguments
@@ -158,6 +847,11 @@ impl<G: UnknownOrderGroup, T: Eq + Hash> Accumulator<G, T> {
   ///
   /// Uses a move instead of a `&self` reference to prevent accidental use of the old accumulator.
   pub fn delete(self, elem_witnesses: &[(T, Witness<G, T>)]) -> Result<Self, AccError> {
+    self.delete_ref(elem_witnesses)
+  }
+
+  /// Like `delete`, but leaves this accumulator in place.
+  pub fn delete_ref(&self, elem_witnesses: &[(T, Witness<G, T>)]) -> Result<Self, AccError> {
     Ok(self.delete_(elem_witnesses)?.0)
   }
 
@@ -167,17 +861,91 @@ impl<G: UnknownOrderGroup, T: Eq + Hash> Accumulator<G, T> {
     self,
     elem_witnesses: &[(T, Witness<G, T>)],
   ) -> Result<(Self, MembershipProof<G, T>), AccError> {
-    let (acc, prime_product) = self.clone().delete_(elem_witnesses)?;
+    let (acc, prime_product) = self.delete_(elem_witnesses)?;
     let proof = Poe::<G>::prove(&acc.value, &prime_product, &self.value);
     Ok((
       acc.clone(),
       MembershipProof {
         witness: Witness(acc),
         proof,
+        nonces: None,
+      },
+    ))
+  }
+
+  /// Adds `primes` directly, for protocols that map elements to primes themselves. Each must be an
+  /// odd prime of at least half of `prime_bits` bits, or `BadPrime` is returned. The bound is loose
+  /// enough that `hash_to_prime` outputs pass it, but keeps primes out of brute-force range.
+  ///
+  /// Primes added this way are indistinguishable from hashed elements, so the mapping must never
+  /// produce a prime that `hash_to_prime` could produce for an element of type `T`, unless the two
+  /// are meant to be the same element.
+  pub fn add_primes(self, primes: &[Integer]) -> Result<Self, AccError> {
+    let x = self
+      .checked_prime_product(primes)
+      .ok_or(AccError::BadPrime)?;
+    Ok(Self {
+      phantom: PhantomData,
+      params: self.params,
+      value: G::exp(&self.value, &x),
+    })
+  }
+
+  /// A specialized version of `add_primes` that also returns a batch membership proof for the
+  /// added primes.
+  pub fn add_primes_with_proof(
+    self,
+    primes: &[Integer],
+  ) -> Result<(Self, MembershipProof<G, T>), AccError> {
+    let x = self
+      .checked_prime_product(primes)
+      .ok_or(AccError::BadPrime)?;
+    let acc = Self {
+      phantom: PhantomData,
+      params: self.params,
+      value: G::exp(&self.value, &x),
+    };
+    let proof = Poe::<G>::prove(&self.value, &x, &acc.value);
+    Ok((
+      acc,
+      MembershipProof {
+        witness: Witness(self),
+        proof,
+        nonces: None,
       },
     ))
   }
 
+  /// Verifies a membership proof for `primes`, added with `add_primes` or `add_primes_with_proof`.
+  /// Rejects primes that `add_primes` would not accept. Any nonces the proof carries are ignored.
+  pub fn verify_membership_primes(
+    &self,
+    primes: &[Integer],
+    proof: &MembershipProof<G, T>,
+  ) -> bool {
+    if proof.witness.0.params != self.params {
+      return false;
+    }
+    match self.checked_prime_product(primes) {
+      Some(x) => Poe::verify(&proof.witness.0.value, &x, &self.value, &proof.proof),
+      None => false,
+    }
+  }
+
+  /// Returns the product of `primes`, or `None` if one is not an odd prime of at least half of
+  /// `prime_bits` bits.
+  fn checked_prime_product(&self, primes: &[Integer]) -> Option<Integer> {
+    let min_bits = self.params.bits.bits() / 2;
+    let valid = primes.iter().all(|p| {
+      p.is_odd() && p.bit_length() >= min_bits && p.is_probable_prime(Security128::MR_ROUNDS)
+    });
+    if valid {
+      Some(product(primes.to_vec()))
+    } else {
+      None
+    }
+  }
+
   /// Computes the batch membership proof for the elements in `elem_witnesses` w.r.t this
   /// accumulator.
   ///
@@ -188,37 +956,304 @@ impl<G: UnknownOrderGroup, T: Eq + Hash> Accumulator<G, T> {
     &self,
     elem_witnesses: &[(T, Witness<G, T>)],
   ) -> Result<MembershipProof<G, T>, AccError> {
-    let witness_accum = self.clone().delete(elem_witnesses)?;
-    let prod = elem_witnesses
-      .iter()
-      .map(|(t, _)| hash_to_prime(t))
-      .product();
-    let proof = Poe::<G>::prove(&witness_accum.value, &prod, &self.value);
+    self.prove_membership_with_context(elem_witnesses, &())
+  }
+
+  /// Like `prove_membership`, but with `context` hashed into the NI-PoE challenge. See
+  /// `Poe::prove_with_context`.
+  pub(crate) fn prove_membership_with_context<C: Hash + ?Sized>(
+    &self,
+    elem_witnesses: &[(T, Witness<G, T>)],
+    context: &C,
+  ) -> Result<MembershipProof<G, T>, AccError> {
+    let witness_accum = self.delete_ref(elem_witnesses)?;
+    let prod = product(elem_witnesses.iter().map(|(t, _)| self.prime(t)).collect());
+    let proof = Poe::<G>::prove_with_context(&witness_accum.value, &prod, &self.value, context);
     Ok(MembershipProof {
       witness: Witness(witness_accum),
       proof,
+      nonces: None,
     })
   }
 
-  /// Verifies a membership proof against the current accumulator and an element `t` whose
-  /// inclusion is being proven.
-  pub fn verify_membership(
-    &self,
-    t: &T,
-    MembershipProof { witness, proof }: &MembershipProof<G, T>,
-  ) -> bool {
-    let exp = hash_to_prime(t);
-    Poe::verify(&witness.0.value, &exp, &self.value, proof)
+  /// Removes `elems` from the accumulator using the full-set `state` instead of per-element
+  /// witnesses, and removes them from `state` as well.
+  ///
+  /// Returns `InexactDivision` if some element is not tracked by `state`, and `BadWitness` if
+  /// `state` does not describe this accumulator.
+  ///
+  /// Uses a move instead of a `&self` reference to prevent accidental use of the old accumulator.
+  pub fn delete_with_state(
+    self,
+    state: &mut ExponentState<G, T>,
+    elems: &[T],
+  ) -> Result<Self, AccError> {
+    self.delete_with_state_ref(state, elems)
   }
 
-  /// Batch version of `verify_membership` for multiple `elems`.
-  pub fn verify_membership_batch(
+  /// Like `delete_with_state`, but leaves this accumulator in place.
+  pub fn delete_with_state_ref(
     &self,
+    state: &mut ExponentState<G, T>,
     elems: &[T],
-    MembershipProof { witness, proof }: &MembershipProof<G, T>,
+  ) -> Result<Self, AccError> {
+    if state.params != self.params {
+      return Err(AccError::BadWitness);
+    }
+    let x = self.prime_product(elems);
+    let acc = Self {
+      phantom: PhantomData,
+      params: self.params,
+      value: state.exp_quotient(&x)?,
+    };
+    if G::exp(&acc.value, &x) != self.value {
+      return Err(AccError::BadWitness);
+    }
+    state.product /= x;
+    Ok(acc)
+  }
+
+  /// Removes `elem` from the accumulator by re-accumulating `remaining_set`, the full set without
+  /// `elem`, for callers that hold the set but no witness or `ExponentState`. Costs as much as
+  /// adding `remaining_set` to an empty accumulator; `delete_with_state` is cheaper when a state is
+  /// kept.
+  ///
+  /// The result is checked with a single exponentiation by the prime of `elem`, and `BadWitness`
+  /// is returned if `elem` and `remaining_set` do not make up this accumulator. That includes any
+  /// accumulator made with `from_initial_exponent`, which cannot be rebuilt from its set alone.
+  ///
+  /// Uses a move instead of a `&self` reference to prevent accidental use of the old accumulator.
+  pub fn delete_unchecked_with_set(self, elem: &T, remaining_set: &[T]) -> Result<Self, AccError> {
+    let acc = Self::with_params(self.params).add(remaining_set);
+    if G::exp(&acc.value, &self.prime(elem)) != self.value {
+      return Err(AccError::BadWitness);
+    }
+    Ok(acc)
+  }
+
+  /// Computes the batch membership proof for `elems` w.r.t this accumulator using the full-set
+  /// `state` instead of per-element witnesses.
+  pub fn prove_membership_with_state(
+    &self,
+    state: &ExponentState<G, T>,
+    elems: &[T],
+  ) -> Result<MembershipProof<G, T>, AccError> {
+    if state.params != self.params {
+      return Err(AccError::BadWitness);
+    }
+    let x = self.prime_product(elems);
+    let witness_accum = Self {
+      phantom: PhantomData,
+      params: self.params,
+      value: state.exp_quotient(&x)?,
+    };
+    if G::exp(&witness_accum.value, &x) != self.value {
+      return Err(AccError::BadWitness);
+    }
+    let proof = Poe::<G>::prove(&witness_accum.value, &x, &self.value);
+    Ok(MembershipProof {
+      witness: Witness(witness_accum),
+      proof,
+      nonces: None,
+    })
+  }
+
+  /// Computes the batch membership proof for `elems` w.r.t this accumulator from `set`, the full
+  /// accumulated set, without ever holding the product of its primes. The witness is built by
+  /// raising the generator to the product of `chunk_size` primes at a time, so peak memory is
+  /// bounded by the chunk size instead of the set size, at the cost of more exponentiations.
+  /// `set` may be any iterator, e.g. one streaming elements from disk.
+  ///
+  /// Returns `BadWitness` if `elems` and the rest of `set` do not make up this accumulator, as for
+  /// any accumulator made with `from_initial_exponent`.
+  pub fn prove_membership_chunked<I: IntoIterator<Item = T>>(
+    &self,
+    set: I,
+    elems: &[T],
+    chunk_size: usize,
+  ) -> Result<MembershipProof<G, T>, AccError> {
+    let chunk_size = chunk_size.max(1);
+    let mut witness = G::unknown_order_elem();
+    let mut chunk = int(1);
+    let mut chunk_len = 0;
+    for t in set.into_iter().filter(|t| !elems.contains(t)) {
+      chunk *= self.prime(&t);
+      chunk_len += 1;
+      if chunk_len == chunk_size {
+        witness = G::exp(&witness, &chunk);
+        chunk = int(1);
+        chunk_len = 0;
+      }
+    }
+    witness = G::exp(&witness, &chunk);
+
+    let x = self.prime_product(elems);
+    if G::exp(&witness, &x) != self.value {
+      return Err(AccError::BadWitness);
+    }
+    let proof = Poe::<G>::prove(&witness, &x, &self.value);
+    Ok(MembershipProof {
+      witness: Witness(Self {
+        phantom: PhantomData,
+        params: self.params,
+        value: witness,
+      }),
+      proof,
+      nonces: None,
+    })
+  }
+
+  /// Verifies a membership proof against the current accumulator and an element `t` whose
+  /// inclusion is being proven.
+  pub fn verify_membership(&self, t: &T, proof: &MembershipProof<G, T>) -> bool {
+    self.verify_membership_batch(std::slice::from_ref(t), proof)
+  }
+
+  /// Batch version of `verify_membership` for multiple `elems`.
+  ///
+  /// If the proof carries nonces, each element is mapped to its prime with a single hash, and the
+  /// proof is rejected if some nonce does not yield a prime.
+  pub fn verify_membership_batch(&self, elems: &[T], proof: &MembershipProof<G, T>) -> bool {
+    self.verify_membership_ref(elems, proof.borrowed())
+  }
+
+  /// Like `verify_membership_batch`, but returns `ParameterMismatch` instead of `false` if the
+  /// proof was made under different prime parameters than this accumulator's. Proofs for other
+  /// groups are already rejected when decoded; see `proof_params_digest`.
+  pub fn verify_membership_checked(
+    &self,
+    elems: &[T],
+    proof: &MembershipProof<G, T>,
+  ) -> Result<bool, AccError> {
+    if proof.witness.0.params != self.params {
+      return Err(AccError::ParameterMismatch);
+    }
+    Ok(self.verify_membership_batch(elems, proof))
+  }
+
+  /// Like `verify_membership_batch`, but reports why the proof failed, for operators debugging
+  /// rejected proofs. Malformed proofs (wrong parameters, bad nonces or repeated elements) are
+  /// reported without checking the witness. Otherwise, if the witness does not hold for the batch,
+  /// each element is checked for being the only one absent, which costs `O(n log n)`
+  /// exponentiations for `n` elements; more than one absent element is reported as `BadWitness`.
+  pub fn verify_membership_detailed(
+    &self,
+    elems: &[T],
+    proof: &MembershipProof<G, T>,
+  ) -> VerificationReport {
+    let failures = |failures| VerificationReport { failures };
+    if proof.witness.0.params != self.params {
+      return failures(vec![MembershipFailure::ParameterMismatch]);
+    }
+    let primes = match &proof.nonces {
+      None => elems.iter().map(|t| self.prime(t)).collect::<Vec<_>>(),
+      Some(nonces) if nonces.len() != elems.len() => {
+        return failures(vec![MembershipFailure::NonceCount]);
+      }
+      Some(nonces) => {
+        let primes = elems
+          .iter()
+          .zip(nonces)
+          .map(|(t, &nonce)| self.params.prime_from_nonce(t, nonce))
+          .collect::<Vec<_>>();
+        let bad_nonces = (0..elems.len())
+          .filter(|&i| primes[i].is_none())
+          .map(MembershipFailure::BadNonce)
+          .collect::<Vec<_>>();
+        if !bad_nonces.is_empty() {
+          return failures(bad_nonces);
+        }
+        primes.into_iter().map(Option::unwrap).collect()
+      }
+    };
+
+    let mut seen = HashSet::with_capacity(primes.len());
+    let duplicates = (0..primes.len())
+      .filter(|&i| !seen.insert(&primes[i]))
+      .map(MembershipFailure::Duplicate)
+      .collect::<Vec<_>>();
+    if !duplicates.is_empty() {
+      return failures(duplicates);
+    }
+
+    let witness = &proof.witness.0.value;
+    let x = product(primes.clone());
+    if Poe::verify(witness, &x, &self.value, &proof.proof) {
+      return failures(Vec::new());
+    }
+    if G::exp(witness, &x) == self.value {
+      return failures(vec![MembershipFailure::BadPoe]);
+    }
+    let absent = root_factor::<G>(witness, &primes)
+      .iter()
+      .enumerate()
+      .filter(|(_, root)| **root == self.value)
+      .map(|(i, _)| MembershipFailure::Absent(i))
+      .collect::<Vec<_>>();
+    if absent.is_empty() {
+      failures(vec![MembershipFailure::BadWitness])
+    } else {
+      failures(absent)
+    }
+  }
+
+  /// Like `verify_membership_detailed`, but returns `StaleState` if the proof fails here and holds
+  /// for one of the earlier accumulators in `history`, so that a prover behind on updates is not
+  /// mistaken for one claiming absent elements. Checking the history only costs anything for
+  /// proofs that fail.
+  pub fn verify_membership_with_history(
+    &self,
+    history: &[Self],
+    elems: &[T],
+    proof: &MembershipProof<G, T>,
+  ) -> Result<VerificationReport, AccError> {
+    let report = self.verify_membership_detailed(elems, proof);
+    if !report.is_valid()
+      && history
+        .iter()
+        .any(|acc| acc.verify_membership_batch(elems, proof))
+    {
+      return Err(AccError::StaleState);
+    }
+    Ok(report)
+  }
+
+  /// Like `verify_membership_batch`, for a proof with borrowed parts.
+  pub fn verify_membership_ref(&self, elems: &[T], proof: MembershipProofRef<'_, G, T>) -> bool {
+    self.verify_membership_with_context(elems, proof, &())
+  }
+
+  /// Verifies a proof made with `prove_membership_with_context` and the same `context`.
+  pub(crate) fn verify_membership_with_context<C: Hash + ?Sized>(
+    &self,
+    elems: &[T],
+    proof: MembershipProofRef<'_, G, T>,
+    context: &C,
   ) -> bool {
-    let exp = prime_hash_product(elems);
-    Poe::verify(&witness.0.value, &exp, &self.value, proof)
+    verify_membership_at(self.params, &self.value, elems, proof, context)
+  }
+
+  /// Verifies a compressed membership proof against the current accumulator and `elems`, the
+  /// elements whose inclusion is being proven.
+  pub fn verify_compressed(&self, elems: &[T], proof: &CompressedMembershipProof<G, T>) -> bool {
+    let CompressedMembershipProof {
+      old_acc,
+      proof,
+      transition,
+    } = proof;
+    old_acc.params == self.params
+      && old_acc.verify_membership_batch(elems, proof)
+      && Poke2::verify(&old_acc.value, &self.value, transition)
+  }
+
+  /// Returns the exponent `proof` claims takes its witness to this accumulator, i.e. the product
+  /// of the primes of `elems`, or `None` if the proof is malformed.
+  pub(crate) fn membership_exponent(
+    &self,
+    elems: &[T],
+    proof: MembershipProofRef<'_, G, T>,
+  ) -> Option<Integer> {
+    membership_exponent_at(self.params, elems, proof)
   }
 
   /// Updates a `witness` for `tracked_elems` w.r.t the current accumulator, adding the elements in
@@ -233,8 +1268,11 @@ impl<G: UnknownOrderGroup, T: Eq + Hash> Accumulator<G, T> {
     untracked_additions: &[T],
     untracked_deletions: &[T],
   ) -> Result<Witness<G, T>, AccError> {
-    let x = prime_hash_product(tracked_elems);
-    let x_hat = prime_hash_product(untracked_deletions);
+    if witness.0.params != self.params {
+      return Err(AccError::BadWitnessUpdate);
+    }
+    let x = self.prime_product(tracked_elems);
+    let x_hat = self.prime_product(untracked_deletions);
 
     for elem in tracked_elems {
       if untracked_additions.contains(elem) || untracked_deletions.contains(elem) {
@@ -242,7 +1280,7 @@ impl<G: UnknownOrderGroup, T: Eq + Hash> Accumulator<G, T> {
       }
     }
 
-    let (gcd, a, b) = <(Integer, Integer, Integer)>::from(x.gcd_cofactors_ref(&x_hat));
+    let (gcd, a, b) = x.xgcd(&x_hat);
     assert!(gcd == int(1));
 
     let w = witness.0.add(untracked_additions);
@@ -250,10 +1288,49 @@ impl<G: UnknownOrderGroup, T: Eq + Hash> Accumulator<G, T> {
     let acc_new_to_a = G::exp(&self.value, &a);
     Ok(Witness(Self {
       phantom: PhantomData,
+      params: self.params,
       value: G::op(&w_to_b, &acc_new_to_a),
     }))
   }
 
+  /// Updates `elem_witnesses`, each a witness for a single tracked element, like
+  /// `update_membership_witness` does for each of them, but hashes `untracked_additions` and
+  /// `untracked_deletions` to primes once for all witnesses. Returns the updated witnesses in
+  /// order.
+  pub fn update_membership_witnesses(
+    &self,
+    elem_witnesses: &[(T, Witness<G, T>)],
+    untracked_additions: &[T],
+    untracked_deletions: &[T],
+  ) -> Result<Vec<Witness<G, T>>, AccError> {
+    let untracked = untracked_additions
+      .iter()
+      .chain(untracked_deletions)
+      .collect::<HashSet<_>>();
+    let x_add = self.prime_product(untracked_additions);
+    let x_hat = self.prime_product(untracked_deletions);
+    elem_witnesses
+      .iter()
+      .map(|(t, witness)| {
+        if witness.0.params != self.params || untracked.contains(t) {
+          return Err(AccError::BadWitnessUpdate);
+        }
+        let x = self.params.hash_to_prime(t);
+        let (gcd, a, b) = x.xgcd(&x_hat);
+        if gcd != 1 {
+          return Err(AccError::BadWitnessUpdate);
+        }
+        let w_to_b = G::exp(&witness.0.value, &(&x_add * b));
+        let acc_new_to_a = G::exp(&self.value, &a);
+        Ok(Witness(Self {
+          phantom: PhantomData,
+          params: self.params,
+          value: G::op(&w_to_b, &acc_new_to_a),
+        }))
+      })
+      .collect()
+  }
+
   /// Computes the batch non-membership proof for the elements in `elems` w.r.t this accumulator
   /// and its `acc_set`.
   ///
@@ -266,9 +1343,19 @@ impl<G: UnknownOrderGroup, T: Eq + Hash> Accumulator<G, T> {
     acc_set: &[T],
     elems: &[T],
   ) -> Result<NonmembershipProof<G, T>, AccError> {
-    let x: Integer = elems.iter().map(hash_to_prime).product();
-    let s = acc_set.iter().map(hash_to_prime).product();
-    let (gcd, a, b) = <(Integer, Integer, Integer)>::from(x.gcd_cofactors_ref(&s));
+    self.prove_nonmembership_with_context(acc_set, elems, &())
+  }
+
+  /// Like `prove_nonmembership`, but with `context` hashed into the challenges of both subproofs.
+  pub(crate) fn prove_nonmembership_with_context<C: Hash + ?Sized>(
+    &self,
+    acc_set: &[T],
+    elems: &[T],
+    context: &C,
+  ) -> Result<NonmembershipProof<G, T>, AccError> {
+    let x = self.prime_product(elems);
+    let s = self.prime_product(acc_set);
+    let (gcd, a, b) = x.xgcd(&s);
 
     if gcd != int(1) {
       return Err(AccError::InputsNotCoprime);
@@ -279,10 +1366,11 @@ impl<G: UnknownOrderGroup, T: Eq + Hash> Accumulator<G, T> {
     let v = G::exp(&self.value, &b);
     let gv_inv = G::op(&g, &G::inv(&v));
 
-    let poke2_proof = Poke2::prove(&self.value, &b, &v);
-    let poe_proof = Poe::prove(&d, &x, &gv_inv);
+    let poke2_proof = Poke2::prove_with_context(&self.value, &b, &v, context);
+    let poe_proof = Poe::prove_with_context(&d, &x, &gv_inv, context);
     Ok(NonmembershipProof {
       phantom: PhantomData,
+      params: self.params,
       d,
       v,
       gv_inv,
@@ -293,26 +1381,700 @@ impl<G: UnknownOrderGroup, T: Eq + Hash> Accumulator<G, T> {
 
   /// Verifies a non-membership proof against the current accumulator and elements `elems` whose
   /// non-inclusion is being proven.
-  pub fn verify_nonmembership(
+  pub fn verify_nonmembership(&self, elems: &[T], proof: &NonmembershipProof<G, T>) -> bool {
+    self.verify_nonmembership_with_context(elems, proof, &())
+  }
+
+  /// Verifies a proof made with `prove_nonmembership_with_context` and the same `context`.
+  pub(crate) fn verify_nonmembership_with_context<C: Hash + ?Sized>(
+    &self,
+    elems: &[T],
+    proof: &NonmembershipProof<G, T>,
+    context: &C,
+  ) -> bool {
+    verify_nonmembership_at(self.params, &self.value, elems, proof, context)
+  }
+
+  /// Like `verify_nonmembership`, but returns `ParameterMismatch` instead of `false` if the proof
+  /// was made under different prime parameters than this accumulator's.
+  pub fn verify_nonmembership_checked(
+    &self,
+    elems: &[T],
+    proof: &NonmembershipProof<G, T>,
+  ) -> Result<bool, AccError> {
+    if proof.params != self.params {
+      return Err(AccError::ParameterMismatch);
+    }
+    Ok(self.verify_nonmembership(elems, proof))
+  }
+
+  /// Like `verify_nonmembership`, but returns `StaleState` if the proof fails here and holds for
+  /// one of the earlier accumulators in `history`. See `verify_membership_with_history`.
+  pub fn verify_nonmembership_with_history(
     &self,
+    history: &[Self],
     elems: &[T],
-    NonmembershipProof {
+    proof: &NonmembershipProof<G, T>,
+  ) -> Result<bool, AccError> {
+    if self.verify_nonmembership(elems, proof) {
+      return Ok(true);
+    }
+    if history
+      .iter()
+      .any(|acc| acc.verify_nonmembership(elems, proof))
+    {
+      return Err(AccError::StaleState);
+    }
+    Ok(false)
+  }
+
+  #[cfg(feature = "verbose_proof")]
+  /// Like `prove_nonmembership`, but returns the explicit LLX form of the proof.
+  pub fn prove_nonmembership_verbose(
+    &self,
+    acc_set: &[T],
+    elems: &[T],
+  ) -> Result<VerboseNonmembershipProof<G, T>, AccError> {
+    let x = self.prime_product(elems);
+    let s = self.prime_product(acc_set);
+    let (gcd, a, b) = x.xgcd(&s);
+    if gcd != int(1) {
+      return Err(AccError::InputsNotCoprime);
+    }
+    Ok(VerboseNonmembershipProof {
+      phantom: PhantomData,
+      params: self.params,
+      d: G::exp(&G::unknown_order_elem(), &a),
+      b,
+    })
+  }
+
+  #[cfg(feature = "verbose_proof")]
+  /// Verifies a proof made with `prove_nonmembership_verbose` against the current accumulator and
+  /// elements `elems` whose non-inclusion is being proven.
+  pub fn verify_nonmembership_verbose(
+    &self,
+    elems: &[T],
+    proof: &VerboseNonmembershipProof<G, T>,
+  ) -> bool {
+    if proof.params != self.params {
+      return false;
+    }
+    let x = self.prime_product(elems);
+    G::op(&G::exp(&proof.d, &x), &G::exp(&self.value, &proof.b)) == G::unknown_order_elem()
+  }
+
+  /// Proves that `other`, an accumulator over any group, holds the same set as this one, given
+  /// `state`, the exponent state of both.
+  ///
+  /// Returns `BadMigration` if the accumulators hash elements to primes differently, or if `state`
+  /// does not describe both of them. For accumulators made with `from_initial_exponent`, the proof
+  /// covers the initial exponent along with the set, so both must have started at the same `r`.
+  pub fn prove_equal_set<H: UnknownOrderGroup>(
+    &self,
+    other: &Accumulator<H, T>,
+    state: &ExponentState<G, T>,
+  ) -> Result<EqualSetProof<G, H, T>, AccError> {
+    if other.params != self.params
+      || state.params != self.params
+      || G::exp(&G::unknown_order_elem(), &state.product) != self.value
+      || H::exp(&H::unknown_order_elem(), &state.product) != other.value
+    {
+      return Err(AccError::BadMigration);
+    }
+    Ok(EqualSetProof {
+      phantom: PhantomData,
+      proof: Poke2Eq::prove(
+        &G::unknown_order_elem(),
+        &H::unknown_order_elem(),
+        &state.product,
+        &self.value,
+        &other.value,
+      ),
+    })
+  }
+
+  /// Verifies that `other` holds the same set as this accumulator.
+  pub fn verify_equal_set<H: UnknownOrderGroup>(
+    &self,
+    other: &Accumulator<H, T>,
+    proof: &EqualSetProof<G, H, T>,
+  ) -> bool {
+    other.params == self.params
+      && Poke2Eq::verify(
+        &G::unknown_order_elem(),
+        &H::unknown_order_elem(),
+        &self.value,
+        &other.value,
+        &proof.proof,
+      )
+  }
+}
+
+impl<G: ElemBytes + UnknownOrderGroup, T> Accumulator<G, T> {
+  /// Returns the canonical encoding of this accumulator: its encoded `PrimeParams`, followed by
+  /// the encoded group element.
+  pub fn to_bytes(&self) -> Vec<u8> {
+    let mut bytes = self.params.to_bytes().to_vec();
+    bytes.extend(G::elem_to_bytes(&self.value));
+    bytes
+  }
+
+  /// Parses the canonical encoding produced by `to_bytes`.
+  pub fn from_bytes(bytes: &[u8]) -> Result<Self, AccError> {
+    Decoder::decode_all(bytes, Self::decode).map_err(|_| AccError::BadEncoding)
+  }
+
+  /// Returns a 32-byte digest of this accumulator and its parameters, for embedding in block
+  /// headers. Equal digests imply equal accumulators, barring a Blake2b collision. Matches the
+  /// `AccumulatorState::digest` of this accumulator with element encoding version 0.
+  pub fn digest(&self) -> [u8; 32] {
+    state::digest::<G>(self.params, 0, &self.to_bytes())
+  }
+
+  pub(crate) fn decode(decoder: &mut Decoder) -> Result<Self, DecodeError> {
+    let params = decode_params(decoder)?;
+    let value = decoder.elem::<G>()?;
+    Ok(Self {
+      phantom: PhantomData,
+      params,
+      value,
+    })
+  }
+}
+
+// Verification against an accumulator value and its parameters, shared by `Accumulator` and
+// `AccumulatorView`.
+
+// Returns the exponent `proof` claims takes its witness to an accumulator with `params`, i.e. the
+// product of the primes of `elems`, or `None` if the proof is malformed.
+pub(crate) fn membership_exponent_at<G: UnknownOrderGroup, T: Hash>(
+  params: PrimeParams,
+  elems: &[T],
+  MembershipProofRef {
+    witness, nonces, ..
+  }: MembershipProofRef<'_, G, T>,
+) -> Option<Integer> {
+  if witness.0.params != params {
+    return None;
+  }
+  match nonces {
+    None => Some(params.prime_hash_product(elems)),
+    Some(nonces) if nonces.len() == elems.len() => elems
+      .iter()
+      .zip(nonces)
+      .map(|(t, &nonce)| params.prime_from_nonce(t, nonce))
+      .collect::<Option<_>>()
+      .map(product),
+    Some(_) => None,
+  }
+}
+
+pub(crate) fn verify_membership_at<G: UnknownOrderGroup, T: Hash, C: Hash + ?Sized>(
+  params: PrimeParams,
+  value: &G::Elem,
+  elems: &[T],
+  proof: MembershipProofRef<'_, G, T>,
+  context: &C,
+) -> bool {
+  match membership_exponent_at(params, elems, proof) {
+    Some(exp) => {
+      Poe::verify_with_context(&proof.witness.0.value, &exp, value, proof.proof, context)
+    }
+    None => false,
+  }
+}
+
+pub(crate) fn verify_nonmembership_at<G: UnknownOrderGroup, T: Hash, C: Hash + ?Sized>(
+  params: PrimeParams,
+  value: &G::Elem,
+  elems: &[T],
+  NonmembershipProof {
+    params: proof_params,
+    d,
+    v,
+    gv_inv,
+    poke2_proof,
+    poe_proof,
+    ..
+  }: &NonmembershipProof<G, T>,
+  context: &C,
+) -> bool {
+  if *proof_params != params {
+    return false;
+  }
+  let x = params.prime_hash_product(elems);
+  *gv_inv == G::op(&G::unknown_order_elem(), &G::inv(v))
+    && Poke2::verify_with_context(value, v, poke2_proof, context)
+    && Poe::verify_with_context(d, &x, gv_inv, poe_proof, context)
+}
+
+// The length of the parameter digest serialized proofs begin with.
+const PARAMS_DIGEST_BYTES: usize = 32;
+
+fn decode_params(decoder: &mut Decoder) -> Result<PrimeParams, DecodeError> {
+  let bytes = decoder.bytes(2)?;
+  PrimeParams::from_bytes([bytes[0], bytes[1]]).ok_or(DecodeError::BadTag)
+}
+
+impl<G: ElemBytes + UnknownOrderGroup, T: Hash> MembershipProof<G, T> {
+  /// Returns the canonical encoding of this proof: the parameter digest (see
+  /// `proof_params_digest`), the encoded witness and NI-PoE, then a flag byte that is 1 if nonces
+  /// are attached and 0 otherwise. Attached nonces follow as a big-endian `u64` count and
+  /// big-endian `u64`s.
+  pub fn to_bytes(&self) -> Vec<u8> {
+    let mut bytes = state::proof_params_digest::<G>(self.witness.0.params).to_vec();
+    bytes.extend(self.witness.0.to_bytes());
+    bytes.extend(self.proof.to_bytes());
+    match &self.nonces {
+      None => bytes.push(0),
+      Some(nonces) => {
+        bytes.push(1);
+        bytes.extend_from_slice(&(nonces.len() as u64).to_be_bytes());
+        for nonce in nonces {
+          bytes.extend_from_slice(&nonce.to_be_bytes());
+        }
+      }
+    }
+    bytes
+  }
+
+  /// Parses the canonical encoding produced by `to_bytes`. Fails with `ParameterMismatch` if the
+  /// proof was encoded for another group.
+  pub fn from_bytes(bytes: &[u8]) -> Result<Self, DecodeError> {
+    Decoder::decode_all(bytes, |decoder| {
+      let digest = decoder.bytes(PARAMS_DIGEST_BYTES)?;
+      let witness = Witness(Accumulator::decode(decoder)?);
+      if state::proof_params_digest::<G>(witness.0.params)[..] != *digest {
+        return Err(DecodeError::ParameterMismatch);
+      }
+      let proof = Poe::decode(decoder)?;
+      let nonces = match decoder.byte()? {
+        0 => None,
+        1 => {
+          let n_nonces = decoder.u64()?;
+          // Check the count against the input before allocating for it.
+          if n_nonces > (decoder.remaining() / 8) as u64 {
+            return Err(DecodeError::Truncated);
+          }
+          Some(
+            (0..n_nonces)
+              .map(|_| decoder.u64())
+              .collect::<Result<_, _>>()?,
+          )
+        }
+        _ => return Err(DecodeError::BadTag),
+      };
+      Ok(Self {
+        witness,
+        proof,
+        nonces,
+      })
+    })
+  }
+}
+
+impl<G: ElemBytes + UnknownOrderGroup, T> NonmembershipProof<G, T> {
+  /// Returns the canonical encoding of this proof: the parameter digest (see
+  /// `proof_params_digest`) and its encoded `PrimeParams`, followed by its group elements and
+  /// subproofs.
+  pub fn to_bytes(&self) -> Vec<u8> {
+    let mut bytes = state::proof_params_digest::<G>(self.params).to_vec();
+    bytes.extend_from_slice(&self.params.to_bytes());
+    for elem in &[&self.d, &self.v, &self.gv_inv] {
+      bytes.extend(G::elem_to_bytes(elem));
+    }
+    bytes.extend(self.poke2_proof.to_bytes());
+    bytes.extend(self.poe_proof.to_bytes());
+    bytes
+  }
+
+  /// Parses the canonical encoding produced by `to_bytes`. Fails with `ParameterMismatch` if the
+  /// proof was encoded for another group.
+  pub fn from_bytes(bytes: &[u8]) -> Result<Self, DecodeError> {
+    Decoder::decode_all(bytes, |decoder| {
+      let digest = decoder.bytes(PARAMS_DIGEST_BYTES)?;
+      let params = decode_params(decoder)?;
+      if state::proof_params_digest::<G>(params)[..] != *digest {
+        return Err(DecodeError::ParameterMismatch);
+      }
+      Ok(Self {
+        phantom: PhantomData,
+        params,
+        d: decoder.elem::<G>()?,
+        v: decoder.elem::<G>()?,
+        gv_inv: decoder.elem::<G>()?,
+        poke2_proof: Poke2::decode(decoder)?,
+        poe_proof: Poe::decode(decoder)?,
+      })
+    })
+  }
+}
+
+impl_encoding_traits!(Accumulator<G, T> where G: ElemBytes + UnknownOrderGroup, T: Eq);
+impl_encoding_traits!(MembershipProof<G, T> where G: ElemBytes + UnknownOrderGroup, T: Eq + Hash);
+impl_encoding_traits!(NonmembershipProof<G, T> where G: ElemBytes + UnknownOrderGroup, T: Eq);
+
+impl<G: UnknownOrderGroup, T: Eq + Hash> From<&[T]> for Accumulator<G, T> {
+  fn from(ts: &[T]) -> Self {
+    Self::empty().add(ts)
+  }
+}
+
+impl<'a, G: UnknownOrderGroup, T: Hash> MembershipProofRef<'a, G, T> {
+  /// Assembles a proof from parts split off with `MembershipProof::into_parts`. The parts need not
+  /// come from the same proof, as long as they fit together: `proof` must be the NI-PoE for
+  /// `witness`, and `nonces` those of the proven elements.
+  pub fn from_parts(
+    witness: &'a Witness<G, T>,
+    proof: &'a Poe<G>,
+    nonces: Option<&'a [u64]>,
+  ) -> Self {
+    Self {
+      witness,
+      proof,
+      nonces,
+    }
+  }
+
+  /// Returns the witness for the proven elements.
+  pub fn witness(&self) -> &'a Witness<G, T> {
+    self.witness
+  }
+
+  /// Returns an owned copy of this proof, cloning the borrowed parts.
+  pub fn into_owned(self) -> MembershipProof<G, T> {
+    MembershipProof {
+      witness: Witness(self.witness.0.clone()),
+      proof: self.proof.clone(),
+      nonces: self.nonces.map(<[u64]>::to_vec),
+    }
+  }
+}
+
+impl<G: UnknownOrderGroup, T: Eq + Hash> MembershipProof<G, T> {
+  /// Attaches the `hash_to_prime` nonces of `elems`, the elements this proof proves, so that
+  /// verifiers can skip the prime search. Verification fails if `elems` differs from the batch
+  /// later passed to `verify_membership_batch`.
+  pub fn with_nonces(self, elems: &[T]) -> Self {
+    let params = self.witness.0.params;
+    let nonces = elems
+      .iter()
+      .map(|t| params.hash_to_prime_with_nonce(t).1)
+      .collect();
+    Self {
+      nonces: Some(nonces),
+      ..self
+    }
+  }
+
+  /// Extends this proof, which verifies against `old_acc`, to `new_acc`, given `exp` such that
+  /// `old_acc ^ exp = new_acc` (e.g. the product of the primes of all elements added since). The
+  /// exponent is proven with NI-PoKE2 instead of being sent, so verifiers need neither the added
+  /// elements nor an updated witness.
+  pub fn compress(
+    self,
+    old_acc: &Accumulator<G, T>,
+    exp: &Integer,
+    new_acc: &Accumulator<G, T>,
+  ) -> CompressedMembershipProof<G, T> {
+    CompressedMembershipProof {
+      old_acc: old_acc.clone(),
+      proof: self,
+      transition: Poke2::prove(&old_acc.value, exp, &new_acc.value),
+    }
+  }
+
+  /// Returns the `hash_to_prime` nonces this proof carries, if any.
+  pub fn nonces(&self) -> Option<&[u64]> {
+    self.nonces.as_deref()
+  }
+
+  /// Returns the NI-PoE that the witness raised to the proven primes gives the accumulator.
+  pub(crate) fn poe(&self) -> &Poe<G> {
+    &self.proof
+  }
+
+  /// Returns a view of this proof with borrowed parts.
+  pub fn borrowed(&self) -> MembershipProofRef<'_, G, T> {
+    MembershipProofRef {
+      witness: &self.witness,
+      proof: &self.proof,
+      nonces: self.nonces.as_deref(),
+    }
+  }
+
+  /// Splits this proof into its witness, NI-PoE and nonces, e.g. to store them in arenas and
+  /// reassemble views of the proof with `MembershipProofRef::from_parts`.
+  pub fn into_parts(self) -> (Witness<G, T>, Poe<G>, Option<Vec<u64>>) {
+    (self.witness, self.proof, self.nonces)
+  }
+
+  /// Merges `proofs` for the element batches `elems` into a single proof for all of them. Every
+  /// proof must be made w.r.t. the same accumulator, and `proofs[i]` must prove `elems[i]`. The
+  /// merged proof is verified with `verify_membership_batch` on the concatenated batches.
+  ///
+  /// Witnesses are combined with the ShamirTrick, which relies on the hashed primes of different
+  /// batches being coprime (i.e. no element appears in two batches).
+  pub fn merge(proofs: &[Self], elems: &[&[T]]) -> Result<Self, AccError> {
+    if proofs.is_empty() || proofs.len() != elems.len() {
+      return Err(AccError::BadWitness);
+    }
+    let params = proofs[0].witness.0.params;
+    if proofs.iter().any(|proof| proof.witness.0.params != params) {
+      return Err(AccError::BadWitness);
+    }
+    let prime_witnesses = proofs
+      .iter()
+      .zip(elems.iter())
+      .map(|(proof, elems)| {
+        (
+          params.prime_hash_product(elems),
+          proof.witness.0.value.clone(),
+        )
+      })
+      .collect::<Vec<_>>();
+
+    let acc_elem = G::exp(&prime_witnesses[0].1, &prime_witnesses[0].0);
+    for (p, witness_elem) in &prime_witnesses[1..] {
+      if G::exp(witness_elem, p) != acc_elem {
+        return Err(AccError::BadWitness);
+      }
+    }
+
+    let (prime_product, witness_elem) =
+      shamir_combine_n::<G>(&prime_witnesses).ok_or(AccError::InputsNotCoprime)?;
+
+    // The merged proof carries nonces only if every input proof did.
+    let nonces = proofs
+      .iter()
+      .map(|proof| proof.nonces.clone())
+      .collect::<Option<Vec<_>>>()
+      .map(|nonces| nonces.concat());
+    let proof = Poe::<G>::prove(&witness_elem, &prime_product, &acc_elem);
+    Ok(Self {
+      witness: Witness(Accumulator {
+        phantom: PhantomData,
+        params,
+        value: witness_elem,
+      }),
+      proof,
+      nonces,
+    })
+  }
+}
+
+impl<G: UnknownOrderGroup, T: Eq + Hash> AggregateNonmembershipProof<G, T> {
+  /// Proves that `elems` are in none of the accumulators in `shards`, each paired with the set it
+  /// commits to. All accumulators must hash elements to primes of the same size.
+  ///
+  /// Each accumulator still contributes its LLX Bezout pair `(d, v)` and a PoKE2 of `v`, but the
+  /// relations `d^x * v = g` are folded into one with random exponents, so that a single PoE covers
+  /// all of them.
+  pub fn prove(shards: &[(&Accumulator<G, T>, &[T])], elems: &[T]) -> Result<Self, AccError> {
+    if shards.is_empty() {
+      return Err(AccError::BadWitness);
+    }
+    let params = shards[0].0.params;
+    if shards.iter().any(|(acc, _)| acc.params != params) {
+      return Err(AccError::BadWitness);
+    }
+
+    let x = params.prime_hash_product(elems);
+    let g = G::unknown_order_elem();
+    let mut d = Vec::with_capacity(shards.len());
+    let mut v = Vec::with_capacity(shards.len());
+    let mut poke2_proofs = Vec::with_capacity(shards.len());
+    for (acc, acc_set) in shards {
+      let s = params.prime_hash_product(acc_set);
+      let (gcd, a, b) = x.xgcd(&s);
+      if gcd != int(1) {
+        return Err(AccError::InputsNotCoprime);
+      }
+      let v_i = G::exp(&acc.value, &b);
+      poke2_proofs.push(Poke2::prove(&acc.value, &b, &v_i));
+      d.push(G::exp(&g, &a));
+      v.push(v_i);
+    }
+
+    let accs = shards.iter().map(|(acc, _)| *acc).collect::<Vec<_>>();
+    let (d_combined, gv_inv_combined) = Self::combine(&accs, &x, &d, &v);
+    let poe_proof = Poe::prove(&d_combined, &x, &gv_inv_combined);
+    Ok(Self {
+      phantom: PhantomData,
+      params,
       d,
       v,
-      gv_inv,
-      poke2_proof,
+      poke2_proofs,
       poe_proof,
-      ..
-    }: &NonmembershipProof<G, T>,
-  ) -> bool {
-    let x = elems.iter().map(hash_to_prime).product();
-    Poke2::verify(&self.value, v, poke2_proof) && Poe::verify(d, &x, gv_inv, poe_proof)
+    })
+  }
+
+  /// Verifies that `elems` are in none of `accs`, which must be given in the order they were
+  /// proven in.
+  pub fn verify(&self, accs: &[&Accumulator<G, T>], elems: &[T]) -> bool {
+    if accs.is_empty()
+      || accs.len() != self.d.len()
+      || accs.len() != self.v.len()
+      || accs.len() != self.poke2_proofs.len()
+      || accs.iter().any(|acc| acc.params != self.params)
+    {
+      return false;
+    }
+    let poke2_statements = accs
+      .iter()
+      .zip(self.v.iter().zip(self.poke2_proofs.iter()))
+      .map(|(acc, (v_i, proof))| (acc.value.clone(), v_i.clone(), proof.clone()))
+      .collect::<Vec<_>>();
+    if !Poke2::verify_batch(&poke2_statements) {
+      return false;
+    }
+
+    let x = self.params.prime_hash_product(elems);
+    let (d_combined, gv_inv_combined) = Self::combine(accs, &x, &self.d, &self.v);
+    Poe::verify(&d_combined, &x, &gv_inv_combined, &self.poe_proof)
+  }
+
+  /// Returns `prod d_i^c_i` and `prod (g * v_i^-1)^c_i` for challenges `c_i` derived from all
+  /// inputs, so that `d_i^x * v_i = g` for every `i` (with overwhelming probability) iff the first
+  /// product raised to `x` is the second. `x`, the product of the element primes, is part of the
+  /// transcript, so the challenges are fixed only once the statement is.
+  fn combine(
+    accs: &[&Accumulator<G, T>],
+    x: &Integer,
+    d: &[G::Elem],
+    v: &[G::Elem],
+  ) -> (G::Elem, G::Elem) {
+    let values = accs.iter().map(|acc| &acc.value).collect::<Vec<_>>();
+    let g = G::unknown_order_elem();
+    let c = (0..d.len())
+      .map(|i| hash_to_prime(&(i, &values, x, d, v)))
+      .collect::<Vec<_>>();
+    let gv_inv = v
+      .iter()
+      .map(|v_i| G::op(&g, &G::inv(v_i)))
+      .collect::<Vec<_>>();
+    (
+      simultaneous_exp::<G>(d, &c),
+      simultaneous_exp::<G>(&gv_inv, &c),
+    )
+  }
+}
+
+impl<G: UnknownOrderGroup, T: Eq + Hash> ExponentState<G, T> {
+  /// Returns the state of an empty accumulator.
+  pub fn empty() -> Self {
+    Self::with_params(PrimeBits::B256)
+  }
+
+  /// Returns the state of an empty accumulator made with `Accumulator::with_params(params)`.
+  pub fn with_params<P: Into<PrimeParams>>(params: P) -> Self {
+    Self {
+      phantom: PhantomData,
+      params: params.into(),
+      product: int(1),
+      order_multiple: None,
+    }
+  }
+
+  /// Returns the state of an accumulator made with
+  /// `Accumulator::from_initial_exponent_with_params(params, r)`, whose exponent starts at `r`.
+  pub fn with_initial_exponent<P: Into<PrimeParams>>(params: P, r: &Integer) -> Self {
+    let mut state = Self::with_params(params);
+    state.product = r.clone();
+    state
+  }
+
+  /// Records that the holder knows `order_multiple`, a positive multiple of the group order.
+  /// Exponents are reduced modulo `order_multiple` before use from now on. Panics if
+  /// `order_multiple` is not positive.
+  pub fn set_order_multiple(&mut self, order_multiple: Integer) {
+    assert!(order_multiple > 0, "order multiple must be positive");
+    self.order_multiple = Some(SecretInteger(order_multiple));
+  }
+
+  /// Tracks `elems` as added. Mirror every `Accumulator::add` with a call to this method.
+  pub fn add(&mut self, elems: &[T]) {
+    self.product *= self.params.prime_hash_product(elems);
+  }
+
+  /// Stops tracking `elems`. Only needed when deleting through `Accumulator::delete` or
+  /// `delete_with_proof`, since `Accumulator::delete_with_state` updates the state itself.
+  pub fn delete(&mut self, elems: &[T]) -> Result<(), AccError> {
+    let x = self.params.prime_hash_product(elems);
+    if !self.product.is_multiple_of(&x) {
+      return Err(AccError::InexactDivision);
+    }
+    self.product /= x;
+    Ok(())
+  }
+
+  /// Returns the product of all tracked primes.
+  pub fn product(&self) -> &Integer {
+    &self.product
+  }
+
+  /// Computes a witness for `elems` directly from the tracked product.
+  pub fn witness(&self, elems: &[T]) -> Result<Witness<G, T>, AccError> {
+    Ok(Witness(Accumulator {
+      phantom: PhantomData,
+      params: self.params,
+      value: self.exp_quotient(&self.params.prime_hash_product(elems))?,
+    }))
   }
-}
 
-impl<G: UnknownOrderGroup, T: Eq + Hash> From<&[T]> for Accumulator<G, T> {
-  fn from(ts: &[T]) -> Self {
-    Self::empty().add(ts)
+  /// Computes a witness for every element of `set`, which must be exactly the tracked set, in the
+  /// order of `set`.
+  ///
+  /// If the holder knows a multiple of the group order, each witness exponent is computed modulo it
+  /// with a product tree and a remainder tree, so that every element costs one exponentiation by an
+  /// exponent the size of the multiple. Otherwise this falls back to RootFactor, which takes
+  /// `O(n log n)` exponentiations by single primes. Returns `AccError::BadWitness` if `set` is not
+  /// the tracked set, which includes every state made with `with_initial_exponent`, since the
+  /// witnesses are built from the standard element; use `witness` for each element of those.
+  pub fn compute_all_witnesses(&self, set: &[T]) -> Result<Vec<Witness<G, T>>, AccError> {
+    let primes = set
+      .iter()
+      .map(|t| self.params.hash_to_prime(t))
+      .collect::<Vec<_>>();
+    if product(primes.clone()) != self.product {
+      return Err(AccError::BadWitness);
+    }
+    let g = G::unknown_order_elem();
+    let values = match &self.order_multiple {
+      Some(m) => cofactors_mod(&primes, &m.0)
+        .iter()
+        .map(|cofactor| G::exp(&g, cofactor))
+        .collect(),
+      None => root_factor::<G>(&g, &primes),
+    };
+    Ok(
+      values
+        .into_iter()
+        .map(|value| {
+          Witness(Accumulator {
+            phantom: PhantomData,
+            params: self.params,
+            value,
+          })
+        })
+        .collect(),
+    )
+  }
+
+  /// Returns `g ^ (product / x)`, where `g` is the generator of the empty accumulator.
+  fn exp_quotient(&self, x: &Integer) -> Result<G::Elem, AccError> {
+    if *x == int(0) {
+      return Err(AccError::DivisionByZero);
+    }
+    let (mut quotient, remainder) = self.product.div_rem_euclid(x);
+    if remainder != int(0) {
+      return Err(AccError::InexactDivision);
+    }
+    if let Some(m) = &self.order_multiple {
+      quotient %= &m.0;
+    }
+    Ok(G::exp(&G::unknown_order_elem(), &quotient))
   }
 }
 
@@ -334,9 +2096,9 @@ impl<G: UnknownOrderGroup, T: Clone + Hash> Witness<G, T> {
       }
     }
 
-    let numerator = prime_hash_product(witness_set);
-    let denominator = prime_hash_product(witness_subset);
-    let (quotient, remainder) = numerator.div_rem(denominator);
+    let numerator = self.0.params.prime_hash_product(witness_set);
+    let denominator = self.0.params.prime_hash_product(witness_subset);
+    let (quotient, remainder) = numerator.div_rem_euclid(&denominator);
 
     if remainder != int(0) {
       return Err(AccError::InexactDivision);
@@ -344,6 +2106,7 @@ impl<G: UnknownOrderGroup, T: Clone + Hash> Witness<G, T> {
 
     Ok(Self(Accumulator {
       phantom: PhantomData,
+      params: self.0.params,
       value: G::exp(&self.0.value, &quotient),
     }))
   }
@@ -351,44 +2114,36 @@ impl<G: UnknownOrderGroup, T: Clone + Hash> Witness<G, T> {
   /// Given a witness for many `elems`, computes a sub-witness for each individual element in
   /// O(N log N) time.
   pub fn compute_individual_witnesses(&self, elems: &[T]) -> Vec<(T, Self)> {
-    let hashes = elems.iter().map(hash_to_prime).collect::<Vec<_>>();
+    let hashes = elems
+      .iter()
+      .map(|t| self.0.params.hash_to_prime(t))
+      .collect::<Vec<_>>();
     elems
       .iter()
-      .zip(self.root_factor(&hashes).iter())
-      .map(|(x, y)| (x.clone(), y.clone()))
-      .collect()
-  }
-
-  #[allow(non_snake_case)]
-  fn root_factor(&self, elems: &[Integer]) -> Vec<Self> {
-    if elems.len() == 1 {
-      return vec![self.clone()];
-    }
-    let half_n = elems.len() / 2;
-    let g_l = elems[..half_n].iter().fold(self.clone(), |sum, x| {
-      Self(Accumulator {
-        phantom: PhantomData,
-        value: G::exp(&sum.0.value, x),
-      })
-    });
-    let g_r = elems[half_n..].iter().fold(self.clone(), |sum, x| {
-      Self(Accumulator {
-        phantom: PhantomData,
-        value: G::exp(&sum.0.value, x),
+      .zip(root_factor::<G>(&self.0.value, &hashes))
+      .map(|(x, value)| {
+        (
+          x.clone(),
+          Self(Accumulator {
+            phantom: PhantomData,
+            params: self.0.params,
+            value,
+          }),
+        )
       })
-    });
-    let mut L = g_r.root_factor(&Vec::from(&elems[..half_n]));
-    let mut R = g_l.root_factor(&Vec::from(&elems[half_n..]));
-    L.append(&mut R);
-    L
+      .collect()
   }
 }
 
 #[cfg(test)]
 mod tests {
   use super::*;
-//  use crate::group::{ClassGroup, Rsa2048};
-  use crate::group::{Rsa2048};
+  use crate::group::{ClassGroup, Group, Rsa2048};
+  use crate::hash::verify_hash_to_prime;
+  use crate::proof::DecodeError;
+  use crate::util::{prime_hash_product, to_hex};
+  use std::collections::BTreeMap;
+  use std::slice;
 
   fn new_acc<G: UnknownOrderGroup, T: Hash + Eq>(data: &[T]) -> Accumulator<G, T> {
     Accumulator::<G, T>::empty().add(data)
@@ -404,7 +2159,6 @@ mod tests {
         $test_func::<Rsa2048>();
       }
 
-/*
       #[test]
       $(
         #[$attr]
@@ -412,7 +2166,6 @@ mod tests {
       fn $func_name_class() {
         $test_func::<ClassGroup>();
       }
-*/
     };
   }
 
@@ -429,6 +2182,22 @@ mod tests {
     assert!(acc_new.verify_membership_batch(&new_elems, &proof));
   }
 
+  #[cfg(feature = "parallel")]
+  test_all_groups!(
+    test_add_parallel,
+    test_add_parallel_rsa2048,
+    test_add_parallel_class,
+  );
+  #[cfg(feature = "parallel")]
+  fn test_add_parallel<G: UnknownOrderGroup>() {
+    let elems: Vec<u64> = (0..600).collect();
+    let acc = new_acc::<G, u64>(&[1000]);
+    let (acc_new, proof) = acc.clone().add_parallel_with_proof(&elems);
+    assert!(acc_new == acc.clone().add(&elems));
+    assert!(acc_new.verify_membership_batch(&elems, &proof));
+    assert!(acc.clone().add_parallel(&elems[..3]) == acc.add(&elems[..3]));
+  }
+
   test_all_groups!(test_delete, test_delete_rsa2048, test_delete_class,);
   fn test_delete<G: UnknownOrderGroup>() {
     let acc_0 = new_acc::<G, &'static str>(&["a", "b"]);
@@ -469,6 +2238,759 @@ mod tests {
     acc.delete(&[("a", a_witness), ("b", b_witness)]).unwrap();
   }
 
+  test_all_groups!(
+    test_delete_batch_lenient,
+    test_delete_batch_lenient_rsa2048,
+    test_delete_batch_lenient_class,
+  );
+  fn test_delete_batch_lenient<G: UnknownOrderGroup>() {
+    let acc = new_acc::<G, &'static str>(&["a", "b", "c"]);
+    let a_witness = Witness(new_acc::<G, &'static str>(&["b", "c"]));
+    let c_witness = Witness(new_acc::<G, &'static str>(&["a", "b"]));
+    let (deleted, failures) = acc.clone().delete_batch_lenient(&[
+      ("a", a_witness.clone()),
+      ("b", a_witness.clone()),
+      ("d", c_witness.clone()),
+      ("c", c_witness),
+      ("a", a_witness),
+    ]);
+    assert!(deleted == new_acc(&["b"]));
+    let failures = failures
+      .into_iter()
+      .map(|(i, e)| (i, matches!(e, AccError::DuplicateElement)))
+      .collect::<Vec<_>>();
+    assert_eq!(failures, vec![(1, false), (2, false), (4, true)]);
+
+    let (unchanged, failures) = acc.clone().delete_batch_lenient(&[]);
+    assert!(unchanged == acc);
+    assert!(failures.is_empty());
+  }
+
+  test_all_groups!(
+    test_delete_with_state,
+    test_delete_with_state_rsa2048,
+    test_delete_with_state_class,
+  );
+  fn test_delete_with_state<G: UnknownOrderGroup>() {
+    let mut state = ExponentState::<G, &'static str>::empty();
+    state.add(&["a", "b", "c"]);
+    let acc = new_acc::<G, &'static str>(&["a", "b", "c"]);
+    let proof = acc
+      .prove_membership_with_state(&state, &["a", "c"])
+      .expect("valid proof expected");
+    assert!(acc.verify_membership_batch(&["a", "c"], &proof));
+    let acc = acc
+      .delete_with_state(&mut state, &["b"])
+      .expect("valid delete expected");
+    assert!(acc == new_acc::<G, &'static str>(&["a", "c"]));
+    assert!(*state.product() == prime_hash_product(&["a", "c"]));
+  }
+
+  test_all_groups!(
+    test_delete_unchecked_with_set,
+    test_delete_unchecked_with_set_rsa2048,
+    test_delete_unchecked_with_set_class,
+  );
+  fn test_delete_unchecked_with_set<G: UnknownOrderGroup>() {
+    let acc = new_acc::<G, &'static str>(&["a", "b", "c"]);
+    let deleted = acc
+      .clone()
+      .delete_unchecked_with_set(&"b", &["a", "c"])
+      .unwrap();
+    assert!(deleted == new_acc(&["a", "c"]));
+    assert!(acc.clone().delete_unchecked_with_set(&"b", &["a"]).is_err());
+    assert!(acc
+      .delete_unchecked_with_set(&"d", &["a", "b", "c"])
+      .is_err());
+  }
+
+  test_all_groups!(
+    test_delete_with_state_untracked,
+    test_delete_with_state_untracked_rsa2048,
+    test_delete_with_state_untracked_class,
+    should_panic(expected = "InexactDivision")
+  );
+  fn test_delete_with_state_untracked<G: UnknownOrderGroup>() {
+    let mut state = ExponentState::<G, &'static str>::empty();
+    state.add(&["a", "b"]);
+    let acc = new_acc::<G, &'static str>(&["a", "b"]);
+    acc.delete_with_state(&mut state, &["c"]).unwrap();
+  }
+
+  #[test]
+  fn test_exponent_state_order_multiple() {
+    // `6` is not a multiple of the RSA-2048 group order; this only checks that reduction happens.
+    let mut state = ExponentState::<Rsa2048, &'static str>::empty();
+    state.set_order_multiple(int(6));
+    state.add(&["a", "b"]);
+    let witness = state.witness(&["a"]).unwrap();
+    let quotient = prime_hash_product(&["b"]);
+    assert!(witness.0.value == Rsa2048::exp(&Rsa2048::unknown_order_elem(), &(quotient % 6)));
+  }
+
+  #[test]
+  #[should_panic(expected = "order multiple must be positive")]
+  fn test_exponent_state_zero_order_multiple() {
+    ExponentState::<Rsa2048, &'static str>::empty().set_order_multiple(int(0));
+  }
+
+  test_all_groups!(
+    test_compute_all_witnesses,
+    test_compute_all_witnesses_rsa2048,
+    test_compute_all_witnesses_class,
+  );
+  fn test_compute_all_witnesses<G: UnknownOrderGroup>() {
+    let set = ["a", "b", "c", "d", "e"];
+    let acc = new_acc::<G, &'static str>(&set);
+    let mut state = ExponentState::<G, &'static str>::empty();
+    state.add(&set);
+    let witnesses = state.compute_all_witnesses(&set).unwrap();
+    assert_eq!(witnesses.len(), set.len());
+    for (t, witness) in set.iter().zip(witnesses) {
+      assert!(witness.0.add(slice::from_ref(t)) == acc);
+    }
+    assert!(state.compute_all_witnesses(&set[1..]).is_err());
+  }
+
+  #[test]
+  fn test_compute_all_witnesses_order_multiple() {
+    // As above, `6` only checks that witness exponents are reduced.
+    let mut state = ExponentState::<Rsa2048, &'static str>::empty();
+    state.set_order_multiple(int(6));
+    state.add(&["a", "b", "c"]);
+    let witnesses = state.compute_all_witnesses(&["a", "b", "c"]).unwrap();
+    let quotient = prime_hash_product(&["a", "c"]);
+    assert!(witnesses[1].0.value == Rsa2048::exp(&Rsa2048::unknown_order_elem(), &(quotient % 6)));
+  }
+
+  test_all_groups!(
+    test_initialization,
+    test_initialization_rsa2048,
+    test_initialization_class,
+  );
+  fn test_initialization<G: UnknownOrderGroup>() {
+    let (acc, attestation) = Accumulator::<G, &'static str>::empty_with_attestation();
+    assert!(acc.verify_initialization(&attestation));
+    // Neither group derives its element of unknown order from a digest.
+    assert!(!acc.verify_initialization(&InitAttestation::DerivedGenerator([0; 32])));
+    assert!(!acc.add(&["a"]).verify_initialization(&attestation));
+  }
+
+  test_all_groups!(
+    test_initial_exponent,
+    test_initial_exponent_rsa2048,
+    test_initial_exponent_class,
+  );
+  fn test_initial_exponent<G: UnknownOrderGroup>() {
+    let r = int(1) << 1000u32 | int(12345);
+    let (acc, attestation) = Accumulator::<G, &'static str>::from_initial_exponent(&r);
+    assert!(acc.verify_initialization(&attestation));
+    assert!(acc.verify_initialization(&InitAttestation::PublishedExponent(r.clone())));
+    assert!(!acc.verify_initialization(&InitAttestation::Standard));
+    assert!(!Accumulator::<G, &'static str>::empty().verify_initialization(&attestation));
+
+    let mut state = ExponentState::with_initial_exponent(acc.params(), &r);
+    state.add(&["a", "b"]);
+    let acc = acc.add(&["a", "b"]);
+    let witness = state.witness(&["a"]).unwrap();
+    let proof = acc.prove_membership(&[("a", witness)]).unwrap();
+    assert!(acc.verify_membership(&"a", &proof));
+  }
+
+  test_all_groups!(
+    test_prime_bits,
+    test_prime_bits_rsa2048,
+    test_prime_bits_class,
+  );
+  fn test_prime_bits<G: UnknownOrderGroup>() {
+    let acc = Accumulator::<G, &'static str>::with_params(PrimeBits::B128).add(&["a", "b"]);
+    let witness = Witness(Accumulator::with_params(PrimeBits::B128).add(&["b"]));
+    let proof = acc.prove_membership(&[("a", witness.clone())]).unwrap();
+    assert!(acc.verify_membership(&"a", &proof));
+
+    // The same set accumulated with the default prime size rejects the proof.
+    let default_acc = new_acc::<G, &'static str>(&["a", "b"]);
+    assert!(!default_acc.verify_membership(&"a", &proof));
+    assert!(default_acc.prove_membership(&[("a", witness)]).is_err());
+  }
+
+  #[test]
+  fn test_division_intractable() {
+    let params = PrimeParams {
+      bits: PrimeBits::B256,
+      hash: PrimeHash::DivisionIntractable,
+    };
+    let acc = Accumulator::<Rsa2048, &'static str>::with_params(params).add(&["a", "b", "c"]);
+    let witness = Witness(Accumulator::with_params(params).add(&["b", "c"]));
+    let proof = acc.prove_membership(&[("a", witness.clone())]).unwrap();
+    assert!(acc.verify_membership(&"a", &proof));
+    assert!(acc.verify_membership(&"a", &proof.clone().with_nonces(&["a"])));
+    assert!(!acc.verify_membership(&"b", &proof));
+    assert!(!new_acc::<Rsa2048, &'static str>(&["a", "b", "c"]).verify_membership(&"a", &proof));
+
+    let deleted = acc.clone().delete(&[("a", witness)]).unwrap();
+    assert!(deleted == Accumulator::with_params(params).add(&["b", "c"]));
+    let decoded = Accumulator::<Rsa2048, &'static str>::from_bytes(&acc.to_bytes()).unwrap();
+    assert!(decoded.params() == params);
+  }
+
+  #[cfg(feature = "poseidon")]
+  #[test]
+  fn test_poseidon() {
+    let params = PrimeParams {
+      bits: PrimeBits::B256,
+      hash: PrimeHash::Poseidon,
+    };
+    let acc = Accumulator::<Rsa2048, &'static str>::with_params(params).add(&["a", "b"]);
+    let witness = Witness(Accumulator::with_params(params).add(&["b"]));
+    let proof = acc.prove_membership(&[("a", witness)]).unwrap();
+    assert!(acc.verify_membership(&"a", &proof));
+    assert!(!new_acc::<Rsa2048, &'static str>(&["a", "b"]).verify_membership(&"a", &proof));
+    let decoded = Accumulator::<Rsa2048, &'static str>::from_bytes(&acc.to_bytes()).unwrap();
+    assert!(decoded.params() == params);
+  }
+
+  test_all_groups!(
+    test_from_value,
+    test_from_value_rsa2048,
+    test_from_value_class,
+  );
+  fn test_from_value<G: UnknownOrderGroup>() {
+    let acc = new_acc::<G, &'static str>(&["a", "b"]);
+    let restored = Accumulator::<G, &'static str>::from_value(acc.value().clone()).unwrap();
+    assert!(restored == acc);
+    let restored =
+      Accumulator::<G, &'static str>::from_value_with_params(PrimeBits::B128, acc.value().clone())
+        .unwrap();
+    assert!(restored.prime_bits() == PrimeBits::B128);
+    assert!(Accumulator::<G, &'static str>::from_value(G::id()).is_err());
+  }
+
+  #[test]
+  fn test_to_bytes() {
+    let acc = Accumulator::<Rsa2048, &'static str>::with_params(PrimeBits::B192).add(&["a"]);
+    let bytes = acc.to_bytes();
+    let decoded = Accumulator::<Rsa2048, &'static str>::from_bytes(&bytes).unwrap();
+    assert!(decoded == acc);
+    assert!(decoded.prime_bits() == PrimeBits::B192);
+
+    let mut bad_prime_bits = bytes.clone();
+    bad_prime_bits[0] = 17;
+    let mut bad_hash = bytes.clone();
+    bad_hash[1] = 0xff;
+    assert!(Accumulator::<Rsa2048, &'static str>::from_bytes(&bad_hash).is_err());
+    assert!(Accumulator::<Rsa2048, &'static str>::from_bytes(&bad_prime_bits).is_err());
+    assert!(Accumulator::<Rsa2048, &'static str>::from_bytes(&bytes[..100]).is_err());
+    assert!(Accumulator::<Rsa2048, &'static str>::from_bytes(&[]).is_err());
+  }
+
+  #[test]
+  fn test_proof_bytes() {
+    let acc = new_acc::<Rsa2048, &'static str>(&["a", "b"]);
+    let witness = Witness(new_acc::<Rsa2048, &'static str>(&["b"]));
+    let proof = acc.prove_membership(&[("a", witness)]).unwrap();
+    let bytes = proof.to_bytes();
+    assert!(MembershipProof::from_bytes(&bytes).unwrap() == proof);
+    let proof = proof.with_nonces(&["a"]);
+    let bytes = proof.to_bytes();
+    let decoded = MembershipProof::from_bytes(&bytes).unwrap();
+    assert!(decoded == proof);
+    assert!(acc.verify_membership(&"a", &decoded));
+
+    let mut extra = bytes.clone();
+    extra.push(0);
+    let mut bad_flag = bytes.clone();
+    bad_flag[bytes.len() - 17] = 2;
+    let mut long_count = bytes.clone();
+    long_count[bytes.len() - 16] = 1;
+    let decode = MembershipProof::<Rsa2048, &'static str>::from_bytes;
+    assert_eq!(decode(&extra), Err(DecodeError::TrailingBytes));
+    assert_eq!(decode(&bad_flag), Err(DecodeError::BadTag));
+    assert_eq!(decode(&long_count), Err(DecodeError::Truncated));
+    assert_eq!(decode(&bytes[..300]), Err(DecodeError::Truncated));
+
+    let proof = acc.prove_nonmembership(&["a", "b"], &["c"]).unwrap();
+    let bytes = proof.to_bytes();
+    let decoded = NonmembershipProof::from_bytes(&bytes).unwrap();
+    assert!(acc.verify_nonmembership(&["c"], &decoded));
+    let mut zero_d = bytes.clone();
+    for byte in &mut zero_d[34..290] {
+      *byte = 0;
+    }
+    assert_eq!(
+      NonmembershipProof::<Rsa2048, &'static str>::from_bytes(&zero_d),
+      Err(DecodeError::BadElement)
+    );
+  }
+
+  test_all_groups!(
+    test_add_with_witnesses,
+    test_add_with_witnesses_rsa2048,
+    test_add_with_witnesses_class,
+  );
+  fn test_add_with_witnesses<G: UnknownOrderGroup>() {
+    let acc = new_acc::<G, &'static str>(&["a", "b"]);
+    let new_elems = ["c", "d", "e"];
+    let (new_acc, proof, witnesses) = acc.clone().add_with_witnesses(&new_elems);
+    assert!(new_acc == acc.clone().add(&new_elems));
+    assert!(new_acc.verify_membership_batch(&new_elems, &proof));
+    assert_eq!(witnesses.len(), new_elems.len());
+    for (t, witness) in new_elems.iter().zip(witnesses) {
+      assert!(witness.0.add(&[*t]) == new_acc);
+    }
+
+    let (same_acc, proof, witnesses) = acc.clone().add_with_witnesses(&[]);
+    assert!(same_acc == acc);
+    assert!(acc.verify_membership_batch(&[], &proof));
+    assert!(witnesses.is_empty());
+  }
+
+  test_all_groups!(test_ref_ops, test_ref_ops_rsa2048, test_ref_ops_class,);
+  fn test_ref_ops<G: UnknownOrderGroup>() {
+    let acc = new_acc::<G, &'static str>(&["a", "b"]);
+    let added = acc.add_ref(&["c"]);
+    assert!(added == acc.clone().add(&["c"]));
+    let witness = Witness(new_acc(&["a", "b"]));
+    let deleted = added.delete_ref(&[("c", witness.clone())]).unwrap();
+    assert!(deleted == acc);
+    assert!(added.delete_ref(&[("d", witness)]).is_err());
+
+    let mut state = ExponentState::empty();
+    state.add(&["a", "b", "c"]);
+    assert!(added.delete_with_state_ref(&mut state, &["a"]).unwrap() == new_acc(&["b", "c"]));
+    assert!(added == new_acc(&["a", "b", "c"]));
+  }
+
+  #[test]
+  fn test_verify_membership_detailed() {
+    let acc = new_acc::<Rsa2048, &'static str>(&["a", "b", "c"]);
+    let proof = acc
+      .prove_membership(&[
+        ("a", Witness(new_acc(&["b", "c"]))),
+        ("b", Witness(new_acc(&["a", "c"]))),
+      ])
+      .unwrap();
+    let detailed = |elems: &[&'static str], proof: &MembershipProof<Rsa2048, &'static str>| {
+      acc.verify_membership_detailed(elems, proof)
+    };
+    assert!(detailed(&["a", "b"], &proof).is_valid());
+
+    // A witness for "a" and "b", presented for "d" too.
+    assert_eq!(
+      detailed(&["a", "d", "b"], &proof).failures,
+      vec![MembershipFailure::Absent(1)]
+    );
+    assert_eq!(
+      detailed(&["a"], &proof).failures,
+      vec![MembershipFailure::BadWitness]
+    );
+    assert_eq!(
+      detailed(&["b", "a", "b"], &proof).failures,
+      vec![MembershipFailure::Duplicate(2)]
+    );
+
+    let other_proof = acc
+      .prove_membership(&[("c", Witness(new_acc(&["a", "b"])))])
+      .unwrap();
+    let (witness, _, nonces) = proof.clone().into_parts();
+    let (_, other_poe, _) = other_proof.into_parts();
+    let bad_poe =
+      MembershipProofRef::from_parts(&witness, &other_poe, nonces.as_deref()).into_owned();
+    assert_eq!(
+      detailed(&["a", "b"], &bad_poe).failures,
+      vec![MembershipFailure::BadPoe]
+    );
+
+    let with_nonces = proof.clone().with_nonces(&["a", "b"]);
+    assert!(detailed(&["a", "b"], &with_nonces).is_valid());
+    assert_eq!(
+      detailed(&["a"], &with_nonces).failures,
+      vec![MembershipFailure::NonceCount]
+    );
+    let (witness, poe, _) = with_nonces.into_parts();
+    let bad_nonces = [0, 0];
+    let bad_nonce_proof =
+      MembershipProofRef::from_parts(&witness, &poe, Some(&bad_nonces)).into_owned();
+    assert!(detailed(&["a", "b"], &bad_nonce_proof)
+      .failures
+      .iter()
+      .all(|failure| matches!(failure, MembershipFailure::BadNonce(_))));
+
+    let other = Accumulator::<Rsa2048, &'static str>::with_params(PrimeBits::B128).add(&["a", "b"]);
+    assert_eq!(
+      other
+        .verify_membership_detailed(&["a", "b"], &proof)
+        .failures,
+      vec![MembershipFailure::ParameterMismatch]
+    );
+  }
+
+  #[test]
+  fn test_verify_with_history() {
+    let old = new_acc::<Rsa2048, &'static str>(&["a", "b"]);
+    let proof = old
+      .prove_membership(&[("a", Witness(new_acc(&["b"])))])
+      .unwrap();
+    let nonmembership = old.prove_nonmembership(&["a", "b"], &["c"]).unwrap();
+    let new = old.clone().add(&["c"]);
+    let history = [new_acc(&[]), old];
+
+    match new.verify_membership_with_history(&history, &["a"], &proof) {
+      Err(AccError::StaleState) => (),
+      _ => panic!("expected StaleState"),
+    }
+    assert_eq!(
+      new
+        .verify_membership_with_history(&[], &["a"], &proof)
+        .unwrap()
+        .failures,
+      vec![MembershipFailure::BadWitness]
+    );
+    assert_eq!(
+      new
+        .verify_membership_with_history(&history, &["b"], &proof)
+        .unwrap()
+        .failures,
+      vec![MembershipFailure::BadWitness]
+    );
+    let proof = new
+      .prove_membership(&[("c", Witness(new_acc(&["a", "b"])))])
+      .unwrap();
+    assert!(new
+      .verify_membership_with_history(&history, &["c"], &proof)
+      .unwrap()
+      .is_valid());
+
+    match new.verify_nonmembership_with_history(&history, &["c"], &nonmembership) {
+      Err(AccError::StaleState) => (),
+      _ => panic!("expected StaleState"),
+    }
+    assert!(!new
+      .verify_nonmembership_with_history(&history, &["d"], &nonmembership)
+      .unwrap());
+  }
+
+  #[test]
+  fn test_proof_params() {
+    let acc = new_acc::<Rsa2048, &'static str>(&["a", "b"]);
+    let witness = Witness(new_acc::<Rsa2048, &'static str>(&["b"]));
+    let proof = acc.prove_membership(&[("a", witness)]).unwrap();
+    let bytes = proof.to_bytes();
+    assert!(bytes[..32] == state::proof_params_digest::<Rsa2048>(acc.params()));
+    let mut bad_digest = bytes.clone();
+    bad_digest[0] ^= 1;
+    assert_eq!(
+      MembershipProof::<Rsa2048, &'static str>::from_bytes(&bad_digest),
+      Err(DecodeError::ParameterMismatch)
+    );
+    assert!(acc.verify_membership_checked(&["a"], &proof).unwrap());
+    assert!(!acc.verify_membership_checked(&["b"], &proof).unwrap());
+
+    let other = Accumulator::<Rsa2048, &'static str>::with_params(PrimeBits::B128).add(&["a", "b"]);
+    match other.verify_membership_checked(&["a"], &proof) {
+      Err(AccError::ParameterMismatch) => (),
+      _ => panic!("expected ParameterMismatch"),
+    }
+
+    let proof = acc.prove_nonmembership(&["a", "b"], &["c"]).unwrap();
+    let mut bad_params = proof.to_bytes();
+    bad_params[32] = (PrimeBits::B128.bits() / 8) as u8;
+    assert_eq!(
+      NonmembershipProof::<Rsa2048, &'static str>::from_bytes(&bad_params),
+      Err(DecodeError::ParameterMismatch)
+    );
+    assert!(acc.verify_nonmembership_checked(&["c"], &proof).unwrap());
+    match other.verify_nonmembership_checked(&["c"], &proof) {
+      Err(AccError::ParameterMismatch) => (),
+      _ => panic!("expected ParameterMismatch"),
+    }
+  }
+
+  #[test]
+  fn test_display_debug_ord() {
+    let accs = (0..5)
+      .map(|i| new_acc::<Rsa2048, u64>(&[i]))
+      .collect::<Vec<_>>();
+    let map = accs
+      .iter()
+      .enumerate()
+      .map(|(i, acc)| (acc.clone(), i))
+      .collect::<BTreeMap<_, _>>();
+    let mut encodings = accs.iter().map(|acc| acc.to_bytes()).collect::<Vec<_>>();
+    encodings.sort();
+    assert!(map
+      .keys()
+      .map(|acc| acc.to_bytes())
+      .eq(encodings.into_iter()));
+    assert_eq!(map[&accs[3]], 3);
+
+    let acc = &accs[0];
+    assert_eq!(acc.to_string(), to_hex(&acc.to_bytes()));
+    let debug = format!("{:?}", acc);
+    assert!(debug.starts_with("Accumulator { params: "));
+    assert!(debug.len() < 200);
+    let proof = acc.prove_nonmembership(&[0], &[1]).unwrap();
+    assert!(format!("{:?}", proof).len() < 1000);
+    assert_eq!(proof.to_string(), to_hex(&proof.to_bytes()));
+  }
+
+  test_all_groups!(
+    test_aggregate_nonmembership,
+    test_aggregate_nonmembership_rsa2048,
+    test_aggregate_nonmembership_class,
+  );
+  fn test_aggregate_nonmembership<G: UnknownOrderGroup>() {
+    let shard_1_set = ["a", "b"];
+    let shard_2_set = ["c"];
+    let shard_1 = new_acc::<G, &'static str>(&shard_1_set);
+    let shard_2 = new_acc::<G, &'static str>(&shard_2_set);
+    let shards: [(&Accumulator<G, &'static str>, &[&'static str]); 2] =
+      [(&shard_1, &shard_1_set), (&shard_2, &shard_2_set)];
+    let elems = ["d", "e"];
+    let proof = AggregateNonmembershipProof::prove(&shards, &elems).unwrap();
+    assert!(proof.verify(&[&shard_1, &shard_2], &elems));
+    assert!(!proof.verify(&[&shard_2, &shard_1], &elems));
+    assert!(!proof.verify(&[&shard_1], &elems));
+    assert!(!proof.verify(&[&shard_1, &shard_2], &["d", "f"]));
+
+    // The batched PoKE2 check still binds each proof to its own accumulator.
+    let mut swapped = proof;
+    swapped.poke2_proofs.swap(0, 1);
+    assert!(!swapped.verify(&[&shard_1, &shard_2], &elems));
+  }
+
+  test_all_groups!(
+    test_aggregate_nonmembership_member,
+    test_aggregate_nonmembership_member_rsa2048,
+    test_aggregate_nonmembership_member_class,
+    should_panic(expected = "InputsNotCoprime")
+  );
+  fn test_aggregate_nonmembership_member<G: UnknownOrderGroup>() {
+    let shard_1_set = ["a", "b"];
+    let shard_2_set = ["c"];
+    let shard_1 = new_acc::<G, &'static str>(&shard_1_set);
+    let shard_2 = new_acc::<G, &'static str>(&shard_2_set);
+    let shards: [(&Accumulator<G, &'static str>, &[&'static str]); 2] =
+      [(&shard_1, &shard_1_set), (&shard_2, &shard_2_set)];
+    AggregateNonmembershipProof::prove(&shards, &["c", "d"]).unwrap();
+  }
+
+  test_all_groups!(
+    test_add_primes,
+    test_add_primes_rsa2048,
+    test_add_primes_class,
+  );
+  fn test_add_primes<G: UnknownOrderGroup>() {
+    let a = hash_to_prime(&"a");
+    let b = hash_to_prime(&"b");
+    let acc = Accumulator::<G, &'static str>::empty()
+      .add_primes(slice::from_ref(&a))
+      .unwrap();
+    assert!(acc == new_acc(&["a"]));
+    let (acc, proof) = acc.add_primes_with_proof(slice::from_ref(&b)).unwrap();
+    assert!(acc.verify_membership_primes(slice::from_ref(&b), &proof));
+    assert!(acc.verify_membership(&"b", &proof));
+    assert!(!acc.verify_membership_primes(slice::from_ref(&a), &proof));
+
+    // Even, composite and too-small primes are all rejected.
+    let mut too_small = int(int(1) << 100);
+    too_small.next_prime_mut();
+    for bad in &[int(&a + 1), int(&a * &b), too_small] {
+      assert!(Accumulator::<G, &'static str>::empty()
+        .add_primes(slice::from_ref(bad))
+        .is_err());
+    }
+    let bad_product = int(&a * &b);
+    let (acc, proof) = Accumulator::<G, &'static str>::empty().add_with_proof(&["a", "b"]);
+    assert!(!acc.verify_membership_primes(slice::from_ref(&bad_product), &proof));
+  }
+
+  test_all_groups!(
+    test_prove_membership_chunked,
+    test_prove_membership_chunked_rsa2048,
+    test_prove_membership_chunked_class,
+  );
+  fn test_prove_membership_chunked<G: UnknownOrderGroup>() {
+    let set = ["a", "b", "c", "d", "e"];
+    let (acc, state) = AccumulatorBuilder::<G, &'static str>::new()
+      .elems(set.to_vec())
+      .build_with_state();
+    let expected = acc
+      .prove_membership_with_state(&state, &["b", "d"])
+      .unwrap();
+    for &chunk_size in &[0, 1, 2, 10] {
+      let proof = acc
+        .prove_membership_chunked(set.iter().cloned(), &["b", "d"], chunk_size)
+        .unwrap();
+      assert!(proof == expected);
+    }
+    assert!(acc
+      .prove_membership_chunked(set[1..].iter().cloned(), &["b"], 2)
+      .is_err());
+  }
+
+  test_all_groups!(test_compress, test_compress_rsa2048, test_compress_class,);
+  fn test_compress<G: UnknownOrderGroup>() {
+    let old_acc = new_acc::<G, &'static str>(&["a", "b"]);
+    let proof = old_acc
+      .prove_membership(&[("a", Witness(new_acc(&["b"])))])
+      .unwrap();
+    let additions = ["c", "d", "e"];
+    let acc = old_acc.clone().add(&additions);
+    let exp = prime_hash_product(&additions);
+    let compressed = proof.compress(&old_acc, &exp, &acc);
+    assert!(acc.verify_compressed(&["a"], &compressed));
+    assert!(!acc.verify_compressed(&["b"], &compressed));
+    assert!(!old_acc.verify_compressed(&["a"], &compressed));
+
+    // The transition proof does not carry over to an accumulator that `a` was deleted from.
+    let deleted = new_acc::<G, &'static str>(&["b", "c", "d", "e"]);
+    assert!(!deleted.verify_compressed(&["a"], &compressed));
+  }
+
+  test_all_groups!(test_equal_set, test_equal_set_rsa2048, test_equal_set_class,);
+  fn test_equal_set<G: UnknownOrderGroup>() {
+    let (acc, state) = AccumulatorBuilder::<G, &'static str>::new()
+      .elems(vec!["a", "b"])
+      .build_with_state();
+    let rsa_acc = new_acc::<Rsa2048, &'static str>(&["a", "b"]);
+    let proof = acc.prove_equal_set(&rsa_acc, &state).unwrap();
+    assert!(acc.verify_equal_set(&rsa_acc, &proof));
+    assert!(!acc.verify_equal_set(&new_acc::<Rsa2048, _>(&["a"]), &proof));
+    assert!(!acc.verify_equal_set(&Accumulator::with_params(PrimeBits::B128), &proof));
+
+    let other_acc = new_acc::<Rsa2048, &'static str>(&["a", "c"]);
+    assert!(acc.prove_equal_set(&other_acc, &state).is_err());
+  }
+
+  test_all_groups!(test_builder, test_builder_rsa2048, test_builder_class,);
+  fn test_builder<G: UnknownOrderGroup>() {
+    let acc = AccumulatorBuilder::<G, &'static str>::new()
+      .elems(vec!["a", "b"])
+      .build();
+    assert!(acc == new_acc(&["a", "b"]));
+
+    let (acc, state) = AccumulatorBuilder::<G, &'static str>::new()
+      .prime_bits(PrimeBits::B128)
+      .elems(vec!["a", "b"])
+      .build_with_state();
+    assert!(acc == Accumulator::with_params(PrimeBits::B128).add(&["a", "b"]));
+    let proof = acc.prove_membership_with_state(&state, &["a"]).unwrap();
+    assert!(acc.verify_membership(&"a", &proof));
+  }
+
+  #[test]
+  fn test_builder_storage() {
+    let builder = || {
+      AccumulatorBuilder::<Rsa2048, &'static str>::new()
+        .prime_bits(PrimeBits::B128)
+        .elems(vec!["a", "b"])
+    };
+    let acc = builder().build();
+    let state = builder().elem_version(3).build_state();
+    assert!(*state.accumulator() == acc);
+    assert_eq!(state.elem_version(), 3);
+
+    let snapshot = builder().build_snapshot();
+    assert!(snapshot.acc == acc);
+    let witnesses = snapshot.witnesses.unwrap();
+    assert_eq!(witnesses.len(), 2);
+    for (elem, witness) in ["a", "b"].iter().zip(witnesses) {
+      let proof = acc.prove_membership(&[(*elem, witness)]).unwrap();
+      assert!(acc.verify_membership(elem, &proof));
+    }
+  }
+
+  #[cfg(feature = "parallel")]
+  #[test]
+  fn test_builder_parallel() {
+    let elems = (0..64).collect::<Vec<u64>>();
+    let acc = AccumulatorBuilder::<Rsa2048, u64>::new()
+      .elems(elems.clone())
+      .parallel()
+      .build();
+    assert!(acc == Accumulator::<Rsa2048, u64>::empty().add(&elems));
+  }
+
+  #[test]
+  fn test_membership_proof_ref() {
+    let acc = new_acc::<Rsa2048, &'static str>(&["a"]);
+    let (acc, proof) = acc.add_with_proof(&["b", "c"]);
+    let proof = proof.with_nonces(&["b", "c"]);
+    assert!(acc.verify_membership_ref(&["b", "c"], proof.borrowed()));
+    assert!(proof.borrowed().into_owned() == proof);
+
+    // Proofs sharing a witness, reassembled from a single stored copy of it.
+    let (witness, poe, nonces) = proof.into_parts();
+    let with_nonces = MembershipProofRef::from_parts(&witness, &poe, nonces.as_deref());
+    let without_nonces = MembershipProofRef::from_parts(&witness, &poe, None);
+    assert!(acc.verify_membership_ref(&["b", "c"], with_nonces));
+    assert!(acc.verify_membership_ref(&["b", "c"], without_nonces));
+    assert!(!acc.verify_membership_ref(&["b"], without_nonces));
+    assert!(*with_nonces.witness() == witness);
+  }
+
+  test_all_groups!(test_merge, test_merge_rsa2048, test_merge_class,);
+  fn test_merge<G: UnknownOrderGroup>() {
+    let acc = new_acc::<G, &'static str>(&["a", "b", "c", "d"]);
+    let a_proof = acc
+      .prove_membership(&[("a", Witness(new_acc(&["b", "c", "d"])))])
+      .unwrap();
+    let bc_proof = acc
+      .prove_membership(&[
+        ("b", Witness(new_acc(&["a", "c", "d"]))),
+        ("c", Witness(new_acc(&["a", "b", "d"]))),
+      ])
+      .unwrap();
+    let proof = MembershipProof::merge(&[a_proof, bc_proof], &[&["a"], &["b", "c"]]).unwrap();
+    assert!(acc.verify_membership_batch(&["a", "b", "c"], &proof));
+    assert!(proof.witness == Witness(new_acc(&["d"])));
+  }
+
+  test_all_groups!(
+    test_membership_nonces,
+    test_membership_nonces_rsa2048,
+    test_membership_nonces_class,
+  );
+  fn test_membership_nonces<G: UnknownOrderGroup>() {
+    let acc = new_acc::<G, &'static str>(&["a", "b", "c"]);
+    let proof = acc
+      .prove_membership(&[
+        ("a", Witness(new_acc(&["b", "c"]))),
+        ("b", Witness(new_acc(&["a", "c"]))),
+      ])
+      .unwrap()
+      .with_nonces(&["a", "b"]);
+    let nonces = proof.nonces().unwrap();
+    assert!(verify_hash_to_prime(&"a", nonces[0], &hash_to_prime(&"a")));
+    assert!(acc.verify_membership_batch(&["a", "b"], &proof));
+    assert!(!acc.verify_membership_batch(&["b", "a"], &proof));
+    assert!(!acc.verify_membership_batch(&["a"], &proof));
+
+    let c_proof = acc
+      .prove_membership(&[("c", Witness(new_acc(&["a", "b"])))])
+      .unwrap()
+      .with_nonces(&["c"]);
+    assert!(acc.verify_membership(&"c", &c_proof));
+    let merged = MembershipProof::merge(&[proof, c_proof], &[&["a", "b"], &["c"]]).unwrap();
+    assert_eq!(merged.nonces().map(<[u64]>::len), Some(3));
+    assert!(acc.verify_membership_batch(&["a", "b", "c"], &merged));
+  }
+
+  test_all_groups!(
+    test_merge_overlapping,
+    test_merge_overlapping_rsa2048,
+    test_merge_overlapping_class,
+    should_panic(expected = "InputsNotCoprime")
+  );
+  fn test_merge_overlapping<G: UnknownOrderGroup>() {
+    let acc = new_acc::<G, &'static str>(&["a", "b"]);
+    let a_proof = acc
+      .prove_membership(&[("a", Witness(new_acc(&["b"])))])
+      .unwrap();
+    MembershipProof::merge(&[a_proof.clone(), a_proof], &[&["a"], &["a"]]).unwrap();
+  }
+
   test_all_groups!(
     test_update_membership_witness,
     test_update_membership_witness_rsa2048,
@@ -483,6 +3005,29 @@ mod tests {
     assert!(witness_new.0.add(&["a"]) == acc);
   }
 
+  test_all_groups!(
+    test_update_membership_witnesses,
+    test_update_membership_witnesses_rsa2048,
+    test_update_membership_witnesses_class,
+  );
+  fn test_update_membership_witnesses<G: UnknownOrderGroup>() {
+    let old_acc = new_acc::<G, &'static str>(&["a", "b", "c", "d"]);
+    let acc = new_acc::<G, &'static str>(&["a", "b", "c", "e"]);
+    let elem_witnesses = [
+      ("a", Witness(new_acc::<G, &'static str>(&["b", "c", "d"]))),
+      ("b", Witness(new_acc::<G, &'static str>(&["a", "c", "d"]))),
+    ];
+    assert!(elem_witnesses[0].1.clone().0.add(&["a"]) == old_acc);
+    let witnesses = acc
+      .update_membership_witnesses(&elem_witnesses, &["e"], &["d"])
+      .unwrap();
+    assert!(witnesses[0].0.clone().add(&["a"]) == acc);
+    assert!(witnesses[1].0.clone().add(&["b"]) == acc);
+    assert!(acc
+      .update_membership_witnesses(&elem_witnesses, &["e"], &["a"])
+      .is_err());
+  }
+
   test_all_groups!(
     test_update_membership_witness_failure,
     test_update_membership_witness_failure_rsa2048,
@@ -512,6 +3057,45 @@ mod tests {
     assert!(acc.verify_nonmembership(&non_members, &proof));
   }
 
+  #[test]
+  fn test_nonmembership_forged_gv_inv() {
+    // `d^x = gv_inv` holds for any `d` when `gv_inv` is not tied to `v`, which would let a member be
+    // proven a nonmember.
+    let acc = new_acc::<Rsa2048, &'static str>(&["a", "b"]);
+    let x = prime_hash_product(&["a"]);
+    let d = Rsa2048::exp(&Rsa2048::unknown_order_elem(), &int(7));
+    let gv_inv = Rsa2048::exp(&d, &x);
+    let v = Rsa2048::exp(&acc.value, &int(3));
+    let forged = NonmembershipProof {
+      phantom: PhantomData,
+      params: acc.params,
+      poke2_proof: Poke2::prove(&acc.value, &int(3), &v),
+      poe_proof: Poe::prove(&d, &x, &gv_inv),
+      d,
+      v,
+      gv_inv,
+    };
+    assert!(!acc.verify_nonmembership(&["a"], &forged));
+  }
+
+  #[cfg(feature = "verbose_proof")]
+  test_all_groups!(
+    test_prove_nonmembership_verbose,
+    test_prove_nonmembership_verbose_rsa2048,
+    test_prove_nonmembership_verbose_class,
+  );
+  #[cfg(feature = "verbose_proof")]
+  fn test_prove_nonmembership_verbose<G: UnknownOrderGroup>() {
+    let acc_set = ["a", "b"];
+    let acc = new_acc::<G, &'static str>(&acc_set);
+    let proof = acc
+      .prove_nonmembership_verbose(&acc_set, &["c", "d"])
+      .unwrap();
+    assert!(acc.verify_nonmembership_verbose(&["c", "d"], &proof));
+    assert!(!acc.verify_nonmembership_verbose(&["c"], &proof));
+    assert!(acc.prove_nonmembership_verbose(&acc_set, &["a"]).is_err());
+  }
+
   test_all_groups!(
     test_compute_sub_witness,
     test_compute_sub_witness_rsa2048,
@@ -0,0 +1,208 @@
+//! Append-only audit logs of accumulator state transitions, enabled with the `audit` feature.
+//!
+//! Each `AuditRecord` describes one mutation: whether elements were added or deleted, the primes
+//! they hashed to, the accumulator before and after, and a digest of the NI-PoE proving the
+//! transition. `RevocationRegistry` and `Bridge` keep a log of every mutation they make; auditors
+//! check a log with `AuditLog::replay`, which needs nothing but the log and the genesis value.
+use crate::accumulator::Accumulator;
+use crate::group::UnknownOrderGroup;
+use crate::hash::{hash, Blake2b};
+use crate::integer::Integer;
+use crate::proof::Poe;
+use std::hash::Hash;
+
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+/// The kinds of accumulator mutations.
+pub enum AuditOperation {
+  /// Elements were added.
+  Add,
+  /// Elements were deleted.
+  Delete,
+}
+
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+/// The ways replaying an audit log can fail. Each variant holds the index of the offending record.
+pub enum AuditError {
+  /// The record does not start from the accumulator the previous record (or genesis) ended with.
+  BrokenChain(usize),
+  /// The record's primes do not take one of its accumulators to the other.
+  BadTransition(usize),
+  /// The record's proof digest does not match the proof of its transition.
+  BadProofDigest(usize),
+}
+
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+/// A single accumulator mutation.
+pub struct AuditRecord<G: UnknownOrderGroup> {
+  /// Whether the elements were added or deleted.
+  pub operation: AuditOperation,
+  /// The primes the added or deleted elements hashed to.
+  pub primes: Vec<Integer>,
+  /// The accumulator before the mutation.
+  pub old: G::Elem,
+  /// The accumulator after the mutation.
+  pub new: G::Elem,
+  /// The Blake2b digest of the NI-PoE that the smaller accumulator raised to the product of
+  /// `primes` is the larger one.
+  pub proof_digest: [u8; 32],
+}
+
+impl<G: UnknownOrderGroup> AuditRecord<G> {
+  fn new(operation: AuditOperation, primes: Vec<Integer>, old: G::Elem, new: G::Elem) -> Self {
+    let mut record = Self {
+      operation,
+      primes,
+      old,
+      new,
+      proof_digest: [0; 32],
+    };
+    let (base, result) = record.transition();
+    let exp = record.primes.iter().product();
+    record.proof_digest = hash(&Blake2b::default, &Poe::<G>::prove(base, &exp, result));
+    record
+  }
+
+  // Returns `(base, result)` such that `base` raised to the product of the primes is `result`.
+  fn transition(&self) -> (&G::Elem, &G::Elem) {
+    match self.operation {
+      AuditOperation::Add => (&self.old, &self.new),
+      AuditOperation::Delete => (&self.new, &self.old),
+    }
+  }
+}
+
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+/// An append-only log of accumulator mutations. See the module docs.
+pub struct AuditLog<G: UnknownOrderGroup> {
+  records: Vec<AuditRecord<G>>,
+}
+
+impl<G: UnknownOrderGroup> AuditLog<G> {
+  /// Returns an empty log.
+  pub fn new() -> Self {
+    Self { records: vec![] }
+  }
+
+  /// Returns the records in the order they were appended.
+  pub fn records(&self) -> &[AuditRecord<G>] {
+    &self.records
+  }
+
+  /// Appends a record of `elems` being added to `old`, giving `new`.
+  pub fn record_add<T: Eq + Hash>(
+    &mut self,
+    old: &Accumulator<G, T>,
+    elems: &[T],
+    new: &Accumulator<G, T>,
+  ) {
+    self.record(AuditOperation::Add, old, elems, new);
+  }
+
+  /// Appends a record of `elems` being deleted from `old`, giving `new`.
+  pub fn record_delete<T: Eq + Hash>(
+    &mut self,
+    old: &Accumulator<G, T>,
+    elems: &[T],
+    new: &Accumulator<G, T>,
+  ) {
+    self.record(AuditOperation::Delete, old, elems, new);
+  }
+
+  fn record<T: Eq + Hash>(
+    &mut self,
+    operation: AuditOperation,
+    old: &Accumulator<G, T>,
+    elems: &[T],
+    new: &Accumulator<G, T>,
+  ) {
+    let params = old.params();
+    let primes = elems.iter().map(|t| params.hash_to_prime(t)).collect();
+    self.records.push(AuditRecord::new(
+      operation,
+      primes,
+      old.value().clone(),
+      new.value().clone(),
+    ));
+  }
+
+  /// Replays the log from `genesis`, checking that the records form a chain and that each one's
+  /// transition and proof digest are correct. Returns the final accumulator value.
+  pub fn replay(&self, genesis: &G::Elem) -> Result<G::Elem, AuditError> {
+    let mut current = genesis;
+    for (i, record) in self.records.iter().enumerate() {
+      if record.old != *current {
+        return Err(AuditError::BrokenChain(i));
+      }
+      let (base, result) = record.transition();
+      let exp = record.primes.iter().product();
+      if G::exp(base, &exp) != *result {
+        return Err(AuditError::BadTransition(i));
+      }
+      let proof = Poe::<G>::prove(base, &exp, result);
+      if hash(&Blake2b::default, &proof) != record.proof_digest {
+        return Err(AuditError::BadProofDigest(i));
+      }
+      current = &record.new;
+    }
+    Ok(current.clone())
+  }
+}
+
+impl<G: UnknownOrderGroup> Default for AuditLog<G> {
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::group::Rsa2048;
+
+  fn new_log() -> (AuditLog<Rsa2048>, Accumulator<Rsa2048, &'static str>) {
+    let mut log = AuditLog::new();
+    let acc_0 = Accumulator::empty();
+    let acc_1 = acc_0.clone().add(&["a", "b", "c"]);
+    log.record_add(&acc_0, &["a", "b", "c"], &acc_1);
+    let acc_2 = Accumulator::empty().add(&["a", "c"]);
+    log.record_delete(&acc_1, &["b"], &acc_2);
+    (log, acc_2)
+  }
+
+  #[test]
+  fn test_replay() {
+    let (log, acc) = new_log();
+    assert_eq!(log.records().len(), 2);
+    assert_eq!(log.records()[1].operation, AuditOperation::Delete);
+    let genesis = Accumulator::<Rsa2048, &'static str>::empty();
+    assert!(log.replay(genesis.value()).unwrap() == *acc.value());
+    assert!(AuditLog::<Rsa2048>::new().replay(genesis.value()).unwrap() == *genesis.value());
+    assert_eq!(log.replay(acc.value()), Err(AuditError::BrokenChain(0)));
+  }
+
+  #[test]
+  fn test_replay_tampered() {
+    let genesis = Accumulator::<Rsa2048, &'static str>::empty();
+    let (log, _) = new_log();
+    let mut tampered = log.clone();
+    tampered.records[1].primes.pop();
+    assert_eq!(
+      tampered.replay(genesis.value()),
+      Err(AuditError::BadTransition(1))
+    );
+
+    let mut tampered = log.clone();
+    tampered.records[0].proof_digest[0] ^= 1;
+    assert_eq!(
+      tampered.replay(genesis.value()),
+      Err(AuditError::BadProofDigest(0))
+    );
+
+    let mut tampered = log;
+    tampered.records.remove(0);
+    assert_eq!(
+      tampered.replay(genesis.value()),
+      Err(AuditError::BrokenChain(0))
+    );
+  }
+}
@@ -0,0 +1,215 @@
+//! Batch verification of membership proofs.
+//!
+//! Verifying a membership proof takes two exponentiations by hash-sized exponents. A
+//! `BatchVerifier` instead checks all queued proofs with one combined equation whose sides are each
+//! computed with a single simultaneous exponentiation, which shares squarings across proofs. This
+//! pays off when many proofs are checked together, e.g. by a node validating chain history.
+//!
+//! An `AggregateMembershipProof` goes further for proofs made against several recent states of one
+//! accumulator, as accepted by rollups that let transactions reference any of the last `k` roots.
+//! The proofs for each state are merged into one, so the aggregate holds a witness and an NI-PoE
+//! per state rather than per proof, and all of them are checked with a single `BatchVerifier`.
+use crate::accumulator::{AccError, Accumulator, MembershipProof};
+use crate::group::{Group, UnknownOrderGroup};
+use crate::integer::Integer;
+use crate::proof::Poe;
+use std::hash::Hash;
+
+// A `(base, exp, result, proof)` statement for `Poe::verify_batch`.
+type Statement<G> = (<G as Group>::Elem, Integer, <G as Group>::Elem, Poe<G>);
+
+// An `(accumulator, elems, proof)` claim for `AggregateMembershipProof::aggregate`.
+type Claim<'a, G, T> = (&'a Accumulator<G, T>, &'a [T], &'a MembershipProof<G, T>);
+
+/// Queues membership proofs to verify them together. See the module docs.
+pub struct BatchVerifier<G: UnknownOrderGroup> {
+  statements: Vec<Statement<G>>,
+  malformed: bool,
+}
+
+impl<G: UnknownOrderGroup> BatchVerifier<G> {
+  /// Returns an empty batch.
+  pub fn new() -> Self {
+    Self {
+      statements: vec![],
+      malformed: false,
+    }
+  }
+
+  /// Queues `proof` that `elems` are in `acc`. Proofs for different accumulators and element
+  /// types can share a batch.
+  pub fn push<T: Eq + Hash>(
+    &mut self,
+    acc: &Accumulator<G, T>,
+    elems: &[T],
+    proof: &MembershipProof<G, T>,
+  ) {
+    match acc.membership_exponent(elems, proof.borrowed()) {
+      Some(exp) => self.statements.push((
+        proof.witness.0.value().clone(),
+        exp,
+        acc.value().clone(),
+        proof.poe().clone(),
+      )),
+      None => self.malformed = true,
+    }
+  }
+
+  /// Returns the number of queued proofs.
+  pub fn len(&self) -> usize {
+    self.statements.len()
+  }
+
+  /// Returns whether no proofs are queued.
+  pub fn is_empty(&self) -> bool {
+    self.statements.is_empty()
+  }
+
+  /// Returns whether every queued proof verifies. A false result does not say which proof failed;
+  /// callers that need to know can fall back to `verify_membership_batch` on each.
+  pub fn verify(&self) -> bool {
+    !self.malformed && Poe::verify_batch(&self.statements)
+  }
+}
+
+impl<G: UnknownOrderGroup> Default for BatchVerifier<G> {
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+/// Membership proofs against several accumulator states, grouped by state. See the module docs.
+pub struct AggregateMembershipProof<G: UnknownOrderGroup, T: Hash> {
+  // Each state with the merged proof for all elements proven against it.
+  groups: Vec<(Accumulator<G, T>, MembershipProof<G, T>)>,
+}
+
+impl<G: UnknownOrderGroup, T: Clone + Eq + Hash> AggregateMembershipProof<G, T> {
+  /// Aggregates `proofs`, each a proof that some elements are in some accumulator. Proofs are
+  /// grouped by accumulator in order of first appearance, and the elements of each group are the
+  /// concatenation of its proofs' elements, in order. Returns `BadWitness` if a proof is not for
+  /// its accumulator, and `InputsNotCoprime` if an element appears twice in a group.
+  pub fn aggregate(proofs: &[Claim<G, T>]) -> Result<Self, AccError> {
+    let mut states: Vec<&Accumulator<G, T>> = vec![];
+    let mut members: Vec<Vec<usize>> = vec![];
+    for (i, (acc, _, _)) in proofs.iter().enumerate() {
+      match states.iter().position(|state| *state == *acc) {
+        Some(j) => members[j].push(i),
+        None => {
+          states.push(acc);
+          members.push(vec![i]);
+        }
+      }
+    }
+    let mut groups = Vec::with_capacity(states.len());
+    for (acc, members) in states.into_iter().zip(members) {
+      let elems = members.iter().map(|&i| proofs[i].1).collect::<Vec<_>>();
+      let proofs = members
+        .iter()
+        .map(|&i| proofs[i].2.clone())
+        .collect::<Vec<_>>();
+      let (first_elems, first) = (elems[0], &proofs[0]);
+      if first.witness.0.params() != acc.params()
+        || G::exp(
+          first.witness.0.value(),
+          &acc.params().prime_hash_product(first_elems),
+        ) != *acc.value()
+      {
+        return Err(AccError::BadWitness);
+      }
+      groups.push((acc.clone(), MembershipProof::merge(&proofs, &elems)?));
+    }
+    Ok(Self { groups })
+  }
+
+  /// Returns the accumulator states the proofs were made against, in group order.
+  pub fn states(&self) -> impl Iterator<Item = &Accumulator<G, T>> {
+    self.groups.iter().map(|(acc, _)| acc)
+  }
+
+  /// Verifies that `elems[i]` are in the `i`-th state, for every group, and that every state is
+  /// one of `accepted`, e.g. the last `k` roots. All groups are checked in one batch.
+  pub fn verify(&self, accepted: &[Accumulator<G, T>], elems: &[&[T]]) -> bool {
+    if elems.len() != self.groups.len() {
+      return false;
+    }
+    let mut batch = BatchVerifier::new();
+    for ((acc, proof), elems) in self.groups.iter().zip(elems) {
+      if !accepted.contains(acc) {
+        return false;
+      }
+      batch.push(acc, elems, proof);
+    }
+    batch.verify()
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::accumulator::Witness;
+  use crate::group::Rsa2048;
+
+  #[test]
+  fn test_batch_verifier() {
+    let mut batch = BatchVerifier::<Rsa2048>::new();
+    assert!(batch.is_empty() && batch.verify());
+    let acc = Accumulator::<Rsa2048, &'static str>::empty().add(&["a"]);
+    let (acc_2, proof_bc) = acc.clone().add_with_proof(&["b", "c"]);
+    let proof_a = acc_2
+      .prove_membership(&[("a", Witness(Accumulator::empty().add(&["b", "c"])))])
+      .unwrap()
+      .with_nonces(&["a"]);
+    let (ints, proof_int) = Accumulator::<Rsa2048, u64>::empty().add_with_proof(&[1, 2]);
+    batch.push(&acc_2, &["b", "c"], &proof_bc);
+    batch.push(&acc_2, &["a"], &proof_a);
+    batch.push(&ints, &[1, 2], &proof_int);
+    assert_eq!(batch.len(), 3);
+    assert!(batch.verify());
+
+    batch.push(&acc_2, &["a", "b"], &proof_bc);
+    assert!(!batch.verify());
+  }
+
+  #[test]
+  fn test_aggregate_membership_proof() {
+    let (acc_0, proof_a) = Accumulator::<Rsa2048, &'static str>::empty().add_with_proof(&["a"]);
+    let (acc_1, proof_bc) = acc_0.clone().add_with_proof(&["b", "c"]);
+    let (acc_2, proof_d) = acc_1.clone().add_with_proof(&["d"]);
+    let proof_a_1 = acc_1
+      .prove_membership(&[("a", Witness(Accumulator::empty().add(&["b", "c"])))])
+      .unwrap();
+    let aggregate = AggregateMembershipProof::aggregate(&[
+      (&acc_1, &["b", "c"], &proof_bc),
+      (&acc_0, &["a"], &proof_a),
+      (&acc_2, &["d"], &proof_d),
+      (&acc_1, &["a"], &proof_a_1),
+    ])
+    .unwrap();
+    assert_eq!(aggregate.states().count(), 3);
+    let elems: &[&[&'static str]] = &[&["b", "c", "a"], &["a"], &["d"]];
+    let roots = vec![acc_0.clone(), acc_1.clone(), acc_2.clone()];
+    assert!(aggregate.verify(&roots, elems));
+    assert!(!aggregate.verify(&roots[1..], elems));
+    assert!(!aggregate.verify(&roots, &[&["b", "c"], &["a"], &["d"]]));
+    assert!(!aggregate.verify(&roots, &elems[..2]));
+
+    assert!(AggregateMembershipProof::aggregate(&[(&acc_2, &["a"], &proof_a)]).is_err());
+    assert!(AggregateMembershipProof::aggregate(&[
+      (&acc_1, &["b", "c"], &proof_bc),
+      (&acc_1, &["b", "c"], &proof_bc),
+    ])
+    .is_err());
+  }
+
+  #[test]
+  fn test_batch_verifier_malformed() {
+    let mut batch = BatchVerifier::<Rsa2048>::new();
+    let (acc, proof) = Accumulator::<Rsa2048, &'static str>::empty().add_with_proof(&["a"]);
+    let proof = proof.with_nonces(&["a"]);
+    batch.push(&acc, &["a", "b"], &proof);
+    assert!(batch.is_empty());
+    assert!(!batch.verify());
+  }
+}
@@ -0,0 +1,292 @@
+//! Soak test: runs random add, delete, prove and verify cycles on accumulators for hours across
+//! threads, and cross-checks each against a `BTreeSet` of the elements it should hold.
+//!
+//! Build with the `soak` feature:
+//!
+//! ```text
+//! cargo run --release --features soak --bin soak -- [--seed N] [--threads N] [--seconds N]
+//!   [--group rsa|test]
+//! ```
+//!
+//! Thread `i` draws its operations from the seed `seed + i`. On the first divergence the binary
+//! prints the operation that diverged and a command that replays that thread alone, then exits
+//! with status 1. `--group test` runs on the insecure 64-bit `TestGroup`, which reaches far more
+//! cycles in the same time; the default is `Rsa2048`.
+use accumulator::group::{Rsa2048, TestGroup, UnknownOrderGroup};
+use accumulator::{Accumulator, ExponentState};
+use std::collections::BTreeSet;
+use std::process;
+use std::thread;
+use std::time::{Duration, Instant};
+
+// The reference set is kept below this size, so that nonmembership proofs and witnesses stay
+// cheap and the cycles spread over additions and deletions alike.
+const MAX_SET_SIZE: usize = 64;
+
+// The accumulator is rebuilt from the reference set and compared every this many cycles.
+const REBUILD_INTERVAL: u64 = 100;
+
+struct Config {
+  seed: u64,
+  threads: u64,
+  seconds: u64,
+  group: String,
+}
+
+// SplitMix64, so that runs reproduce from a seed without depending on an RNG crate's stream.
+struct Rng(u64);
+
+impl Rng {
+  fn next(&mut self) -> u64 {
+    self.0 = self.0.wrapping_add(0x9e37_79b9_7f4a_7c15);
+    let mut z = self.0;
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^ (z >> 31)
+  }
+
+  fn below(&mut self, n: u64) -> u64 {
+    self.next() % n
+  }
+}
+
+struct Soak<G: UnknownOrderGroup> {
+  rng: Rng,
+  set: BTreeSet<u64>,
+  acc: Accumulator<G, u64>,
+  state: ExponentState<G, u64>,
+  since_rebuild: u64,
+}
+
+impl<G: UnknownOrderGroup> Soak<G> {
+  fn new(seed: u64) -> Self {
+    Self {
+      rng: Rng(seed),
+      set: BTreeSet::new(),
+      acc: Accumulator::empty(),
+      state: ExponentState::empty(),
+      since_rebuild: 0,
+    }
+  }
+
+  fn fresh_elem(&mut self) -> u64 {
+    loop {
+      let elem = self.rng.next();
+      if !self.set.contains(&elem) {
+        return elem;
+      }
+    }
+  }
+
+  fn random_member(&mut self) -> Option<u64> {
+    let n = self.set.len() as u64;
+    if n == 0 {
+      return None;
+    }
+    let i = self.rng.below(n) as usize;
+    self.set.iter().nth(i).copied()
+  }
+
+  // Runs one random operation and returns a description of the divergence, if any.
+  fn cycle(&mut self) -> Result<(), String> {
+    let op = if self.set.len() >= MAX_SET_SIZE {
+      1
+    } else {
+      self.rng.below(4)
+    };
+    match op {
+      0 => self.add(),
+      1 => self.delete(),
+      2 => self.prove_membership(),
+      _ => self.prove_nonmembership(),
+    }?;
+    self.since_rebuild += 1;
+    if self.since_rebuild == REBUILD_INTERVAL {
+      self.since_rebuild = 0;
+      let elems = self.set.iter().copied().collect::<Vec<_>>();
+      if Accumulator::<G, u64>::empty().add(&elems) != self.acc {
+        return Err(format!(
+          "accumulator differs from one rebuilt from {:?}",
+          elems
+        ));
+      }
+      let mut state = ExponentState::<G, u64>::empty();
+      state.add(&elems);
+      if state.product() != self.state.product() {
+        return Err(format!(
+          "exponent state differs from one rebuilt from {:?}",
+          elems
+        ));
+      }
+    }
+    Ok(())
+  }
+
+  fn add(&mut self) -> Result<(), String> {
+    let n = 1 + self.rng.below(4);
+    let elems = (0..n).map(|_| self.fresh_elem()).collect::<Vec<_>>();
+    let (acc, proof) = self.acc.clone().add_with_proof(&elems);
+    if !acc.verify_membership_batch(&elems, &proof) {
+      return Err(format!("addition proof for {:?} rejected", elems));
+    }
+    self.acc = acc;
+    self.state.add(&elems);
+    self.set.extend(elems);
+    Ok(())
+  }
+
+  fn delete(&mut self) -> Result<(), String> {
+    let elem = match self.random_member() {
+      Some(elem) => elem,
+      None => return Ok(()),
+    };
+    let witness = self
+      .state
+      .witness(&[elem])
+      .map_err(|e| format!("no witness for member {}: {:?}", elem, e))?;
+    let (acc, proof) = self
+      .acc
+      .clone()
+      .delete_with_proof(&[(elem, witness)])
+      .map_err(|e| format!("deleting member {} failed: {:?}", elem, e))?;
+    if !self.acc.verify_membership(&elem, &proof) {
+      return Err(format!("deletion proof for {} rejected", elem));
+    }
+    self.acc = acc;
+    self
+      .state
+      .delete(&[elem])
+      .map_err(|e| format!("exponent state rejected deleting {}: {:?}", elem, e))?;
+    self.set.remove(&elem);
+    Ok(())
+  }
+
+  fn prove_membership(&mut self) -> Result<(), String> {
+    let elem = match self.random_member() {
+      Some(elem) => elem,
+      None => return Ok(()),
+    };
+    let witness = self
+      .state
+      .witness(&[elem])
+      .map_err(|e| format!("no witness for member {}: {:?}", elem, e))?;
+    let proof = self
+      .acc
+      .prove_membership(&[(elem, witness)])
+      .map_err(|e| format!("proving member {} failed: {:?}", elem, e))?;
+    if !self.acc.verify_membership(&elem, &proof) {
+      return Err(format!("membership proof for {} rejected", elem));
+    }
+    let other = self.fresh_elem();
+    if self.acc.verify_membership(&other, &proof) {
+      return Err(format!(
+        "membership proof for {} accepted for {}",
+        elem, other
+      ));
+    }
+    Ok(())
+  }
+
+  fn prove_nonmembership(&mut self) -> Result<(), String> {
+    let elem = self.fresh_elem();
+    let elems = self.set.iter().copied().collect::<Vec<_>>();
+    let proof = self
+      .acc
+      .prove_nonmembership(&elems, &[elem])
+      .map_err(|e| format!("proving nonmember {} failed: {:?}", elem, e))?;
+    if !self.acc.verify_nonmembership(&[elem], &proof) {
+      return Err(format!("nonmembership proof for {} rejected", elem));
+    }
+    if let Some(member) = elems.first() {
+      if self.acc.verify_nonmembership(&[*member], &proof) {
+        return Err(format!(
+          "nonmembership proof accepted for member {}",
+          member
+        ));
+      }
+    }
+    Ok(())
+  }
+}
+
+// Runs cycles with `seed` until `deadline`, and returns how many ran. Exits the process on the
+// first divergence.
+fn run<G: UnknownOrderGroup>(config: &Config, seed: u64, deadline: Instant) -> u64 {
+  let mut soak = Soak::<G>::new(seed);
+  let mut cycle = 0;
+  while Instant::now() < deadline {
+    cycle += 1;
+    if let Err(divergence) = soak.cycle() {
+      eprintln!(
+        "divergence at cycle {} of seed {}: {}",
+        cycle, seed, divergence
+      );
+      eprintln!(
+        "reproduce with: cargo run --release --features soak --bin soak -- --seed {} --threads 1 \
+         --group {}",
+        seed, config.group
+      );
+      process::exit(1);
+    }
+  }
+  cycle
+}
+
+fn parse_args() -> Result<Config, String> {
+  let mut config = Config {
+    seed: 0,
+    threads: 4,
+    seconds: 3600,
+    group: "rsa".to_string(),
+  };
+  let mut args = std::env::args().skip(1);
+  while let Some(flag) = args.next() {
+    let value = args
+      .next()
+      .ok_or_else(|| format!("missing value for {}", flag))?;
+    let number = || {
+      value
+        .parse::<u64>()
+        .map_err(|_| format!("bad value for {}: {}", flag, value))
+    };
+    match flag.as_str() {
+      "--seed" => config.seed = number()?,
+      "--threads" => config.threads = number()?.max(1),
+      "--seconds" => config.seconds = number()?,
+      "--group" if value == "rsa" || value == "test" => config.group = value.clone(),
+      _ => return Err(format!("unknown argument {} {}", flag, value)),
+    }
+  }
+  Ok(config)
+}
+
+fn main() {
+  let config = match parse_args() {
+    Ok(config) => config,
+    Err(e) => {
+      eprintln!("{}", e);
+      eprintln!("usage: soak [--seed N] [--threads N] [--seconds N] [--group rsa|test]");
+      process::exit(2);
+    }
+  };
+  let deadline = Instant::now() + Duration::from_secs(config.seconds);
+  let config = std::sync::Arc::new(config);
+  let handles = (0..config.threads)
+    .map(|i| {
+      let config = config.clone();
+      let seed = config.seed.wrapping_add(i);
+      thread::spawn(move || match config.group.as_str() {
+        "test" => run::<TestGroup>(&config, seed, deadline),
+        _ => run::<Rsa2048>(&config, seed, deadline),
+      })
+    })
+    .collect::<Vec<_>>();
+  let cycles: u64 = handles.into_iter().map(|h| h.join().unwrap()).sum();
+  println!(
+    "no divergence in {} cycles over {} threads (seeds {} to {})",
+    cycles,
+    config.threads,
+    config.seed,
+    config.seed.wrapping_add(config.threads - 1)
+  );
+}
@@ -0,0 +1,420 @@
+//! Blocks and bridge nodes of a stateless blockchain.
+//!
+//! In a stateless blockchain, validators store only an accumulator of the UTXO set, and users
+//! attach membership witnesses to the outputs they spend. Each `Block` carries an `UpdateProof`
+//! that lets validators check the accumulator transition without redoing its exponentiations.
+//!
+//! Most users cannot afford to follow every block to keep their witnesses current, so they rely on
+//! a bridge: a node that holds the full set, serves witnesses on request, and after each block
+//! publishes updated witnesses for the elements it was asked to track. See BBF, Section 6.1.
+//! Services pushing the raw updates instead can register an `UpdateListener`.
+use crate::accumulator::{AccError, Accumulator, ExponentState, Witness};
+#[cfg(feature = "audit")]
+use crate::audit::AuditLog;
+use crate::collision::{CollisionPolicy, PrimeIndex};
+use crate::group::UnknownOrderGroup;
+use crate::hash::PrimeParams;
+use crate::listener::{UpdateListener, UpdateListeners};
+use crate::proof::Poe;
+use std::collections::HashSet;
+use std::hash::Hash;
+use std::slice;
+
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+/// A proof that an accumulator transition deleted and then added given elements: the intermediate
+/// accumulator after deletions, an NI-PoE that raising it to the deleted primes gives the previous
+/// accumulator, and an NI-PoE that raising it to the added primes gives the new one.
+///
+/// BBF uses a proof of knowledge of the exponent for deletions. Validators know the deleted
+/// elements, though, and a PoE additionally binds the exponent to them.
+pub struct UpdateProof<G: UnknownOrderGroup> {
+  deleted: G::Elem,
+  delete_proof: Poe<G>,
+  add_proof: Poe<G>,
+}
+
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+/// A block, reduced to its effect on the accumulated set.
+pub struct Block<G: UnknownOrderGroup, T: Hash> {
+  /// The height of the block.
+  pub height: u64,
+  /// The elements created by the block.
+  pub additions: Vec<T>,
+  /// The elements spent by the block.
+  pub deletions: Vec<T>,
+  /// The accumulator after the block.
+  pub acc: Accumulator<G, T>,
+  /// A proof that `acc` follows from the previous accumulator.
+  pub proof: UpdateProof<G>,
+}
+
+impl<G: UnknownOrderGroup, T: Eq + Hash> Block<G, T> {
+  /// Builds the block at `height` on top of `prev_acc`, using `state` (the exponent state of
+  /// `prev_acc`) to delete without per-element witnesses. `state` is updated to describe the new
+  /// accumulator.
+  pub fn new(
+    height: u64,
+    prev_acc: &Accumulator<G, T>,
+    state: &mut ExponentState<G, T>,
+    additions: Vec<T>,
+    deletions: Vec<T>,
+  ) -> Result<Self, AccError> {
+    let params = prev_acc.params();
+    let deleted = prev_acc.delete_with_state_ref(state, &deletions)?;
+    let acc = deleted.add_ref(&additions);
+    state.add(&additions);
+    let delete_proof = Poe::prove(
+      deleted.value(),
+      &params.prime_hash_product(&deletions),
+      prev_acc.value(),
+    );
+    let add_proof = Poe::prove(
+      deleted.value(),
+      &params.prime_hash_product(&additions),
+      acc.value(),
+    );
+    Ok(Self {
+      height,
+      additions,
+      deletions,
+      acc,
+      proof: UpdateProof {
+        deleted: deleted.value().clone(),
+        delete_proof,
+        add_proof,
+      },
+    })
+  }
+
+  /// Verifies that this block's accumulator follows from `prev_acc` by deleting `deletions` and
+  /// adding `additions`.
+  pub fn verify(&self, prev_acc: &Accumulator<G, T>) -> bool {
+    let params = prev_acc.params();
+    let UpdateProof {
+      deleted,
+      delete_proof,
+      add_proof,
+    } = &self.proof;
+    params == self.acc.params()
+      && Poe::verify(
+        deleted,
+        &params.prime_hash_product(&self.deletions),
+        prev_acc.value(),
+        delete_proof,
+      )
+      && Poe::verify(
+        deleted,
+        &params.prime_hash_product(&self.additions),
+        self.acc.value(),
+        add_proof,
+      )
+  }
+}
+
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+/// What a bridge publishes after applying a block.
+pub struct BlockHints<G: UnknownOrderGroup, T: Hash> {
+  /// The height of the block.
+  pub height: u64,
+  /// The accumulator after the block.
+  pub acc: Accumulator<G, T>,
+  /// Fresh witnesses for every tracked element still in the set.
+  pub witnesses: Vec<(T, Witness<G, T>)>,
+}
+
+/// A bridge node, holding the full accumulated set. See the module docs.
+pub struct Bridge<G: UnknownOrderGroup, T: Hash> {
+  height: u64,
+  acc: Accumulator<G, T>,
+  state: ExponentState<G, T>,
+  elems: HashSet<T>,
+  tracked: HashSet<T>,
+  prime_index: Option<PrimeIndex<T>>,
+  listeners: UpdateListeners<G>,
+  #[cfg(feature = "audit")]
+  audit_log: AuditLog<G>,
+}
+
+impl<G: UnknownOrderGroup, T: Clone + Eq + Hash> Bridge<G, T> {
+  /// Returns a bridge for a chain whose genesis accumulator is empty and made with `params`.
+  pub fn new<P: Into<PrimeParams>>(params: P) -> Self {
+    let params = params.into();
+    Self {
+      height: 0,
+      acc: Accumulator::with_params(params),
+      state: ExponentState::with_params(params),
+      elems: HashSet::new(),
+      tracked: HashSet::new(),
+      prime_index: None,
+      listeners: UpdateListeners::new(),
+      #[cfg(feature = "audit")]
+      audit_log: AuditLog::new(),
+    }
+  }
+
+  /// Starts checking added elements for prime collisions with the elements in the set, and
+  /// handling them according to `policy`. Fails with `PrimeCollision` under
+  /// `CollisionPolicy::Error` if elements in the set already collide. See `PrimeIndex`.
+  pub fn set_collision_policy(&mut self, policy: CollisionPolicy) -> Result<(), AccError> {
+    let mut index = PrimeIndex::new(self.acc.params(), policy);
+    index.update(&self.elems.iter().cloned().collect::<Vec<_>>(), &[])?;
+    self.prime_index = Some(index);
+    Ok(())
+  }
+
+  /// Returns the prime index, if collisions are being checked.
+  pub fn prime_index(&self) -> Option<&PrimeIndex<T>> {
+    self.prime_index.as_ref()
+  }
+
+  /// Registers `listener` to be called after every applied block from now on, once per block.
+  pub fn add_listener<L: UpdateListener<G> + 'static>(&mut self, listener: L) {
+    self.listeners.push(listener);
+  }
+
+  /// Returns the height of the last applied block.
+  pub fn height(&self) -> u64 {
+    self.height
+  }
+
+  /// Returns the current accumulator.
+  pub fn accumulator(&self) -> &Accumulator<G, T> {
+    &self.acc
+  }
+
+  /// Returns the audit log of every change to the accumulator, two records (deletions, then
+  /// additions) per block, to be replayed from the genesis accumulator.
+  #[cfg(feature = "audit")]
+  pub fn audit_log(&self) -> &AuditLog<G> {
+    &self.audit_log
+  }
+
+  /// Returns whether `elem` is in the current set.
+  pub fn contains(&self, elem: &T) -> bool {
+    self.elems.contains(elem)
+  }
+
+  /// Serves a request for a witness to `elem` w.r.t. the current accumulator. Returns `BadWitness`
+  /// if `elem` is not in the set.
+  pub fn witness(&self, elem: &T) -> Result<Witness<G, T>, AccError> {
+    if !self.elems.contains(elem) {
+      return Err(AccError::BadWitness);
+    }
+    self.state.witness(slice::from_ref(elem))
+  }
+
+  /// Publishes a fresh witness for `elem` with every block from now on, until it is spent.
+  pub fn track(&mut self, elem: T) {
+    self.tracked.insert(elem);
+  }
+
+  /// Stops publishing witnesses for `elem`.
+  pub fn untrack(&mut self, elem: &T) {
+    self.tracked.remove(elem);
+  }
+
+  /// Applies the next block, which spends `deletions` and creates `additions`, and returns the
+  /// hints to publish for it. Spent elements are no longer tracked.
+  ///
+  /// Returns `BadWitness` without changing the bridge if some deleted element is not in the set,
+  /// `DuplicateElement` if some added element already is or an element is repeated within
+  /// `additions` or `deletions`, and `PrimeCollision` if some added element collides with another
+  /// under the collision policy.
+  pub fn apply_block(
+    &mut self,
+    additions: &[T],
+    deletions: &[T],
+  ) -> Result<BlockHints<G, T>, AccError> {
+    if has_repeats(additions) || has_repeats(deletions) {
+      return Err(AccError::DuplicateElement);
+    }
+    if deletions.iter().any(|elem| !self.elems.contains(elem)) {
+      return Err(AccError::BadWitness);
+    }
+    if additions
+      .iter()
+      .any(|elem| self.elems.contains(elem) && !deletions.contains(elem))
+    {
+      return Err(AccError::DuplicateElement);
+    }
+    let deleted = self.acc.delete_with_state_ref(&mut self.state, deletions)?;
+    // The index changes only once the deletion has succeeded, and the state is restored if the
+    // index rejects the block, so that a failed block leaves the bridge unchanged.
+    if let Some(index) = &mut self.prime_index {
+      if let Err(e) = index.update(additions, deletions) {
+        self.state.add(deletions);
+        return Err(e);
+      }
+    }
+    let acc = deleted.add_ref(additions);
+    #[cfg(feature = "audit")]
+    {
+      self.audit_log.record_delete(&self.acc, deletions, &deleted);
+      self.audit_log.record_add(&deleted, additions, &acc);
+    }
+    self.acc = acc;
+    self.state.add(additions);
+    for elem in deletions {
+      self.elems.remove(elem);
+      self.tracked.remove(elem);
+    }
+    self.elems.extend(additions.iter().cloned());
+    self.height += 1;
+    self
+      .listeners
+      .notify(self.acc.params(), additions, deletions, self.acc.value());
+
+    // Compute a witness for all tracked elements at once, then split it into individual witnesses
+    // in O(N log N) exponentiations instead of one full-set exponentiation per element.
+    let tracked = self
+      .tracked
+      .iter()
+      .filter(|elem| self.elems.contains(elem))
+      .cloned()
+      .collect::<Vec<_>>();
+    let witnesses = if tracked.is_empty() {
+      vec![]
+    } else {
+      self
+        .state
+        .witness(&tracked)?
+        .compute_individual_witnesses(&tracked)
+    };
+    Ok(BlockHints {
+      height: self.height,
+      acc: self.acc.clone(),
+      witnesses,
+    })
+  }
+}
+
+// Returns whether some element occurs more than once in `elems`.
+fn has_repeats<T: Eq + Hash>(elems: &[T]) -> bool {
+  let mut seen = HashSet::with_capacity(elems.len());
+  elems.iter().any(|elem| !seen.insert(elem))
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::group::{Group, Rsa2048};
+  use crate::hash::PrimeBits;
+  use crate::integer::Integer;
+  use std::sync::{Arc, Mutex};
+
+  #[test]
+  fn test_block() {
+    let genesis = Accumulator::<Rsa2048, u64>::empty();
+    let mut state = ExponentState::empty();
+    let block_1 = Block::new(1, &genesis, &mut state, vec![1, 2, 3], vec![]).unwrap();
+    assert!(block_1.verify(&genesis));
+    assert!(block_1.acc == Accumulator::empty().add(&[1, 2, 3]));
+
+    let block_2 = Block::new(2, &block_1.acc, &mut state, vec![4], vec![1, 3]).unwrap();
+    assert!(block_2.verify(&block_1.acc));
+    assert!(!block_2.verify(&genesis));
+    assert!(block_2.acc == Accumulator::empty().add(&[2, 4]));
+
+    let mut bad_block = block_2.clone();
+    bad_block.deletions = vec![1];
+    assert!(!bad_block.verify(&block_1.acc));
+    let mut bad_block = block_2;
+    bad_block.additions = vec![4, 5];
+    assert!(!bad_block.verify(&block_1.acc));
+
+    assert!(Block::new(3, &block_1.acc, &mut state, vec![], vec![5]).is_err());
+  }
+
+  fn is_witness(
+    acc: &Accumulator<Rsa2048, u64>,
+    elem: u64,
+    witness: &Witness<Rsa2048, u64>,
+  ) -> bool {
+    Rsa2048::exp(witness.0.value(), &acc.params().hash_to_prime(&elem)) == *acc.value()
+  }
+
+  #[test]
+  fn test_apply_block() {
+    let mut bridge = Bridge::<Rsa2048, u64>::new(PrimeBits::B256);
+    let hints = bridge.apply_block(&[1, 2, 3], &[]).unwrap();
+    assert_eq!(hints.height, 1);
+    assert!(hints.witnesses.is_empty());
+    assert!(hints.acc == Accumulator::empty().add(&[1, 2, 3]));
+
+    bridge.track(1);
+    bridge.track(2);
+    let hints = bridge.apply_block(&[4, 5], &[2]).unwrap();
+    assert!(hints.acc == Accumulator::empty().add(&[1, 3, 4, 5]));
+    assert_eq!(hints.witnesses.len(), 1);
+    let (elem, witness) = &hints.witnesses[0];
+    assert_eq!(*elem, 1);
+    assert!(is_witness(&hints.acc, 1, witness));
+
+    let witness = bridge.witness(&4).unwrap();
+    assert!(is_witness(bridge.accumulator(), 4, &witness));
+    assert!(bridge.witness(&2).is_err());
+  }
+
+  #[test]
+  fn test_apply_bad_block() {
+    let mut bridge = Bridge::<Rsa2048, u64>::new(PrimeBits::B256);
+    bridge.apply_block(&[1, 2], &[]).unwrap();
+    assert!(bridge.apply_block(&[3], &[4]).is_err());
+    assert!(bridge.apply_block(&[2], &[1]).is_err());
+    assert_eq!(bridge.height(), 1);
+    assert!(bridge.contains(&2) && !bridge.contains(&3));
+    bridge.apply_block(&[2], &[2]).unwrap();
+    assert!(bridge.contains(&2));
+    assert!(matches!(
+      bridge.apply_block(&[3, 3], &[]),
+      Err(AccError::DuplicateElement)
+    ));
+    assert!(matches!(
+      bridge.apply_block(&[], &[1, 1]),
+      Err(AccError::DuplicateElement)
+    ));
+    assert_eq!(bridge.height(), 2);
+    assert!(!bridge.contains(&3) && bridge.contains(&1));
+  }
+
+  #[test]
+  fn test_block_listener() {
+    let events = Arc::new(Mutex::new(vec![]));
+    let mut bridge = Bridge::<Rsa2048, u64>::new(PrimeBits::B256);
+    let sink = Arc::clone(&events);
+    bridge.add_listener(
+      move |added: &[Integer], deleted: &[Integer], root: &<Rsa2048 as Group>::Elem| {
+        // The new root raised to the deleted primes is the old one raised to the added ones.
+        let exp = deleted.iter().product::<Integer>();
+        sink
+          .lock()
+          .unwrap()
+          .push((added.len(), Rsa2048::exp(root, &exp)))
+      },
+    );
+    bridge.apply_block(&[1, 2, 3], &[]).unwrap();
+    assert!(bridge.apply_block(&[4], &[5]).is_err());
+    bridge.apply_block(&[4, 5], &[2]).unwrap();
+    let events = events.lock().unwrap();
+    assert_eq!(events.len(), 2);
+    assert_eq!(events[1].0, 2);
+    assert!(
+      events[1].1
+        == *Accumulator::<Rsa2048, u64>::empty()
+          .add(&[1, 2, 3, 4, 5])
+          .value()
+    );
+  }
+
+  #[cfg(feature = "audit")]
+  #[test]
+  fn test_audit_log() {
+    let mut bridge = Bridge::<Rsa2048, u64>::new(PrimeBits::B256);
+    bridge.apply_block(&[1, 2], &[]).unwrap();
+    bridge.apply_block(&[3], &[1]).unwrap();
+    let log = bridge.audit_log();
+    assert_eq!(log.records().len(), 4);
+    let genesis = Accumulator::<Rsa2048, u64>::empty();
+    assert!(log.replay(genesis.value()).unwrap() == *bridge.accumulator().value());
+  }
+}
@@ -0,0 +1,124 @@
+//! Work budgets for verifying proofs from untrusted sources, e.g. on consensus nodes.
+//!
+//! Verifying a membership or nonmembership proof maps every element to a prime, so a transaction
+//! claiming a million elements costs a million `hash_to_prime` calls before its proof is rejected.
+//! The `_with_budget` verification variants estimate the work a proof needs from its size alone,
+//! and return `AccError::BudgetExceeded` without doing any of it if the estimate exceeds a
+//! `VerifyBudget`. The budget counts operations rather than time, so every node aborts on the same
+//! proofs whatever its hardware or load.
+//!
+//! One operation is a group squaring or multiplication, and an exponentiation by a `k`-bit integer
+//! counts as `k` of them. Each `hash_to_prime` counts as an exponentiation by a prime of the size
+//! it outputs, which over-estimates its primality test but leaves room for the prime search.
+use crate::accumulator::{AccError, Accumulator, MembershipProof, NonmembershipProof};
+use crate::group::UnknownOrderGroup;
+use crate::hash::PrimeParams;
+use crate::security::{Security128, SecurityLevel};
+use std::hash::Hash;
+
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+/// A bound on the operations a verification may take. See the module docs.
+pub struct VerifyBudget {
+  max_ops: u64,
+}
+
+// Returns the operations to hash `elems` elements to primes and verify an NI-PoE on their product:
+// a challenge prime, then an exponentiation by it and one by the product modulo it.
+fn poe_ops(params: PrimeParams, elems: usize) -> u64 {
+  let challenge_bits = u64::from(Security128::CHALLENGE_BITS.bits());
+  (elems as u64)
+    .saturating_mul(u64::from(params.max_bits()))
+    .saturating_add(3 * challenge_bits)
+}
+
+impl VerifyBudget {
+  /// Returns a budget of `max_ops` operations.
+  pub fn new(max_ops: u64) -> Self {
+    Self { max_ops }
+  }
+
+  /// Returns the number of operations this budget allows.
+  pub fn max_ops(self) -> u64 {
+    self.max_ops
+  }
+
+  /// Returns the estimated operations to verify a membership proof for `elems` elements mapped to
+  /// primes according to `params`.
+  pub fn membership_ops(params: PrimeParams, elems: usize) -> u64 {
+    poe_ops(params, elems)
+  }
+
+  /// Returns the estimated operations to verify a nonmembership proof for `elems` elements mapped
+  /// to primes according to `params`. On top of the NI-PoE, its NI-PoKE2 takes a challenge prime
+  /// and four exponentiations by integers of at most the challenge size, since the residue `r` is
+  /// rejected without exponentiating if it is larger.
+  pub fn nonmembership_ops(params: PrimeParams, elems: usize) -> u64 {
+    let challenge_bits = u64::from(Security128::CHALLENGE_BITS.bits());
+    poe_ops(params, elems).saturating_add(5 * challenge_bits)
+  }
+
+  fn check(self, ops: u64) -> Result<(), AccError> {
+    if ops > self.max_ops {
+      return Err(AccError::BudgetExceeded);
+    }
+    Ok(())
+  }
+}
+
+impl<G: UnknownOrderGroup, T: Eq + Hash> Accumulator<G, T> {
+  /// Like `verify_membership_batch`, but returns `BudgetExceeded` without verifying anything if
+  /// the proof would take more operations than `budget` allows.
+  pub fn verify_membership_with_budget(
+    &self,
+    elems: &[T],
+    proof: &MembershipProof<G, T>,
+    budget: &VerifyBudget,
+  ) -> Result<bool, AccError> {
+    budget.check(VerifyBudget::membership_ops(self.params(), elems.len()))?;
+    Ok(self.verify_membership_batch(elems, proof))
+  }
+
+  /// Like `verify_nonmembership`, but returns `BudgetExceeded` without verifying anything if the
+  /// proof would take more operations than `budget` allows.
+  pub fn verify_nonmembership_with_budget(
+    &self,
+    elems: &[T],
+    proof: &NonmembershipProof<G, T>,
+    budget: &VerifyBudget,
+  ) -> Result<bool, AccError> {
+    budget.check(VerifyBudget::nonmembership_ops(self.params(), elems.len()))?;
+    Ok(self.verify_nonmembership(elems, proof))
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::group::Rsa2048;
+
+  #[test]
+  fn test_verify_budget() {
+    let (acc, proof) = Accumulator::<Rsa2048, &'static str>::empty().add_with_proof(&["a", "b"]);
+    let params = acc.params();
+    let budget = VerifyBudget::new(VerifyBudget::membership_ops(params, 2));
+    assert!(acc
+      .verify_membership_with_budget(&["a", "b"], &proof, &budget)
+      .unwrap());
+    assert!(!acc
+      .verify_membership_with_budget(&["a", "c"], &proof, &budget)
+      .unwrap());
+    assert!(acc
+      .verify_membership_with_budget(&["a", "b", "c"], &proof, &budget)
+      .is_err());
+
+    let proof = acc.prove_nonmembership(&["a", "b"], &["c"]).unwrap();
+    let budget = VerifyBudget::new(VerifyBudget::nonmembership_ops(params, 1));
+    assert!(acc
+      .verify_nonmembership_with_budget(&["c"], &proof, &budget)
+      .unwrap());
+    assert!(acc
+      .verify_nonmembership_with_budget(&["c", "d"], &proof, &budget)
+      .is_err());
+    assert!(VerifyBudget::membership_ops(params, 1) < VerifyBudget::nonmembership_ops(params, 1));
+  }
+}
@@ -0,0 +1,146 @@
+//! Byte strings as accumulator elements, hashed the same whatever type holds them.
+//!
+//! Elements are mapped to primes through their `Hash` impls, which differ between types with the
+//! same content: `str` and `String` hash as their bytes followed by `0xff`, while `[u8]`, `Vec<u8>`
+//! and byte arrays hash as a `usize` length followed by their bytes, and `usize` is 4 bytes wide
+//! on 32-bit platforms. Switching an accumulator's element type from `&'static str` to `Vec<u8>`
+//! therefore changes every prime. `ByteElem` hashes as a 64-bit length followed by the bytes, so
+//! elements built from any `ByteContent` type map to the same prime when their bytes match.
+use crate::accumulator::AccumulatorElement;
+use std::hash::{Hash, Hasher};
+
+/// Types holding a byte string, which can be accumulated as a `ByteElem`.
+pub trait ByteContent {
+  /// Returns the bytes held.
+  fn byte_content(&self) -> &[u8];
+}
+
+impl ByteContent for str {
+  fn byte_content(&self) -> &[u8] {
+    self.as_bytes()
+  }
+}
+
+impl ByteContent for String {
+  fn byte_content(&self) -> &[u8] {
+    self.as_bytes()
+  }
+}
+
+impl ByteContent for [u8] {
+  fn byte_content(&self) -> &[u8] {
+    self
+  }
+}
+
+impl ByteContent for Vec<u8> {
+  fn byte_content(&self) -> &[u8] {
+    self
+  }
+}
+
+impl<B: ByteContent + ?Sized> ByteContent for &B {
+  fn byte_content(&self) -> &[u8] {
+    (**self).byte_content()
+  }
+}
+
+macro_rules! impl_byte_content_for_arrays {
+  ($($n:expr),*) => {
+    $(impl ByteContent for [u8; $n] {
+      fn byte_content(&self) -> &[u8] {
+        self
+      }
+    })*
+  };
+}
+
+impl_byte_content_for_arrays!(
+  0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15, 16, 17, 18, 19, 20, 21, 22, 23, 24, 25, 26,
+  27, 28, 29, 30, 31, 32, 48, 64
+);
+
+#[derive(Clone, Debug, Eq, PartialEq, PartialOrd, Ord)]
+/// A byte string that hashes as its 64-bit big-endian length followed by its bytes. See the module
+/// docs.
+pub struct ByteElem(pub Vec<u8>);
+
+impl ByteElem {
+  /// Returns the element holding the bytes of `b`.
+  pub fn new<B: ByteContent + ?Sized>(b: &B) -> Self {
+    Self(b.byte_content().to_vec())
+  }
+
+  /// Returns the elements holding the bytes of each of `bs`, e.g. to add `&'static str` elements
+  /// to an `Accumulator<G, ByteElem>`.
+  pub fn from_all<B: ByteContent>(bs: &[B]) -> Vec<Self> {
+    bs.iter().map(Self::new).collect()
+  }
+}
+
+// The length prefix has a fixed width, unlike `usize`, and keeps elements inside tuples apart.
+impl Hash for ByteElem {
+  fn hash<H: Hasher>(&self, state: &mut H) {
+    state.write(&(self.0.len() as u64).to_be_bytes());
+    state.write(&self.0);
+  }
+}
+
+impl ByteContent for ByteElem {
+  fn byte_content(&self) -> &[u8] {
+    &self.0
+  }
+}
+
+impl AccumulatorElement for ByteElem {}
+
+impl From<Vec<u8>> for ByteElem {
+  fn from(bytes: Vec<u8>) -> Self {
+    Self(bytes)
+  }
+}
+
+impl From<String> for ByteElem {
+  fn from(s: String) -> Self {
+    Self(s.into_bytes())
+  }
+}
+
+impl<'a, B: ByteContent + ?Sized> From<&'a B> for ByteElem {
+  fn from(b: &'a B) -> Self {
+    Self::new(b)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::accumulator::Accumulator;
+  use crate::group::Rsa2048;
+  use crate::hash::hash_to_prime;
+
+  #[test]
+  fn test_byte_elem_hash() {
+    let p = hash_to_prime(&ByteElem::from("abc"));
+    assert_eq!(hash_to_prime(&ByteElem::from("abc".to_string())), p);
+    assert_eq!(hash_to_prime(&ByteElem::from(b"abc".to_vec())), p);
+    assert_eq!(hash_to_prime(&ByteElem::from(b"abc")), p);
+    assert_eq!(hash_to_prime(&ByteElem::from(&b"abc"[..])), p);
+    // The element types' own impls disagree.
+    assert!(hash_to_prime(&"abc") != hash_to_prime(&b"abc".to_vec()));
+    assert!(
+      hash_to_prime(&(ByteElem::from("ab"), ByteElem::from("c")))
+        != hash_to_prime(&(ByteElem::from("a"), ByteElem::from("bc")))
+    );
+  }
+
+  #[test]
+  fn test_byte_elem_accumulator() {
+    let from_strs = ByteElem::from_all(&["a", "b"]);
+    let from_arrays = ByteElem::from_all(&[[b'a'], [b'b']]);
+    let from_vecs = vec![ByteElem::from(vec![b'a']), ByteElem::from(vec![b'b'])];
+    let (acc, proof) = Accumulator::<Rsa2048, ByteElem>::empty().add_with_proof(&from_strs);
+    assert!(acc == Accumulator::empty().add(&from_arrays));
+    assert!(acc.verify_membership_batch(&from_vecs, &proof));
+  }
+}
@@ -0,0 +1,156 @@
+//! Detection of distinct elements that hash to the same prime.
+//!
+//! `hash_to_prime` maps elements to primes of a fixed size, so two different elements can in
+//! principle map to the same prime. The accumulator cannot tell them apart: adding the second does
+//! not change the set of primes beyond what a duplicate would, and a witness for one is a witness
+//! for the other. With 256-bit primes this takes around `2^124` elements to happen by chance, but
+//! smaller primes, or elements chosen adversarially against a weaker prime hash, bring it closer.
+//!
+//! Holders of the full set can keep a `PrimeIndex`, mapping each accumulated prime to a digest of
+//! the element it came from, and decide with a `CollisionPolicy` what happens when an addition
+//! maps to a prime already taken by a different element. `RevocationRegistry` and `Bridge` keep
+//! one once `set_collision_policy` is called.
+use crate::accumulator::AccError;
+use crate::hash::{hash, Blake2b, PrimeParams};
+use crate::integer::Integer;
+use std::collections::HashMap;
+use std::hash::Hash;
+use std::marker::PhantomData;
+
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+/// What to do when an added element hashes to the same prime as a different accumulated element.
+pub enum CollisionPolicy {
+  /// Reject the addition with `AccError::PrimeCollision`.
+  Error,
+  /// Accept the addition, and count the collision in `PrimeIndex::collisions`. The accumulator
+  /// cannot tell the two elements apart from then on: a witness for either is a witness for both.
+  Ignore,
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+/// A map from each accumulated prime to the Blake2b digest of the element it came from.
+pub struct PrimeIndex<T> {
+  phantom: PhantomData<T>,
+  params: PrimeParams,
+  policy: CollisionPolicy,
+  digests: HashMap<Integer, [u8; 32]>,
+  collisions: u64,
+}
+
+impl<T: Hash> PrimeIndex<T> {
+  /// Returns an empty index for elements mapped to primes according to `params`.
+  pub fn new<P: Into<PrimeParams>>(params: P, policy: CollisionPolicy) -> Self {
+    Self {
+      phantom: PhantomData,
+      params: params.into(),
+      policy,
+      digests: HashMap::new(),
+      collisions: 0,
+    }
+  }
+
+  /// Returns the policy applied to collisions.
+  pub fn policy(&self) -> CollisionPolicy {
+    self.policy
+  }
+
+  /// Returns the number of collisions accepted under `CollisionPolicy::Ignore`.
+  pub fn collisions(&self) -> u64 {
+    self.collisions
+  }
+
+  /// Returns the number of indexed primes.
+  pub fn len(&self) -> usize {
+    self.digests.len()
+  }
+
+  /// Returns whether no primes are indexed.
+  pub fn is_empty(&self) -> bool {
+    self.digests.is_empty()
+  }
+
+  /// Removes `deletions`, then indexes `additions`. Under `CollisionPolicy::Error`, returns
+  /// `AccError::PrimeCollision` without changing the index if an addition maps to the same prime as
+  /// a different element, whether indexed or earlier in `additions`. Re-adding an indexed element is
+  /// not a collision.
+  pub fn update(&mut self, additions: &[T], deletions: &[T]) -> Result<(), AccError> {
+    let deleted = deletions
+      .iter()
+      .map(|t| self.entry(t))
+      .filter(|(p, digest)| self.digests.get(p) == Some(digest))
+      .collect::<HashMap<_, _>>();
+    let mut added: HashMap<Integer, [u8; 32]> = HashMap::new();
+    let mut collisions = 0;
+    for t in additions {
+      let (p, digest) = self.entry(t);
+      let existing = added.get(&p).or_else(|| {
+        if deleted.contains_key(&p) {
+          None
+        } else {
+          self.digests.get(&p)
+        }
+      });
+      match existing {
+        Some(existing) if *existing != digest => collisions += 1,
+        Some(_) => (),
+        None => {
+          added.insert(p, digest);
+        }
+      }
+    }
+    if collisions > 0 && self.policy == CollisionPolicy::Error {
+      return Err(AccError::PrimeCollision);
+    }
+
+    for p in deleted.keys() {
+      self.digests.remove(p);
+    }
+    self.digests.extend(added);
+    self.collisions += collisions;
+    Ok(())
+  }
+
+  fn entry(&self, t: &T) -> (Integer, [u8; 32]) {
+    (self.params.hash_to_prime(t), hash(&Blake2b::default, t))
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::hash::PrimeBits;
+
+  // Real collisions cannot be found, so tests plant a digest of some other element under the prime
+  // of `t`.
+  fn plant_collision(index: &mut PrimeIndex<&'static str>, t: &'static str) {
+    let (p, _) = index.entry(&t);
+    index.digests.insert(p, hash(&Blake2b::default, "other"));
+  }
+
+  #[test]
+  fn test_update() {
+    let mut index = PrimeIndex::new(PrimeBits::B128, CollisionPolicy::Error);
+    index.update(&["a", "b"], &[]).unwrap();
+    index.update(&["a", "c"], &["b"]).unwrap();
+    assert_eq!(index.len(), 2);
+    index.update(&[], &["a", "c", "d"]).unwrap();
+    assert!(index.is_empty());
+    assert_eq!(index.collisions(), 0);
+  }
+
+  #[test]
+  fn test_collision_policy() {
+    let mut index = PrimeIndex::new(PrimeBits::B128, CollisionPolicy::Error);
+    plant_collision(&mut index, "a");
+    assert!(index.update(&["b", "a"], &[]).is_err());
+    assert_eq!(index.len(), 1);
+    // Deleting "a" does not free the prime, since it is indexed for a different element.
+    assert!(index.update(&["a"], &["a"]).is_err());
+
+    let mut index = PrimeIndex::new(PrimeBits::B128, CollisionPolicy::Ignore);
+    plant_collision(&mut index, "a");
+    index.update(&["b", "a"], &[]).unwrap();
+    assert_eq!(index.collisions(), 1);
+    assert_eq!(index.len(), 2);
+  }
+}
@@ -0,0 +1,106 @@
+//! Constant-size proofs that one accumulator follows from another by a claimed set of changes.
+//!
+//! An auditor given two accumulators `old` and `new` and the claimed additions and deletions wants
+//! to check that `new` is `old` with the deleted elements removed and the added ones accumulated,
+//! i.e. that there is some `w` with `w^D = old` and `w^A = new` for the prime products `D` and `A`
+//! of the deletions and additions. A `DeltaProof` shows the equivalent `new^D = old^A` instead: it
+//! holds `x = old^A` and an NI-PoE for each of `old^A = x` and `new^D = x`. If `A` and `D` are
+//! coprime, the Shamir trick turns any such pair into `w`, so the deleted elements were in `old`.
+//! Unlike a block's `UpdateProof`, proving needs neither `w` nor the exponent state of `old`, only
+//! exponentiations by `A` and `D`.
+use crate::accumulator::{AccError, Accumulator};
+use crate::group::UnknownOrderGroup;
+use crate::integer::IntegerOps;
+use crate::proof::Poe;
+use std::hash::Hash;
+
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+/// A proof that one accumulator follows from another by given additions and deletions. See the
+/// module docs.
+pub struct DeltaProof<G: UnknownOrderGroup> {
+  common: G::Elem,
+  old_proof: Poe<G>,
+  new_proof: Poe<G>,
+}
+
+/// Proves that `new` is `old` with `deleted` removed and `added` accumulated. Returns `BadWitness`
+/// if it is not (or the accumulators' parameters differ), and `InputsNotCoprime` if an element is
+/// both added and deleted, since the proof would then not show that it was in `old`.
+pub fn prove_delta<G: UnknownOrderGroup, T: Eq + Hash>(
+  old: &Accumulator<G, T>,
+  new: &Accumulator<G, T>,
+  added: &[T],
+  deleted: &[T],
+) -> Result<DeltaProof<G>, AccError> {
+  if old.params() != new.params() {
+    return Err(AccError::BadWitness);
+  }
+  let a = old.params().prime_hash_product(added);
+  let d = old.params().prime_hash_product(deleted);
+  if a.xgcd(&d).0 != 1 {
+    return Err(AccError::InputsNotCoprime);
+  }
+  let common = G::exp(old.value(), &a);
+  if G::exp(new.value(), &d) != common {
+    return Err(AccError::BadWitness);
+  }
+  Ok(DeltaProof {
+    old_proof: Poe::prove(old.value(), &a, &common),
+    new_proof: Poe::prove(new.value(), &d, &common),
+    common,
+  })
+}
+
+/// Verifies that `proof` shows `new` is `old` with `deleted` removed and `added` accumulated.
+pub fn verify_delta<G: UnknownOrderGroup, T: Eq + Hash>(
+  old: &Accumulator<G, T>,
+  new: &Accumulator<G, T>,
+  added: &[T],
+  deleted: &[T],
+  proof: &DeltaProof<G>,
+) -> bool {
+  if old.params() != new.params() {
+    return false;
+  }
+  let a = old.params().prime_hash_product(added);
+  let d = old.params().prime_hash_product(deleted);
+  a.xgcd(&d).0 == 1
+    && Poe::verify(old.value(), &a, &proof.common, &proof.old_proof)
+    && Poe::verify(new.value(), &d, &proof.common, &proof.new_proof)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::group::Rsa2048;
+
+  fn new_acc(elems: &[&'static str]) -> Accumulator<Rsa2048, &'static str> {
+    Accumulator::empty().add(elems)
+  }
+
+  #[test]
+  fn test_delta_proof() {
+    let old = new_acc(&["a", "b", "c"]);
+    let new = new_acc(&["a", "c", "d", "e"]);
+    let proof = prove_delta(&old, &new, &["d", "e"], &["b"]).unwrap();
+    assert!(verify_delta(&old, &new, &["d", "e"], &["b"], &proof));
+    assert!(!verify_delta(&old, &new, &["d"], &["b"], &proof));
+    assert!(!verify_delta(&old, &new, &["d", "e"], &["a"], &proof));
+    assert!(!verify_delta(&new, &old, &["b"], &["d", "e"], &proof));
+
+    let proof = prove_delta(&old, &old, &[], &[]).unwrap();
+    assert!(verify_delta(&old, &old, &[], &[], &proof));
+    let proof = prove_delta(&new_acc(&[]), &old, &["a", "b", "c"], &[]).unwrap();
+    assert!(verify_delta(
+      &new_acc(&[]),
+      &old,
+      &["a", "b", "c"],
+      &[],
+      &proof
+    ));
+
+    assert!(prove_delta(&old, &new, &["d"], &["b"]).is_err());
+    // Deleting and re-adding `f` leaves `old` unchanged, but `f` was never in it.
+    assert!(prove_delta(&old, &old, &["f"], &["f"]).is_err());
+  }
+}
@@ -0,0 +1,207 @@
+//! Succinct proofs that an accumulator's exponent is no larger than a given number of elements
+//! allows.
+//!
+//! An accumulator is the generator raised to the product `x` of its elements' primes, each of which
+//! has at most `bits` bits, so `n` elements give an exponent below `2^(n * bits)`. An
+//! `ExponentBoundProof` shows that the accumulator's exponent is below this bound for a claimed
+//! maximum `n`, without revealing the set.
+//!
+//! The proof halves the bound on the exponent round by round, following the bounded evaluation
+//! argument of DARK (Bünz, Fisch and Szepieniec 2019)
+//! [\[Link\]](https://eprint.iacr.org/2019/1229.pdf). Each round splits the exponent into a low
+//! and a high half, commits to both, proves with an NI-PoE that they recombine to the current
+//! commitment, and folds them with a 128-bit challenge. Once the bound is small enough, the folded
+//! exponent is revealed. Proofs hold `O(log(n * bits))` group elements, and verification takes as
+//! many 128-bit exponentiations.
+//!
+//! Two caveats apply. Folding costs soundness slack: an extractor recovers an exponent whose size
+//! is only bounded up to a factor that grows with the number of rounds, so applications should
+//! leave headroom in the claimed maximum. And the proof bounds the exponent, not the count:
+//! `hash_to_prime` does not force the top bit of its primes, so the bound only caps the count if
+//! every prime has nearly `bits` bits. Honestly hashed primes have fewer than `bits - t` bits with
+//! probability about `2^-t`, but a dishonest manager could accumulate many small primes that are
+//! not the hash of any element.
+use crate::accumulator::{AccError, Accumulator, ExponentState};
+use crate::group::UnknownOrderGroup;
+use crate::hash::{blake2b, PrimeParams};
+use crate::integer::{Integer, IntegerOps};
+use crate::proof::Poe;
+use crate::util::int;
+use std::hash::Hash;
+use std::marker::PhantomData;
+
+// Folding stops once the bound on the exponent is at most this many bits.
+const FINAL_BITS: u64 = 512;
+// Larger bounds would overflow the `u32` shift amounts of the first round.
+const MAX_BOUND_BITS: u64 = u32::MAX as u64;
+const CHALLENGE_BITS: u64 = 128;
+
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+struct BoundRound<G: UnknownOrderGroup> {
+  low: G::Elem,
+  high: G::Elem,
+  // Proves that `high` raised to `2^half` is the current commitment divided by `low`.
+  proof: Poe<G>,
+}
+
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+/// A proof that an accumulator's exponent is below the bound for a given number of elements. See
+/// the module docs.
+pub struct ExponentBoundProof<G: UnknownOrderGroup, T> {
+  phantom: PhantomData<T>,
+  rounds: Vec<BoundRound<G>>,
+  exp: Integer,
+}
+
+// Returns the bound on the exponent of an accumulator of at most `max_elems` elements.
+fn bound_bits(params: PrimeParams, max_elems: u64) -> u64 {
+  max_elems.saturating_mul(u64::from(params.max_bits()))
+}
+
+// Returns the bound on a `bits`-bit exponent after folding its halves at `half` bits.
+fn folded_bits(bits: u64, half: u64) -> u64 {
+  half.max(bits - half + CHALLENGE_BITS) + 1
+}
+
+fn challenge<G: UnknownOrderGroup>(
+  commitment: &G::Elem,
+  round: &BoundRound<G>,
+  bits: u64,
+) -> Integer {
+  blake2b(&(commitment, &round.low, &round.high, bits)).low_bits(CHALLENGE_BITS as u32)
+}
+
+impl<G: UnknownOrderGroup, T: Eq + Hash> ExponentBoundProof<G, T> {
+  /// Proves that the exponent of `acc` is below the bound for `max_elems` elements, using the
+  /// exponent `state` tracked for it. Returns `AccError::BadWitness` if `state` does not describe
+  /// `acc`, and `AccError::BoundExceeded` if the accumulated primes are too large for `max_elems`
+  /// elements or the bound has more than `u32::MAX` bits.
+  pub fn prove(
+    acc: &Accumulator<G, T>,
+    state: &ExponentState<G, T>,
+    max_elems: u64,
+  ) -> Result<Self, AccError> {
+    let g = G::unknown_order_elem();
+    let mut exp = state.product().clone();
+    if G::exp(&g, &exp) != *acc.value() {
+      return Err(AccError::BadWitness);
+    }
+    let mut bits = bound_bits(acc.params(), max_elems);
+    if bits > MAX_BOUND_BITS || u64::from(exp.bit_length()) > bits {
+      return Err(AccError::BoundExceeded);
+    }
+    let mut commitment = acc.value().clone();
+    let mut rounds = Vec::new();
+    while bits > FINAL_BITS {
+      let half = bits / 2;
+      let low_exp = exp.low_bits(half as u32);
+      let high_exp = exp >> half as u32;
+      let low = G::exp(&g, &low_exp);
+      let high = G::exp(&g, &high_exp);
+      let shifted = G::op(&commitment, &G::inv(&low));
+      let proof = Poe::prove(&high, &(int(1) << half as u32), &shifted);
+      let round = BoundRound { low, high, proof };
+      let alpha = challenge(&commitment, &round, bits);
+      commitment = G::op(&round.low, &G::exp(&round.high, &alpha));
+      exp = low_exp + alpha * high_exp;
+      bits = folded_bits(bits, half);
+      rounds.push(round);
+    }
+    Ok(Self {
+      phantom: PhantomData,
+      rounds,
+      exp,
+    })
+  }
+
+  /// Verifies that the exponent of `acc` is below the bound for `max_elems` elements.
+  pub fn verify(&self, acc: &Accumulator<G, T>, max_elems: u64) -> bool {
+    let mut bits = bound_bits(acc.params(), max_elems);
+    if bits > MAX_BOUND_BITS {
+      return false;
+    }
+    let mut commitment = acc.value().clone();
+    let mut rounds = self.rounds.iter();
+    while bits > FINAL_BITS {
+      let round = match rounds.next() {
+        Some(round) => round,
+        None => return false,
+      };
+      let half = bits / 2;
+      let shifted = G::op(&commitment, &G::inv(&round.low));
+      if !Poe::verify(
+        &round.high,
+        &(int(1) << half as u32),
+        &shifted,
+        &round.proof,
+      ) {
+        return false;
+      }
+      let alpha = challenge(&commitment, round, bits);
+      commitment = G::op(&round.low, &G::exp(&round.high, &alpha));
+      bits = folded_bits(bits, half);
+    }
+    rounds.next().is_none()
+      && self.exp >= 0
+      && u64::from(self.exp.bit_length()) <= bits
+      && G::exp(&G::unknown_order_elem(), &self.exp) == commitment
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::group::Rsa2048;
+
+  fn setup(n: usize) -> (Accumulator<Rsa2048, usize>, ExponentState<Rsa2048, usize>) {
+    let elems: Vec<usize> = (0..n).collect();
+    let mut state = ExponentState::empty();
+    state.add(&elems);
+    (Accumulator::empty().add(&elems), state)
+  }
+
+  #[test]
+  fn test_exponent_bound_proof() {
+    let (acc, state) = setup(20);
+    let proof = ExponentBoundProof::prove(&acc, &state, 32).unwrap();
+    assert!(proof.rounds.len() > 1);
+    assert!(proof.verify(&acc, 32));
+    assert!(!proof.verify(&acc, 31));
+    assert!(!proof.verify(&acc.clone().add(&[20]), 32));
+
+    match ExponentBoundProof::prove(&acc, &state, 2) {
+      Err(AccError::BoundExceeded) => (),
+      _ => panic!("expected BoundExceeded"),
+    }
+  }
+
+  #[test]
+  fn test_exponent_bound_proof_too_large() {
+    // 2^32 256-bit primes give a bound of 2^40 bits, which would truncate as a shift amount.
+    let (acc, state) = setup(2);
+    let max_elems = 1 << 32;
+    match ExponentBoundProof::prove(&acc, &state, max_elems) {
+      Err(AccError::BoundExceeded) => (),
+      _ => panic!("expected BoundExceeded"),
+    }
+    let proof = ExponentBoundProof::prove(&acc, &state, 4).unwrap();
+    assert!(!proof.verify(&acc, max_elems));
+  }
+
+  #[test]
+  fn test_exponent_bound_proof_tampered() {
+    let (acc, state) = setup(5);
+    assert!(ExponentBoundProof::prove(&acc.clone().add(&[5]), &state, 8).is_err());
+
+    let proof = ExponentBoundProof::prove(&acc, &state, 8).unwrap();
+    let mut tampered = proof.clone();
+    tampered.exp += 1;
+    assert!(!tampered.verify(&acc, 8));
+    let mut tampered = proof.clone();
+    tampered.rounds.pop();
+    assert!(!tampered.verify(&acc, 8));
+    let mut tampered = proof;
+    tampered.rounds.swap(0, 1);
+    assert!(!tampered.verify(&acc, 8));
+  }
+}
@@ -0,0 +1,219 @@
+//! Epoch-bound proofs, for verifiers that must reject stale proofs.
+//!
+//! An accumulator that changes over time (e.g. a revocation list) goes through numbered epochs.
+//! An `EpochProof` is a membership or nonmembership proof made against the accumulator of one
+//! epoch, with the epoch hashed into the Fiat-Shamir challenges of its subproofs, so it cannot be
+//! relabeled with another epoch. An `EpochVerifier` keeps the accumulators of the most recent
+//! epochs and rejects proofs older than its maximum age with `StaleProof`, so credential verifiers
+//! get freshness without an external timestamping mechanism.
+//!
+//! A NI-PoE for an exponent smaller than its challenge prime, e.g. that of a single element, is
+//! trivial and binds nothing, so relabeling a proof only fails if the accumulators of the two
+//! epochs differ. Such a proof is then valid for the new epoch anyway.
+use crate::accumulator::{AccError, Accumulator, MembershipProof, NonmembershipProof, Witness};
+use crate::group::UnknownOrderGroup;
+use std::collections::VecDeque;
+use std::hash::Hash;
+
+// Separates epoch contexts from any other context hashed into proofs.
+const EPOCH_CONTEXT: &str = "accumulator epoch";
+
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+/// A proof bound to the epoch of the accumulator it was made against. See the module docs.
+pub struct EpochProof<P> {
+  epoch: u64,
+  proof: P,
+}
+
+impl<P> EpochProof<P> {
+  /// Returns the epoch the proof is bound to.
+  pub fn epoch(&self) -> u64 {
+    self.epoch
+  }
+
+  /// Returns the underlying proof. It does not verify with the plain verification functions.
+  pub fn proof(&self) -> &P {
+    &self.proof
+  }
+}
+
+impl<G: UnknownOrderGroup, T: Eq + Hash> Accumulator<G, T> {
+  /// Like `prove_membership`, for this accumulator as of `epoch`.
+  pub fn prove_membership_at_epoch(
+    &self,
+    epoch: u64,
+    elem_witnesses: &[(T, Witness<G, T>)],
+  ) -> Result<EpochProof<MembershipProof<G, T>>, AccError> {
+    let proof = self.prove_membership_with_context(elem_witnesses, &(EPOCH_CONTEXT, epoch))?;
+    Ok(EpochProof { epoch, proof })
+  }
+
+  /// Verifies that `proof` shows `elems` are in this accumulator, as of `epoch`.
+  pub fn verify_membership_at_epoch(
+    &self,
+    epoch: u64,
+    elems: &[T],
+    proof: &EpochProof<MembershipProof<G, T>>,
+  ) -> bool {
+    proof.epoch == epoch
+      && self.verify_membership_with_context(elems, proof.proof.borrowed(), &(EPOCH_CONTEXT, epoch))
+  }
+
+  /// Like `prove_nonmembership`, for this accumulator as of `epoch`.
+  pub fn prove_nonmembership_at_epoch(
+    &self,
+    epoch: u64,
+    acc_set: &[T],
+    elems: &[T],
+  ) -> Result<EpochProof<NonmembershipProof<G, T>>, AccError> {
+    let proof = self.prove_nonmembership_with_context(acc_set, elems, &(EPOCH_CONTEXT, epoch))?;
+    Ok(EpochProof { epoch, proof })
+  }
+
+  /// Verifies that `proof` shows `elems` are not in this accumulator, as of `epoch`.
+  pub fn verify_nonmembership_at_epoch(
+    &self,
+    epoch: u64,
+    elems: &[T],
+    proof: &EpochProof<NonmembershipProof<G, T>>,
+  ) -> bool {
+    proof.epoch == epoch
+      && self.verify_nonmembership_with_context(elems, &proof.proof, &(EPOCH_CONTEXT, epoch))
+  }
+}
+
+/// Verifies epoch-bound proofs against the accumulators of recent epochs. See the module docs.
+pub struct EpochVerifier<G: UnknownOrderGroup, T: Hash> {
+  max_age: u64,
+  // The accumulators of the recorded epochs still within `max_age`, oldest first.
+  accs: VecDeque<(u64, Accumulator<G, T>)>,
+}
+
+impl<G: UnknownOrderGroup, T: Eq + Hash> EpochVerifier<G, T> {
+  /// Returns a verifier with no recorded epochs that accepts proofs up to `max_age` epochs older
+  /// than the current one.
+  pub fn new(max_age: u64) -> Self {
+    Self {
+      max_age,
+      accs: VecDeque::new(),
+    }
+  }
+
+  /// Returns the maximum age of accepted proofs, in epochs.
+  pub fn max_age(&self) -> u64 {
+    self.max_age
+  }
+
+  /// Returns the most recently recorded epoch, if any.
+  pub fn current_epoch(&self) -> Option<u64> {
+    self.accs.back().map(|(epoch, _)| *epoch)
+  }
+
+  /// Records `acc` as the accumulator of `epoch`, which becomes the current epoch, and forgets
+  /// accumulators more than `max_age` epochs older. Panics if `epoch` is not after the current
+  /// epoch.
+  pub fn record(&mut self, epoch: u64, acc: Accumulator<G, T>) {
+    if let Some(current) = self.current_epoch() {
+      assert!(
+        epoch > current,
+        "epochs must be recorded in increasing order"
+      );
+    }
+    self.accs.push_back((epoch, acc));
+    while self.accs[0].0 < epoch.saturating_sub(self.max_age) {
+      self.accs.pop_front();
+    }
+  }
+
+  /// Verifies that `proof` shows `elems` are accumulated as of its epoch. Returns `StaleProof` if
+  /// its epoch is older than `max_age` epochs or was never recorded.
+  pub fn verify_membership(
+    &self,
+    elems: &[T],
+    proof: &EpochProof<MembershipProof<G, T>>,
+  ) -> Result<bool, AccError> {
+    let acc = self.accumulator_at(proof.epoch)?;
+    Ok(acc.verify_membership_at_epoch(proof.epoch, elems, proof))
+  }
+
+  /// Verifies that `proof` shows `elems` are not accumulated as of its epoch. Returns
+  /// `StaleProof` if its epoch is older than `max_age` epochs or was never recorded.
+  pub fn verify_nonmembership(
+    &self,
+    elems: &[T],
+    proof: &EpochProof<NonmembershipProof<G, T>>,
+  ) -> Result<bool, AccError> {
+    let acc = self.accumulator_at(proof.epoch)?;
+    Ok(acc.verify_nonmembership_at_epoch(proof.epoch, elems, proof))
+  }
+
+  fn accumulator_at(&self, epoch: u64) -> Result<&Accumulator<G, T>, AccError> {
+    self
+      .accs
+      .iter()
+      .find(|(e, _)| *e == epoch)
+      .map(|(_, acc)| acc)
+      .ok_or(AccError::StaleProof)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::group::Rsa2048;
+
+  fn is_stale(result: Result<bool, AccError>) -> bool {
+    matches!(result, Err(AccError::StaleProof))
+  }
+
+  #[test]
+  fn test_epoch_proofs() {
+    let empty = Accumulator::<Rsa2048, &'static str>::empty();
+    let acc_0 = empty.clone().add(&["a", "b", "c"]);
+    let acc_1 = acc_0.clone().add(&["e"]);
+    let witness_a = Witness(empty.clone().add(&["b", "c"]));
+    let witness_b = Witness(empty.add(&["a", "c"]));
+    let proof = acc_0
+      .prove_membership_at_epoch(0, &[("a", witness_a), ("b", witness_b)])
+      .unwrap();
+    assert_eq!(proof.epoch(), 0);
+    assert!(acc_0.verify_membership_at_epoch(0, &["a", "b"], &proof));
+    assert!(!acc_0.verify_membership_at_epoch(1, &["a", "b"], &proof));
+    assert!(!acc_0.verify_membership_batch(&["a", "b"], proof.proof()));
+    let relabeled = EpochProof {
+      epoch: 1,
+      proof: proof.proof().clone(),
+    };
+    assert!(!acc_0.verify_membership_at_epoch(1, &["a", "b"], &relabeled));
+
+    let nonmembership = acc_1
+      .prove_nonmembership_at_epoch(1, &["a", "b", "c", "e"], &["d"])
+      .unwrap();
+    assert!(acc_1.verify_nonmembership_at_epoch(1, &["d"], &nonmembership));
+    assert!(!acc_1.verify_nonmembership_at_epoch(2, &["d"], &nonmembership));
+    assert!(!acc_1.verify_nonmembership(&["d"], nonmembership.proof()));
+
+    let mut verifier = EpochVerifier::new(1);
+    verifier.record(0, acc_0);
+    verifier.record(1, acc_1.clone());
+    assert!(verifier.verify_membership(&["a", "b"], &proof).unwrap());
+    assert!(verifier
+      .verify_nonmembership(&["d"], &nonmembership)
+      .unwrap());
+    verifier.record(2, acc_1.clone().add(&["d"]));
+    assert_eq!(verifier.current_epoch(), Some(2));
+    assert!(is_stale(verifier.verify_membership(&["a", "b"], &proof)));
+    assert!(verifier
+      .verify_nonmembership(&["d"], &nonmembership)
+      .unwrap());
+    verifier.record(4, acc_1);
+    assert!(is_stale(
+      verifier.verify_nonmembership(&["d"], &nonmembership)
+    ));
+    let future = EpochProof {
+      epoch: 5,
+      proof: proof.proof().clone(),
+    };
+    assert!(is_stale(verifier.verify_membership(&["a", "b"], &future)));
+  }
+}
@@ -0,0 +1,171 @@
+//! Accumulators held together with their full set of elements.
+//!
+//! Some operations need the whole accumulated set: deleting without witnesses, computing a witness
+//! for any element, and proving nonmembership. On `Accumulator` they take the set or an
+//! `ExponentState` as an argument, and nothing ties the argument to the accumulator beyond a
+//! runtime check. A `FullSetAccumulator` instead owns its set and state, and keeps them in step
+//! with the accumulator through its own `add` and `delete`, so these operations can only be called
+//! where the set is actually tracked. A plain `Accumulator` is the view without the set, handed out
+//! by `accumulator` and `into_accumulator` for publishing.
+use crate::accumulator::{
+  AccError, Accumulator, ExponentState, MembershipProof, NonmembershipProof, Witness,
+};
+use crate::group::UnknownOrderGroup;
+use crate::hash::{PrimeBits, PrimeParams};
+use crate::integer::Integer;
+use std::collections::HashSet;
+use std::hash::Hash;
+
+/// An accumulator together with its full set of elements. See the module docs.
+pub struct FullSetAccumulator<G: UnknownOrderGroup, T: Hash> {
+  acc: Accumulator<G, T>,
+  state: ExponentState<G, T>,
+  set: HashSet<T>,
+}
+
+// Manual clone impl for the same reason as `Accumulator`.
+impl<G: UnknownOrderGroup, T: Clone + Hash> Clone for FullSetAccumulator<G, T> {
+  fn clone(&self) -> Self {
+    Self {
+      acc: self.acc.clone(),
+      state: self.state.clone(),
+      set: self.set.clone(),
+    }
+  }
+}
+
+impl<G: UnknownOrderGroup, T: Clone + Eq + Hash> FullSetAccumulator<G, T> {
+  /// Returns an empty accumulator with default parameters.
+  pub fn empty() -> Self {
+    Self::with_params(PrimeBits::B256)
+  }
+
+  /// Returns an empty accumulator whose elements are mapped to primes according to `params`.
+  pub fn with_params<P: Into<PrimeParams>>(params: P) -> Self {
+    let params = params.into();
+    Self {
+      acc: Accumulator::with_params(params),
+      state: ExponentState::with_params(params),
+      set: HashSet::new(),
+    }
+  }
+
+  /// Returns the accumulator, without the set.
+  pub fn accumulator(&self) -> &Accumulator<G, T> {
+    &self.acc
+  }
+
+  /// Drops the set and returns the accumulator.
+  pub fn into_accumulator(self) -> Accumulator<G, T> {
+    self.acc
+  }
+
+  /// Returns the tracked exponent state.
+  pub fn state(&self) -> &ExponentState<G, T> {
+    &self.state
+  }
+
+  /// Returns the accumulated set.
+  pub fn set(&self) -> &HashSet<T> {
+    &self.set
+  }
+
+  /// Returns whether `t` is accumulated.
+  pub fn contains(&self, t: &T) -> bool {
+    self.set.contains(t)
+  }
+
+  /// Records that the holder knows `order_multiple`, a positive multiple of the group order. See
+  /// `ExponentState::set_order_multiple`. Panics if `order_multiple` is not positive.
+  pub fn set_order_multiple(&mut self, order_multiple: Integer) {
+    self.state.set_order_multiple(order_multiple);
+  }
+
+  /// Adds `elems`. Returns `DuplicateElement` without changing anything if an element is already
+  /// accumulated or repeated in `elems`.
+  pub fn add(&mut self, elems: &[T]) -> Result<(), AccError> {
+    let mut added = HashSet::with_capacity(elems.len());
+    if elems
+      .iter()
+      .any(|t| self.set.contains(t) || !added.insert(t))
+    {
+      return Err(AccError::DuplicateElement);
+    }
+    self.acc = self.acc.add_ref(elems);
+    self.state.add(elems);
+    self.set.extend(elems.iter().cloned());
+    Ok(())
+  }
+
+  /// Deletes `elems` without witnesses, at the cost of one exponentiation. Returns `BadWitness`
+  /// without changing anything if an element is not accumulated or repeated in `elems`.
+  pub fn delete(&mut self, elems: &[T]) -> Result<(), AccError> {
+    let mut deleted = HashSet::with_capacity(elems.len());
+    if elems
+      .iter()
+      .any(|t| !self.set.contains(t) || !deleted.insert(t))
+    {
+      return Err(AccError::BadWitness);
+    }
+    self.acc = self.acc.delete_with_state_ref(&mut self.state, elems)?;
+    for t in elems {
+      self.set.remove(t);
+    }
+    Ok(())
+  }
+
+  /// Computes a witness for `elems`, which must all be accumulated.
+  pub fn witness(&self, elems: &[T]) -> Result<Witness<G, T>, AccError> {
+    if !elems.iter().all(|t| self.set.contains(t)) {
+      return Err(AccError::BadWitness);
+    }
+    self.state.witness(elems)
+  }
+
+  /// Proves that `elems` are accumulated.
+  pub fn prove_membership(&self, elems: &[T]) -> Result<MembershipProof<G, T>, AccError> {
+    if !elems.iter().all(|t| self.set.contains(t)) {
+      return Err(AccError::BadWitness);
+    }
+    self.acc.prove_membership_with_state(&self.state, elems)
+  }
+
+  /// Proves that none of `elems` are accumulated.
+  pub fn prove_nonmembership(&self, elems: &[T]) -> Result<NonmembershipProof<G, T>, AccError> {
+    let set = self.set.iter().cloned().collect::<Vec<_>>();
+    self.acc.prove_nonmembership(&set, elems)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::group::Rsa2048;
+
+  #[test]
+  fn test_full_set_accumulator() {
+    let mut acc = FullSetAccumulator::<Rsa2048, &'static str>::empty();
+    acc.add(&["a", "b", "c"]).unwrap();
+    assert!(acc.add(&["d", "a"]).is_err());
+    assert!(acc.add(&["d", "d"]).is_err());
+    assert_eq!(acc.set().len(), 3);
+    assert!(*acc.accumulator() == Accumulator::empty().add(&["a", "b", "c"]));
+
+    let witness = acc.witness(&["b"]).unwrap();
+    assert!(witness.0.add(&["b"]) == *acc.accumulator());
+    let proof = acc.prove_membership(&["a", "c"]).unwrap();
+    assert!(acc
+      .accumulator()
+      .verify_membership_batch(&["a", "c"], &proof));
+    assert!(acc.prove_membership(&["d"]).is_err());
+
+    acc.delete(&["b"]).unwrap();
+    assert!(!acc.contains(&"b"));
+    assert!(acc.delete(&["b"]).is_err());
+    assert!(acc.delete(&["a", "a"]).is_err());
+    assert!(acc.witness(&["b"]).is_err());
+    let proof = acc.prove_nonmembership(&["b", "d"]).unwrap();
+    assert!(acc.accumulator().verify_nonmembership(&["b", "d"], &proof));
+    assert!(acc.into_accumulator() == Accumulator::empty().add(&["a", "c"]));
+  }
+}
@@ -0,0 +1,249 @@
+//! Accumulators whose updates must be authorized by a threshold of maintainers.
+//!
+//! A `GovernedAccumulator` applies an `AccumulatorUpdate` only if it carries valid signatures from
+//! at least a threshold of maintainers, as decided by its `UpdatePolicy`. The crate does not pick a
+//! signature scheme: the policy holds the maintainers' public keys, and a callback that checks one
+//! signature on the digest of an update. Updates are numbered by epoch and name the digest of the
+//! accumulator they apply to, so a signed update cannot be replayed, neither later nor against
+//! another state or instance.
+use crate::accumulator::{AccError, Accumulator, ExponentState};
+use crate::group::{ElemBytes, UnknownOrderGroup};
+use crate::hash::{hash, Blake2b, PrimeBits, PrimeParams};
+use std::hash::Hash;
+
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+/// A batch of changes to a `GovernedAccumulator`, for its maintainers to sign.
+pub struct AccumulatorUpdate<T> {
+  /// The epoch of the accumulator the update applies to.
+  pub epoch: u64,
+  /// The `Accumulator::digest` of the accumulator the update applies to, which covers its value
+  /// and parameters.
+  pub base: [u8; 32],
+  /// The elements to add.
+  pub additions: Vec<T>,
+  /// The elements to delete.
+  pub deletions: Vec<T>,
+}
+
+impl<T: Hash> AccumulatorUpdate<T> {
+  /// Returns the message maintainers sign: the Blake2b digest of the update, including the
+  /// accumulator it applies to.
+  pub fn digest(&self) -> [u8; 32] {
+    hash(&Blake2b::default, &("accumulator update", self))
+  }
+}
+
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+/// An update together with maintainers' signatures on its digest. Each signature is tagged with the
+/// signer's index in the `UpdatePolicy`.
+pub struct SignedUpdate<T> {
+  /// The update.
+  pub update: AccumulatorUpdate<T>,
+  /// `(maintainer index, signature)` pairs.
+  pub signatures: Vec<(usize, Vec<u8>)>,
+}
+
+/// Checks a signature on an update digest against a maintainer's public key.
+pub type SignatureVerifier<K> = fn(key: &K, digest: &[u8; 32], signature: &[u8]) -> bool;
+
+#[derive(Clone, Debug)]
+/// The maintainers of a `GovernedAccumulator`, and how many of them must sign an update.
+pub struct UpdatePolicy<K> {
+  maintainers: Vec<K>,
+  threshold: usize,
+  verify: SignatureVerifier<K>,
+}
+
+impl<K> UpdatePolicy<K> {
+  /// Returns a policy requiring valid signatures, as checked by `verify`, from `threshold` of
+  /// `maintainers`.
+  ///
+  /// Panics if `threshold` is zero or exceeds the number of maintainers.
+  pub fn new(maintainers: Vec<K>, threshold: usize, verify: SignatureVerifier<K>) -> Self {
+    assert!(
+      threshold > 0 && threshold <= maintainers.len(),
+      "threshold must be between 1 and the number of maintainers"
+    );
+    Self {
+      maintainers,
+      threshold,
+      verify,
+    }
+  }
+
+  /// Returns whether `signed` carries valid signatures from at least the threshold of distinct
+  /// maintainers. Signatures from unknown indices, repeated signers and invalid signatures do not
+  /// count.
+  pub fn authorizes<T: Hash>(&self, signed: &SignedUpdate<T>) -> bool {
+    let digest = signed.update.digest();
+    let mut signed_by = vec![false; self.maintainers.len()];
+    for (i, signature) in &signed.signatures {
+      if let Some(key) = self.maintainers.get(*i) {
+        if !signed_by[*i] && (self.verify)(key, &digest, signature) {
+          signed_by[*i] = true;
+        }
+      }
+    }
+    signed_by.iter().filter(|&&signed| signed).count() >= self.threshold
+  }
+}
+
+/// An accumulator that only changes through updates authorized by its `UpdatePolicy`. Keeps the
+/// exponent state, so that deletions need no witnesses.
+pub struct GovernedAccumulator<G: UnknownOrderGroup, T: Hash, K> {
+  acc: Accumulator<G, T>,
+  state: ExponentState<G, T>,
+  epoch: u64,
+  policy: UpdatePolicy<K>,
+}
+
+impl<G: UnknownOrderGroup, T: Eq + Hash, K> GovernedAccumulator<G, T, K> {
+  /// Returns an empty accumulator governed by `policy`. Elements are hashed to 256-bit primes.
+  pub fn new(policy: UpdatePolicy<K>) -> Self {
+    Self::with_params(policy, PrimeBits::B256)
+  }
+
+  /// Returns an empty accumulator governed by `policy`, whose elements are mapped to primes
+  /// according to `params`.
+  pub fn with_params<P: Into<PrimeParams>>(policy: UpdatePolicy<K>, params: P) -> Self {
+    let params = params.into();
+    Self {
+      acc: Accumulator::with_params(params),
+      state: ExponentState::with_params(params),
+      epoch: 0,
+      policy,
+    }
+  }
+
+  /// Returns the current accumulator.
+  pub fn accumulator(&self) -> &Accumulator<G, T> {
+    &self.acc
+  }
+
+  /// Returns the number of updates applied so far, which the next update must be for.
+  pub fn epoch(&self) -> u64 {
+    self.epoch
+  }
+
+  /// Returns the policy updates are checked against.
+  pub fn policy(&self) -> &UpdatePolicy<K> {
+    &self.policy
+  }
+}
+
+impl<G: ElemBytes + UnknownOrderGroup, T: Eq + Hash, K> GovernedAccumulator<G, T, K> {
+  /// Applies `signed`, deleting then adding its elements. Returns `Unauthorized` if it is not for
+  /// the current epoch and accumulator or the policy does not authorize it, and leaves the
+  /// accumulator unchanged on any error.
+  pub fn apply_update(&mut self, signed: &SignedUpdate<T>) -> Result<(), AccError> {
+    let update = &signed.update;
+    if update.epoch != self.epoch
+      || update.base != self.acc.digest()
+      || !self.policy.authorizes(signed)
+    {
+      return Err(AccError::Unauthorized);
+    }
+    let mut state = self.state.clone();
+    let acc = self
+      .acc
+      .delete_with_state_ref(&mut state, &update.deletions)?
+      .add(&update.additions);
+    state.add(&update.additions);
+    self.acc = acc;
+    self.state = state;
+    self.epoch += 1;
+    Ok(())
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::group::Rsa2048;
+
+  // A stand-in signature scheme: keys are secrets and signatures are keyed hashes.
+  fn sign(key: &u8, update: &AccumulatorUpdate<&'static str>) -> Vec<u8> {
+    hash(&Blake2b::default, &(key, update.digest())).to_vec()
+  }
+
+  fn verify(key: &u8, digest: &[u8; 32], signature: &[u8]) -> bool {
+    signature == hash(&Blake2b::default, &(key, digest))
+  }
+
+  fn signed(
+    update: AccumulatorUpdate<&'static str>,
+    signers: &[usize],
+  ) -> SignedUpdate<&'static str> {
+    let signatures = signers
+      .iter()
+      .map(|&i| (i, sign(&(i as u8), &update)))
+      .collect();
+    SignedUpdate { update, signatures }
+  }
+
+  fn update(
+    governed: &GovernedAccumulator<Rsa2048, &'static str, u8>,
+    epoch: u64,
+    additions: &[&'static str],
+    deletions: &[&'static str],
+  ) -> AccumulatorUpdate<&'static str> {
+    AccumulatorUpdate {
+      epoch,
+      base: governed.accumulator().digest(),
+      additions: additions.to_vec(),
+      deletions: deletions.to_vec(),
+    }
+  }
+
+  #[test]
+  fn test_apply_update() {
+    let policy = UpdatePolicy::new(vec![0, 1, 2], 2, verify);
+    let mut governed = GovernedAccumulator::<Rsa2048, &'static str, u8>::new(policy);
+    governed
+      .apply_update(&signed(update(&governed, 0, &["a", "b"], &[]), &[0, 2]))
+      .unwrap();
+    governed
+      .apply_update(&signed(update(&governed, 1, &["c"], &["a"]), &[1, 2]))
+      .unwrap();
+    assert_eq!(governed.epoch(), 2);
+    assert!(*governed.accumulator() == Accumulator::empty().add(&["b", "c"]));
+  }
+
+  #[test]
+  fn test_apply_update_unauthorized() {
+    let policy = UpdatePolicy::new(vec![0, 1, 2], 2, verify);
+    let mut governed = GovernedAccumulator::<Rsa2048, &'static str, u8>::new(policy);
+    let acc = governed.accumulator().clone();
+
+    // Too few, repeated, unknown and forged signers.
+    assert!(governed
+      .apply_update(&signed(update(&governed, 0, &["a"], &[]), &[1]))
+      .is_err());
+    assert!(governed
+      .apply_update(&signed(update(&governed, 0, &["a"], &[]), &[1, 1]))
+      .is_err());
+    assert!(governed
+      .apply_update(&signed(update(&governed, 0, &["a"], &[]), &[1, 3]))
+      .is_err());
+    let mut forged = signed(update(&governed, 0, &["a"], &[]), &[0, 1]);
+    forged.update.additions.push("b");
+    assert!(governed.apply_update(&forged).is_err());
+
+    // Updates for another accumulator.
+    let mut other = update(&governed, 0, &["a"], &[]);
+    other.base = Accumulator::<Rsa2048, &'static str>::empty()
+      .add(&["x"])
+      .digest();
+    assert!(governed.apply_update(&signed(other, &[0, 1])).is_err());
+
+    // Replays and deletions of elements not present.
+    assert!(governed
+      .apply_update(&signed(update(&governed, 1, &["a"], &[]), &[0, 1]))
+      .is_err());
+    assert!(governed
+      .apply_update(&signed(update(&governed, 0, &[], &["a"]), &[0, 1]))
+      .is_err());
+    assert!(*governed.accumulator() == acc);
+    assert_eq!(governed.epoch(), 0);
+  }
+}
@@ -0,0 +1,89 @@
+//! Runtime calibration of algorithm thresholds, enabled with the `calibrate` feature.
+//!
+//! Which algorithm is fastest for an operation depends on the relative cost of group operations,
+//! exponentiations, and bookkeeping on the host, which differs between e.g. desktops and low-power
+//! ARM verifiers. Without this feature, thresholds are fixed defaults tuned on desktops. With it,
+//! each threshold is measured the first time it is needed and cached for the life of the process.
+use super::{multi_exp_, multi_exp_direct, ElemFrom, Rsa2048};
+use crate::hash::{hash_to_prime_bits, PrimeBits};
+use std::time::{Duration, Instant};
+
+// Each candidate is timed this many times, keeping the best run to filter out scheduling noise.
+const REPS: usize = 3;
+
+lazy_static! {
+  static ref MULTI_EXP_CUTOFF: usize = measure_multi_exp_cutoff();
+}
+
+/// Returns the longest input for which `multi_exp` exponentiates each element directly instead of
+/// splitting the input in half.
+pub fn multi_exp_cutoff() -> usize {
+  *MULTI_EXP_CUTOFF
+}
+
+/// Returns the candidate for which `f` runs fastest.
+#[allow(dead_code)] // Only used by the class group, which is not currently exported.
+pub(crate) fn fastest<C: Copy>(candidates: &[C], mut f: impl FnMut(C)) -> C {
+  fastest_by(candidates, |c| time(|| f(c)))
+}
+
+// Returns the candidate with the least `cost`, the first one on ties.
+fn fastest_by<C: Copy>(candidates: &[C], mut cost: impl FnMut(C) -> Duration) -> C {
+  candidates.iter().copied().min_by_key(|&c| cost(c)).unwrap()
+}
+
+fn time(mut f: impl FnMut()) -> Duration {
+  (0..REPS)
+    .map(|_| {
+      let start = Instant::now();
+      f();
+      start.elapsed()
+    })
+    .min()
+    .unwrap()
+}
+
+// Doubles the input length until splitting beats direct exponentiation. Measured on RSA-2048 with
+// 128-bit exponents, which is representative of PoKCR verification.
+fn measure_multi_exp_cutoff() -> usize {
+  let mut cutoff = 1;
+  for &len in &[2, 4, 8, 16] {
+    let alphas = (0..len).map(|i| Rsa2048::elem(i + 2)).collect::<Vec<_>>();
+    let x = (0..len)
+      .map(|i| hash_to_prime_bits(&i, PrimeBits::B128))
+      .collect::<Vec<_>>();
+    let direct = time(|| {
+      multi_exp_direct::<Rsa2048>(&alphas, &x);
+    });
+    let split = time(|| {
+      multi_exp_::<Rsa2048>(&alphas, &x, 1);
+    });
+    if split < direct {
+      break;
+    }
+    cutoff = len;
+  }
+  cutoff
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_fastest() {
+    let millis = fastest_by(&[20, 1, 10], Duration::from_millis);
+    assert_eq!(millis, 1);
+    assert_eq!(fastest_by(&[3, 2, 2], Duration::from_millis), 2);
+
+    // Each candidate is run `REPS` times.
+    let mut runs = [0; 3];
+    fastest(&[0, 1, 2], |i| runs[i] += 1);
+    assert_eq!(runs, [REPS; 3]);
+  }
+
+  #[test]
+  fn test_multi_exp_cutoff() {
+    assert!([1, 2, 4, 8, 16].contains(&multi_exp_cutoff()));
+  }
+}
@@ -1,19 +1,57 @@
-//! Fixed-discriminant implementation of an ideal class group, with future optimizations.
+//! Fixed-discriminant implementations of ideal class groups, with future optimizations.
 //!
 //! Using a class group instead of an RSA group for accumulators or vector commitments eliminates
 //! the need for a trusted setup, albeit at the expense of slower operations.
-use super::{ElemFrom, Group, UnknownOrderGroup};
+use super::{ElemBytes, ElemFrom, Group, UnknownOrderGroup};
+use crate::hash::hash_to_prime;
+use crate::integer::Integer;
 use crate::util;
 use crate::util::{int, TypeRep};
-use rug::{Assign, Integer};
+use rug::integer::Order;
+use rug::ops::NegAssign;
+use rug::Assign;
+use std::fmt::Debug;
 use std::hash::{Hash, Hasher};
+use std::marker::PhantomData;
 use std::str::FromStr;
 
 #[allow(clippy::module_name_repetitions)]
 #[derive(Clone, Debug, PartialEq, Eq, Hash)]
 /// Class group implementation, with future optimizations available via the `--features` flag.
-/// Discriminant generated via OpenSSL.
-pub enum ClassGroup {}
+/// The discriminant, and so the security level, is chosen by `D`; see `ClassGroup`,
+/// `ClassGroup1827` and `ClassGroup3598`.
+pub struct ClassGroupOf<D: Discriminant>(PhantomData<D>);
+
+/// Class group with a 2048-bit discriminant generated via OpenSSL.
+pub type ClassGroup = ClassGroupOf<Discriminant2048>;
+
+/// Class group with a 1827-bit discriminant, for 128-bit security.
+pub type ClassGroup1827 = ClassGroupOf<Discriminant1827>;
+
+/// Class group with a 3598-bit discriminant, for 192-bit security.
+pub type ClassGroup3598 = ClassGroupOf<Discriminant3598>;
+
+/// A class group parameter set: a negated prime discriminant `D`, with `-D = 7 mod 8` so that
+/// `(2, 1, (1 - D) / 8)` is a form of discriminant `D`.
+pub trait Discriminant: Clone + Debug + Eq + Hash + Send + Sync + 'static {
+  /// The `ElemBytes::GROUP_ID` of the class group with this discriminant.
+  const GROUP_ID: u8;
+
+  /// Returns the discriminant.
+  fn discriminant() -> &'static Integer;
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+/// The discriminant of `ClassGroup`.
+pub enum Discriminant2048 {}
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+/// The discriminant of `ClassGroup1827`.
+pub enum Discriminant1827 {}
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+/// The discriminant of `ClassGroup3598`.
+pub enum Discriminant3598 {}
 
 // 2048-bit prime, negated, congruent to `3 mod 4`. Generated using OpenSSL.
 // According to "A Survey of IQ Cryptography" (Buchmann & Hamdy) Table 1, IQ-MPQS for computing
@@ -28,9 +66,77 @@ const DISCRIMINANT2048_DECIMAL: &str =
   9453371727344087286361426404588335160385998280988603297435639020911295652025967761702701701471162\
   3966286152805654229445219531956098223";
 
+// Discriminant sizes for 128-bit and 192-bit security, following the estimates used by Castagnos
+// and Laguillaumie ("Linearly Homomorphic Encryption from DDH", CT-RSA 2015). Each is the negation
+// of the least prime `p = 7 mod 8` at or above `x`, where `x` is the Blake2b expansion of
+// `("accumulator class group discriminant <bits>", i)` for `i = 0, 1, ...`, truncated to `bits`
+// bits with the top bit set.
+const DISCRIMINANT1827_DECIMAL: &str =
+  "-51288459466767162027881567527108393093134056955455919151834507345604928292195684166479975796008\
+  5263750177784175877462334720746033427405716617395787721434219390292542379018839314408489425119379\
+  6505945926300851369741753895641830466910581404390760607899097911017180450666198656641226552614112\
+  3499250942259108325849669872889239625805760681267153250486095508408924498628053113590536052543671\
+  0028431975048681768403948935541444768301982302554235617635062282123261635614846144181639417090970\
+  4210111526372599781436565040644183670958861740482803539295145058807";
+
+const DISCRIMINANT3598_DECIMAL: &str =
+  "-75564240147300613441589068173312474165480064471871133002771141146735746109539597461166533301068\
+  5564099626559042706287089866720479519673398055994322217239517149397548777380497025164444422711428\
+  4044543770911172690918088436945232554517949078155066267452511633297996070059167618939671039761703\
+  9465059977313129651347583054918801924237707759307381926233049355127665621501820024916358141080602\
+  2037009767084534242297991884221170555990050022241260807736562924095979790779974194403561134264366\
+  1232517647793940932711109762076389019400592084557881816919344306964781988507930115885295680186848\
+  7371947757050838617760450703545005442050004846709970676119201817142174232916162266756920362730678\
+  0468786191287208835302051849584677954351376490780797204702702545332284656284600509413095771906705\
+  3356192429164734335524289879595520854994333494969396037907859175122013818652958131388334871865433\
+  5894132316549558346884087311997235564651508608082028993217125947405519010635804216008582157358287\
+  5615572375011246806318631396407361982668596175617333266105127656553779274361424400294708206326652\
+  906734563281480879";
+
+// Width of the windowed NAF used by `exp_`, unless the `calibrate` feature measures a better one.
+// Wider windows trade a larger table of precomputed odd powers for fewer compositions.
+#[cfg(any(test, not(feature = "calibrate")))]
+const EXP_WINDOW: u32 = 5;
+
 lazy_static! {
   pub static ref CLASS_GROUP_DISCRIMINANT: Integer =
     Integer::from_str(DISCRIMINANT2048_DECIMAL).unwrap();
+  static ref CLASS_GROUP_DISCRIMINANT_1827: Integer =
+    Integer::from_str(DISCRIMINANT1827_DECIMAL).unwrap();
+  static ref CLASS_GROUP_DISCRIMINANT_3598: Integer =
+    Integer::from_str(DISCRIMINANT3598_DECIMAL).unwrap();
+}
+
+impl Discriminant for Discriminant2048 {
+  const GROUP_ID: u8 = 2;
+  fn discriminant() -> &'static Integer {
+    &CLASS_GROUP_DISCRIMINANT
+  }
+}
+
+impl Discriminant for Discriminant1827 {
+  const GROUP_ID: u8 = 3;
+  fn discriminant() -> &'static Integer {
+    &CLASS_GROUP_DISCRIMINANT_1827
+  }
+}
+
+impl Discriminant for Discriminant3598 {
+  const GROUP_ID: u8 = 4;
+  fn discriminant() -> &'static Integer {
+    &CLASS_GROUP_DISCRIMINANT_3598
+  }
+}
+
+#[cfg(feature = "calibrate")]
+lazy_static! {
+  static ref CALIBRATED_EXP_WINDOW: u32 = {
+    let g = ClassGroup::unknown_order_elem();
+    let n = crate::hash::blake2b(&"calibrate");
+    super::calibrate::fastest(&[3, 4, 5, 6], |w| {
+      ClassGroup::exp_windowed(&g, &n, w);
+    })
+  };
 }
 
 #[allow(clippy::module_name_repetitions)]
@@ -43,9 +149,9 @@ pub struct ClassElem {
   c: Integer,
 }
 
-// `ClassElem` and `ClassGroup` ops based on Chia's fantastic doc explaining applied class groups:
+// `ClassElem` and `ClassGroupOf` ops based on Chia's fantastic doc explaining applied class groups:
 // https://github.com/Chia-Network/vdf-competition/blob/master/classgroups.pdf.
-impl ClassGroup {
+impl<D: Discriminant> ClassGroupOf<D> {
   /// This method is only public for benchmarking. You should not need to use it.
   pub fn normalize(a: Integer, b: Integer, c: Integer) -> (Integer, Integer, Integer) {
     if Self::is_normal(&a, &b, &c) {
@@ -63,6 +169,14 @@ impl ClassGroup {
   // Note: Does not return a `ClassElem` because the output is not guaranteed to be
   // a valid `ClassElem` for all inputs.
   pub fn reduce(mut a: Integer, mut b: Integer, mut c: Integer) -> (Integer, Integer, Integer) {
+    // No reduction step takes a coefficient of a positive definite form past the bits of the
+    // largest input coefficient or of the discriminant, so anything larger is an arithmetic bug.
+    #[cfg(all(feature = "paranoid", debug_assertions))]
+    let (d, max_bits) = {
+      let d = Self::discriminant(&a, &b, &c);
+      let max_bits = Self::coefficient_bits(&a, &b, &c).max(d.significant_bits());
+      (d, max_bits)
+    };
     while !Self::is_reduced(&a, &b, &c) {
       // s = floor_div(c + b, 2c)
       let (s, _) = int(&c + &b).div_rem_floor(int(2 * &c));
@@ -73,27 +187,190 @@ impl ClassGroup {
       a = c.clone();
       b = -b + 2 * int(&s * &c);
       c = -int(&old_b * &s) + old_a + c * s.square();
+      #[cfg(all(feature = "paranoid", debug_assertions))]
+      assert!(
+        Self::coefficient_bits(&a, &b, &c) <= max_bits,
+        "reduction step grew a coefficient past {} bits: ({}, {}, {})",
+        max_bits,
+        a,
+        b,
+        c
+      );
     }
-    Self::normalize(a, b, c)
+    let (a, b, c) = Self::normalize(a, b, c);
+    #[cfg(all(feature = "paranoid", debug_assertions))]
+    assert!(
+      Self::within_reduced_bounds(&d, &a, &b, &c),
+      "reduction produced coefficients too large for discriminant {}: ({}, {}, {})",
+      d,
+      a,
+      b,
+      c
+    );
+    (a, b, c)
   }
 
   #[allow(non_snake_case)]
   /// This method is only public for benchmarking. You should not need to use it.
+  // Shanks' NUDUPL, following Cohen's "A Course in Computational Algebraic Number Theory",
+  // Algorithm 5.4.8. Squaring by `op_(x, x)` composes to a form with coefficients twice the size
+  // of the discriminant's square root and leaves all of the work to `reduce`. NUDUPL instead runs
+  // a partial Euclidean algorithm on half-size integers, stopped at `L = (|D| / 4)^(1/4)`, to
+  // produce a nearly reduced square. Exponentiation is dominated by squarings, so this is the hot
+  // path; see the `group_class_square_vs_op` bench.
   pub fn square(x: &ClassElem) -> ClassElem {
-    // Solve `bk = c mod a` for `k`, represented by `mu`, `v` and any integer `n` s.t.
-    // `k = mu + v * n`.
-    let (mu, _) = util::solve_linear_congruence(&x.b, &x.c, &x.a).unwrap();
-
-    // A = a^2
-    // B = b - 2a * mu
-    // tmp = (b * mu) / a
-    // C = mu^2 - tmp
-    let a = int(x.a.square_ref());
-    let b = &x.b - int(2 * &x.a) * &mu;
-    let (tmp, _) = <(Integer, Integer)>::from(int((&x.b * &mu) - &x.c).div_rem_floor_ref(&x.a));
-    let c = mu.square() - tmp;
+    let L = int(int(Self::rep().abs_ref()) >> 2).root(4);
+
+    // d1 = gcd(b, a) = u * b + v * a
+    // A = a / d1
+    // B = b / d1
+    // C = -cu mod A, taking the representative of least absolute value
+    let (d1, u, _) = <(Integer, Integer, Integer)>::from(x.b.gcd_cofactors_ref(&x.a));
+    let A = int(x.a.div_exact_ref(&d1));
+    let B = int(x.b.div_exact_ref(&d1));
+    let (_, mut C) = <(Integer, Integer)>::from(int(-int(&x.c * &u)).div_rem_euc_ref(&A));
+    let C1 = int(&A - &C);
+    if C1 < C {
+      C = -C1;
+    }
 
-    Self::elem((a, b, c))
+    // Partial Euclidean algorithm on `(A, C)`, stopped once `|v3| <= L`, with `d = v3_prev`
+    // and cofactors `v` and `v2`.
+    let mut v = int(0);
+    let mut d = A.clone();
+    let mut v2 = int(1);
+    let mut v3 = C;
+    let mut z = 0;
+    while int(v3.abs_ref()) > L {
+      let (q, t3) = <(Integer, Integer)>::from(d.div_rem_euc_ref(&v3));
+      let t2 = &v - q * &v2;
+      v = v2;
+      d = v3;
+      v2 = t2;
+      v3 = t3;
+      z += 1;
+    }
+    if z % 2 == 1 {
+      v2.neg_assign();
+      v3.neg_assign();
+    }
+
+    // a2 = d^2
+    // c2 = v3^2
+    // b2 = b + (d + v3)^2 - a2 - c2 when no step was taken, with the rest filled in below.
+    let a2 = int(d.square_ref());
+    let c2 = int(v3.square_ref());
+    if z == 0 {
+      // g = (B * v3 + c) / d
+      // c2 = c2 + g * d1
+      let g = int(int(&B * &v3) + &x.c).div_exact(&d);
+      let b2 = int(&x.b + int(&d + &v3).square()) - &a2 - &c2;
+      let c2 = c2 + g * &d1;
+      return Self::elem((a2, b2, c2));
+    }
+
+    // e = (c * v + B * d) / A
+    // g = (e * v2 - B) / v
+    // b2 = d1 * (e * v2 + v * g)
+    // v = d1 * v, v2 = d1 * v2
+    let e = int(int(&x.c * &v) + int(&B * &d)).div_exact(&A);
+    let g = int(int(&e * &v2) - &B).div_exact(&v);
+    let mut b2 = int(&e * &v2) + int(&v * &g);
+    if d1 > 1 {
+      b2 *= &d1;
+      v *= &d1;
+      v2 *= &d1;
+    }
+
+    // b2 = b2 + (d + v3)^2 - a2 - c2
+    // a2 = a2 + e * v
+    // c2 = c2 + g * v2
+    let b2 = b2 + int(&d + &v3).square() - &a2 - &c2;
+    let a2 = a2 + e * v;
+    let c2 = c2 + g * v2;
+    Self::elem((a2, b2, c2))
+  }
+
+  /// Returns the width-`w` non-adjacent form of `n >= 0`, least significant digit first. Nonzero
+  /// digits are odd and less than `2^(w - 1)` in absolute value, and at most one in any `w`
+  /// consecutive digits is nonzero.
+  fn wnaf(n: &Integer, w: u32) -> Vec<i32> {
+    let modulus = 1 << w;
+    let mut n = n.clone();
+    let mut digits = Vec::with_capacity(n.significant_bits() as usize + 1);
+    while n > int(0) {
+      let mut digit = 0;
+      if n.is_odd() {
+        digit = n.mod_u(modulus) as i32;
+        if digit >= (modulus / 2) as i32 {
+          digit -= modulus as i32;
+        }
+        n -= digit;
+      }
+      digits.push(digit);
+      n >>= 1;
+    }
+    digits
+  }
+
+  // Inverses are free and squaring is cheaper than composition, so we recode the exponent in
+  // windowed NAF: one squaring per bit, but only about one composition per `w + 1` bits.
+  fn exp_windowed(a: &ClassElem, n: &Integer, w: u32) -> ClassElem {
+    let (a, n) = {
+      if *n < int(0) {
+        (Self::inv(a), int(-n))
+      } else {
+        (a.clone(), n.clone())
+      }
+    };
+    let digits = Self::wnaf(&n, w);
+    if digits.is_empty() {
+      return Self::id();
+    }
+
+    // `odd_powers[i]` is `a^(2i + 1)`, up to the largest digit actually used.
+    let max_digit = digits.iter().map(|d| d.abs()).max().unwrap();
+    let mut odd_powers = vec![a];
+    if max_digit > 1 {
+      let a_squared = Self::square(&odd_powers[0]);
+      for i in 1..=(max_digit as usize / 2) {
+        let next = Self::op(&odd_powers[i - 1], &a_squared);
+        odd_powers.push(next);
+      }
+    }
+
+    // The leading digit of a positive integer's NAF is positive.
+    let mut digits = digits.iter().rev();
+    let mut val = odd_powers[*digits.next().unwrap() as usize / 2].clone();
+    for &digit in digits {
+      Self::square_assign(&mut val);
+      if digit > 0 {
+        Self::op_assign(&mut val, &odd_powers[digit as usize / 2]);
+      } else if digit < 0 {
+        Self::op_assign(&mut val, &Self::inv(&odd_powers[-digit as usize / 2]));
+      }
+    }
+    val
+  }
+
+  #[cfg(feature = "calibrate")]
+  fn exp_window() -> u32 {
+    *CALIBRATED_EXP_WINDOW
+  }
+
+  #[cfg(not(feature = "calibrate"))]
+  fn exp_window() -> u32 {
+    EXP_WINDOW
+  }
+
+  // The identity is the only reduced form with `a = 1`.
+  fn is_id(x: &ClassElem) -> bool {
+    x.a == 1
+  }
+
+  // Whether `y` is the inverse form of `x`, as constructed by `inv_`.
+  fn is_inverse(x: &ClassElem, y: &ClassElem) -> bool {
+    x.a == y.a && x.c == y.c && *x.b.as_neg() == y.b
   }
 
   fn discriminant(a: &Integer, b: &Integer, c: &Integer) -> Integer {
@@ -111,20 +388,55 @@ impl ClassGroup {
   fn is_normal(a: &Integer, b: &Integer, _c: &Integer) -> bool {
     -int(a) < int(b) && b <= a
   }
+
+  // The most bits of any coefficient of `(a, b, c)`.
+  #[cfg(all(feature = "paranoid", debug_assertions))]
+  fn coefficient_bits(a: &Integer, b: &Integer, c: &Integer) -> u32 {
+    a.significant_bits()
+      .max(b.significant_bits())
+      .max(c.significant_bits())
+  }
+
+  // Whether `(a, b, c)` has coefficients no longer than those of a reduced form of discriminant
+  // `d`. Reduced forms have `|b| <= a <= sqrt(|d| / 3)`, so `a` has at most half the bits of `d`,
+  // rounded up, and `c = (b^2 - d) / 4a <= a / 4 + |d| / 4` has at most as many bits as `d`.
+  #[cfg(any(test, all(feature = "paranoid", debug_assertions)))]
+  fn within_reduced_bounds(d: &Integer, a: &Integer, b: &Integer, c: &Integer) -> bool {
+    let d_bits = d.significant_bits();
+    a.significant_bits() * 2 <= d_bits + 1
+      && b.significant_bits() <= a.significant_bits()
+      && c.significant_bits() <= d_bits
+  }
+
+  // Bytes per coefficient in encoded elements: `(bits(D) + 16) / 16`, as in Chia's reference
+  // implementation. Enough for any coefficient of a reduced form, including its sign.
+  fn coefficient_bytes() -> usize {
+    (Self::rep().significant_bits() as usize + 16) >> 4
+  }
 }
 
-impl TypeRep for ClassGroup {
+impl<D: Discriminant> TypeRep for ClassGroupOf<D> {
   type Rep = Integer;
   fn rep() -> &'static Self::Rep {
-    &CLASS_GROUP_DISCRIMINANT
+    D::discriminant()
   }
 }
 
-impl Group for ClassGroup {
+impl<D: Discriminant> Group for ClassGroupOf<D> {
   type Elem = ClassElem;
 
   #[allow(non_snake_case)]
-  fn op_(_: &Integer, x: &ClassElem, y: &ClassElem) -> ClassElem {
+  fn op_(d: &Integer, x: &ClassElem, y: &ClassElem) -> ClassElem {
+    if Self::is_id(x) {
+      return y.clone();
+    }
+    if Self::is_id(y) {
+      return x.clone();
+    }
+    if Self::is_inverse(x, y) {
+      return Self::id_(d);
+    }
+
     // g = (b1 + b2) / 2
     // h = (b2 - b1) / 2
     // w = gcd(a1, a2, g)
@@ -187,36 +499,51 @@ impl Group for ClassGroup {
   }
 
   // Constructs the inverse directly instead of using `Self::Elem()`.
-  fn inv_(_: &Integer, x: &ClassElem) -> ClassElem {
-    ClassElem {
-      a: int(&x.a),
-      b: int(-(&x.b)),
-      c: int(&x.c),
-    }
+  fn inv_(d: &Integer, x: &ClassElem) -> ClassElem {
+    let mut inv = x.clone();
+    Self::inv_assign_(d, &mut inv);
+    inv
   }
 
   fn exp_(_: &Integer, a: &ClassElem, n: &Integer) -> ClassElem {
-    let (mut val, mut a, mut n) = {
-      if *n < int(0) {
-        (Self::id(), Self::inv(a), int(-n))
-      } else {
-        (Self::id(), a.clone(), n.clone())
-      }
-    };
-    loop {
-      if n == int(0) {
-        return val;
-      }
-      if n.is_odd() {
-        val = Self::op(&val, &a);
-      }
-      a = Self::square(&a);
-      n >>= 1;
+    Self::exp_windowed(a, n, Self::exp_window())
+  }
+
+  // Composing with the identity only copies coefficients into storage `x` already owns.
+  fn op_assign_(d: &Integer, x: &mut ClassElem, y: &ClassElem) {
+    if Self::is_id(y) {
+      return;
+    }
+    if Self::is_id(x) {
+      x.a.assign(&y.a);
+      x.b.assign(&y.b);
+      x.c.assign(&y.c);
+      return;
+    }
+    *x = Self::op_(d, x, y);
+  }
+
+  fn square_assign_(_: &Integer, x: &mut ClassElem) {
+    if !Self::is_id(x) {
+      *x = Self::square(x);
+    }
+  }
+
+  // `(a, -b, c)` is reduced unless `b = a` or `a = c`, and reducing it then only restores `b`: such
+  // forms are their own inverses.
+  fn inv_assign_(_: &Integer, x: &mut ClassElem) {
+    x.b.neg_assign();
+    if !Self::is_reduced(&x.a, &x.b, &x.c) {
+      x.b.neg_assign();
     }
   }
+
+  fn is_valid_elem_(_: &Integer, x: &ClassElem) -> bool {
+    Self::validate(&x.a, &x.b, &x.c) && Self::is_reduced(&x.a, &x.b, &x.c)
+  }
 }
 
-impl UnknownOrderGroup for ClassGroup {
+impl<D: Discriminant> UnknownOrderGroup for ClassGroupOf<D> {
   fn unknown_order_elem_(d: &Integer) -> ClassElem {
     // a = 2
     // b = 1
@@ -226,6 +553,92 @@ impl UnknownOrderGroup for ClassGroup {
     let c = int(1 - d) / int(8);
     ClassElem { a, b, c }
   }
+
+  // The class number satisfies `h(D) < sqrt(|D|) (2 + ln |D|) / pi`, and the bit length of `|D|`
+  // exceeds `(2 + ln |D|) / pi`.
+  fn order_upper_bound_(d: &Integer) -> Integer {
+    let abs = int(d.abs_ref());
+    let bits = abs.significant_bits();
+    abs.sqrt() * bits
+  }
+
+  // Hashes `tag` to a prime `a = 3 mod 4` for which `D` is a square mod `a`, and returns the
+  // reduction of the form `(a, b, c)` where `b` is the odd square root of `D` mod `a`.
+  fn elem_from_hash_<T: Hash + ?Sized>(d: &Integer, tag: &T) -> ClassElem {
+    for nonce in 0u64.. {
+      let a = hash_to_prime(&(tag, nonce));
+      if a.mod_u(4) != 3 || d.jacobi(&a) != 1 {
+        continue;
+      }
+      // Since `a = 3 mod 4`, `D^((a + 1) / 4)` is a square root of `D` mod `a`. Exactly one of it
+      // and its negation is odd, as `a` is.
+      let mut b = int(d.pow_mod_ref(&(int(&a + 1) >> 2), &a).unwrap());
+      if b.is_even() {
+        b = &a - b;
+      }
+      // `b^2 = D mod 4a`, since both are `1 mod 4`.
+      let c = (int(b.square_ref()) - d) / int(4 * &a);
+      return Self::elem((a, b, c));
+    }
+    unreachable!()
+  }
+}
+
+/// Encodes only `a` and `b`, as big-endian two's-complement integers of equal width, matching
+/// Chia's serialization format. On decode, `c` is recomputed as `(b^2 - D) / 4a`, which halves the
+/// size of the encoding compared to storing all three coefficients.
+impl<D: Discriminant> ElemBytes for ClassGroupOf<D> {
+  const GROUP_ID: u8 = D::GROUP_ID;
+
+  fn elem_bytes_len() -> usize {
+    2 * Self::coefficient_bytes()
+  }
+
+  fn elem_to_bytes(x: &ClassElem) -> Vec<u8> {
+    let n_bytes = Self::coefficient_bytes();
+    let mut bytes = signed_to_bytes(&x.a, n_bytes);
+    bytes.extend(signed_to_bytes(&x.b, n_bytes));
+    bytes
+  }
+
+  fn elem_from_bytes(bytes: &[u8]) -> Option<ClassElem> {
+    let n_bytes = Self::coefficient_bytes();
+    if bytes.len() != Self::elem_bytes_len() {
+      return None;
+    }
+    let a = signed_from_bytes(&bytes[..n_bytes]);
+    let b = signed_from_bytes(&bytes[n_bytes..]);
+    if a <= 0 {
+      return None;
+    }
+    let (c, remainder) = (int(b.square_ref()) - Self::rep()).div_rem(int(4 * &a));
+    if remainder != 0 || !Self::is_reduced(&a, &b, &c) {
+      return None;
+    }
+    Some(ClassElem { a, b, c })
+  }
+}
+
+// Encodes `n` in `n_bytes` big-endian bytes of two's complement. `n` must fit.
+fn signed_to_bytes(n: &Integer, n_bytes: usize) -> Vec<u8> {
+  let unsigned = if *n < 0 {
+    (int(1) << (8 * n_bytes as u32)) + n
+  } else {
+    int(n)
+  };
+  let digits = unsigned.to_digits::<u8>(Order::Msf);
+  let mut bytes = vec![0; n_bytes - digits.len()];
+  bytes.extend_from_slice(&digits);
+  bytes
+}
+
+fn signed_from_bytes(bytes: &[u8]) -> Integer {
+  let n = Integer::from_digits(bytes, Order::Msf);
+  if bytes[0] & 0x80 == 0 {
+    n
+  } else {
+    n - (int(1) << (8 * bytes.len() as u32))
+  }
 }
 
 impl Hash for ClassElem {
@@ -245,7 +658,7 @@ impl PartialEq for ClassElem {
 }
 
 /// Panics if `(a, b, c)` cannot be reduced to a valid class element.
-impl<A, B, C> ElemFrom<(A, B, C)> for ClassGroup
+impl<D: Discriminant, A, B, C> ElemFrom<(A, B, C)> for ClassGroupOf<D>
 where
   Integer: From<A>,
   Integer: From<B>,
@@ -476,6 +889,55 @@ mod tests {
     assert_eq!(ClassElem { a, b, c }, reduced_ground_truth);
   }
 
+  // Returns a form equivalent to `(a, b, c)` with coefficients of about `k_bits` bits, by
+  // translating `b` by `2ka` and swapping `a` and `c`.
+  fn unreduce(a: &Integer, b: &Integer, c: &Integer, k_bits: u32) -> (Integer, Integer, Integer) {
+    let k: Integer = (int(1) << k_bits) + 3;
+    let b_k = b + int(2 * int(&k * a));
+    let c_k = a * int(k.square_ref()) + int(b * &k) + c;
+    (c_k, -b_k, int(a))
+  }
+
+  fn check_reduce_bounds(a: Integer, b: Integer, c: Integer) {
+    let d = ClassGroup::discriminant(&a, &b, &c);
+    for k_bits in &[1, 64, 2 * d.significant_bits(), 10_000] {
+      let (a_k, b_k, c_k) = unreduce(&a, &b, &c, *k_bits);
+      assert_eq!(ClassGroup::discriminant(&a_k, &b_k, &c_k), d);
+      assert!(!ClassGroup::is_reduced(&a_k, &b_k, &c_k));
+      let reduced = ClassGroup::reduce(a_k, b_k, c_k);
+      assert!(ClassGroup::within_reduced_bounds(
+        &d, &reduced.0, &reduced.1, &reduced.2
+      ));
+      assert_eq!(reduced, (int(&a), int(&b), int(&c)));
+    }
+  }
+
+  #[test]
+  fn test_reduce_bounds() {
+    // The smallest discriminants, where the bounds are tightest.
+    check_reduce_bounds(int(1), int(1), int(1));
+    check_reduce_bounds(int(1), int(1), int(2));
+    check_reduce_bounds(int(2), int(1), int(3));
+    check_reduce_bounds(int(2), int(-1), int(3));
+
+    // The largest discriminant in use.
+    let g = ClassGroup3598::unknown_order_elem();
+    let x = ClassGroup3598::exp(&g, &int(12345));
+    check_reduce_bounds(x.a, x.b, x.c);
+
+    let d = ClassGroup::rep();
+    let g = ClassGroup::unknown_order_elem();
+    assert!(ClassGroup::within_reduced_bounds(d, &g.a, &g.b, &g.c));
+    let (a, b, c) = unreduce(&g.a, &g.b, &g.c, 64);
+    assert!(!ClassGroup::within_reduced_bounds(d, &a, &b, &c));
+    assert!(!ClassGroup::within_reduced_bounds(
+      &int(-3),
+      &int(2),
+      &int(1),
+      &int(1)
+    ));
+  }
+
   #[test]
   // REVIEW: This test should be restructured to not construct `ClassElem`s but it will do for now.
   fn test_normalize_basic() {
@@ -656,6 +1118,45 @@ mod tests {
     }
   }
 
+  #[test]
+  fn test_assign_ops() {
+    let g = ClassGroup::unknown_order_elem();
+    let h = ClassGroup::exp(&g, &int(12_345));
+    let mut x = h.clone();
+    ClassGroup::op_assign(&mut x, &g);
+    assert_eq!(x, ClassGroup::exp(&g, &int(12_346)));
+    ClassGroup::square_assign(&mut x);
+    assert_eq!(x, ClassGroup::exp(&g, &int(24_692)));
+    ClassGroup::inv_assign(&mut x);
+    assert_eq!(x, ClassGroup::exp(&g, &int(-24_692)));
+
+    // Identity and inverse fast paths.
+    let mut x = ClassGroup::id();
+    ClassGroup::op_assign(&mut x, &h);
+    assert_eq!(x, h);
+    ClassGroup::op_assign(&mut x, &ClassGroup::id());
+    assert_eq!(x, h);
+    ClassGroup::op_assign(&mut x, &ClassGroup::inv(&h));
+    assert_eq!(x, ClassGroup::id());
+    ClassGroup::square_assign(&mut x);
+    assert_eq!(x, ClassGroup::id());
+  }
+
+  #[test]
+  fn test_square_matches_op() {
+    let g = ClassGroup::unknown_order_elem();
+    assert_eq!(ClassGroup::square(&ClassGroup::id()), ClassGroup::id());
+    let mut x = g.clone();
+    for k in 1..200 {
+      assert_eq!(ClassGroup::square(&x), ClassGroup::op(&x, &x), "g^{}", k);
+      let x_inv = ClassGroup::inv(&x);
+      assert_eq!(ClassGroup::square(&x_inv), ClassGroup::op(&x_inv, &x_inv));
+      ClassGroup::op_assign(&mut x, &g);
+    }
+    let x = ClassGroup::exp(&g, &(int(1) << 1000));
+    assert_eq!(ClassGroup::square(&x), ClassGroup::op(&x, &x));
+  }
+
   #[test]
   fn test_id_basic() {
     let g = ClassGroup::unknown_order_elem();
@@ -693,6 +1194,30 @@ mod tests {
     }
   }
 
+  #[test]
+  fn test_inv_ambiguous() {
+    // `b = a` in the identity, so negating `b` alone leaves it unreduced.
+    let id = ClassGroup::id();
+    assert_eq!(ClassGroup::inv(&id), id);
+    let mut inv = ClassGroup::id();
+    ClassGroup::inv_assign(&mut inv);
+    assert_eq!(inv, id);
+    assert!(ClassGroup::is_valid_elem(&inv));
+  }
+
+  #[test]
+  fn test_is_valid_elem() {
+    let g = ClassGroup::unknown_order_elem();
+    assert!(ClassGroup::is_valid_elem(&g));
+    assert!(ClassGroup::is_valid_elem(&ClassGroup::exp(&g, &int(1000))));
+    let unreduced = ClassElem {
+      a: int(&g.c),
+      b: int(-&g.b),
+      c: int(&g.a),
+    };
+    assert!(!ClassGroup::is_valid_elem(&unreduced));
+  }
+
   #[test]
   fn test_exp_basic() {
     let g_anchor = ClassGroup::unknown_order_elem();
@@ -704,6 +1229,38 @@ mod tests {
     }
   }
 
+  #[test]
+  fn test_exp_large() {
+    let g = ClassGroup::unknown_order_elem();
+    let n = Integer::from_str("123456789012345678901234567890123456789").unwrap();
+
+    // Square-and-multiply using `op` only, as a reference.
+    let mut expected = ClassGroup::id();
+    let mut base = g.clone();
+    for i in 0..n.significant_bits() {
+      if n.get_bit(i) {
+        expected = ClassGroup::op(&expected, &base);
+      }
+      base = ClassGroup::op(&base, &base);
+    }
+    assert_eq!(expected, ClassGroup::exp(&g, &n));
+    assert_eq!(ClassGroup::inv(&expected), ClassGroup::exp(&g, &-n));
+  }
+
+  #[test]
+  fn test_wnaf() {
+    for i in 0..2000 {
+      let n = int(i);
+      let digits = ClassGroup::wnaf(&n, EXP_WINDOW);
+      let mut recoded = int(0);
+      for (j, digit) in digits.iter().enumerate() {
+        assert!(*digit == 0 || (digit % 2 != 0 && digit.abs() < 1 << (EXP_WINDOW - 1)));
+        recoded += int(*digit) << j as u32;
+      }
+      assert_eq!(recoded, n);
+    }
+  }
+
   #[test]
   fn test_square_basic() {
     let g = ClassGroup::unknown_order_elem();
@@ -722,4 +1279,103 @@ mod tests {
 
     assert_eq!(&g2, &g4);
   }
+
+  fn from_hex(hex: &str) -> Vec<u8> {
+    (0..hex.len())
+      .step_by(2)
+      .map(|i| u8::from_str_radix(&hex[i..i + 2], 16).unwrap())
+      .collect()
+  }
+
+  #[test]
+  fn test_elem_bytes() {
+    for n in &[0, 1, 2, 12345, 12346] {
+      let x = ClassGroup::exp(&ClassGroup::unknown_order_elem(), &int(*n));
+      let bytes = ClassGroup::elem_to_bytes(&x);
+      assert_eq!(bytes.len(), 258);
+      assert_eq!(ClassGroup::elem_from_bytes(&bytes), Some(x));
+    }
+  }
+
+  // Encodings as produced by `ClassGroup.serialize` in Chia's sample implementation, with powers
+  // of `g` computed independently in Python.
+  #[test]
+  fn test_elem_bytes_chia() {
+    let g = ClassGroup::unknown_order_elem();
+    let g_bytes = ClassGroup::elem_to_bytes(&g);
+    assert_eq!(g_bytes[128], 2);
+    assert_eq!(g_bytes[257], 1);
+    assert!(g_bytes
+      .iter()
+      .enumerate()
+      .all(|(i, &byte)| i == 128 || i == 257 || byte == 0));
+
+    // g^12346, whose `b` is negative.
+    let bytes = from_hex(
+      "0075d8ec0689efb2c47641cfd4fd2cfc604956c84aa4bb315676e14a30e6bb8486ec6c8b27fe99e173cb2f\
+      2af2c0655dca3979965359462665d2c4f006c4790552ad0099c3f4f1e5979dadfec8cde7a49672668117f5\
+      f84a104379b0832cf3c5b187fd617aa974f6781c391a096955a625d47b75bad580650b12080c0a546672be\
+      ff987fdec8eae23443db624ae1355c1aa2641ecd1f0b6a2f99c650eb0d0c17c608e0d034419da4a1e1cde4\
+      17bbc6db7394f086df5cdf888539fab39e97a202e22aa045c1c8dd7f3914acb0053d4ff456a060fd381469\
+      89699ee88891a6d1a855fc973799512bad199f72107faf6466a88a2d5ce937c0d399f9d6c2ee62cd9788a9",
+    );
+    let x = ClassGroup::exp(&g, &int(12346));
+    assert_eq!(ClassGroup::elem_to_bytes(&x), bytes);
+    assert_eq!(ClassGroup::elem_from_bytes(&bytes), Some(x));
+  }
+
+  #[test]
+  fn test_elem_bytes_invalid() {
+    let g_bytes = ClassGroup::elem_to_bytes(&ClassGroup::unknown_order_elem());
+    assert_eq!(ClassGroup::elem_from_bytes(&g_bytes[1..]), None);
+    // Zero and negative `a`, `b` for which `c` is not an integer, and a non-reduced `b`.
+    for &(i, byte) in &[(128, 0), (0, 0xff), (257, 2), (257, 3)] {
+      let mut bytes = g_bytes.clone();
+      bytes[i] = byte;
+      assert_eq!(ClassGroup::elem_from_bytes(&bytes), None);
+    }
+  }
+
+  #[test]
+  fn test_elem_from_hash() {
+    let g = ClassGroup::elem_from_hash("g");
+    assert!(ClassGroup::is_valid_elem(&g));
+    assert_eq!(g, ClassGroup::elem_from_hash("g"));
+    assert_ne!(g, ClassGroup::elem_from_hash("h"));
+    assert_ne!(g, ClassGroup::unknown_order_elem());
+    assert_ne!(ClassGroup::exp(&g, &int(1000)), ClassGroup::id());
+  }
+
+  fn check_discriminant<D: Discriminant>(bits: u32) {
+    let d = ClassGroupOf::<D>::rep();
+    assert!(*d < 0);
+    assert_eq!(d.significant_bits(), bits);
+    assert_eq!(int(-d).mod_u(8), 7);
+    assert_ne!(int(-d).is_probably_prime(10), rug::integer::IsPrime::No);
+
+    let g = ClassGroupOf::<D>::unknown_order_elem();
+    assert!(ClassGroupOf::<D>::is_valid_elem(&g));
+    let x = ClassGroupOf::<D>::exp(&g, &int(12345));
+    assert!(ClassGroupOf::<D>::is_valid_elem(&x));
+    assert_eq!(
+      ClassGroupOf::<D>::op(&x, &ClassGroupOf::<D>::inv(&x)),
+      ClassGroupOf::<D>::id()
+    );
+    let bytes = ClassGroupOf::<D>::elem_to_bytes(&x);
+    assert_eq!(bytes.len(), ClassGroupOf::<D>::elem_bytes_len());
+    assert_eq!(ClassGroupOf::<D>::elem_from_bytes(&bytes), Some(x));
+  }
+
+  #[test]
+  fn test_discriminant_sets() {
+    check_discriminant::<Discriminant2048>(2048);
+    check_discriminant::<Discriminant1827>(1827);
+    check_discriminant::<Discriminant3598>(3598);
+    let ids = [
+      ClassGroup::GROUP_ID,
+      ClassGroup1827::GROUP_ID,
+      ClassGroup3598::GROUP_ID,
+    ];
+    assert!(ids[0] != ids[1] && ids[1] != ids[2] && ids[0] != ids[2]);
+  }
 }
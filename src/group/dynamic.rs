@@ -0,0 +1,177 @@
+//! Runtime selection of the group, for applications that read it from configuration.
+//!
+//! `Group` cannot be made into a trait object: elements are associated types, and the modulus or
+//! discriminant is reached through `TypeRep` rather than `self`. `DynGroup` instead enumerates the
+//! supported groups, and `DynElem` holds an element of any of them. Code generic over
+//! `UnknownOrderGroup` (e.g. accumulators) can be dispatched with a `match` on the `DynGroup`.
+use super::{ClassElem, ClassGroup, ElemBytes, Group, Rsa2048, Rsa2048Elem, UnknownOrderGroup};
+use crate::integer::Integer;
+use std::hash::Hash;
+
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+/// A group chosen at runtime.
+pub enum DynGroup {
+  /// `Rsa2048`, named `"rsa2048"` in configuration.
+  Rsa2048,
+  /// `ClassGroup`, named `"class"` in configuration.
+  ClassGroup,
+}
+
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+/// An element of a group chosen at runtime.
+pub enum DynElem {
+  /// An element of `Rsa2048`.
+  Rsa2048(Rsa2048Elem),
+  /// An element of `ClassGroup`.
+  ClassGroup(ClassElem),
+}
+
+impl DynGroup {
+  /// Looks up a group by its configuration name, returning `None` for unknown names.
+  pub fn from_name(name: &str) -> Option<Self> {
+    match name {
+      "rsa2048" => Some(DynGroup::Rsa2048),
+      "class" => Some(DynGroup::ClassGroup),
+      _ => None,
+    }
+  }
+
+  /// Returns the configuration name of this group.
+  pub fn name(self) -> &'static str {
+    match self {
+      DynGroup::Rsa2048 => "rsa2048",
+      DynGroup::ClassGroup => "class",
+    }
+  }
+
+  /// Looks up a group by its `ElemBytes::GROUP_ID`, e.g. one read from serialized state.
+  pub fn from_group_id(group_id: u8) -> Option<Self> {
+    match group_id {
+      Rsa2048::GROUP_ID => Some(DynGroup::Rsa2048),
+      ClassGroup::GROUP_ID => Some(DynGroup::ClassGroup),
+      _ => None,
+    }
+  }
+
+  /// Returns the `ElemBytes::GROUP_ID` of this group.
+  pub fn group_id(self) -> u8 {
+    match self {
+      DynGroup::Rsa2048 => Rsa2048::GROUP_ID,
+      DynGroup::ClassGroup => ClassGroup::GROUP_ID,
+    }
+  }
+
+  /// Returns the identity element of this group.
+  pub fn id(self) -> DynElem {
+    match self {
+      DynGroup::Rsa2048 => DynElem::Rsa2048(Rsa2048::id()),
+      DynGroup::ClassGroup => DynElem::ClassGroup(ClassGroup::id()),
+    }
+  }
+
+  /// Returns this group's element of unknown order.
+  pub fn unknown_order_elem(self) -> DynElem {
+    match self {
+      DynGroup::Rsa2048 => DynElem::Rsa2048(Rsa2048::unknown_order_elem()),
+      DynGroup::ClassGroup => DynElem::ClassGroup(ClassGroup::unknown_order_elem()),
+    }
+  }
+
+  /// Returns the element of this group derived by hashing `tag`. See
+  /// `UnknownOrderGroup::elem_from_hash`.
+  pub fn elem_from_hash<T: Hash + ?Sized>(self, tag: &T) -> DynElem {
+    match self {
+      DynGroup::Rsa2048 => DynElem::Rsa2048(Rsa2048::elem_from_hash(tag)),
+      DynGroup::ClassGroup => DynElem::ClassGroup(ClassGroup::elem_from_hash(tag)),
+    }
+  }
+
+  /// Parses the canonical encoding of an element of this group.
+  pub fn elem_from_bytes(self, bytes: &[u8]) -> Option<DynElem> {
+    match self {
+      DynGroup::Rsa2048 => Rsa2048::elem_from_bytes(bytes).map(DynElem::Rsa2048),
+      DynGroup::ClassGroup => ClassGroup::elem_from_bytes(bytes).map(DynElem::ClassGroup),
+    }
+  }
+}
+
+impl DynElem {
+  /// Returns the group this element belongs to.
+  pub fn group(&self) -> DynGroup {
+    match self {
+      DynElem::Rsa2048(_) => DynGroup::Rsa2048,
+      DynElem::ClassGroup(_) => DynGroup::ClassGroup,
+    }
+  }
+
+  /// Applies the group operation to this element and `other`, or returns `None` if they belong
+  /// to different groups.
+  pub fn op(&self, other: &Self) -> Option<Self> {
+    match (self, other) {
+      (DynElem::Rsa2048(a), DynElem::Rsa2048(b)) => Some(DynElem::Rsa2048(Rsa2048::op(a, b))),
+      (DynElem::ClassGroup(a), DynElem::ClassGroup(b)) => {
+        Some(DynElem::ClassGroup(ClassGroup::op(a, b)))
+      }
+      _ => None,
+    }
+  }
+
+  /// Raises this element to the power `n`.
+  pub fn exp(&self, n: &Integer) -> Self {
+    match self {
+      DynElem::Rsa2048(a) => DynElem::Rsa2048(Rsa2048::exp(a, n)),
+      DynElem::ClassGroup(a) => DynElem::ClassGroup(ClassGroup::exp(a, n)),
+    }
+  }
+
+  /// Returns the inverse of this element.
+  pub fn inv(&self) -> Self {
+    match self {
+      DynElem::Rsa2048(a) => DynElem::Rsa2048(Rsa2048::inv(a)),
+      DynElem::ClassGroup(a) => DynElem::ClassGroup(ClassGroup::inv(a)),
+    }
+  }
+
+  /// Returns the canonical encoding of this element in its group.
+  pub fn to_bytes(&self) -> Vec<u8> {
+    match self {
+      DynElem::Rsa2048(a) => Rsa2048::elem_to_bytes(a),
+      DynElem::ClassGroup(a) => ClassGroup::elem_to_bytes(a),
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::util::int;
+
+  #[test]
+  fn test_dyn_group() {
+    assert_eq!(DynGroup::from_name("rsa4096"), None);
+    assert_eq!(DynGroup::from_group_id(0), None);
+    for name in &["rsa2048", "class"] {
+      let group = DynGroup::from_name(name).unwrap();
+      assert_eq!(group.name(), *name);
+      assert_eq!(DynGroup::from_group_id(group.group_id()), Some(group));
+
+      let g = group.unknown_order_elem();
+      let x = g.exp(&int(5));
+      assert_eq!(x.op(&g.inv()).unwrap(), g.exp(&int(4)));
+      assert_eq!(x.op(&x.inv()).unwrap(), group.id());
+      assert_eq!(group.elem_from_bytes(&x.to_bytes()), Some(x));
+      assert_eq!(group.elem_from_hash("h").group(), group);
+    }
+    assert_eq!(
+      DynGroup::Rsa2048.unknown_order_elem().exp(&int(5)),
+      DynElem::Rsa2048(Rsa2048::exp(&Rsa2048::unknown_order_elem(), &int(5)))
+    );
+  }
+
+  #[test]
+  fn test_dyn_op_across_groups() {
+    let rsa = DynGroup::Rsa2048.unknown_order_elem();
+    let class = DynGroup::ClassGroup.unknown_order_elem();
+    assert_eq!(rsa.op(&class), None);
+  }
+}
@@ -0,0 +1,370 @@
+//! RSA groups with a modulus generated outside this crate, e.g. by an MPC ceremony.
+//!
+//! `Rsa2048` fixes the RSA-2048 challenge number in source. Deployments that generate their own
+//! modulus (so that nobody, not even RSA Laboratories, could have kept its factors) instead parse
+//! the ceremony's output with `RsaModulus::from_str` or `from_bytes`, which reject moduli that are
+//! too small, even, prime, perfect powers or divisible by a prime below `2^16`, compare its
+//! `digest` with the one the ceremony published, and `ImportedRsa::install` it once at startup.
+//! `ImportedRsa` is then used like any other group. None of these checks can show that nobody
+//! knows the factorization; that rests on the ceremony itself.
+//!
+//! Elements are the cosets `{x, -x}`, as for `Rsa2048`. The element of unknown order is derived by
+//! hashing the modulus digest rather than fixed to 2, so it encodes differently under every
+//! modulus, and `proof_params_digest` (which covers it) keeps proofs from different ceremonies
+//! apart even though they share a group ID. State and snapshot digests cover the modulus digest
+//! through `ElemBytes::instance_bytes`, so state exported under one ceremony is rejected by a
+//! process that installed another.
+use super::{ElemBytes, ElemFrom, ElemTo, Group, UnknownOrderGroup, DERIVED_GENERATOR_TAG};
+use crate::accumulator::InitAttestation;
+use crate::hash::{blake2b, Blake2b, GeneralHasher};
+use crate::integer::{Integer, IntegerOps};
+use crate::util::{int, TypeRep};
+use std::hash::{Hash, Hasher};
+use std::str::FromStr;
+use std::sync::OnceLock;
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+/// An RSA group with the modulus passed to `ImportedRsa::install`. See the module docs.
+pub enum ImportedRsa {}
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+/// An `ImportedRsa` element.
+pub struct ImportedRsaElem(Integer);
+
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+/// The ways importing a modulus can fail.
+pub enum ModulusError {
+  /// The input was not a decimal or `0x`-prefixed hexadecimal integer.
+  BadEncoding,
+  /// The modulus has fewer than `RsaModulus::MIN_BITS` or more than `RsaModulus::MAX_BITS` bits.
+  BadSize,
+  /// The modulus is even, prime, a perfect power, or divisible by a prime below `2^16`.
+  NotRsaModulus,
+  /// The modulus digest differs from the expected one.
+  DigestMismatch,
+  /// A different modulus is already installed.
+  AlreadyInstalled,
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+/// A validated RSA modulus for `ImportedRsa`.
+pub struct RsaModulus {
+  n: Integer,
+  half: Integer,
+  bytes_len: usize,
+  digest: [u8; 32],
+  unknown_order_elem: ImportedRsaElem,
+}
+
+lazy_static! {
+  // The product of the primes below `2^16`, for rejecting moduli with a small factor at once.
+  static ref SMALL_PRIMORIAL: Integer = Integer::from(Integer::primorial(1 << 16));
+}
+
+impl RsaModulus {
+  /// The smallest modulus accepted, in bits.
+  pub const MIN_BITS: u32 = 2048;
+  /// The largest modulus accepted, in bits.
+  pub const MAX_BITS: u32 = 16384;
+
+  /// Validates `n` as a modulus. See the module docs.
+  pub fn new(n: Integer) -> Result<Self, ModulusError> {
+    let bits = n.bit_length();
+    if n < 0 || !(Self::MIN_BITS..=Self::MAX_BITS).contains(&bits) {
+      return Err(ModulusError::BadSize);
+    }
+    if !n.is_odd()
+      || n.xgcd(&SMALL_PRIMORIAL).0 != 1
+      || n.is_perfect_power()
+      || n.is_probable_prime(30)
+    {
+      return Err(ModulusError::NotRsaModulus);
+    }
+    let mut hasher = Blake2b::default();
+    hasher.write(b"rsa modulus");
+    let bytes = n.to_bytes_be();
+    hasher.write(&bytes);
+    let digest = hasher.finalize();
+    let half = int(&n / 2);
+    let unknown_order_elem = hash_to_elem(&n, &half, &(DERIVED_GENERATOR_TAG, digest));
+    Ok(Self {
+      n,
+      half,
+      bytes_len: bytes.len(),
+      digest,
+      unknown_order_elem,
+    })
+  }
+
+  /// Parses and validates a big-endian modulus.
+  pub fn from_bytes(bytes: &[u8]) -> Result<Self, ModulusError> {
+    Self::new(Integer::from_bytes_be(bytes))
+  }
+
+  /// Returns the modulus.
+  pub fn modulus(&self) -> &Integer {
+    &self.n
+  }
+
+  /// Returns the Blake2b digest identifying this modulus, i.e. the hash of `"rsa modulus"` and its
+  /// big-endian encoding, for comparison with the digest a ceremony published.
+  pub fn digest(&self) -> [u8; 32] {
+    self.digest
+  }
+
+  /// Returns this modulus if its digest is `expected`, and `DigestMismatch` otherwise.
+  pub fn check_digest(self, expected: &[u8; 32]) -> Result<Self, ModulusError> {
+    if self.digest != *expected {
+      return Err(ModulusError::DigestMismatch);
+    }
+    Ok(self)
+  }
+}
+
+impl FromStr for RsaModulus {
+  type Err = ModulusError;
+
+  /// Parses and validates a decimal or `0x`-prefixed hexadecimal modulus. Whitespace, including
+  /// line breaks within the digits, is ignored.
+  fn from_str(s: &str) -> Result<Self, ModulusError> {
+    let digits = s.chars().filter(|c| !c.is_whitespace()).collect::<String>();
+    let (digits, radix) = match digits.strip_prefix("0x") {
+      Some(hex) => (hex, 16),
+      None => (&digits[..], 10),
+    };
+    if digits.is_empty() || !digits.chars().all(|c| c.is_digit(radix)) {
+      return Err(ModulusError::BadEncoding);
+    }
+    let n = Integer::from_str_radix(digits, radix as i32).map_err(|_| ModulusError::BadEncoding)?;
+    Self::new(n)
+  }
+}
+
+static INSTALLED: OnceLock<RsaModulus> = OnceLock::new();
+
+impl ImportedRsa {
+  /// Makes `modulus` the modulus of `ImportedRsa` for the rest of the process. Installing the same
+  /// modulus again is a no-op; installing a different one fails with `AlreadyInstalled`, since
+  /// elements and accumulators already created would silently change meaning.
+  pub fn install(modulus: RsaModulus) -> Result<(), ModulusError> {
+    match INSTALLED.set(modulus) {
+      Ok(()) => Ok(()),
+      Err(modulus) if INSTALLED.get() == Some(&modulus) => Ok(()),
+      Err(_) => Err(ModulusError::AlreadyInstalled),
+    }
+  }
+
+  /// Returns the installed modulus, if any.
+  pub fn installed() -> Option<&'static RsaModulus> {
+    INSTALLED.get()
+  }
+
+  /// Returns the attestation for accumulators starting at the element of unknown order, which
+  /// commits to the modulus digest it was hashed from. Verifiers holding the digest the ceremony
+  /// published can check it instead of trusting the installed element. Panics if no modulus is
+  /// installed.
+  pub fn init_attestation() -> InitAttestation<Self> {
+    InitAttestation::DerivedGenerator(Self::rep().digest)
+  }
+}
+
+impl TypeRep for ImportedRsa {
+  type Rep = RsaModulus;
+  /// Panics if no modulus is installed.
+  fn rep() -> &'static RsaModulus {
+    Self::installed().expect("ImportedRsa used before ImportedRsa::install")
+  }
+}
+
+// Reduces `x` to the representative in `[0, n / 2]` of its coset `{x, -x}`.
+fn reduce(n: &Integer, half: &Integer, x: Integer) -> ImportedRsaElem {
+  let (_, val) = x.div_rem_euclid(n);
+  if val > *half {
+    ImportedRsaElem(n - val)
+  } else {
+    ImportedRsaElem(val)
+  }
+}
+
+// Concatenates Blake2b hashes of `(tag, i)` into an integer 128 bits longer than `n`, as for
+// `Rsa2048`.
+fn hash_to_elem<T: Hash + ?Sized>(n: &Integer, half: &Integer, tag: &T) -> ImportedRsaElem {
+  let n_blocks = (n.bit_length() + 128) / 256 + 1;
+  let mut x = int(0);
+  for i in 0..n_blocks {
+    x = (x << 256) + blake2b(&(tag, i));
+  }
+  reduce(n, half, x)
+}
+
+impl Group for ImportedRsa {
+  type Elem = ImportedRsaElem;
+
+  fn op_(rep: &RsaModulus, a: &ImportedRsaElem, b: &ImportedRsaElem) -> ImportedRsaElem {
+    reduce(&rep.n, &rep.half, int(&a.0 * &b.0))
+  }
+
+  fn id_(_: &RsaModulus) -> ImportedRsaElem {
+    ImportedRsaElem(int(1))
+  }
+
+  fn inv_(rep: &RsaModulus, x: &ImportedRsaElem) -> ImportedRsaElem {
+    reduce(&rep.n, &rep.half, x.0.mod_pow(&int(-1), &rep.n).unwrap())
+  }
+
+  fn exp_(rep: &RsaModulus, x: &ImportedRsaElem, n: &Integer) -> ImportedRsaElem {
+    reduce(&rep.n, &rep.half, x.0.mod_pow(n, &rep.n).unwrap())
+  }
+
+  fn is_valid_elem_(rep: &RsaModulus, x: &ImportedRsaElem) -> bool {
+    x.0 > 0 && x.0 <= rep.half
+  }
+}
+
+impl<T> ElemFrom<T> for ImportedRsa
+where
+  Integer: From<T>,
+{
+  fn elem(t: T) -> ImportedRsaElem {
+    let rep = Self::rep();
+    reduce(&rep.n, &rep.half, int(t))
+  }
+}
+
+impl<T> ElemTo<T> for ImportedRsa
+where
+  T: From<Integer>,
+{
+  fn elem_to(val: &ImportedRsaElem) -> T {
+    val.0.clone().into()
+  }
+}
+
+/// Elements are encoded as big-endian integers as long as the modulus. Only the representative in
+/// `[0, N / 2]` of each coset `{x, -x}` is accepted when decoding.
+impl ElemBytes for ImportedRsa {
+  const GROUP_ID: u8 = 5;
+
+  fn elem_bytes_len() -> usize {
+    Self::rep().bytes_len
+  }
+
+  fn elem_to_bytes(val: &ImportedRsaElem) -> Vec<u8> {
+    let digits = val.0.to_bytes_be();
+    let mut bytes = vec![0; Self::elem_bytes_len() - digits.len()];
+    bytes.extend_from_slice(&digits);
+    bytes
+  }
+
+  fn elem_from_bytes(bytes: &[u8]) -> Option<ImportedRsaElem> {
+    let rep = Self::rep();
+    if bytes.len() != Self::elem_bytes_len() {
+      return None;
+    }
+    let val = Integer::from_bytes_be(bytes);
+    if val == 0 || val > rep.half || val.xgcd(&rep.n).0 != 1 {
+      return None;
+    }
+    Some(ImportedRsaElem(val))
+  }
+
+  fn instance_bytes() -> Vec<u8> {
+    Self::rep().digest.to_vec()
+  }
+}
+
+impl UnknownOrderGroup for ImportedRsa {
+  fn unknown_order_elem_(rep: &RsaModulus) -> ImportedRsaElem {
+    rep.unknown_order_elem.clone()
+  }
+
+  fn order_upper_bound_(rep: &RsaModulus) -> Integer {
+    rep.n.clone()
+  }
+
+  fn elem_from_hash_<T: Hash + ?Sized>(rep: &RsaModulus, tag: &T) -> ImportedRsaElem {
+    hash_to_elem(&rep.n, &rep.half, tag)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::accumulator::Accumulator;
+  use crate::group::rsa::RSA2048_MODULUS;
+  use crate::group::Rsa2048;
+  use crate::state::AccumulatorState;
+
+  #[test]
+  fn test_modulus_validation() {
+    let rsa2048 = RSA2048_MODULUS.clone();
+    let modulus = RsaModulus::new(rsa2048.clone()).unwrap();
+    let hex = format!("0x{}", rsa2048.to_string_radix(16));
+    assert_eq!(hex.parse::<RsaModulus>(), Ok(modulus.clone()));
+    assert_eq!(
+      rsa2048.to_string().parse::<RsaModulus>(),
+      Ok(modulus.clone())
+    );
+    let bytes = rsa2048.to_bytes_be();
+    assert_eq!(RsaModulus::from_bytes(&bytes), Ok(modulus.clone()));
+    let digest = modulus.digest();
+    assert!(modulus.clone().check_digest(&digest).is_ok());
+    assert_eq!(
+      modulus.check_digest(&[0; 32]),
+      Err(ModulusError::DigestMismatch)
+    );
+
+    assert_eq!("12a".parse::<RsaModulus>(), Err(ModulusError::BadEncoding));
+    assert_eq!("".parse::<RsaModulus>(), Err(ModulusError::BadEncoding));
+    let small = int(&rsa2048 >> 2);
+    assert_eq!(RsaModulus::new(small), Err(ModulusError::BadSize));
+    let mut prime: Integer = int(1) << 2048;
+    prime.next_prime_mut();
+    let even = int(&rsa2048 + 1);
+    let root: Integer = (int(1) << 1100) + 1;
+    let square = root.square();
+    let small_factor = int(&rsa2048 * 65521);
+    for n in [prime, even, square, small_factor] {
+      assert_eq!(RsaModulus::new(n), Err(ModulusError::NotRsaModulus));
+    }
+  }
+
+  #[test]
+  fn test_imported_rsa() {
+    // RSA-2048 stands in for a ceremony modulus; its factors are unknown.
+    let modulus = RsaModulus::new(RSA2048_MODULUS.clone()).unwrap();
+    ImportedRsa::install(modulus.clone()).unwrap();
+    assert!(ImportedRsa::install(modulus.clone()).is_ok());
+    let other = RsaModulus::new(int(&*RSA2048_MODULUS * 65537) * 65539).unwrap();
+    assert_eq!(
+      ImportedRsa::install(other),
+      Err(ModulusError::AlreadyInstalled)
+    );
+    assert!(ImportedRsa::installed() == Some(&modulus));
+
+    let g = ImportedRsa::unknown_order_elem();
+    assert!(ImportedRsa::op(&g, &ImportedRsa::inv(&g)) == ImportedRsa::id());
+    assert!(ImportedRsa::exp(&g, &int(-1)) == ImportedRsa::inv(&g));
+    assert!(ImportedRsa::elem(-8) == ImportedRsa::elem(8));
+    let bytes = ImportedRsa::elem_to_bytes(&g);
+    assert_eq!(bytes.len(), 256);
+    assert!(ImportedRsa::elem_from_bytes(&bytes) == Some(g));
+    // Same modulus as `Rsa2048`, but a different element of unknown order.
+    assert!(bytes != Rsa2048::elem_to_bytes(&Rsa2048::unknown_order_elem()));
+
+    let (acc, proof) =
+      Accumulator::<ImportedRsa, &'static str>::empty().add_with_proof(&["a", "b"]);
+    assert!(acc.verify_membership_batch(&["a", "b"], &proof));
+    assert!(!acc.verify_membership(&"c", &proof));
+
+    assert_eq!(ImportedRsa::instance_bytes(), modulus.digest().to_vec());
+    let attestation = ImportedRsa::init_attestation();
+    assert!(attestation == InitAttestation::DerivedGenerator(modulus.digest()));
+    let genesis = Accumulator::<ImportedRsa, &'static str>::empty();
+    assert!(genesis.verify_initialization(&attestation));
+    assert!(!acc.verify_initialization(&attestation));
+    assert!(!genesis.verify_initialization(&InitAttestation::DerivedGenerator([0; 32])));
+    let state = AccumulatorState::new(acc, 0);
+    assert!(AccumulatorState::<ImportedRsa, &'static str>::from_bytes(&state.to_bytes()).is_ok());
+  }
+}
@@ -4,18 +4,31 @@
 //!
 //! The preferred elliptic group implementation is the `Ristretto` group, which is a cyclic subset
 //! of the `Ed25519` group.
+use crate::integer::Integer;
 use crate::util::{int, TypeRep};
-use rug::Integer;
 use std::fmt::Debug;
 use std::hash::Hash;
 use std::marker::Sized;
 
-//mod class;
-//pub use class::{ClassElem, ClassGroup};
+#[cfg(feature = "calibrate")]
+pub mod calibrate;
+mod class;
+pub use class::{
+  ClassElem, ClassGroup, ClassGroup1827, ClassGroup3598, ClassGroupOf, Discriminant,
+  Discriminant1827, Discriminant2048, Discriminant3598,
+};
+mod dynamic;
+pub use dynamic::{DynElem, DynGroup};
+mod imported;
+pub use imported::{ImportedRsa, ImportedRsaElem, ModulusError, RsaModulus};
 //mod ristretto;
 //pub use ristretto::{Ristretto, RistrettoElem};
 mod rsa;
 pub use rsa::{Rsa2048, Rsa2048Elem};
+#[cfg(any(test, feature = "test_utils"))]
+mod test_group;
+#[cfg(any(test, feature = "test_utils"))]
+pub use test_group::{TestGroup, TestGroupElem};
 
 /// A mathematical group.
 ///
@@ -30,7 +43,7 @@ pub use rsa::{Rsa2048, Rsa2048Elem};
 ///
 /// Clients of this trait need to implement functions of the form `*_`, which take in `TypeRep`
 /// data as a parameter. Consumers use functions without the underscore: `id`, `op`, `exp`, and
-/// `inv`.
+/// `inv`, and their in-place counterparts `op_assign`, `square_assign`, and `inv_assign`.
 
 // The other traits are only required here because Rust can't figure out how to do stuff with an
 // `Accumulator<G>` even though it's just a wrapped `G::Elem`. If possible we'd remove them.
@@ -64,9 +77,9 @@ pub trait Group: Clone + Debug + Eq + Hash + TypeRep + Send + Sync {
     };
     while n > int(0) {
       if n.is_odd() {
-        val = Self::op(&val, &a);
+        Self::op_assign(&mut val, &a);
       }
-      a = Self::op(&a, &a);
+      Self::square_assign(&mut a);
       n >>= 1;
     }
     val
@@ -75,29 +88,107 @@ pub trait Group: Clone + Debug + Eq + Hash + TypeRep + Send + Sync {
   /// A group-specific wrapper for `inv`.
   fn inv_(rep: &Self::Rep, a: &Self::Elem) -> Self::Elem;
 
+  /// A group-specific wrapper for `is_valid_elem`, although it comes with a default implementation
+  /// that accepts every element.
+  fn is_valid_elem_(_rep: &Self::Rep, _a: &Self::Elem) -> bool {
+    true
+  }
+
+  /// A group-specific wrapper for `op_assign`, although it comes with a default implementation via
+  /// `op_`.
+  fn op_assign_(rep: &Self::Rep, a: &mut Self::Elem, b: &Self::Elem) {
+    *a = Self::op_(rep, a, b);
+  }
+
+  /// A group-specific wrapper for `square_assign`, although it comes with a default implementation
+  /// via `op_`.
+  fn square_assign_(rep: &Self::Rep, a: &mut Self::Elem) {
+    *a = Self::op_(rep, a, a);
+  }
+
+  /// A group-specific wrapper for `inv_assign`, although it comes with a default implementation
+  /// via `inv_`.
+  fn inv_assign_(rep: &Self::Rep, a: &mut Self::Elem) {
+    *a = Self::inv_(rep, a);
+  }
+
   // -------------------
   // END OF REQUIRED FNS
   // -------------------
 
   /// Returns the identity element of the group.
   fn id() -> Self::Elem {
-    Self::id_(Self::rep())
+    let id = Self::id_(Self::rep());
+    #[cfg(all(feature = "paranoid", debug_assertions))]
+    check_elem::<Self>(&id, "id");
+    id
   }
 
   /// Applies the group operation to elements `a` and `b` and returns the result.
   fn op(a: &Self::Elem, b: &Self::Elem) -> Self::Elem {
-    Self::op_(Self::rep(), a, b)
+    let result = Self::op_(Self::rep(), a, b);
+    #[cfg(all(feature = "paranoid", debug_assertions))]
+    check_elem::<Self>(&result, "op");
+    result
   }
 
   /// Applies the group operation to `a` and itself `n` times and returns the result.
   fn exp(a: &Self::Elem, n: &Integer) -> Self::Elem {
-    Self::exp_(Self::rep(), a, n)
+    #[cfg(feature = "metrics")]
+    let _timer = crate::metrics::Timer::start(crate::metrics::Operation::Exp, n.significant_bits());
+    let result = Self::exp_(Self::rep(), a, n);
+    #[cfg(all(feature = "paranoid", debug_assertions))]
+    check_elem::<Self>(&result, "exp");
+    result
   }
 
   /// Returns the group inverse of `a`.
   fn inv(a: &Self::Elem) -> Self::Elem {
-    Self::inv_(Self::rep(), a)
+    let result = Self::inv_(Self::rep(), a);
+    #[cfg(all(feature = "paranoid", debug_assertions))]
+    check_elem::<Self>(&result, "inv");
+    result
+  }
+
+  /// Replaces `a` with the result of applying the group operation to `a` and `b`. Groups may
+  /// reuse the storage of `a`, so hot loops should prefer this to `op`.
+  fn op_assign(a: &mut Self::Elem, b: &Self::Elem) {
+    Self::op_assign_(Self::rep(), a, b);
+    #[cfg(all(feature = "paranoid", debug_assertions))]
+    check_elem::<Self>(a, "op_assign");
+  }
+
+  /// Replaces `a` with the result of applying the group operation to `a` and itself.
+  fn square_assign(a: &mut Self::Elem) {
+    Self::square_assign_(Self::rep(), a);
+    #[cfg(all(feature = "paranoid", debug_assertions))]
+    check_elem::<Self>(a, "square_assign");
+  }
+
+  /// Replaces `a` with its group inverse.
+  fn inv_assign(a: &mut Self::Elem) {
+    Self::inv_assign_(Self::rep(), a);
+    #[cfg(all(feature = "paranoid", debug_assertions))]
+    check_elem::<Self>(a, "inv_assign");
   }
+
+  /// Returns whether `a` satisfies the invariants of this group's element representation (e.g.
+  /// being reduced). Elements returned by group operations should always be valid.
+  fn is_valid_elem(a: &Self::Elem) -> bool {
+    Self::is_valid_elem_(Self::rep(), a)
+  }
+}
+
+/// Panics if `op` produced an element breaking its group's invariants. Used by the `paranoid`
+/// feature to catch arithmetic regressions where they happen.
+#[cfg(all(feature = "paranoid", debug_assertions))]
+fn check_elem<G: Group>(a: &G::Elem, op: &str) {
+  assert!(
+    G::is_valid_elem(a),
+    "`{}` produced an invalid element: {:?}",
+    op,
+    a
+  );
 }
 
 /// A group containing elements of unknown order.
@@ -115,13 +206,27 @@ pub trait UnknownOrderGroup: Group {
     Self::order_upper_bound_(Self::rep())
   }
 
+  /// Returns an element derived by hashing `tag`. Nobody knows a discrete-log relation between
+  /// the elements for distinct tags, or between them and `unknown_order_elem`, which makes them
+  /// suitable as independent bases (e.g. for vector commitments with several generators).
+  fn elem_from_hash<T: Hash + ?Sized>(tag: &T) -> Self::Elem {
+    Self::elem_from_hash_(Self::rep(), tag)
+  }
+
   /// A group-specific wrapper for `unknown_order_elem`.
   fn unknown_order_elem_(rep: &Self::Rep) -> Self::Elem;
 
+  /// A group-specific wrapper for `elem_from_hash`.
+  fn elem_from_hash_<T: Hash + ?Sized>(rep: &Self::Rep, tag: &T) -> Self::Elem;
+
   /// A group-specific wrapper for `order_upper_bound`.
   fn order_upper_bound_(rep: &Self::Rep) -> Integer;
 }
 
+/// The tag `ImportedRsa` hashes, together with its modulus digest, into its element of unknown
+/// order. See `InitAttestation::DerivedGenerator`.
+pub(crate) const DERIVED_GENERATOR_TAG: &[u8] = b"unknown order elem";
+
 /// Like `From<T>`, but implemented on the `Group` instead of the element type.
 pub trait ElemFrom<T>: Group {
   /// Returns a group element from an initial value.
@@ -134,12 +239,42 @@ pub trait ElemTo<T>: Group {
   fn elem_to(val: &Self::Elem) -> T;
 }
 
+/// Canonical byte encodings of group elements, used to serialize accumulators and proofs.
+pub trait ElemBytes: Group {
+  /// Identifies the group in serialized state, so that state from one group is never parsed as
+  /// another's.
+  const GROUP_ID: u8;
+
+  /// Returns the length of every element's encoding.
+  fn elem_bytes_len() -> usize;
+
+  /// Returns the canonical encoding of `a`.
+  fn elem_to_bytes(a: &Self::Elem) -> Vec<u8>;
+
+  /// Parses a canonical encoding, returning `None` if `bytes` do not encode a group element.
+  fn elem_from_bytes(bytes: &[u8]) -> Option<Self::Elem>;
+
+  /// Returns bytes telling apart instances of the group that share `GROUP_ID`, e.g. the modulus
+  /// digest of `ImportedRsa`, for state digests to cover. Empty for groups fixed in source.
+  fn instance_bytes() -> Vec<u8> {
+    Vec::new()
+  }
+}
+
 /// Computes the product of `alpha_i ^ (p(x) / x_i)`, where `i` is an index into the `alphas` and
 /// `x` arrays, and `p(x)` is the product of all `x_i`. See BBF (page 11).
 pub fn multi_exp<G: Group>(alphas: &[G::Elem], x: &[Integer]) -> G::Elem {
+  multi_exp_::<G>(alphas, x, multi_exp_cutoff())
+}
+
+/// Splits inputs longer than `cutoff` in half, as in BBF, and exponentiates shorter ones directly.
+fn multi_exp_<G: Group>(alphas: &[G::Elem], x: &[Integer], cutoff: usize) -> G::Elem {
   if alphas.len() == 1 {
     return alphas[0].clone();
   }
+  if alphas.len() <= cutoff {
+    return multi_exp_direct::<G>(alphas, x);
+  }
 
   let n_half = alphas.len() / 2;
   let alpha_l = &alphas[..n_half];
@@ -148,16 +283,83 @@ pub fn multi_exp<G: Group>(alphas: &[G::Elem], x: &[Integer]) -> G::Elem {
   let x_r = &x[n_half..];
   let x_star_l = x_l.iter().product();
   let x_star_r = x_r.iter().product();
-  let l = multi_exp::<G>(alpha_l, x_l);
-  let r = multi_exp::<G>(alpha_r, x_r);
+  let l = multi_exp_::<G>(alpha_l, x_l, cutoff);
+  let r = multi_exp_::<G>(alpha_r, x_r, cutoff);
   G::op(&G::exp(&l, &x_star_r), &G::exp(&r, &x_star_l))
 }
 
+/// Computes the same product as `multi_exp` with one exponentiation per `alpha_i`. This does more
+/// work overall, but skips the bookkeeping of splitting, which can pay off for short inputs.
+fn multi_exp_direct<G: Group>(alphas: &[G::Elem], x: &[Integer]) -> G::Elem {
+  let x_star: Integer = x.iter().product();
+  alphas.iter().zip(x).fold(G::id(), |acc, (alpha, x_i)| {
+    G::op(&acc, &G::exp(alpha, &int(&x_star / x_i)))
+  })
+}
+
+/// Computes the product of `bases_i ^ exps_i` for nonnegative `exps_i`, sharing one chain of
+/// squarings across all bases (Straus's method, with 4-bit windows). This replaces most of the
+/// squarings of exponentiating each base separately, at the cost of 14 operations per base for its
+/// window table.
+pub fn simultaneous_exp<G: Group>(bases: &[G::Elem], exps: &[Integer]) -> G::Elem {
+  let tables = bases
+    .iter()
+    .map(|base| {
+      let mut table = vec![G::id(), base.clone()];
+      for i in 2..16 {
+        table.push(G::op(&table[i - 1], base));
+      }
+      table
+    })
+    .collect::<Vec<_>>();
+  let n_bits = exps
+    .iter()
+    .map(Integer::significant_bits)
+    .max()
+    .unwrap_or(0);
+  let mut acc = G::id();
+  for window_start in (0..n_bits).step_by(4).rev() {
+    for _ in 0..4 {
+      acc = G::op(&acc, &acc);
+    }
+    for (table, exp) in tables.iter().zip(exps) {
+      let digit = (0..4).fold(0, |digit, bit| {
+        digit | (usize::from(exp.get_bit(window_start + bit)) << bit)
+      });
+      if digit != 0 {
+        acc = G::op(&acc, &table[digit]);
+      }
+    }
+  }
+  acc
+}
+
+#[cfg(feature = "calibrate")]
+fn multi_exp_cutoff() -> usize {
+  calibrate::multi_exp_cutoff()
+}
+
+#[cfg(not(feature = "calibrate"))]
+fn multi_exp_cutoff() -> usize {
+  1
+}
+
 #[cfg(test)]
 mod tests {
   use super::*;
   use crate::util::int;
 
+  #[test]
+  fn test_assign_ops() {
+    let mut x = Rsa2048::elem(2);
+    Rsa2048::op_assign(&mut x, &Rsa2048::elem(3));
+    assert!(x == Rsa2048::elem(6));
+    Rsa2048::square_assign(&mut x);
+    assert!(x == Rsa2048::elem(36));
+    Rsa2048::inv_assign(&mut x);
+    assert!(Rsa2048::op(&x, &Rsa2048::elem(36)) == Rsa2048::id());
+  }
+
   #[test]
   fn test_multi_exp() {
     let alpha_1 = Rsa2048::elem(2);
@@ -174,4 +376,27 @@ mod tests {
     let res_2 = multi_exp::<Rsa2048>(&[alpha_1, alpha_2, alpha_3], &[x_1, x_2, x_3]);
     assert!(res_2 == Rsa2048::elem(1_687_500));
   }
+
+  #[test]
+  fn test_simultaneous_exp() {
+    let bases = [Rsa2048::elem(2), Rsa2048::elem(3), Rsa2048::elem(5)];
+    let exps = [int(1) << 300, int(12345), int(0)];
+    let expected = bases
+      .iter()
+      .zip(&exps)
+      .fold(Rsa2048::id(), |acc, (base, exp)| {
+        Rsa2048::op(&acc, &Rsa2048::exp(base, exp))
+      });
+    assert!(simultaneous_exp::<Rsa2048>(&bases, &exps) == expected);
+    assert!(simultaneous_exp::<Rsa2048>(&[], &[]) == Rsa2048::id());
+  }
+
+  #[test]
+  fn test_multi_exp_direct() {
+    let alphas = [Rsa2048::elem(2), Rsa2048::elem(3), Rsa2048::elem(5)];
+    let x = [int(3), int(2), int(1)];
+    let direct = multi_exp_direct::<Rsa2048>(&alphas, &x);
+    assert!(direct == Rsa2048::elem(1_687_500));
+    assert!(direct == multi_exp_::<Rsa2048>(&alphas, &x, 2));
+  }
 }
@@ -6,7 +6,7 @@ use curve25519_dalek::scalar::Scalar;
 use curve25519_dalek::traits::Identity;
 use rug::integer::Order;
 use rug::ops::Pow;
-use rug::Integer;
+use crate::integer::Integer;
 use std::hash::{Hash, Hasher};
 
 #[allow(clippy::module_name_repetitions)]
@@ -1,7 +1,10 @@
 //! RSA (2048) group using GMP integers in the `rug` crate.
-use super::{ElemFrom, ElemTo, Group, UnknownOrderGroup};
+use super::{ElemBytes, ElemFrom, ElemTo, Group, UnknownOrderGroup};
+use crate::hash::blake2b;
+use crate::integer::Integer;
 use crate::util::{int, TypeRep};
-use rug::Integer;
+use rug::integer::Order;
+use std::hash::Hash;
 use std::str::FromStr;
 
 #[allow(clippy::module_name_repetitions)]
@@ -12,6 +15,9 @@ use std::str::FromStr;
 /// element for sound proofs-of-exponentiation. See BBF (page 9).
 pub enum Rsa2048 {}
 
+/// Length in bytes of encoded RSA-2048 group elements.
+const ELEM_BYTES: usize = 256;
+
 /// RSA-2048 modulus, taken from [Wikipedia](https://en.wikipedia.org/wiki/RSA_numbers#RSA-2048).
 const RSA2048_MODULUS_DECIMAL: &str =
   "251959084756578934940271832400483985714292821262040320277771378360436620207075955562640185258807\
@@ -57,6 +63,11 @@ impl Group for Rsa2048 {
     // A side-channel resistant impl is 40% slower; we'll consider it in the future if we need to.
     Self::elem(x.0.pow_mod_ref(n, modulus).unwrap())
   }
+
+  // Valid elements are the representatives in `(0, N / 2]` of their cosets `{x, -x}`.
+  fn is_valid_elem_(_: &Integer, x: &Rsa2048Elem) -> bool {
+    x.0 > 0 && x.0 <= *HALF_MODULUS
+  }
 }
 
 impl<T> ElemFrom<T> for Rsa2048
@@ -83,6 +94,34 @@ where
   }
 }
 
+/// Elements are encoded as fixed-length big-endian integers. Only the representative in
+/// `[0, N / 2]` of each coset `{x, -x}` is accepted when decoding.
+impl ElemBytes for Rsa2048 {
+  const GROUP_ID: u8 = 1;
+
+  fn elem_bytes_len() -> usize {
+    ELEM_BYTES
+  }
+
+  fn elem_to_bytes(val: &Rsa2048Elem) -> Vec<u8> {
+    let digits = val.0.to_digits::<u8>(Order::Msf);
+    let mut bytes = vec![0; ELEM_BYTES - digits.len()];
+    bytes.extend_from_slice(&digits);
+    bytes
+  }
+
+  fn elem_from_bytes(bytes: &[u8]) -> Option<Rsa2048Elem> {
+    if bytes.len() != ELEM_BYTES {
+      return None;
+    }
+    let val = Integer::from_digits(bytes, Order::Msf);
+    if val == 0 || val > *HALF_MODULUS || int(val.gcd_ref(Self::rep())) != 1 {
+      return None;
+    }
+    Some(Rsa2048Elem(val))
+  }
+}
+
 impl UnknownOrderGroup for Rsa2048 {
   fn unknown_order_elem_(_: &Integer) -> Rsa2048Elem {
     Self::elem(2)
@@ -91,6 +130,17 @@ impl UnknownOrderGroup for Rsa2048 {
   fn order_upper_bound_(_: &Integer) -> Integer {
       RSA2048_MODULUS.clone()
   }
+
+  // Concatenates Blake2b hashes of `(tag, i)` into an integer 128 bits longer than the modulus, so
+  // that its residue is statistically close to uniform.
+  fn elem_from_hash_<T: Hash + ?Sized>(modulus: &Integer, tag: &T) -> Rsa2048Elem {
+    let n_blocks = (modulus.significant_bits() + 128) / 256 + 1;
+    let mut x = int(0);
+    for i in 0..n_blocks {
+      x = (x << 256) + blake2b(&(tag, i));
+    }
+    Self::elem(x)
+  }
 }
 
 #[cfg(test)]
@@ -110,6 +160,18 @@ mod tests {
     assert!(b == Rsa2048::elem(6));
   }
 
+  #[test]
+  fn test_is_valid_elem() {
+    assert!(Rsa2048::is_valid_elem(&Rsa2048::elem(int(
+      &*RSA2048_MODULUS - 2
+    ))));
+    assert!(!Rsa2048::is_valid_elem(&Rsa2048Elem(int(0))));
+    assert!(!Rsa2048::is_valid_elem(&Rsa2048Elem(int(-2))));
+    assert!(!Rsa2048::is_valid_elem(&Rsa2048Elem(int(
+      &*RSA2048_MODULUS - 2
+    ))));
+  }
+
   /// Tests that `-x` and `x` are treated as the same element.
   #[test]
   fn test_cosets() {
@@ -142,10 +204,33 @@ mod tests {
     dbg!(d);
   }
 
+  #[test]
+  fn test_elem_bytes() {
+    let x = Rsa2048::exp(&Rsa2048::elem(2), &int(4096));
+    let bytes = Rsa2048::elem_to_bytes(&x);
+    assert_eq!(bytes.len(), ELEM_BYTES);
+    assert!(Rsa2048::elem_from_bytes(&bytes) == Some(x));
+    assert!(Rsa2048::elem_from_bytes(&bytes[1..]).is_none());
+    assert!(Rsa2048::elem_from_bytes(&[0; ELEM_BYTES]).is_none());
+    let non_canonical = int(&*RSA2048_MODULUS - 3).to_digits::<u8>(Order::Msf);
+    assert!(Rsa2048::elem_from_bytes(&non_canonical).is_none());
+  }
+
   #[test]
   fn test_inv() {
     let x = Rsa2048::elem(2);
     let inv = Rsa2048::inv(&x);
     assert!(Rsa2048::op(&x, &inv) == Rsa2048::id());
   }
+
+  #[test]
+  fn test_elem_from_hash() {
+    let g = Rsa2048::elem_from_hash("g");
+    assert!(Rsa2048::is_valid_elem(&g));
+    assert!(g == Rsa2048::elem_from_hash("g"));
+    assert!(g != Rsa2048::elem_from_hash("h"));
+    assert!(g != Rsa2048::unknown_order_elem());
+    // The hash covers the whole modulus, not just the low bits.
+    assert!(g.0.significant_bits() > 2000);
+  }
 }
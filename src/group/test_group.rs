@@ -0,0 +1,161 @@
+//! A small, fast and insecure group for unit tests, enabled with the `test_utils` feature.
+//!
+//! `TestGroup` works like `Rsa2048`, with the cosets `{x, -x}` as elements, but modulo the 64-bit
+//! safe prime `p = 2^64 - 1469` instead of an RSA modulus. Its order `(p - 1) / 2` is public (and
+//! prime, so every element but the identity has full order), so it offers no security at all, but
+//! exponentiations take microseconds instead of milliseconds. Downstream code generic over
+//! `UnknownOrderGroup` can run its unit tests on it, and switch to a real group in production.
+use super::{ElemBytes, ElemFrom, ElemTo, Group, UnknownOrderGroup};
+use crate::hash::blake2b;
+use crate::integer::Integer;
+use crate::util::{int, TypeRep};
+use rug::integer::Order;
+use std::hash::Hash;
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+/// An insecure group of known order for tests. See the module docs.
+pub enum TestGroup {}
+
+/// Length in bytes of encoded `TestGroup` elements.
+const ELEM_BYTES: usize = 8;
+
+/// The modulus of `TestGroup`, the largest safe prime below `2^64`.
+const TEST_MODULUS: u64 = 0xffff_ffff_ffff_fa43;
+
+lazy_static! {
+  static ref MODULUS: Integer = int(TEST_MODULUS);
+  static ref HALF_MODULUS: Integer = int(TEST_MODULUS / 2);
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+/// A `TestGroup` element.
+pub struct TestGroupElem(Integer);
+
+impl TypeRep for TestGroup {
+  type Rep = Integer;
+  fn rep() -> &'static Self::Rep {
+    &MODULUS
+  }
+}
+
+impl Group for TestGroup {
+  type Elem = TestGroupElem;
+
+  fn op_(modulus: &Integer, a: &TestGroupElem, b: &TestGroupElem) -> TestGroupElem {
+    Self::elem(int(&a.0 * &b.0) % modulus)
+  }
+
+  fn id_(_: &Integer) -> TestGroupElem {
+    Self::elem(1)
+  }
+
+  fn inv_(modulus: &Integer, x: &TestGroupElem) -> TestGroupElem {
+    Self::elem(x.0.invert_ref(modulus).unwrap())
+  }
+
+  fn exp_(modulus: &Integer, x: &TestGroupElem, n: &Integer) -> TestGroupElem {
+    Self::elem(x.0.pow_mod_ref(n, modulus).unwrap())
+  }
+
+  // Valid elements are the representatives in `(0, p / 2]` of their cosets `{x, -x}`.
+  fn is_valid_elem_(_: &Integer, x: &TestGroupElem) -> bool {
+    x.0 > 0 && x.0 <= *HALF_MODULUS
+  }
+}
+
+impl<T> ElemFrom<T> for TestGroup
+where
+  Integer: From<T>,
+{
+  fn elem(t: T) -> TestGroupElem {
+    let modulus = Self::rep();
+    let val = (int(t) % modulus + modulus) % modulus;
+    if val > *HALF_MODULUS {
+      TestGroupElem(modulus - val)
+    } else {
+      TestGroupElem(val)
+    }
+  }
+}
+
+impl<T> ElemTo<T> for TestGroup
+where
+  T: From<Integer>,
+{
+  fn elem_to(val: &TestGroupElem) -> T {
+    val.0.clone().into()
+  }
+}
+
+/// Elements are encoded as 8-byte big-endian integers, like `Rsa2048` elements.
+impl ElemBytes for TestGroup {
+  const GROUP_ID: u8 = 0xff;
+
+  fn elem_bytes_len() -> usize {
+    ELEM_BYTES
+  }
+
+  fn elem_to_bytes(val: &TestGroupElem) -> Vec<u8> {
+    let digits = val.0.to_digits::<u8>(Order::Msf);
+    let mut bytes = vec![0; ELEM_BYTES - digits.len()];
+    bytes.extend_from_slice(&digits);
+    bytes
+  }
+
+  fn elem_from_bytes(bytes: &[u8]) -> Option<TestGroupElem> {
+    if bytes.len() != ELEM_BYTES {
+      return None;
+    }
+    let val = Integer::from_digits(bytes, Order::Msf);
+    if val == 0 || val > *HALF_MODULUS {
+      return None;
+    }
+    Some(TestGroupElem(val))
+  }
+}
+
+impl UnknownOrderGroup for TestGroup {
+  fn unknown_order_elem_(_: &Integer) -> TestGroupElem {
+    Self::elem(2)
+  }
+
+  fn order_upper_bound_(modulus: &Integer) -> Integer {
+    modulus.clone()
+  }
+
+  fn elem_from_hash_<T: Hash + ?Sized>(_: &Integer, tag: &T) -> TestGroupElem {
+    Self::elem(blake2b(tag))
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::accumulator::{Accumulator, Witness};
+
+  #[test]
+  fn test_group_ops() {
+    let order = int(TEST_MODULUS / 2);
+    let g = TestGroup::unknown_order_elem();
+    assert!(TestGroup::exp(&g, &order) == TestGroup::id());
+    assert!(TestGroup::exp(&g, &int(3)) == TestGroup::elem(8));
+    assert!(TestGroup::elem(-8) == TestGroup::elem(8));
+    assert!(TestGroup::op(&g, &TestGroup::inv(&g)) == TestGroup::id());
+    assert!(TestGroup::exp(&g, &int(-1)) == TestGroup::inv(&g));
+    assert!(TestGroup::is_valid_elem(&TestGroup::elem_from_hash("g")));
+    let bytes = TestGroup::elem_to_bytes(&g);
+    assert!(TestGroup::elem_from_bytes(&bytes) == Some(g));
+    assert!(TestGroup::elem_from_bytes(&[0xff; ELEM_BYTES]).is_none());
+  }
+
+  #[test]
+  fn test_accumulator_on_test_group() {
+    let (acc, proof) = Accumulator::<TestGroup, &'static str>::empty().add_with_proof(&["a", "b"]);
+    assert!(acc.verify_membership_batch(&["a", "b"], &proof));
+    assert!(!acc.verify_membership(&"c", &proof));
+    let acc = acc
+      .delete(&[("a", Witness(Accumulator::empty().add(&["b"])))])
+      .unwrap();
+    assert!(acc == Accumulator::empty().add(&["b"]));
+  }
+}
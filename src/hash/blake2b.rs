@@ -1,7 +1,7 @@
 //! `GeneralHasher` interface for `blake2_rfc`.
 use super::GeneralHasher;
 use blake2_rfc::blake2b::Blake2b as Blake2b_;
-use std::hash::Hasher;
+use std::hash::{Hash, Hasher};
 
 /// Thin wrapper around `Blake2b` from `blake2_rfc`.
 pub struct Blake2b(pub Blake2b_);
@@ -30,3 +30,11 @@ impl GeneralHasher for Blake2b {
     *array_ref![res.as_bytes(), 0, 32]
   }
 }
+
+// Hashes `t` with 48 bytes of output, for 384-bit prime candidates.
+pub(super) fn blake2b_384<T: Hash + ?Sized>(t: &T) -> [u8; 48] {
+  let mut hasher = Blake2b(Blake2b_::new(48));
+  t.hash(&mut hasher);
+  let res = hasher.0.finalize();
+  *array_ref![res.as_bytes(), 0, 48]
+}
@@ -0,0 +1,54 @@
+//! `GeneralHasher` interface for `blake3`.
+use super::GeneralHasher;
+use std::hash::Hasher;
+
+/// Thin wrapper around `Hasher` from `blake3`, in its default hashing mode with 32-byte output.
+#[derive(Default)]
+pub struct Blake3(pub ::blake3::Hasher);
+
+impl Hasher for Blake3 {
+  /// We could return a truncated hash but it's easier just to not use this fn for now.
+  fn finish(&self) -> u64 {
+    panic!("Don't use! Prefer finalize(self).")
+  }
+  fn write(&mut self, bytes: &[u8]) {
+    self.0.update(bytes);
+  }
+}
+
+impl GeneralHasher for Blake3 {
+  type Output = [u8; 32];
+  fn finalize(self) -> Self::Output {
+    *self.0.finalize().as_bytes()
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_blake3() {
+    // Test vectors from the BLAKE3 repository, whose inputs repeat the bytes 0, 1, ..., 250.
+    let cases = [
+      (
+        0,
+        "af1349b9f5f9a1a6a0404dea36dcc9499bcb25c9adc112b7cc9a93cae41f3262",
+      ),
+      (
+        1025,
+        "d00278ae47eb27b34faecf67b4fe263f82d5412916c1ffd97c8cb7fb814b8444",
+      ),
+    ];
+    for &(len, expected) in &cases {
+      let mut hasher = Blake3::default();
+      hasher.write(&(0..len).map(|i| (i % 251) as u8).collect::<Vec<_>>());
+      let hex = hasher
+        .finalize()
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect::<String>();
+      assert_eq!(hex, expected);
+    }
+  }
+}
@@ -1,14 +1,35 @@
 //! This module wraps `blake2b_rfc` into a convenient hashing interface (`GeneralHasher`) and
-//! exports the generalized `hash` function. Also exported is `hash_to_prime`, which works by
-//! repeatedly `hash`ing a value together with an incrementing nonce until the output is prime.
-use crate::uint::u256;
+//! exports the generalized `hash` function. BLAKE3 and SHA3-256 implement the same interface, for
+//! applications that must match their protocol's hash suite. Also exported is `hash_to_prime`,
+//! which works by repeatedly `hash`ing a value together with an incrementing nonce until the output
+//! is prime. Given that nonce, `verify_hash_to_prime` re-checks the mapping with a single hash and
+//! primality test instead of repeating the search. Inputs too large to hold in memory are read
+//! into a `StreamDigest` first.
+use crate::integer::Integer;
+use crate::uint::{u256, u384, Uint, U256, U384};
+use crate::util::product;
 use rug::integer::Order;
-use rug::Integer;
 use std::hash::{Hash, Hasher};
 
 mod blake2b;
 pub use blake2b::Blake2b;
+#[cfg(feature = "blake3")]
+mod blake3;
+#[cfg(feature = "blake3")]
+pub use self::blake3::Blake3;
+#[cfg(feature = "poseidon")]
+pub mod poseidon;
 pub mod primality;
+#[cfg(feature = "sha2")]
+mod sha256;
+#[cfg(feature = "sha2")]
+pub use sha256::Sha256;
+#[cfg(feature = "sha3")]
+mod sha3;
+#[cfg(feature = "sha3")]
+pub use self::sha3::Sha3_256;
+mod stream;
+pub use stream::*;
 
 /// Like `std::hash::Hasher`, but general over output type.
 pub trait GeneralHasher: Hasher {
@@ -42,6 +63,270 @@ pub fn blake2b<T: Hash + ?Sized>(t: &T) -> Integer {
   Integer::from_digits(&hash(&Blake2b::default, t), Order::Msf)
 }
 
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+/// The maximum bit length of primes output by `hash_to_prime_bits`. Smaller primes make
+/// accumulator operations faster at the cost of security: collisions between elements become
+/// likelier, and the soundness of proofs over accumulated primes degrades accordingly.
+pub enum PrimeBits {
+  /// 128-bit primes.
+  B128,
+  /// 192-bit primes.
+  B192,
+  /// 224-bit primes, for 112-bit security (see `Security112`).
+  B224,
+  /// 256-bit primes (the default).
+  B256,
+  /// 384-bit primes, for applications that want a wider margin than the default.
+  B384,
+}
+
+impl PrimeBits {
+  /// Returns the maximum bit length of primes of this size.
+  pub fn bits(self) -> u32 {
+    match self {
+      PrimeBits::B128 => 128,
+      PrimeBits::B192 => 192,
+      PrimeBits::B224 => 224,
+      PrimeBits::B256 => 256,
+      PrimeBits::B384 => 384,
+    }
+  }
+
+  /// Returns the size with the given maximum bit length, if supported.
+  pub fn from_bits(bits: u32) -> Option<Self> {
+    match bits {
+      128 => Some(PrimeBits::B128),
+      192 => Some(PrimeBits::B192),
+      224 => Some(PrimeBits::B224),
+      256 => Some(PrimeBits::B256),
+      384 => Some(PrimeBits::B384),
+      _ => None,
+    }
+  }
+}
+
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+/// The hash function elements are mapped to primes with.
+pub enum PrimeHash {
+  /// `hash_to_prime_bits`, built on Blake2b (the default).
+  Blake2b,
+  /// `HashBackend::Blake3`.
+  #[cfg(feature = "blake3")]
+  Blake3,
+  /// `HashBackend::Sha256`.
+  #[cfg(feature = "sha2")]
+  Sha256,
+  /// `poseidon::poseidon_to_prime`, which is cheap to check inside SNARK circuits.
+  #[cfg(feature = "poseidon")]
+  Poseidon,
+  /// `hash_to_division_intractable`, which maps elements to large odd integers rather than primes
+  /// and ignores the prime size. Membership proofs stay sound if the hash is division intractable
+  /// (see the appendix of BBF), a stronger assumption than collision resistance. Hashing takes a
+  /// few Blake2b calls instead of a prime search, but exponents are eight times as long as with
+  /// 256-bit primes, and since the integers need not be coprime, deletions and nonmembership
+  /// proofs can fail with `InputsNotCoprime`. Best suited to add-only accumulators.
+  DivisionIntractable,
+}
+
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+/// How elements are mapped to primes. Accumulators are bound to the parameters they were created
+/// with; converting from `PrimeBits` picks the default hash function.
+pub struct PrimeParams {
+  /// The maximum bit length of the primes.
+  pub bits: PrimeBits,
+  /// The hash function.
+  pub hash: PrimeHash,
+}
+
+impl PrimeParams {
+  /// Hashes `t` to a prime according to these parameters.
+  pub fn hash_to_prime<T: Hash + ?Sized>(self, t: &T) -> Integer {
+    self.hash_to_prime_with_nonce(t).0
+  }
+
+  /// Like `hash_to_prime`, but also returns the nonce the prime was found at.
+  pub fn hash_to_prime_with_nonce<T: Hash + ?Sized>(self, t: &T) -> (Integer, u64) {
+    match self.hash {
+      PrimeHash::Blake2b => hash_to_prime_bits_with_nonce(t, self.bits),
+      #[cfg(feature = "blake3")]
+      PrimeHash::Blake3 => HashBackend::Blake3.hash_to_prime_bits_with_nonce(t, self.bits),
+      #[cfg(feature = "sha2")]
+      PrimeHash::Sha256 => HashBackend::Sha256.hash_to_prime_bits_with_nonce(t, self.bits),
+      #[cfg(feature = "poseidon")]
+      PrimeHash::Poseidon => poseidon::poseidon_to_prime_with_nonce(t, self.bits),
+      PrimeHash::DivisionIntractable => (hash_to_division_intractable(t), 0),
+    }
+  }
+
+  /// Returns the candidate for `t` at `nonce` if it is prime, with a single hash and primality
+  /// test.
+  pub fn prime_from_nonce<T: Hash + ?Sized>(self, t: &T, nonce: u64) -> Option<Integer> {
+    match self.hash {
+      PrimeHash::Blake2b => blake2b_prime_from_nonce(t, nonce, self.bits),
+      #[cfg(feature = "blake3")]
+      PrimeHash::Blake3 => HashBackend::Blake3.prime_from_nonce(t, nonce, self.bits),
+      #[cfg(feature = "sha2")]
+      PrimeHash::Sha256 => HashBackend::Sha256.prime_from_nonce(t, nonce, self.bits),
+      #[cfg(feature = "poseidon")]
+      PrimeHash::Poseidon => poseidon::prime_from_nonce(t, nonce, self.bits),
+      PrimeHash::DivisionIntractable if nonce == 0 => Some(hash_to_division_intractable(t)),
+      PrimeHash::DivisionIntractable => None,
+    }
+  }
+
+  /// Checks that `prime` is the prime `t` hashes to at `nonce`. See `verify_hash_to_prime`.
+  pub fn verify_hash_to_prime<T: Hash + ?Sized>(self, t: &T, nonce: u64, prime: &Integer) -> bool {
+    self.prime_from_nonce(t, nonce).as_ref() == Some(prime)
+  }
+
+  /// Returns the maximum bit length of the integers elements are mapped to.
+  pub fn max_bits(self) -> u32 {
+    match self.hash {
+      PrimeHash::DivisionIntractable => DIVISION_INTRACTABLE_BITS,
+      _ => self.bits.bits(),
+    }
+  }
+
+  /// Hashes each of `ts` to a prime according to these parameters and returns their product.
+  pub fn prime_hash_product<T: Hash>(self, ts: &[T]) -> Integer {
+    product(ts.iter().map(|t| self.hash_to_prime(t)).collect())
+  }
+
+  /// Returns the encoding of these parameters: the size of the primes in bytes, followed by an
+  /// identifier for the hash function.
+  #[allow(clippy::cast_possible_truncation)]
+  pub fn to_bytes(self) -> [u8; 2] {
+    let hash_id = match self.hash {
+      PrimeHash::Blake2b => 0,
+      #[cfg(feature = "poseidon")]
+      PrimeHash::Poseidon => 1,
+      PrimeHash::DivisionIntractable => 2,
+      #[cfg(feature = "blake3")]
+      PrimeHash::Blake3 => 3,
+      #[cfg(feature = "sha2")]
+      PrimeHash::Sha256 => 4,
+    };
+    [(self.bits.bits() / 8) as u8, hash_id]
+  }
+
+  /// Parses the encoding produced by `to_bytes`. Fails for hash functions disabled by features.
+  pub fn from_bytes(bytes: [u8; 2]) -> Option<Self> {
+    let bits = PrimeBits::from_bits(u32::from(bytes[0]) * 8)?;
+    let hash = match bytes[1] {
+      0 => PrimeHash::Blake2b,
+      #[cfg(feature = "poseidon")]
+      1 => PrimeHash::Poseidon,
+      2 => PrimeHash::DivisionIntractable,
+      #[cfg(feature = "blake3")]
+      3 => PrimeHash::Blake3,
+      #[cfg(feature = "sha2")]
+      4 => PrimeHash::Sha256,
+      _ => return None,
+    };
+    Some(Self { bits, hash })
+  }
+}
+
+impl From<PrimeBits> for PrimeParams {
+  fn from(bits: PrimeBits) -> Self {
+    Self {
+      bits,
+      hash: PrimeHash::Blake2b,
+    }
+  }
+}
+
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+/// A hash function for mapping elements to primes, chosen at runtime, e.g. by name from a
+/// configuration file, where `hash_to_prime_with` fixes it at compile time. `Blake2` agrees with
+/// `hash_to_prime_bits`, and the others with `hash_to_prime_with` on their hashers. Converts to the
+/// `PrimeHash` of accumulators that map elements with it.
+pub enum HashBackend {
+  /// Blake2b, the default.
+  Blake2,
+  /// BLAKE3, with the `blake3` feature.
+  #[cfg(feature = "blake3")]
+  Blake3,
+  /// SHA-256, with the `sha2` feature.
+  #[cfg(feature = "sha2")]
+  Sha256,
+}
+
+impl HashBackend {
+  /// Looks up a backend by its configuration name: `"blake2"`, `"blake3"` or `"sha256"`. Returns
+  /// `None` for backends disabled by features.
+  pub fn from_name(name: &str) -> Option<Self> {
+    match name {
+      "blake2" => Some(HashBackend::Blake2),
+      #[cfg(feature = "blake3")]
+      "blake3" => Some(HashBackend::Blake3),
+      #[cfg(feature = "sha2")]
+      "sha256" => Some(HashBackend::Sha256),
+      _ => None,
+    }
+  }
+
+  /// Returns the configuration name of this backend.
+  pub fn name(self) -> &'static str {
+    match self {
+      HashBackend::Blake2 => "blake2",
+      #[cfg(feature = "blake3")]
+      HashBackend::Blake3 => "blake3",
+      #[cfg(feature = "sha2")]
+      HashBackend::Sha256 => "sha256",
+    }
+  }
+
+  /// Like `hash_to_prime`, with this backend.
+  pub fn hash_to_prime<T: Hash + ?Sized>(self, t: &T) -> Integer {
+    self.hash_to_prime_bits_with_nonce(t, PrimeBits::B256).0
+  }
+
+  /// Like `hash_to_prime_bits_with_nonce`, with this backend.
+  pub fn hash_to_prime_bits_with_nonce<T: Hash + ?Sized>(
+    self,
+    t: &T,
+    bits: PrimeBits,
+  ) -> (Integer, u64) {
+    match self {
+      HashBackend::Blake2 => hash_to_prime_bits_with_nonce(t, bits),
+      #[cfg(feature = "blake3")]
+      HashBackend::Blake3 => hash_to_prime_with(&Blake3::default, t, bits),
+      #[cfg(feature = "sha2")]
+      HashBackend::Sha256 => hash_to_prime_with(&Sha256::default, t, bits),
+    }
+  }
+
+  /// Returns the candidate for `t` at `nonce` if it is prime, with a single hash and primality
+  /// test.
+  pub fn prime_from_nonce<T: Hash + ?Sized>(
+    self,
+    t: &T,
+    nonce: u64,
+    bits: PrimeBits,
+  ) -> Option<Integer> {
+    match self {
+      HashBackend::Blake2 => blake2b_prime_from_nonce(t, nonce, bits),
+      #[cfg(feature = "blake3")]
+      HashBackend::Blake3 => prime_from_nonce_with(&Blake3::default, t, nonce, bits),
+      #[cfg(feature = "sha2")]
+      HashBackend::Sha256 => prime_from_nonce_with(&Sha256::default, t, nonce, bits),
+    }
+  }
+}
+
+impl From<HashBackend> for PrimeHash {
+  fn from(backend: HashBackend) -> Self {
+    match backend {
+      HashBackend::Blake2 => PrimeHash::Blake2b,
+      #[cfg(feature = "blake3")]
+      HashBackend::Blake3 => PrimeHash::Blake3,
+      #[cfg(feature = "sha2")]
+      HashBackend::Sha256 => PrimeHash::Sha256,
+    }
+  }
+}
+
 /// Hashes `t` to an odd prime.
 ///
 /// Uses `Blake2b` as the hash function, and hashes with a counter until a prime is found via
@@ -50,22 +335,167 @@ pub fn blake2b<T: Hash + ?Sized>(t: &T) -> Integer {
 /// This function is optimized for 256-bit integers.
 #[allow(clippy::module_name_repetitions)]
 pub fn hash_to_prime<T: Hash + ?Sized>(t: &T) -> Integer {
-  let mut counter = 0_u64;
+  hash_to_prime_bits(t, PrimeBits::B256)
+}
+
+/// Like `hash_to_prime`, but also returns the nonce the prime was found at, so that verifiers can
+/// check the mapping with `verify_hash_to_prime`.
+#[allow(clippy::module_name_repetitions)]
+pub fn hash_to_prime_with_nonce<T: Hash + ?Sized>(t: &T) -> (Integer, u64) {
+  hash_to_prime_bits_with_nonce(t, PrimeBits::B256)
+}
+
+/// Checks that `prime` is what `hash_to_prime` outputs for `t` when the search ends at `nonce`,
+/// with a single hash and primality test.
+///
+/// This does not check that no smaller nonce yields a prime, so a dishonest prover can map `t` to
+/// any of several primes. That is harmless when the prime is checked against an accumulator built
+/// with `hash_to_prime`, which only contains the first one.
+#[allow(clippy::module_name_repetitions)]
+pub fn verify_hash_to_prime<T: Hash + ?Sized>(t: &T, nonce: u64, prime: &Integer) -> bool {
+  PrimeParams::from(PrimeBits::B256).verify_hash_to_prime(t, nonce, prime)
+}
+
+/// Like `hash_to_prime`, but outputs primes of at most `bits` bits. `PrimeBits::B256` agrees with
+/// `hash_to_prime`.
+#[allow(clippy::module_name_repetitions)]
+pub fn hash_to_prime_bits<T: Hash + ?Sized>(t: &T, bits: PrimeBits) -> Integer {
+  hash_to_prime_bits_with_nonce(t, bits).0
+}
+
+/// Like `hash_to_prime_bits`, but also returns the nonce the prime was found at.
+#[allow(clippy::module_name_repetitions)]
+pub fn hash_to_prime_bits_with_nonce<T: Hash + ?Sized>(t: &T, bits: PrimeBits) -> (Integer, u64) {
+  #[cfg(feature = "metrics")]
+  let _timer = crate::metrics::Timer::start(crate::metrics::Operation::HashToPrime, bits.bits());
+  let mut nonce = 0_u64;
+  loop {
+    if let Some(prime) = blake2b_prime_from_nonce(t, nonce, bits) {
+      return (prime, nonce);
+    }
+    nonce += 1;
+  }
+}
+
+/// Like `hash_to_prime_bits_with_nonce`, but hashes with `new_hasher`'s hasher, chosen at compile
+/// time. Candidates are derived as for Blake2b, so `hash_to_prime_with(&Blake2b::default, ..)`
+/// agrees with `hash_to_prime_bits` up to 256 bits. A 32-byte output is too short for 384-bit
+/// candidates, which are instead the hashes of `(t, nonce, 0_u8)` and `(t, nonce, 1_u8)`,
+/// concatenated and truncated.
+pub fn hash_to_prime_with<H, T>(
+  new_hasher: &dyn Fn() -> H,
+  t: &T,
+  bits: PrimeBits,
+) -> (Integer, u64)
+where
+  H: GeneralHasher<Output = [u8; 32]>,
+  T: Hash + ?Sized,
+{
+  let mut nonce = 0_u64;
   loop {
-    let mut hash = hash(&Blake2b::default, &(t, counter));
-    // Make the candidate prime odd. This gives ~7% performance gain on a 2018 Macbook Pro.
-    hash[0] |= 1;
-    let candidate_prime = u256(hash);
-    if primality::is_prob_prime(&candidate_prime) {
-      return Integer::from(candidate_prime);
+    if let Some(prime) = prime_from_nonce_with(new_hasher, t, nonce, bits) {
+      return (prime, nonce);
+    }
+    nonce += 1;
+  }
+}
+
+/// Returns the candidate `hash_to_prime_with` derives for `t` at `nonce` if it is prime.
+pub fn prime_from_nonce_with<H, T>(
+  new_hasher: &dyn Fn() -> H,
+  t: &T,
+  nonce: u64,
+  bits: PrimeBits,
+) -> Option<Integer>
+where
+  H: GeneralHasher<Output = [u8; 32]>,
+  T: Hash + ?Sized,
+{
+  match bits {
+    PrimeBits::B384 => {
+      let mut bytes = [0; 48];
+      bytes[..32].copy_from_slice(&hash(new_hasher, &(t, nonce, 0_u8)));
+      bytes[32..].copy_from_slice(&hash(new_hasher, &(t, nonce, 1_u8))[..16]);
+      bytes[0] |= 1;
+      checked_candidate(u384(bytes))
     }
-    counter += 1;
+    _ => checked_candidate(candidate(new_hasher, t, nonce, bits)),
   }
 }
 
+/// The bit length of `hash_to_division_intractable` outputs, well beyond the sizes for which
+/// Coron and Naccache found division intractability to fail.
+pub const DIVISION_INTRACTABLE_BITS: u32 = 2048;
+
+// Separates division-intractable hashes from prime candidates.
+const DIVISION_INTRACTABLE_DOMAIN: &str = "division intractable";
+
+/// Hashes `t` to an odd integer of exactly `DIVISION_INTRACTABLE_BITS` bits, from Blake2b hashes
+/// of `t` with a counter. See `PrimeHash::DivisionIntractable`.
+pub fn hash_to_division_intractable<T: Hash + ?Sized>(t: &T) -> Integer {
+  let n_blocks = DIVISION_INTRACTABLE_BITS / 256;
+  let mut bytes = Vec::with_capacity(n_blocks as usize * 32);
+  for i in 0..n_blocks {
+    bytes.extend_from_slice(&hash(
+      &Blake2b::default,
+      &(t, DIVISION_INTRACTABLE_DOMAIN, i),
+    ));
+  }
+  bytes[0] |= 0x80;
+  let last = bytes.len() - 1;
+  bytes[last] |= 1;
+  Integer::from_digits(&bytes, Order::Msf)
+}
+
+/// Returns the candidate for `t` at `nonce` if it is prime. Candidates of up to 256 bits are tested
+/// as `U256`s and wider ones as `U384`s, so neither allocates.
+fn blake2b_prime_from_nonce<T: Hash + ?Sized>(
+  t: &T,
+  nonce: u64,
+  bits: PrimeBits,
+) -> Option<Integer> {
+  match bits {
+    PrimeBits::B384 => checked_candidate(blake2b_candidate_384(t, nonce)),
+    _ => checked_candidate(candidate(&Blake2b::default, t, nonce, bits)),
+  }
+}
+
+fn checked_candidate<U: Uint>(candidate: U) -> Option<Integer> {
+  if primality::is_prob_prime(&candidate) {
+    Some(candidate.into())
+  } else {
+    None
+  }
+}
+
+/// The candidate prime for `t` at `nonce`: the hash of both, truncated to `bits` bits and made odd.
+fn candidate<H, T>(new_hasher: &dyn Fn() -> H, t: &T, nonce: u64, bits: PrimeBits) -> U256
+where
+  H: GeneralHasher<Output = [u8; 32]>,
+  T: Hash + ?Sized,
+{
+  let n_bytes = bits.bits() as usize / 8;
+  let mut hash = hash(new_hasher, &(t, nonce));
+  // The hash is interpreted as little-endian, so truncate by zeroing its high bytes.
+  for byte in hash[n_bytes..].iter_mut() {
+    *byte = 0;
+  }
+  // Make the candidate prime odd. This gives ~7% performance gain on a 2018 Macbook Pro.
+  hash[0] |= 1;
+  u256(hash)
+}
+
+/// Like `candidate` for 384-bit candidates, from a Blake2b hash with 48 bytes of output.
+fn blake2b_candidate_384<T: Hash + ?Sized>(t: &T, nonce: u64) -> U384 {
+  let mut hash = blake2b::blake2b_384(&(t, nonce));
+  hash[0] |= 1;
+  u384(hash)
+}
+
 #[cfg(test)]
 mod tests {
   use super::*;
+  use rug::integer::IsPrime;
 
   #[test]
   fn test_blake2() {
@@ -88,4 +518,124 @@ mod tests {
     h_2.write_digits(&mut digits2, Order::Lsf);
     assert!(primality::is_prob_prime(&u256(digits2)));
   }
+
+  #[test]
+  fn test_hash_to_prime_bits() {
+    let b = "boom i got ur boyfriend";
+    assert_eq!(hash_to_prime_bits(b, PrimeBits::B256), hash_to_prime(b));
+    for &bits in &[PrimeBits::B128, PrimeBits::B192, PrimeBits::B224] {
+      let h = hash_to_prime_bits(b, bits);
+      assert!(h.significant_bits() <= bits.bits());
+      let mut digits = [0; 4];
+      h.write_digits(&mut digits, Order::Lsf);
+      assert!(primality::is_prob_prime(&u256(digits)));
+    }
+
+    let h = hash_to_prime_bits(b, PrimeBits::B384);
+    assert!(h.significant_bits() <= 384 && h.significant_bits() > 256);
+    assert!(h.is_probably_prime(30) != IsPrime::No);
+    let params = PrimeParams::from(PrimeBits::B384);
+    let (prime, nonce) = params.hash_to_prime_with_nonce(b);
+    assert_eq!(prime, h);
+    assert!(params.verify_hash_to_prime(b, nonce, &prime));
+    assert_eq!(PrimeParams::from_bytes(params.to_bytes()), Some(params));
+  }
+
+  #[test]
+  fn test_verify_hash_to_prime() {
+    let b = "boom i got ur boyfriend";
+    let (prime, nonce) = hash_to_prime_with_nonce(b);
+    assert_eq!(prime, hash_to_prime(b));
+    assert!(verify_hash_to_prime(b, nonce, &prime));
+    assert!(!verify_hash_to_prime(
+      "boom i got ur boyfriene",
+      nonce,
+      &prime
+    ));
+    assert!(!verify_hash_to_prime(b, nonce + 1, &prime));
+    assert!(!verify_hash_to_prime(b, nonce, &(prime.clone() + 2)));
+
+    let params = PrimeParams::from(PrimeBits::B128);
+    let (prime, nonce) = params.hash_to_prime_with_nonce(b);
+    assert_eq!(prime, hash_to_prime_bits(b, PrimeBits::B128));
+    assert!(params.verify_hash_to_prime(b, nonce, &prime));
+    assert!(!verify_hash_to_prime(b, nonce, &prime));
+  }
+
+  #[test]
+  fn test_hash_to_division_intractable() {
+    let b = "boom i got ur boyfriend";
+    let h = hash_to_division_intractable(b);
+    assert_eq!(h.significant_bits(), DIVISION_INTRACTABLE_BITS);
+    assert!(h.is_odd());
+    assert_ne!(h, hash_to_division_intractable("boom i got ur boyfriene"));
+
+    let params = PrimeParams {
+      bits: PrimeBits::B256,
+      hash: PrimeHash::DivisionIntractable,
+    };
+    assert_eq!(params.hash_to_prime_with_nonce(b), (h.clone(), 0));
+    assert!(params.verify_hash_to_prime(b, 0, &h));
+    assert!(!params.verify_hash_to_prime(b, 1, &h));
+    assert_eq!(params.max_bits(), DIVISION_INTRACTABLE_BITS);
+    assert_eq!(PrimeParams::from_bytes(params.to_bytes()), Some(params));
+  }
+
+  #[test]
+  fn test_hash_backend() {
+    let b = "boom i got ur boyfriend";
+    #[allow(unused_mut)]
+    let mut backends = vec![HashBackend::Blake2];
+    #[cfg(feature = "blake3")]
+    backends.push(HashBackend::Blake3);
+    #[cfg(feature = "sha2")]
+    backends.push(HashBackend::Sha256);
+    for &backend in &backends {
+      assert_eq!(HashBackend::from_name(backend.name()), Some(backend));
+    }
+    assert_eq!(HashBackend::from_name("md5"), None);
+    assert_eq!(HashBackend::Blake2.hash_to_prime(b), hash_to_prime(b));
+    assert_eq!(
+      hash_to_prime_with(&Blake2b::default, b, PrimeBits::B192),
+      hash_to_prime_bits_with_nonce(b, PrimeBits::B192)
+    );
+    // 384-bit candidates come from two hashes, so they differ from `hash_to_prime_bits`'s.
+    let (prime, nonce) = hash_to_prime_with(&Blake2b::default, b, PrimeBits::B384);
+    assert!(prime.significant_bits() > 256 && prime.significant_bits() <= 384);
+    assert_eq!(
+      prime_from_nonce_with(&Blake2b::default, b, nonce, PrimeBits::B384),
+      Some(prime.clone())
+    );
+    assert!(prime != hash_to_prime_bits(b, PrimeBits::B384));
+
+    for &backend in &backends[1..] {
+      for &bits in &[PrimeBits::B128, PrimeBits::B384] {
+        let params = PrimeParams {
+          bits,
+          hash: backend.into(),
+        };
+        let (prime, nonce) = params.hash_to_prime_with_nonce(b);
+        assert!(prime.significant_bits() <= bits.bits());
+        assert!(prime.is_probably_prime(30) != IsPrime::No);
+        assert_eq!(
+          backend.prime_from_nonce(b, nonce, bits),
+          Some(prime.clone())
+        );
+        assert!(params.verify_hash_to_prime(b, nonce, &prime));
+        assert_eq!(PrimeParams::from_bytes(params.to_bytes()), Some(params));
+      }
+    }
+  }
+
+  #[cfg(feature = "sha2")]
+  #[test]
+  fn test_sha256_backend() {
+    let b = "boom i got ur boyfriend";
+    let (prime, nonce) = HashBackend::Sha256.hash_to_prime_bits_with_nonce(b, PrimeBits::B256);
+    assert_eq!(
+      (prime.clone(), nonce),
+      hash_to_prime_with(&Sha256::default, b, PrimeBits::B256)
+    );
+    assert!(prime != hash_to_prime(b));
+  }
 }
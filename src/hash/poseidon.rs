@@ -0,0 +1,289 @@
+//! The Poseidon hash over the BN254 scalar field, enabled with the `poseidon` feature, and a
+//! hash-to-prime built on it. Checking that an element maps to a prime then costs a few hundred
+//! constraints inside a SNARK circuit, instead of the tens of thousands a Blake2b hash would.
+//!
+//! Parameters are those of circomlib's two-input Poseidon (width 3, `x^5` S-box, 8 full and 57
+//! partial rounds), with round constants and MDS matrix derived from the Grain LFSR as in the
+//! reference implementation. `poseidon_hash(a, b)` therefore agrees with circomlib's
+//! `Poseidon(2)` template.
+use super::{hash, primality, GeneralHasher, PrimeBits};
+use crate::integer::Integer;
+use crate::uint::u256;
+use crate::util::int;
+use rug::integer::Order;
+use std::hash::{Hash, Hasher};
+use std::str::FromStr;
+
+const MODULUS_DECIMAL: &str =
+  "21888242871839275222246405745257275088548364400416034343698204186575808495617";
+const MODULUS_BITS: u32 = 254;
+const WIDTH: usize = 3;
+const FULL_ROUNDS: usize = 8;
+const PARTIAL_ROUNDS: usize = 57;
+
+// Bytes per field element when packing encoded elements, chosen so every chunk is less than the
+// modulus.
+const CHUNK_BYTES: usize = 31;
+
+lazy_static! {
+  static ref MODULUS: Integer = Integer::from_str(MODULUS_DECIMAL).unwrap();
+  static ref ROUND_CONSTANTS_AND_MDS: (Vec<Integer>, Vec<Vec<Integer>>) = generate_parameters();
+}
+
+/// The Grain LFSR in self-shrinking mode, as used by the Poseidon reference implementation to
+/// derive its parameters. Bit `i` of the state is the `i`-th oldest bit.
+struct Grain(u128);
+
+impl Grain {
+  fn new() -> Self {
+    // Field type (prime field), S-box type (`x^alpha`), field size, width, and round numbers,
+    // followed by 30 set bits.
+    let fields: [(u128, u32); 7] = [
+      (1, 2),
+      (0, 4),
+      (u128::from(MODULUS_BITS), 12),
+      (WIDTH as u128, 12),
+      (FULL_ROUNDS as u128, 10),
+      (PARTIAL_ROUNDS as u128, 10),
+      ((1 << 30) - 1, 30),
+    ];
+    let mut state = 0;
+    let mut i = 0;
+    for &(value, width) in &fields {
+      for j in (0..width).rev() {
+        state |= ((value >> j) & 1) << i;
+        i += 1;
+      }
+    }
+    let mut grain = Grain(state);
+    for _ in 0..160 {
+      grain.step();
+    }
+    grain
+  }
+
+  fn step(&mut self) -> bool {
+    let s = self.0;
+    let bit = ((s >> 62) ^ (s >> 51) ^ (s >> 38) ^ (s >> 23) ^ (s >> 13) ^ s) & 1;
+    self.0 = (s >> 1) | (bit << 79);
+    bit == 1
+  }
+
+  fn next_bit(&mut self) -> bool {
+    loop {
+      let keep = self.step();
+      let bit = self.step();
+      if keep {
+        return bit;
+      }
+    }
+  }
+
+  fn next_int(&mut self) -> Integer {
+    let mut n = int(0);
+    for _ in 0..MODULUS_BITS {
+      n <<= 1;
+      if self.next_bit() {
+        n += 1;
+      }
+    }
+    n
+  }
+
+  fn next_field_elem(&mut self) -> Integer {
+    loop {
+      let n = self.next_int();
+      if n < *MODULUS {
+        return n;
+      }
+    }
+  }
+}
+
+fn generate_parameters() -> (Vec<Integer>, Vec<Vec<Integer>>) {
+  let mut grain = Grain::new();
+  let round_constants = (0..(FULL_ROUNDS + PARTIAL_ROUNDS) * WIDTH)
+    .map(|_| grain.next_field_elem())
+    .collect();
+
+  // A Cauchy matrix `1 / (x_i + y_j)` over distinct `x_i`, `y_j`. The reference implementation also
+  // rejects matrices admitting invariant subspace trails; the first candidate passes for these
+  // parameters.
+  let mds = loop {
+    let xs_ys = (0..2 * WIDTH)
+      .map(|_| grain.next_int() % &*MODULUS)
+      .collect::<Vec<_>>();
+    let (xs, ys) = xs_ys.split_at(WIDTH);
+    let distinct = (0..2 * WIDTH).all(|i| (0..i).all(|j| xs_ys[i] != xs_ys[j]));
+    let entries = xs
+      .iter()
+      .map(|x| {
+        ys.iter()
+          .map(|y| int(x + y).invert(&MODULUS).ok())
+          .collect::<Option<Vec<_>>>()
+      })
+      .collect::<Option<Vec<_>>>();
+    if let (true, Some(mds)) = (distinct, entries) {
+      break mds;
+    }
+  };
+  (round_constants, mds)
+}
+
+fn permute(state: &mut Vec<Integer>) {
+  let (round_constants, mds) = &*ROUND_CONSTANTS_AND_MDS;
+  let modulus = &*MODULUS;
+  for round in 0..FULL_ROUNDS + PARTIAL_ROUNDS {
+    for (i, elem) in state.iter_mut().enumerate() {
+      *elem += &round_constants[round * WIDTH + i];
+    }
+    let is_partial_round = (FULL_ROUNDS / 2..FULL_ROUNDS / 2 + PARTIAL_ROUNDS).contains(&round);
+    let n_sboxes = if is_partial_round { 1 } else { WIDTH };
+    for elem in state.iter_mut().take(n_sboxes) {
+      elem.pow_mod_mut(&int(5), modulus).unwrap();
+    }
+    *state = mds
+      .iter()
+      .map(|row| {
+        let sum: Integer = row.iter().zip(state.iter()).map(|(m, s)| int(m * s)).sum();
+        sum % modulus
+      })
+      .collect();
+  }
+}
+
+/// Hashes two field elements, reducing them modulo the BN254 scalar field order first.
+pub fn poseidon_hash(a: &Integer, b: &Integer) -> Integer {
+  let reduce = |x: &Integer| <(Integer, Integer)>::from(x.div_rem_euc_ref(&MODULUS)).1;
+  let mut state = vec![int(0), reduce(a), reduce(b)];
+  permute(&mut state);
+  state.swap_remove(0)
+}
+
+/// Collects the bytes fed to it by a `Hash` impl.
+#[derive(Default)]
+struct ByteCollector(Vec<u8>);
+
+impl Hasher for ByteCollector {
+  fn finish(&self) -> u64 {
+    panic!("Don't use! Prefer finalize(self).")
+  }
+  fn write(&mut self, bytes: &[u8]) {
+    self.0.extend_from_slice(bytes)
+  }
+}
+
+impl GeneralHasher for ByteCollector {
+  type Output = Vec<u8>;
+  fn finalize(self) -> Vec<u8> {
+    self.0
+  }
+}
+
+/// Hashes `t` to a field element: its `Hash` encoding is split into 31-byte big-endian chunks,
+/// which are chained through `poseidon_hash` starting from the encoding's length.
+pub fn poseidon<T: Hash + ?Sized>(t: &T) -> Integer {
+  let bytes = hash(&ByteCollector::default, t);
+  bytes
+    .chunks(CHUNK_BYTES)
+    .fold(int(bytes.len()), |acc, chunk| {
+      poseidon_hash(&acc, &Integer::from_digits(chunk, Order::Msf))
+    })
+}
+
+/// Like `hash_to_prime_bits`, but built on Poseidon: candidates are `poseidon_hash(poseidon(t), i)`
+/// for `i = 0, 1, ...`, truncated to `bits` bits and made odd. Note that `PrimeBits::B256` and
+/// `PrimeBits::B384` yield primes of at most 254 bits, the size of the field.
+pub fn poseidon_to_prime<T: Hash + ?Sized>(t: &T, bits: PrimeBits) -> Integer {
+  poseidon_to_prime_with_nonce(t, bits).0
+}
+
+/// Like `poseidon_to_prime`, but also returns the nonce `i` the prime was found at.
+pub fn poseidon_to_prime_with_nonce<T: Hash + ?Sized>(t: &T, bits: PrimeBits) -> (Integer, u64) {
+  let h = poseidon(t);
+  let mut nonce = 0_u64;
+  loop {
+    if let Some(prime) = checked_candidate(&h, nonce, bits) {
+      return (prime, nonce);
+    }
+    nonce += 1;
+  }
+}
+
+/// Returns the candidate for `t` at `nonce` if it is prime.
+pub(super) fn prime_from_nonce<T: Hash + ?Sized>(
+  t: &T,
+  nonce: u64,
+  bits: PrimeBits,
+) -> Option<Integer> {
+  checked_candidate(&poseidon(t), nonce, bits)
+}
+
+fn checked_candidate(h: &Integer, nonce: u64, bits: PrimeBits) -> Option<Integer> {
+  let mut candidate = poseidon_hash(h, &int(nonce));
+  candidate.keep_bits_mut(bits.bits());
+  candidate.set_bit(0, true);
+  let mut digits = [0_u64; 4];
+  candidate.write_digits(&mut digits, Order::Lsf);
+  if primality::is_prob_prime(&u256(digits)) {
+    Some(candidate)
+  } else {
+    None
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_parameters() {
+    let (round_constants, mds) = &*ROUND_CONSTANTS_AND_MDS;
+    assert_eq!(
+      round_constants[0],
+      Integer::from_str_radix(
+        "0ee9a592ba9a9518d05986d656f40c2114c4993c11bb29938d21d47304cd8e6e",
+        16
+      )
+      .unwrap()
+    );
+    assert_eq!(
+      mds[0][0],
+      Integer::from_str_radix(
+        "109b7f411ba0e4c9b2b70caf5c36a7b194be7c11ad24378bfedb68592ba8118b",
+        16
+      )
+      .unwrap()
+    );
+  }
+
+  // Test vector from circomlib's `Poseidon(2)`.
+  #[test]
+  fn test_poseidon_hash() {
+    assert_eq!(
+      poseidon_hash(&int(1), &int(2)),
+      Integer::from_str_radix(
+        "115cc0f5e7d690413df64c6b9662e9cf2a3617f2743245519e19607a4417189a",
+        16
+      )
+      .unwrap()
+    );
+  }
+
+  #[test]
+  fn test_poseidon_to_prime() {
+    let p = poseidon_to_prime("boom i got ur boyfriend", PrimeBits::B128);
+    assert!(p.significant_bits() <= 128);
+    assert!(p.is_probably_prime(30) != rug::integer::IsPrime::No);
+    assert_ne!(
+      p,
+      poseidon_to_prime("boom i got ur boyfriene", PrimeBits::B128)
+    );
+    let (q, nonce) = poseidon_to_prime_with_nonce("boom i got ur boyfriend", PrimeBits::B128);
+    assert_eq!(p, q);
+    assert_eq!(
+      prime_from_nonce("boom i got ur boyfriend", nonce, PrimeBits::B128),
+      Some(p)
+    );
+  }
+}
@@ -1,6 +1,6 @@
-//! Primality testing for U256 inputs. Use `is_prob_prime` unless you have a specific reason to use
-//! a lower-level test.
-use crate::uint::{u256, u512, U256};
+//! Primality testing for fixed-width (`U256` or `U384`) inputs. Use `is_prob_prime` unless you
+//! have a specific reason to use a lower-level test.
+use crate::uint::{Montgomery, Uint};
 
 mod constants;
 use constants::{D_VALUES, SMALL_PRIMES};
@@ -12,31 +12,86 @@ use constants::{D_VALUES, SMALL_PRIMES};
 /// Fermat and Lucas pseudoprimes have been shown to be anticorrelated. Steps of BPSW are as
 /// follows:
 ///
-/// 1. Accept small primes and reject multiples of them.
+/// 1. Accept small primes and reject multiples of them (see `passes_trial_division`).
 /// 2. Do a single iteration of Miller-Rabin (in particular, a base-2 Fermat test).
 /// 3. Do a strong probabilistic Lucas test (squares filtered during test initialization).
-pub fn is_prob_prime(n: &U256) -> bool {
-  for &p in SMALL_PRIMES.iter() {
-    if n.is_divisible_u(p) {
-      return *n == p;
+pub fn is_prob_prime<U: Uint>(n: &U) -> bool {
+  match passes_trial_division(n) {
+    Some(is_prime) => is_prime,
+    None => passes_miller_rabin_base_2(n) && passes_lucas(n),
+  }
+}
+
+// The primes whose product is the wheel modulus, `WHEEL`.
+const WHEEL_PRIMES: [u64; 4] = [2, 3, 5, 7];
+const WHEEL: u64 = 2 * 3 * 5 * 7;
+
+lazy_static! {
+  // Whether each residue mod `WHEEL` is coprime to it.
+  static ref WHEEL_SPOKES: Vec<bool> = (0..WHEEL)
+    .map(|r| WHEEL_PRIMES.iter().all(|p| r % p != 0))
+    .collect();
+  // The remaining `SMALL_PRIMES` in groups whose products fit in a `u64`, each with its product.
+  // The first product is also a multiple of `WHEEL`.
+  static ref SMALL_PRIME_GROUPS: Vec<(u64, Vec<u64>)> = {
+    let mut groups = Vec::new();
+    let (mut product, mut primes) = (WHEEL, Vec::new());
+    for &p in SMALL_PRIMES[WHEEL_PRIMES.len()..].iter() {
+      if let Some(next) = product.checked_mul(p) {
+        product = next;
+        primes.push(p);
+      } else {
+        groups.push((product, primes));
+        product = p;
+        primes = vec![p];
+      }
+    }
+    groups.push((product, primes));
+    groups
+  };
+}
+
+/// Trial division by `SMALL_PRIMES`: returns whether `n` is prime if it is one of them or a
+/// multiple of one, or `None` to continue with the probabilistic tests.
+///
+/// Rather than dividing `n` by each prime, takes one multiprecision remainder per group of primes
+/// whose product fits in a word and divides that instead, and rejects multiples of 2, 3, 5 and 7
+/// with a single lookup of the first remainder on a wheel mod 210. (The candidates of
+/// `hash_to_prime` for successive nonces are independent hashes, not an arithmetic progression,
+/// so they cannot share a sieve.)
+pub fn passes_trial_division<U: Uint>(n: &U) -> Option<bool> {
+  for (i, (product, primes)) in SMALL_PRIME_GROUPS.iter().enumerate() {
+    let r = n.rem_u(*product);
+    if i == 0 && !WHEEL_SPOKES[(r % WHEEL) as usize] {
+      return Some(WHEEL_PRIMES.iter().any(|&p| *n == p));
+    }
+    if let Some(&p) = primes.iter().find(|&&p| r % p == 0) {
+      return Some(*n == p);
     }
   }
-  passes_miller_rabin_base_2(&n) && passes_lucas(&n)
+  None
 }
 
-/// A single iteration of the Miller-Rabin test (base-2 Fermat test).
-pub fn passes_miller_rabin_base_2(n: &U256) -> bool {
-  let (d, r) = (n - 1).remove_factor(u256(2));
-  let mut x = u256(2).pow_mod(d, n);
-  if x == 1 || x == n - 1 {
+/// A single iteration of the Miller-Rabin test (base-2 Fermat test). Works in Montgomery form
+/// throughout.
+pub fn passes_miller_rabin_base_2<U: Uint>(n: &U) -> bool {
+  // No even `n` passes, not even 2, and Montgomery arithmetic needs an odd modulus.
+  if !n.is_odd() {
+    return false;
+  }
+  let mont = U::Mont::new(*n);
+  let (one, minus_one) = (mont.to_mont(U::from(1)), mont.to_mont(*n - 1));
+  let (d, r) = (*n - 1).remove_factor(U::from(2));
+  let mut x = mont.pow2(&d);
+  if x == one || x == minus_one {
     return true;
   }
   for _ in 1..r {
-    x = x * x % n;
-    if x == 1 {
+    x = mont.square(x);
+    if x == one {
       return false;
     }
-    if x == n - 1 {
+    if x == minus_one {
       return true;
     }
   }
@@ -53,7 +108,11 @@ pub fn passes_miller_rabin_base_2(n: &U256) -> bool {
 /// more tests in Section 6 of [Baillie and Wagstaff 1980], but for now this is TODO.
 ///
 /// See also: [Lucas pseudoprime](https://en.wikipedia.org/wiki/Lucas_pseudoprime) on Wikipedia.
-pub fn passes_lucas(n: &U256) -> bool {
+pub fn passes_lucas<U: Uint>(n: &U) -> bool {
+  // Montgomery arithmetic needs an odd modulus, and the test fails on 2 anyway.
+  if !n.is_odd() {
+    return false;
+  }
   let d_ = choose_d(n);
   if d_.is_err() {
     return false;
@@ -62,14 +121,14 @@ pub fn passes_lucas(n: &U256) -> bool {
   let q = (1 - d) / 4;
 
   let (u_delta, v_delta, q_delta_over_2) =
-    compute_lucas_sequences(*n + 1, n, u256(1), u256(1), q, d);
+    compute_lucas_sequences(*n + 1, n, U::from(1), U::from(1), q, d);
   // `u_delta % n != 0` proves n composite.
   u_delta == 0
   // Additional check which is not strictly part of Lucas test but nonetheless filters some
     // composite n for free. See section "Checking additional congruence conditions" on Wikipedia.
     && v_delta.is_congruent(2 * q, n)
     // Congruence check which holds for prime n by Euler's criterion.
-    && q_delta_over_2.is_congruent(q * U256::jacobi(q, n), n)
+    && q_delta_over_2.is_congruent(q * U::jacobi(q, n), n)
 }
 
 #[derive(Debug)]
@@ -85,12 +144,12 @@ struct IsPerfectSquare();
 /// We experimented with postponing the `is_perfect_square` check until after some number of
 /// iterations but ultimately found no performance gain. It is likely that most perfect squares
 /// are caught by the Miller-Rabin test.
-fn choose_d(n: &U256) -> Result<i32, IsPerfectSquare> {
+fn choose_d<U: Uint>(n: &U) -> Result<i32, IsPerfectSquare> {
   if n.is_perfect_square() {
     return Err(IsPerfectSquare());
   }
   for &d in D_VALUES.iter() {
-    if U256::jacobi(d, n) == -1 {
+    if U::jacobi(d, n) == -1 {
       return Ok(d);
     }
   }
@@ -101,81 +160,77 @@ fn choose_d(n: &U256) -> Result<i32, IsPerfectSquare> {
 /// in O(log(`k_target`)) time by recursively calculating only the `(2i)`th and `(2i+1)`th elements
 /// in an order determined by the binary expansion of `k`. Also returns `q^{k/2} (mod n)`, which is
 /// used in a stage of the strong Lucas test. In the Lucas case we specify that `d = p^2 - 4q` and
-/// set `k_target = delta = n - (d/n) = n + 1`.
+/// set `k_target = delta = n - (d/n) = n + 1`. `n` must be odd.
 ///
-/// Note that `p` does not show up in the code because it is set to 1.
+/// Note that `p` does not show up in the code because it is set to 1. The sequences are computed
+/// in Montgomery form, since halving and the additions are linear and so commute with it.
 #[allow(clippy::cast_sign_loss)]
-fn compute_lucas_sequences(
-  k_target: U256,
-  n: &U256,
-  mut u: U256,
-  mut v: U256,
-  q0: i32,
-  d: i32,
-) -> (U256, U256, U256) {
+fn compute_lucas_sequences<U: Uint>(k_target: U, n: &U, u: U, v: U, q0: i32, d: i32) -> (U, U, U) {
+  let mont = U::Mont::new(*n);
   // Mod an `i32` into the `[0, n)` range.
   let i_mod_n = |x: i32| {
     if x < 0 {
-      *n - (u256(x.abs() as u64) % n)
+      mont.sub(U::from(0), U::from(x.abs() as u64) % n)
     } else {
-      u256(x as u64) % n
+      U::from(x as u64) % n
     }
   };
-  let q0 = i_mod_n(q0);
-  let d = i_mod_n(d);
+  let q0 = mont.to_mont(i_mod_n(q0));
+  let d = mont.to_mont(i_mod_n(d));
+  let mut u = mont.to_mont(u);
+  let mut v = mont.to_mont(v);
   let mut q = q0;
   let mut q_k_over_2 = q0;
 
   // Finds `t` in `Z_n` with `2t = x (mod n)`.
   // Assumes `x` in `[0, n)`.
-  let half = |x: U256| {
+  let half = |x: U| {
     if x.is_odd() {
       (x >> 1) + (*n >> 1) + 1
     } else {
       x >> 1
     }
   };
-  let sub_mod_n = |a, b| {
-    if a > b {
-      (a - b) % n
-    } else {
-      *n - (b - a) % n
-    }
-  };
   // Write binary expansion of `k` as [x_1, ..., x_l], e.g. [1, 0, 1, 1] for 11. `x_1` is always
   // 1. For `i = 2, 3, ..., l`, do the following: if `x_i = 0`, then update `u_k` and `v_k` to
   // `u_{2k}` and `v_{2k}`, respectively. Else if `x_i = 1`, update to `u_{2k+1}` and `v_{2k+1}`.
   // At the end of the loop we will have computed `u_k` and `v_k`, with `k` as given, in
   // `log(delta)` time.
-  let mut k_target_bits = [0; 257];
+  // Room for `n + 1` with `n` of up to 384 bits.
+  debug_assert!(U::BITS <= 384);
+  let mut k_target_bits = [0; 385];
   let len = k_target.write_binary(&mut k_target_bits);
   for &bit in k_target_bits[..len].iter().skip(1) {
     // Compute `(u, v)_{2k}` from `(u, v)_k` according to the following:
     // u_2k = u_k * v_k (mod n)
     // v_2k = v_k^2 - 2*q^k (mod n)
-    u = u * v % n;
-    v = sub_mod_n(v * v, u512(q) << 1);
+    u = mont.mul(u, v);
+    v = mont.sub(mont.square(v), mont.add(q, q));
     // Continuously maintain `q_k = q^k (mod n)` and `q_k_over_2 = q^{k/2} (mod n)`.
     q_k_over_2 = q;
-    q = q * q % n;
+    q = mont.square(q);
     if bit == 1 {
       // Compute `(u, v)_{2k+1}` from `(u, v)_{2k}` according to the following:
       // u_{2k+1} = 1/2 * (p*u_{2k} + v_{2k}) (mod n)
       // v_{2k+1} = 1/2 * (d*u_{2k} + p*v_{2k}) (mod n)
       let u_old = u;
-      u = half((u512(u) + u512(v)) % n);
-      v = half((d * u_old + u512(v)) % n);
-      q = q * q0 % n;
+      u = half(mont.add(u, v));
+      v = half(mont.add(mont.mul(d, u_old), v));
+      q = mont.mul(q, q0);
     }
   }
-  // These are all `mod n` so the `low_u256` is lossless. We could make it checked...
-  (u, v, q_k_over_2)
+  (
+    mont.from_mont(u),
+    mont.from_mont(v),
+    mont.from_mont(q_k_over_2),
+  )
 }
 
 #[cfg(test)]
 mod tests {
   use self::constants::*;
   use super::*;
+  use crate::uint::{u256, u384};
   #[test]
   fn test_miller_rabin() {
     assert!(passes_miller_rabin_base_2(&u256(13)));
@@ -209,6 +264,26 @@ mod tests {
     }
   }
 
+  #[test]
+  fn test_trial_division() {
+    let has_small_factor = |n: u64| SMALL_PRIMES.iter().any(|&p| u256(n).is_divisible_u(p));
+    for n in 0..20_000 {
+      let expected = if has_small_factor(n) {
+        Some(SMALL_PRIMES.contains(&n))
+      } else {
+        None
+      };
+      assert_eq!(passes_trial_division(&u256(n)), expected, "{}", n);
+    }
+    for &p in LARGE_PRIMES.iter() {
+      assert_eq!(passes_trial_division(&u256(p)), None);
+      for &q in [2, 7, 47, 53, 1223].iter() {
+        let n = (u256(p) * u256(q)).low_u256();
+        assert_eq!(passes_trial_division(&n), Some(false));
+      }
+    }
+  }
+
   #[test]
   fn test_is_prob_prime() {
     // Sanity checks.
@@ -238,4 +313,37 @@ mod tests {
       }
     }
   }
+
+  #[test]
+  fn test_is_prob_prime_384() {
+    // The field modulus and group order of NIST P-384.
+    let p384 = u384([
+      0x0000_0000_ffff_ffff,
+      0xffff_ffff_0000_0000,
+      0xffff_ffff_ffff_fffe,
+      0xffff_ffff_ffff_ffff,
+      0xffff_ffff_ffff_ffff,
+      0xffff_ffff_ffff_ffff,
+    ]);
+    let n384 = u384([
+      0xecec_196a_ccc5_2973,
+      0x581a_0db2_48b0_a77a,
+      0xc763_4d81_f437_2ddf,
+      0xffff_ffff_ffff_ffff,
+      0xffff_ffff_ffff_ffff,
+      0xffff_ffff_ffff_ffff,
+    ]);
+    assert!(is_prob_prime(&p384));
+    assert!(is_prob_prime(&n384));
+    assert!(!is_prob_prime(&(p384 - 2)));
+    assert!(!is_prob_prime(&(n384 - 2)));
+
+    for &p in LARGE_PRIMES.iter() {
+      assert!(is_prob_prime(&u384(p)));
+      for &q in LARGE_PRIMES.iter() {
+        let pq = (u384(p) * u384(q)).low_u384();
+        assert!(!is_prob_prime(&pq));
+      }
+    }
+  }
 }
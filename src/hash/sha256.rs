@@ -0,0 +1,65 @@
+//! `GeneralHasher` interface for `sha2`.
+use super::GeneralHasher;
+use sha2::Digest;
+use std::hash::Hasher;
+
+/// Thin wrapper around `Sha256` from `sha2`.
+#[derive(Default)]
+pub struct Sha256(pub sha2::Sha256);
+
+impl Hasher for Sha256 {
+  /// We could return a truncated hash but it's easier just to not use this fn for now.
+  fn finish(&self) -> u64 {
+    panic!("Don't use! Prefer finalize(self).")
+  }
+  fn write(&mut self, bytes: &[u8]) {
+    Digest::update(&mut self.0, bytes)
+  }
+}
+
+impl GeneralHasher for Sha256 {
+  type Output = [u8; 32];
+  fn finalize(self) -> Self::Output {
+    let res = Digest::finalize(self.0);
+    *array_ref![res.as_slice(), 0, 32]
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_sha256() {
+    // Test vectors from NIST and Python's `hashlib.sha256`, including messages whose padding just
+    // fits in, just spills out of, and fills a whole block.
+    let cases: &[(&[u8], &str)] = &[
+      (
+        b"abc",
+        "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad",
+      ),
+      (
+        &[b'a'; 55],
+        "9f4390f8d30c2dd92ec9f095b65e2b9ae9b0a925a5258e241c9f1e910f734318",
+      ),
+      (
+        &[b'a'; 56],
+        "b35439a4ac6f0948b6d6f9e3c6af0f5f590ce20f1bde7090ef7970686ec6738a",
+      ),
+      (
+        &[b'a'; 64],
+        "ffe054fe7ae0cb6dc65c3af9b61d5209f439851db43d0ba5997337df154668eb",
+      ),
+    ];
+    for (input, expected) in cases {
+      let mut hasher = Sha256::default();
+      hasher.write(input);
+      let hex = hasher
+        .finalize()
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect::<String>();
+      assert_eq!(hex, *expected);
+    }
+  }
+}
@@ -0,0 +1,56 @@
+//! `GeneralHasher` interface for `sha3`.
+use super::GeneralHasher;
+use ::sha3::Digest;
+use std::hash::Hasher;
+
+/// Thin wrapper around `Sha3_256` from `sha3`.
+#[derive(Default)]
+pub struct Sha3_256(pub ::sha3::Sha3_256);
+
+impl Hasher for Sha3_256 {
+  /// We could return a truncated hash but it's easier just to not use this fn for now.
+  fn finish(&self) -> u64 {
+    panic!("Don't use! Prefer finalize(self).")
+  }
+  fn write(&mut self, bytes: &[u8]) {
+    Digest::update(&mut self.0, bytes)
+  }
+}
+
+impl GeneralHasher for Sha3_256 {
+  type Output = [u8; 32];
+  fn finalize(self) -> Self::Output {
+    let res = Digest::finalize(self.0);
+    *array_ref![res.as_slice(), 0, 32]
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_sha3_256() {
+    // Test vectors from NIST and Python's `hashlib.sha3_256`.
+    let cases: &[(&[u8], &str)] = &[
+      (
+        b"abc",
+        "3a985da74fe225b2045c172d6bd390bd855f086e3e9d525b46bfe24511431532",
+      ),
+      (
+        &[0xa3; 200],
+        "79f38adec5c20307a98ef76e8324afbfd46cfd81b22e3973c65fa1bd9de31787",
+      ),
+    ];
+    for (input, expected) in cases {
+      let mut hasher = Sha3_256::default();
+      hasher.write(input);
+      let hex = hasher
+        .finalize()
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect::<String>();
+      assert_eq!(hex, *expected);
+    }
+  }
+}
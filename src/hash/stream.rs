@@ -0,0 +1,106 @@
+//! Hashing of byte streams too large to hold in memory, e.g. documents or binaries.
+//!
+//! `hash_to_prime` hashes its input once per nonce it tries, so it needs the whole input at hand.
+//! A stream is instead read once into a `StreamDigest`, and the digest stands in for the stream
+//! wherever elements are hashed: accumulate `StreamDigest`s, and map them to primes with
+//! `hash_to_prime` as usual. The prime of a stream therefore differs from that of the same bytes
+//! hashed as a slice.
+use super::{hash_to_prime, Blake2b, GeneralHasher};
+use crate::integer::Integer;
+use std::hash::Hasher;
+use std::io::{self, Read, Write};
+
+// Separates stream digests from Blake2b digests computed elsewhere in the crate.
+const DOMAIN: &[u8] = b"accumulator stream";
+
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+/// The Blake2b digest of a byte stream, to accumulate in place of the stream itself.
+pub struct StreamDigest(pub [u8; 32]);
+
+impl StreamDigest {
+  /// Returns the digest of `bytes`. Equal to the digest of a reader yielding `bytes`.
+  pub fn of(bytes: &[u8]) -> Self {
+    let mut hasher = StreamHasher::new();
+    hasher.update(bytes);
+    hasher.finish()
+  }
+
+  /// Reads `reader` to the end and returns the digest of its contents, holding only a small buffer
+  /// in memory at a time.
+  pub fn from_reader<R: Read>(mut reader: R) -> io::Result<Self> {
+    let mut hasher = StreamHasher::new();
+    io::copy(&mut reader, &mut hasher)?;
+    Ok(hasher.finish())
+  }
+}
+
+/// Computes a `StreamDigest` from input fed in pieces. Also implements `io::Write`, so that streams
+/// can be copied into it.
+pub struct StreamHasher {
+  hasher: Blake2b,
+}
+
+impl StreamHasher {
+  /// Returns a hasher that has consumed no input.
+  pub fn new() -> Self {
+    let mut hasher = Blake2b::default();
+    hasher.write(DOMAIN);
+    Self { hasher }
+  }
+
+  /// Feeds `bytes` to the hasher.
+  pub fn update(&mut self, bytes: &[u8]) {
+    self.hasher.write(bytes);
+  }
+
+  /// Returns the digest of all input fed so far.
+  pub fn finish(self) -> StreamDigest {
+    StreamDigest(self.hasher.finalize())
+  }
+}
+
+impl Default for StreamHasher {
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
+impl Write for StreamHasher {
+  fn write(&mut self, bytes: &[u8]) -> io::Result<usize> {
+    self.update(bytes);
+    Ok(bytes.len())
+  }
+
+  fn flush(&mut self) -> io::Result<()> {
+    Ok(())
+  }
+}
+
+/// Reads `reader` to the end and hashes its `StreamDigest` to a prime.
+pub fn hash_reader_to_prime<R: Read>(reader: R) -> io::Result<Integer> {
+  Ok(hash_to_prime(&StreamDigest::from_reader(reader)?))
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_stream_digest() {
+    let blob = (0..100_000u32).map(|i| i as u8).collect::<Vec<_>>();
+    let digest = StreamDigest::from_reader(&blob[..]).unwrap();
+    assert_eq!(digest, StreamDigest::of(&blob));
+
+    let mut hasher = StreamHasher::new();
+    for chunk in blob.chunks(777) {
+      hasher.update(chunk);
+    }
+    assert_eq!(hasher.finish(), digest);
+    assert_ne!(StreamDigest::of(&blob[1..]), digest);
+
+    assert_eq!(
+      hash_reader_to_prime(&blob[..]).unwrap(),
+      hash_to_prime(&digest)
+    );
+  }
+}
@@ -0,0 +1,267 @@
+//! A commitment combining Merkle trees over fixed-size chunks of elements with an accumulator of
+//! their roots.
+//!
+//! Elements are committed a chunk at a time. Each chunk is hashed into a Merkle tree, and the
+//! roots of the `recent_chunks` most recent chunks are kept as they are. Older roots are moved into
+//! an accumulator. A proof of membership is a Merkle path to the root of the element's chunk,
+//! which for a recent chunk is checked against the kept root, costing a few hashes. For an older
+//! chunk, the proof also carries a constant-size accumulator proof for the root. Applications whose
+//! lookups mostly hit recent elements get hash-based proofs for those, while the state verifiers
+//! keep stays bounded.
+//!
+//! Verifiers only use the accumulator and the recent roots. Proving membership in an older chunk
+//! needs the roots of all older chunks, which the committer keeps.
+use crate::accumulator::{AccError, Accumulator, MembershipProof, Witness};
+use crate::group::UnknownOrderGroup;
+use crate::hash::{hash, Blake2b};
+use std::collections::VecDeque;
+use std::hash::Hash;
+use std::marker::PhantomData;
+
+// Domain separators for Merkle leaves and inner nodes, so that no leaf hashes like a node.
+const LEAF: u8 = 0;
+const NODE: u8 = 1;
+
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+/// A proof that an element is in a `HybridCommitment`.
+pub struct HybridProof<G: UnknownOrderGroup> {
+  chunk: u64,
+  index: u64,
+  path: Vec<[u8; 32]>,
+  root_proof: Option<MembershipProof<G, [u8; 32]>>,
+}
+
+impl<G: UnknownOrderGroup> HybridProof<G> {
+  /// Returns whether the proof needs an accumulator proof, i.e. is for an element outside the
+  /// recent chunks at the time it was made.
+  pub fn is_accumulated(&self) -> bool {
+    self.root_proof.is_some()
+  }
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+/// A commitment to elements in fixed-size chunks. See the module docs.
+pub struct HybridCommitment<G: UnknownOrderGroup, T> {
+  phantom: PhantomData<T>,
+  chunk_size: usize,
+  recent_chunks: usize,
+  acc: Accumulator<G, [u8; 32]>,
+  // The roots of all chunks moved into `acc`, in order.
+  old_roots: Vec<[u8; 32]>,
+  // The roots of the most recent chunks, oldest first.
+  recent_roots: VecDeque<[u8; 32]>,
+}
+
+impl<G: UnknownOrderGroup, T: Hash> HybridCommitment<G, T> {
+  /// Returns an empty commitment to chunks of `chunk_size` elements, keeping the roots of the
+  /// `recent_chunks` most recent ones outside the accumulator. Panics if `chunk_size` is not a
+  /// power of two.
+  pub fn new(chunk_size: usize, recent_chunks: usize) -> Self {
+    assert!(
+      chunk_size.is_power_of_two(),
+      "chunk size must be a power of two"
+    );
+    Self {
+      phantom: PhantomData,
+      chunk_size,
+      recent_chunks,
+      acc: Accumulator::empty(),
+      old_roots: Vec::new(),
+      recent_roots: VecDeque::new(),
+    }
+  }
+
+  /// Returns the number of committed chunks.
+  pub fn len(&self) -> usize {
+    self.old_roots.len() + self.recent_roots.len()
+  }
+
+  /// Returns whether no chunks are committed.
+  pub fn is_empty(&self) -> bool {
+    self.len() == 0
+  }
+
+  /// Returns the accumulator of the roots of all but the recent chunks.
+  pub fn accumulator(&self) -> &Accumulator<G, [u8; 32]> {
+    &self.acc
+  }
+
+  /// Returns the roots of the recent chunks, oldest first.
+  pub fn recent_roots(&self) -> &VecDeque<[u8; 32]> {
+    &self.recent_roots
+  }
+
+  /// Commits to `chunk`, which becomes the most recent chunk, and moves the root of the oldest
+  /// recent chunk into the accumulator if there are more than `recent_chunks`. Panics if `chunk`
+  /// does not hold exactly `chunk_size` elements.
+  pub fn add_chunk(&mut self, chunk: &[T]) {
+    assert_eq!(chunk.len(), self.chunk_size, "chunk has the wrong size");
+    self.recent_roots.push_back(merkle_root(chunk));
+    if self.recent_roots.len() > self.recent_chunks {
+      let root = self.recent_roots.pop_front().unwrap();
+      self.acc = self.acc.add_ref(&[root]);
+      self.old_roots.push(root);
+    }
+  }
+
+  /// Proves that the element at `index` in `chunk` is committed, where `chunk` is the contents of
+  /// the chunk numbered `chunk_index` in the order chunks were added. Returns `BadWitness` if
+  /// `chunk` does not match the committed chunk.
+  pub fn prove_membership(
+    &self,
+    chunk_index: usize,
+    chunk: &[T],
+    index: usize,
+  ) -> Result<HybridProof<G>, AccError> {
+    if chunk.len() != self.chunk_size || index >= chunk.len() || chunk_index >= self.len() {
+      return Err(AccError::BadWitness);
+    }
+    let root = merkle_root(chunk);
+    let root_proof = match self.old_roots.get(chunk_index) {
+      Some(old_root) => {
+        if root != *old_root {
+          return Err(AccError::BadWitness);
+        }
+        let mut others = self.old_roots.clone();
+        others.remove(chunk_index);
+        let witness = Witness(Accumulator::empty().add(&others));
+        Some(self.acc.prove_membership(&[(root, witness)])?)
+      }
+      None => {
+        if root != self.recent_roots[chunk_index - self.old_roots.len()] {
+          return Err(AccError::BadWitness);
+        }
+        None
+      }
+    };
+    Ok(HybridProof {
+      chunk: chunk_index as u64,
+      index: index as u64,
+      path: merkle_path(chunk, index),
+      root_proof,
+    })
+  }
+
+  /// Verifies that `t` is committed, using only the accumulator and the recent roots.
+  pub fn verify_membership(&self, t: &T, proof: &HybridProof<G>) -> bool {
+    if proof.path.len() != self.chunk_size.trailing_zeros() as usize
+      || proof.index >= self.chunk_size as u64
+    {
+      return false;
+    }
+    let root = merkle_root_from_path(t, proof.index, &proof.path);
+    let old_chunks = self.old_roots.len() as u64;
+    match &proof.root_proof {
+      None => {
+        proof.chunk >= old_chunks
+          && self.recent_roots.get((proof.chunk - old_chunks) as usize) == Some(&root)
+      }
+      Some(root_proof) => proof.chunk < old_chunks && self.acc.verify_membership(&root, root_proof),
+    }
+  }
+}
+
+fn leaf_hash<T: Hash>(t: &T) -> [u8; 32] {
+  hash(&Blake2b::default, &(LEAF, t))
+}
+
+fn node_hash(left: &[u8; 32], right: &[u8; 32]) -> [u8; 32] {
+  hash(&Blake2b::default, &(NODE, left, right))
+}
+
+// Returns the levels of the Merkle tree over `chunk`, from the leaves up to the root.
+fn merkle_levels<T: Hash>(chunk: &[T]) -> Vec<Vec<[u8; 32]>> {
+  let mut levels = vec![chunk.iter().map(leaf_hash).collect::<Vec<_>>()];
+  while levels[levels.len() - 1].len() > 1 {
+    let level = &levels[levels.len() - 1];
+    let next = level
+      .chunks(2)
+      .map(|pair| node_hash(&pair[0], &pair[1]))
+      .collect();
+    levels.push(next);
+  }
+  levels
+}
+
+fn merkle_root<T: Hash>(chunk: &[T]) -> [u8; 32] {
+  merkle_levels(chunk).pop().unwrap()[0]
+}
+
+// Returns the siblings on the path from leaf `index` to the root, from the bottom up.
+fn merkle_path<T: Hash>(chunk: &[T], index: usize) -> Vec<[u8; 32]> {
+  let levels = merkle_levels(chunk);
+  levels[..levels.len() - 1]
+    .iter()
+    .enumerate()
+    .map(|(height, level)| level[(index >> height) ^ 1])
+    .collect()
+}
+
+fn merkle_root_from_path<T: Hash>(t: &T, index: u64, path: &[[u8; 32]]) -> [u8; 32] {
+  path
+    .iter()
+    .enumerate()
+    .fold(leaf_hash(t), |node, (height, sibling)| {
+      if (index >> height) & 1 == 0 {
+        node_hash(&node, sibling)
+      } else {
+        node_hash(sibling, &node)
+      }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::group::Rsa2048;
+
+  fn chunk(i: usize) -> Vec<usize> {
+    (4 * i..4 * i + 4).collect()
+  }
+
+  #[test]
+  fn test_hybrid_commitment() {
+    let mut commitment = HybridCommitment::<Rsa2048, usize>::new(4, 2);
+    for i in 0..5 {
+      commitment.add_chunk(&chunk(i));
+    }
+    assert_eq!(commitment.len(), 5);
+    assert_eq!(commitment.recent_roots().len(), 2);
+
+    for i in 0..5 {
+      for index in 0..4 {
+        let proof = commitment.prove_membership(i, &chunk(i), index).unwrap();
+        assert_eq!(proof.is_accumulated(), i < 3);
+        assert!(commitment.verify_membership(&chunk(i)[index], &proof));
+        assert!(!commitment.verify_membership(&chunk(i)[(index + 1) % 4], &proof));
+      }
+    }
+    assert!(commitment.prove_membership(1, &chunk(2), 0).is_err());
+    assert!(commitment.prove_membership(4, &chunk(3), 0).is_err());
+    assert!(commitment.prove_membership(5, &chunk(5), 0).is_err());
+  }
+
+  #[test]
+  fn test_hybrid_proof_tampered() {
+    let mut commitment = HybridCommitment::<Rsa2048, usize>::new(4, 1);
+    commitment.add_chunk(&chunk(0));
+    commitment.add_chunk(&chunk(1));
+    let recent = commitment.prove_membership(1, &chunk(1), 2).unwrap();
+    let old = commitment.prove_membership(0, &chunk(0), 2).unwrap();
+
+    // Claiming a recent chunk is accumulated, or the reverse, fails.
+    let mut tampered = recent.clone();
+    tampered.root_proof = old.root_proof.clone();
+    assert!(!commitment.verify_membership(&6, &tampered));
+    let mut tampered = old.clone();
+    tampered.root_proof = None;
+    assert!(!commitment.verify_membership(&2, &tampered));
+    let mut tampered = recent;
+    tampered.path.pop();
+    assert!(!commitment.verify_membership(&6, &tampered));
+
+    // Old proofs go stale once the accumulator changes, like other accumulator proofs.
+    commitment.add_chunk(&chunk(2));
+    assert!(!commitment.verify_membership(&2, &old));
+  }
+}
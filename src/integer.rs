@@ -0,0 +1,119 @@
+//! The integer backend.
+//!
+//! Accumulator, proof and vector commitment code does its arithmetic on `Integer` through the
+//! standard operators and the methods of `IntegerOps`, never through the backend's own API. The
+//! default backend is GMP, via `rug`. Another backend (e.g. a pure-Rust bignum library, or one
+//! offloading to hardware) needs an integer type with the same operators, an `IntegerOps` impl for
+//! it, and a change to the `Integer` alias below, but no change to protocol logic. The rest of the
+//! crate also names the type through the alias, but group implementations and hashing to primes
+//! still call `rug` methods where `IntegerOps` has no counterpart (e.g. `is_perfect_power`), and
+//! `uint` works on GMP limbs directly.
+use std::fmt::Debug;
+use std::hash::Hash;
+
+/// The integer type of the current backend.
+pub type Integer = rug::Integer;
+
+/// Integer operations protocol code needs beyond the standard operators. Methods take references
+/// and return owned results, so that backends without in-place arithmetic can implement them.
+pub trait IntegerOps: Clone + Debug + Default + Eq + Hash + Ord + From<u64> {
+  /// Returns `(g, a, b)` such that `g = gcd(self, other) = a * self + b * other` and `g >= 0`.
+  fn xgcd(&self, other: &Self) -> (Self, Self, Self);
+
+  /// Returns the quotient and remainder of Euclidean division by `divisor`, so that the remainder
+  /// is in `[0, |divisor|)`. Panics if `divisor` is zero.
+  fn div_rem_euclid(&self, divisor: &Self) -> (Self, Self);
+
+  /// Returns whether `divisor` divides `self`.
+  fn is_multiple_of(&self, divisor: &Self) -> bool;
+
+  /// Returns whether `self` is odd.
+  fn is_odd(&self) -> bool;
+
+  /// Returns whether `self` is prime, with error probability at most `4^(-reps)` for composites.
+  fn is_probable_prime(&self, reps: u32) -> bool;
+
+  /// Returns the number of bits in the absolute value of `self`, or 0 for 0.
+  fn bit_length(&self) -> u32;
+
+  /// Returns `self mod 2^bits`.
+  fn low_bits(&self, bits: u32) -> Self;
+
+  /// Returns `self^exp mod modulus`, or `None` if `exp` is negative and `self` has no inverse.
+  fn mod_pow(&self, exp: &Self, modulus: &Self) -> Option<Self>;
+
+  /// Returns the minimal big-endian bytes of the absolute value of `self`.
+  fn to_bytes_be(&self) -> Vec<u8>;
+
+  /// Returns the nonnegative integer with big-endian bytes `bytes`.
+  fn from_bytes_be(bytes: &[u8]) -> Self;
+}
+
+impl IntegerOps for rug::Integer {
+  fn xgcd(&self, other: &Self) -> (Self, Self, Self) {
+    <(Self, Self, Self)>::from(self.gcd_cofactors_ref(other))
+  }
+
+  fn div_rem_euclid(&self, divisor: &Self) -> (Self, Self) {
+    <(Self, Self)>::from(self.div_rem_euc_ref(divisor))
+  }
+
+  fn is_multiple_of(&self, divisor: &Self) -> bool {
+    self.is_divisible(divisor)
+  }
+
+  fn is_odd(&self) -> bool {
+    rug::Integer::is_odd(self)
+  }
+
+  fn is_probable_prime(&self, reps: u32) -> bool {
+    self.is_probably_prime(reps) != rug::integer::IsPrime::No
+  }
+
+  fn bit_length(&self) -> u32 {
+    self.significant_bits()
+  }
+
+  fn low_bits(&self, bits: u32) -> Self {
+    self.keep_bits_ref(bits).into()
+  }
+
+  fn mod_pow(&self, exp: &Self, modulus: &Self) -> Option<Self> {
+    self.pow_mod_ref(exp, modulus).map(Self::from)
+  }
+
+  fn to_bytes_be(&self) -> Vec<u8> {
+    self.to_digits(rug::integer::Order::Msf)
+  }
+
+  fn from_bytes_be(bytes: &[u8]) -> Self {
+    Self::from_digits(bytes, rug::integer::Order::Msf)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::util::int;
+
+  #[test]
+  fn test_rug_backend() {
+    let (g, a, b) = int(240).xgcd(&int(46));
+    assert_eq!(g, 2);
+    assert_eq!(a * 240 + b * 46, 2);
+    assert_eq!(int(-7).div_rem_euclid(&int(3)), (int(-3), int(2)));
+    assert!(int(12).is_multiple_of(&int(4)));
+    assert!(!int(12).is_multiple_of(&int(5)));
+    assert!(IntegerOps::is_odd(&int(7)));
+    assert!(int(7919).is_probable_prime(30));
+    assert!(!int(7917).is_probable_prime(30));
+    assert_eq!(int(255).bit_length(), 8);
+    assert_eq!(int(0).bit_length(), 0);
+    assert_eq!(int(0x1234).low_bits(8), 0x34);
+    assert_eq!(int(3).mod_pow(&int(4), &int(7)), Some(int(4)));
+    assert_eq!(int(3).mod_pow(&int(-1), &int(7)), Some(int(5)));
+    assert_eq!(int(2).mod_pow(&int(-1), &int(4)), None);
+    assert_eq!(int(0x1234).to_bytes_be(), vec![0x12, 0x34]);
+    assert_eq!(Integer::from_bytes_be(&[0, 0x12, 0x34]), 0x1234);
+  }
+}
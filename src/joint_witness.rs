@@ -0,0 +1,117 @@
+//! Joint computation of membership witnesses by parties that each hold part of the set.
+//!
+//! A witness for `x` is the generator raised to the primes of every other accumulated element.
+//! When the set is split among several parties (e.g. the bridges of a privacy-preserving bridge
+//! network), no single party can compute it, and none should have to reveal its elements to the
+//! others. Instead a `WitnessRequest` is passed from party to party, and each raises it to the
+//! product of the primes it holds. The requesting party starts with its own elements other than
+//! `x`, and checks the finished witness against the accumulator.
+//!
+//! Parties only ever see group elements. Recovering elements from them means either computing
+//! discrete logarithms in the group, or guessing the entire set of elements contributed so far, so
+//! the protocol hides elements drawn from sets too large to enumerate. It does not hide how many
+//! parties a request has passed through, and a party holding all elements but one learns that one
+//! by testing the guesses it can enumerate.
+use crate::accumulator::{AccError, Accumulator, Witness};
+use crate::group::UnknownOrderGroup;
+use std::hash::Hash;
+use std::slice;
+
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+/// A witness under joint computation. Holds only the partial witness, not the element it is for.
+pub struct WitnessRequest<G: UnknownOrderGroup, T: Hash> {
+  partial: Accumulator<G, T>,
+}
+
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+/// A party holding a share of an accumulator's elements. Shares of different parties must be
+/// disjoint, and together make up the accumulated set.
+pub struct WitnessParty<T> {
+  elems: Vec<T>,
+}
+
+impl<T: Eq + Hash> WitnessParty<T> {
+  /// Returns a party holding `elems`.
+  pub fn new(elems: Vec<T>) -> Self {
+    Self { elems }
+  }
+
+  /// Returns the elements this party holds.
+  pub fn elems(&self) -> &[T] {
+    &self.elems
+  }
+
+  /// Starts computing a witness for `elem`, which this party must hold, with respect to `acc`. The
+  /// returned request is passed to every other party's `contribute`, then back to `finish`.
+  pub fn request_witness<G: UnknownOrderGroup>(
+    &self,
+    acc: &Accumulator<G, T>,
+    elem: &T,
+  ) -> Result<WitnessRequest<G, T>, AccError> {
+    if !self.elems.contains(elem) {
+      return Err(AccError::BadWitness);
+    }
+    let others = self.elems.iter().filter(|t| *t != elem);
+    Ok(WitnessRequest {
+      partial: others.fold(Accumulator::with_params(acc.params()), |partial, t| {
+        partial.add(slice::from_ref(t))
+      }),
+    })
+  }
+
+  /// Raises `request` to the primes of every element this party holds.
+  pub fn contribute<G: UnknownOrderGroup>(
+    &self,
+    request: WitnessRequest<G, T>,
+  ) -> WitnessRequest<G, T> {
+    WitnessRequest {
+      partial: request.partial.add(&self.elems),
+    }
+  }
+
+  /// Returns the witness for `elem` computed by `request`, or `AccError::BadWitness` if it is not a
+  /// witness for `elem` in `acc`, e.g. because a party did not contribute.
+  pub fn finish<G: UnknownOrderGroup>(
+    &self,
+    acc: &Accumulator<G, T>,
+    elem: &T,
+    request: WitnessRequest<G, T>,
+  ) -> Result<Witness<G, T>, AccError> {
+    if request.partial.add_ref(slice::from_ref(elem)) != *acc {
+      return Err(AccError::BadWitness);
+    }
+    Ok(Witness(request.partial))
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::group::Rsa2048;
+
+  #[test]
+  fn test_joint_witness() {
+    let parties = [
+      WitnessParty::new(vec!["a", "b"]),
+      WitnessParty::new(vec!["c"]),
+      WitnessParty::new(vec!["d", "e", "f"]),
+    ];
+    let acc = Accumulator::<Rsa2048, &'static str>::empty().add(&["a", "b", "c", "d", "e", "f"]);
+
+    let request = parties[0].request_witness(&acc, &"b").unwrap();
+    let request = parties[2].contribute(parties[1].contribute(request));
+    let witness = parties[0].finish(&acc, &"b", request).unwrap();
+    let proof = acc.prove_membership(&[("b", witness.clone())]).unwrap();
+    assert!(acc.verify_membership(&"b", &proof));
+    assert!(witness.0 == Accumulator::empty().add(&["a", "c", "d", "e", "f"]));
+  }
+
+  #[test]
+  fn test_joint_witness_missing_party() {
+    let parties = [WitnessParty::new(vec!["a"]), WitnessParty::new(vec!["b"])];
+    let acc = Accumulator::<Rsa2048, &'static str>::empty().add(&["a", "b"]);
+    assert!(parties[0].request_witness(&acc, &"b").is_err());
+    let request = parties[0].request_witness(&acc, &"a").unwrap();
+    assert!(parties[0].finish(&acc, &"a", request).is_err());
+  }
+}
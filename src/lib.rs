@@ -104,14 +104,105 @@ extern crate lazy_static;
 #[macro_use]
 extern crate arrayref;
 
+// Implements `Display` as the hex of the type's canonical encoding, and orders values by their
+// canonical encodings so that they can key ordered maps. Encodings are injective, so the order is
+// consistent with `Eq`.
+macro_rules! impl_encoding_traits {
+  ($ty:ident<$($param:ident),*> where $($bounds:tt)*) => {
+    impl<$($param),*> std::fmt::Display for $ty<$($param),*> where $($bounds)* {
+      fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.write_str(&crate::util::to_hex(&self.to_bytes()))
+      }
+    }
+
+    impl<$($param),*> PartialOrd for $ty<$($param),*> where $($bounds)* {
+      fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+      }
+    }
+
+    impl<$($param),*> Ord for $ty<$($param),*> where $($bounds)* {
+      fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        self.to_bytes().cmp(&other.to_bytes())
+      }
+    }
+  };
+}
+
 mod accumulator;
 pub use crate::accumulator::*;
+mod batch;
+pub use batch::*;
+mod bridge;
+pub use bridge::*;
+mod budget;
+pub use budget::*;
+mod byte_elem;
+pub use byte_elem::*;
+mod collision;
+pub use collision::*;
+mod delta;
+pub use delta::*;
+mod exponent_bound;
+pub use exponent_bound::*;
+mod freshness;
+pub use freshness::*;
+mod full_set;
+pub use full_set::*;
+mod governed;
+pub use governed::*;
+mod group_elem;
+pub use group_elem::*;
+mod hybrid;
+pub use hybrid::*;
+mod joint_witness;
+pub use joint_witness::*;
+mod limits;
+pub use limits::*;
+mod listener;
+pub use listener::*;
+mod namespace;
+pub use namespace::*;
+mod nested;
+pub use nested::*;
+mod planner;
+pub use planner::*;
+mod refresh;
+pub use refresh::*;
+mod revocation;
+pub use revocation::*;
+mod salted;
+pub use salted::*;
+mod security;
+pub use security::*;
+mod shared;
+pub use shared::*;
+mod snapshot;
+pub use snapshot::*;
+mod split_witness;
+pub use split_witness::*;
+mod state;
+pub use state::*;
+mod vault;
+pub use vault::*;
 mod vector_commitment;
 pub use vector_commitment::*;
+mod view;
+pub use view::*;
 
+#[cfg(feature = "audit")]
+pub mod audit;
 pub mod group;
 pub mod hash;
+pub mod integer;
+#[cfg(feature = "metrics")]
+pub mod metrics;
+#[cfg(feature = "parallel")]
+pub mod parallel;
 pub mod proof;
+#[cfg(feature = "test_vectors")]
+pub mod test_vectors;
 #[allow(missing_docs)]
 pub mod uint;
 pub mod util;
+pub mod vdf;
@@ -0,0 +1,162 @@
+//! Size limits on batches, for services that accumulate or prove elements chosen by others.
+//!
+//! The cost of an addition, deletion or proof grows with the number of elements in the batch, and
+//! that of directly added primes with their size: a single request with a million elements, or a
+//! "prime" of a million bits, keeps the service busy for minutes. The `_limited` variants of these
+//! operations take `Limits` and return `AccError::LimitExceeded` before hashing or exponentiating
+//! anything if a batch has more elements than `max_batch`, or its exponent (bounded by the number
+//! of elements times the largest prime an element can map to) may exceed `max_exponent_bits`.
+use crate::accumulator::{AccError, Accumulator, MembershipProof, NonmembershipProof, Witness};
+use crate::group::UnknownOrderGroup;
+use crate::hash::PrimeParams;
+use crate::integer::Integer;
+use std::hash::Hash;
+
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+/// Limits on the batches accepted by the `_limited` operations. See the module docs.
+pub struct Limits {
+  /// The maximum number of elements or primes in one batch.
+  pub max_batch: usize,
+  /// The maximum bit length of the exponent a batch is accumulated, deleted or proven with.
+  pub max_exponent_bits: u64,
+}
+
+impl Limits {
+  /// Returns limits of `max_batch` elements and `max_exponent_bits` bits.
+  pub fn new(max_batch: usize, max_exponent_bits: u64) -> Self {
+    Self {
+      max_batch,
+      max_exponent_bits,
+    }
+  }
+
+  /// Checks a batch of `len` elements mapped to primes according to `params`.
+  pub fn check_batch(self, params: PrimeParams, len: usize) -> Result<(), AccError> {
+    let bits = (len as u64).saturating_mul(u64::from(params.max_bits()));
+    self.check(len, bits)
+  }
+
+  /// Checks a batch of `primes` supplied directly.
+  pub fn check_primes(self, primes: &[Integer]) -> Result<(), AccError> {
+    let bits = primes
+      .iter()
+      .map(|p| u64::from(p.significant_bits()))
+      .fold(0, u64::saturating_add);
+    self.check(primes.len(), bits)
+  }
+
+  fn check(self, len: usize, bits: u64) -> Result<(), AccError> {
+    if len > self.max_batch || bits > self.max_exponent_bits {
+      return Err(AccError::LimitExceeded);
+    }
+    Ok(())
+  }
+}
+
+/// Allows batches of up to 10,000 elements, or 10,000 256-bit primes.
+impl Default for Limits {
+  fn default() -> Self {
+    Self::new(10_000, 10_000 * 256)
+  }
+}
+
+impl<G: UnknownOrderGroup, T: Eq + Hash> Accumulator<G, T> {
+  /// Like `add`, but returns `LimitExceeded` if `elems` exceed `limits`.
+  pub fn add_limited(self, elems: &[T], limits: &Limits) -> Result<Self, AccError> {
+    limits.check_batch(self.params(), elems.len())?;
+    Ok(self.add(elems))
+  }
+
+  /// Like `add_with_proof`, but returns `LimitExceeded` if `elems` exceed `limits`.
+  pub fn add_with_proof_limited(
+    self,
+    elems: &[T],
+    limits: &Limits,
+  ) -> Result<(Self, MembershipProof<G, T>), AccError> {
+    limits.check_batch(self.params(), elems.len())?;
+    Ok(self.add_with_proof(elems))
+  }
+
+  /// Like `add_primes`, but returns `LimitExceeded` if `primes` exceed `limits`. The limits are
+  /// checked before primality, whose cost also grows with the size of the primes.
+  pub fn add_primes_limited(self, primes: &[Integer], limits: &Limits) -> Result<Self, AccError> {
+    limits.check_primes(primes)?;
+    self.add_primes(primes)
+  }
+
+  /// Like `delete`, but returns `LimitExceeded` if the deleted elements exceed `limits`.
+  pub fn delete_limited(
+    self,
+    elem_witnesses: &[(T, Witness<G, T>)],
+    limits: &Limits,
+  ) -> Result<Self, AccError> {
+    limits.check_batch(self.params(), elem_witnesses.len())?;
+    self.delete(elem_witnesses)
+  }
+
+  /// Like `delete_with_proof`, but returns `LimitExceeded` if the deleted elements exceed `limits`.
+  pub fn delete_with_proof_limited(
+    self,
+    elem_witnesses: &[(T, Witness<G, T>)],
+    limits: &Limits,
+  ) -> Result<(Self, MembershipProof<G, T>), AccError> {
+    limits.check_batch(self.params(), elem_witnesses.len())?;
+    self.delete_with_proof(elem_witnesses)
+  }
+
+  /// Like `prove_membership`, but returns `LimitExceeded` if the proven elements exceed `limits`.
+  pub fn prove_membership_limited(
+    &self,
+    elem_witnesses: &[(T, Witness<G, T>)],
+    limits: &Limits,
+  ) -> Result<MembershipProof<G, T>, AccError> {
+    limits.check_batch(self.params(), elem_witnesses.len())?;
+    self.prove_membership(elem_witnesses)
+  }
+
+  /// Like `prove_nonmembership`, but returns `LimitExceeded` if `elems` exceed `limits`. The
+  /// accumulated set is the prover's own, so only `elems` count towards them.
+  pub fn prove_nonmembership_limited(
+    &self,
+    acc_set: &[T],
+    elems: &[T],
+    limits: &Limits,
+  ) -> Result<NonmembershipProof<G, T>, AccError> {
+    limits.check_batch(self.params(), elems.len())?;
+    self.prove_nonmembership(acc_set, elems)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::group::Rsa2048;
+  use crate::util::int;
+
+  #[test]
+  fn test_limits() {
+    let limits = Limits::new(2, 2 * 256);
+    let acc = Accumulator::<Rsa2048, &'static str>::empty();
+    assert!(acc.clone().add_limited(&["a", "b", "c"], &limits).is_err());
+    let (acc, proof) = acc.add_with_proof_limited(&["a", "b"], &limits).unwrap();
+    assert!(acc.verify_membership_batch(&["a", "b"], &proof));
+
+    let witnesses = vec![
+      ("a", Witness(Accumulator::empty().add(&["b"]))),
+      ("b", Witness(Accumulator::empty().add(&["a"]))),
+    ];
+    assert!(acc.prove_membership_limited(&witnesses, &limits).is_ok());
+    assert!(acc
+      .prove_nonmembership_limited(&["a", "b"], &["c", "d", "e"], &limits)
+      .is_err());
+    let tight = Limits::new(2, 256);
+    assert!(acc.clone().delete_limited(&witnesses, &tight).is_err());
+    assert!(acc.delete_limited(&witnesses, &limits).unwrap() == Accumulator::empty());
+
+    let mut big_prime: Integer = int(1) << 600;
+    big_prime.next_prime_mut();
+    assert!(Accumulator::<Rsa2048, &'static str>::empty()
+      .add_primes_limited(&[big_prime], &limits)
+      .is_err());
+  }
+}
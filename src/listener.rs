@@ -0,0 +1,85 @@
+//! Event streams of accumulator mutations, for services that push witness updates to holders.
+//!
+//! `RevocationRegistry` and `Bridge` call each registered `UpdateListener` after every mutation
+//! with the primes added and deleted and the new accumulator value. That is what holders need to
+//! update their witnesses (see `Accumulator::update_membership_witness`), so a listener can forward
+//! it to a message queue or a publish-subscribe channel without knowing the elements. Closures
+//! with the right signature are listeners.
+use crate::group::UnknownOrderGroup;
+use crate::hash::PrimeParams;
+use crate::integer::Integer;
+use std::hash::Hash;
+
+/// Receives accumulator mutations. See the module docs.
+pub trait UpdateListener<G: UnknownOrderGroup>: Send + Sync {
+  /// Called after a mutation that added the elements with primes `added` and deleted those with
+  /// primes `deleted`, leaving the accumulator at `root`.
+  fn on_update(&mut self, added: &[Integer], deleted: &[Integer], root: &G::Elem);
+}
+
+impl<G: UnknownOrderGroup, F> UpdateListener<G> for F
+where
+  F: FnMut(&[Integer], &[Integer], &G::Elem) + Send + Sync,
+{
+  fn on_update(&mut self, added: &[Integer], deleted: &[Integer], root: &G::Elem) {
+    self(added, deleted, root)
+  }
+}
+
+/// The listeners registered with a mutable accumulator holder.
+pub struct UpdateListeners<G: UnknownOrderGroup> {
+  listeners: Vec<Box<dyn UpdateListener<G>>>,
+}
+
+impl<G: UnknownOrderGroup> UpdateListeners<G> {
+  /// Returns an empty set of listeners.
+  pub fn new() -> Self {
+    Self { listeners: vec![] }
+  }
+
+  /// Registers `listener`.
+  pub fn push<L: UpdateListener<G> + 'static>(&mut self, listener: L) {
+    self.listeners.push(Box::new(listener));
+  }
+
+  /// Returns the number of registered listeners.
+  pub fn len(&self) -> usize {
+    self.listeners.len()
+  }
+
+  /// Returns whether no listeners are registered.
+  pub fn is_empty(&self) -> bool {
+    self.listeners.is_empty()
+  }
+
+  /// Calls every listener with the primes of `added` and `deleted` under `params` and with `root`.
+  /// Elements are only hashed if some listener is registered.
+  pub fn notify<T: Hash>(
+    &mut self,
+    params: PrimeParams,
+    added: &[T],
+    deleted: &[T],
+    root: &G::Elem,
+  ) {
+    if self.listeners.is_empty() {
+      return;
+    }
+    let added = added
+      .iter()
+      .map(|t| params.hash_to_prime(t))
+      .collect::<Vec<_>>();
+    let deleted = deleted
+      .iter()
+      .map(|t| params.hash_to_prime(t))
+      .collect::<Vec<_>>();
+    for listener in &mut self.listeners {
+      listener.on_update(&added, &deleted, root);
+    }
+  }
+}
+
+impl<G: UnknownOrderGroup> Default for UpdateListeners<G> {
+  fn default() -> Self {
+    Self::new()
+  }
+}
@@ -0,0 +1,112 @@
+//! Optional profiling hooks, enabled with the `metrics` feature. Install a callback with
+//! `set_hook` to be told how long `hash_to_prime`, group exponentiations, and proof generation take,
+//! and how large their inputs were, without patching the crate.
+//!
+//! The hook is global and shared by all threads, so it should be cheap (e.g. bump a histogram).
+use std::sync::RwLock;
+use std::time::{Duration, Instant};
+
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+/// The instrumented operations.
+pub enum Operation {
+  /// A `hash_to_prime` call.
+  HashToPrime,
+  /// A group exponentiation via `Group::exp`.
+  Exp,
+  /// Generation of an NI-PoE proof.
+  ProvePoe,
+  /// Generation of an NI-PoKCR proof.
+  ProvePokcr,
+  /// Generation of an NI-PoKE2 proof.
+  ProvePoke2,
+}
+
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+/// A single timed operation, as reported to the hook.
+pub struct Measurement {
+  /// The operation performed.
+  pub operation: Operation,
+  /// Wall-clock time spent in the operation.
+  pub elapsed: Duration,
+  /// Size of the operation's driving input in bits: the exponent for `Exp`, `ProvePoe`, and
+  /// `ProvePoke2`, and the output prime for `HashToPrime`. Zero for `ProvePokcr`.
+  pub bits: u32,
+}
+
+lazy_static! {
+  static ref HOOK: RwLock<Option<fn(&Measurement)>> = RwLock::new(None);
+}
+
+/// Installs `hook` to be called after every instrumented operation, replacing any previous hook.
+pub fn set_hook(hook: fn(&Measurement)) {
+  *HOOK.write().unwrap() = Some(hook);
+}
+
+/// Removes the installed hook, if any.
+pub fn clear_hook() {
+  *HOOK.write().unwrap() = None;
+}
+
+/// Reports the time elapsed since `start` to the installed hook when dropped.
+pub(crate) struct Timer {
+  operation: Operation,
+  start: Instant,
+  bits: u32,
+}
+
+impl Timer {
+  pub(crate) fn start(operation: Operation, bits: u32) -> Self {
+    Self {
+      operation,
+      start: Instant::now(),
+      bits,
+    }
+  }
+}
+
+impl Drop for Timer {
+  fn drop(&mut self) {
+    // Releases the lock before calling the hook, which may itself call `set_hook`.
+    let hook = *HOOK.read().unwrap();
+    if let Some(hook) = hook {
+      hook(&Measurement {
+        operation: self.operation,
+        elapsed: self.start.elapsed(),
+        bits: self.bits,
+      });
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::group::{ElemFrom, Group, Rsa2048};
+  use crate::util::int;
+  use std::sync::atomic::{AtomicUsize, Ordering};
+
+  static EXPS: AtomicUsize = AtomicUsize::new(0);
+
+  fn count_exps(m: &Measurement) {
+    // Other tests exponentiate concurrently, so only count our unusually-sized exponent.
+    if m.operation == Operation::Exp && m.bits == 77 {
+      EXPS.fetch_add(1, Ordering::SeqCst);
+    }
+  }
+
+  #[test]
+  fn test_hook() {
+    set_hook(count_exps);
+    Rsa2048::exp(&Rsa2048::elem(2), &(int(1) << 76));
+    clear_hook();
+    Rsa2048::exp(&Rsa2048::elem(2), &(int(1) << 76));
+    assert_eq!(EXPS.load(Ordering::SeqCst), 1);
+
+    // A hook replacing itself must not deadlock.
+    set_hook(|_| set_hook(count_exps));
+    Rsa2048::exp(&Rsa2048::elem(2), &(int(1) << 76));
+    Rsa2048::exp(&Rsa2048::elem(2), &(int(1) << 76));
+    clear_hook();
+    assert_eq!(EXPS.load(Ordering::SeqCst), 2);
+  }
+}
@@ -0,0 +1,128 @@
+//! Namespaced accumulation, for serving several logical sets from one accumulator.
+//!
+//! Accumulate `Namespaced` elements, each hashed to a prime together with the name of the set it
+//! belongs to, e.g. `acc.add_in_namespace("utxo", &elems)`. The same value in two namespaces maps
+//! to two unrelated primes, so the sets are independent: membership in one namespace says nothing
+//! about another, and a proof for an element in one namespace does not verify in any other.
+use crate::accumulator::{AccError, Accumulator, AccumulatorElement, MembershipProof, Witness};
+use crate::group::UnknownOrderGroup;
+use std::hash::Hash;
+
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+/// An element tagged with its namespace. Accumulated as `hash_to_prime((namespace, elem))`.
+///
+/// `str` hashes include a terminator, so no two (namespace, element) pairs hash alike by moving
+/// bytes between the namespace and the element.
+pub struct Namespaced<T> {
+  /// The namespace `elem` belongs to.
+  pub namespace: String,
+  /// The underlying element.
+  pub elem: T,
+}
+
+impl<T> Namespaced<T> {
+  /// Tags `elem` with `namespace`.
+  pub fn new(namespace: &str, elem: T) -> Self {
+    Self {
+      namespace: namespace.to_owned(),
+      elem,
+    }
+  }
+}
+
+impl<T: AccumulatorElement> AccumulatorElement for Namespaced<T> {}
+
+/// A witness for namespaced elements.
+pub type NamespacedWitness<G, T> = Witness<G, Namespaced<T>>;
+
+fn in_namespace<T: Clone>(namespace: &str, elems: &[T]) -> Vec<Namespaced<T>> {
+  elems
+    .iter()
+    .map(|t| Namespaced::new(namespace, t.clone()))
+    .collect()
+}
+
+impl<G: UnknownOrderGroup, T: Clone + Eq + Hash> Accumulator<G, Namespaced<T>> {
+  /// Adds `elems` to `namespace`.
+  ///
+  /// Uses a move instead of a `&self` reference to prevent accidental use of the old accumulator.
+  pub fn add_in_namespace(self, namespace: &str, elems: &[T]) -> Self {
+    self.add(&in_namespace(namespace, elems))
+  }
+
+  /// Removes the elements in `elem_witnesses` from `namespace`. Witnesses are for the namespaced
+  /// elements, as for any accumulator of `Namespaced` elements.
+  ///
+  /// Uses a move instead of a `&self` reference to prevent accidental use of the old accumulator.
+  pub fn delete_in_namespace(
+    self,
+    namespace: &str,
+    elem_witnesses: &[(T, NamespacedWitness<G, T>)],
+  ) -> Result<Self, AccError> {
+    self.delete(&namespaced_witnesses(namespace, elem_witnesses))
+  }
+
+  /// Proves that the elements in `elem_witnesses` are in `namespace`. The proof only verifies for
+  /// the same namespace.
+  pub fn prove_membership_in_namespace(
+    &self,
+    namespace: &str,
+    elem_witnesses: &[(T, NamespacedWitness<G, T>)],
+  ) -> Result<MembershipProof<G, Namespaced<T>>, AccError> {
+    self.prove_membership(&namespaced_witnesses(namespace, elem_witnesses))
+  }
+
+  /// Verifies a proof made with `prove_membership_in_namespace` that `elems` are in `namespace`.
+  pub fn verify_membership_in_namespace(
+    &self,
+    namespace: &str,
+    elems: &[T],
+    proof: &MembershipProof<G, Namespaced<T>>,
+  ) -> bool {
+    self.verify_membership_batch(&in_namespace(namespace, elems), proof)
+  }
+}
+
+fn namespaced_witnesses<G: UnknownOrderGroup, T: Clone + Hash>(
+  namespace: &str,
+  elem_witnesses: &[(T, NamespacedWitness<G, T>)],
+) -> Vec<(Namespaced<T>, NamespacedWitness<G, T>)> {
+  elem_witnesses
+    .iter()
+    .map(|(t, witness)| (Namespaced::new(namespace, t.clone()), witness.clone()))
+    .collect()
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::group::Rsa2048;
+
+  #[test]
+  fn test_namespaces() {
+    let empty = Accumulator::<Rsa2048, Namespaced<&'static str>>::empty();
+    let utxo = empty.clone().add_in_namespace("utxo", &["b"]);
+    let acc = utxo.clone().add_in_namespace("stxo", &["b"]);
+    let acc = acc.add_in_namespace("utxo", &["a"]);
+    assert!(
+      acc
+        == empty
+          .clone()
+          .add_in_namespace("stxo", &["b"])
+          .add_in_namespace("utxo", &["a", "b"])
+    );
+
+    let witness = Witness(empty.add_in_namespace("utxo", &["a", "b"]));
+    let proof = acc
+      .prove_membership_in_namespace("stxo", &[("b", witness.clone())])
+      .unwrap();
+    assert!(acc.verify_membership_in_namespace("stxo", &["b"], &proof));
+    assert!(!acc.verify_membership_in_namespace("utxo", &["b"], &proof));
+    assert!(acc
+      .prove_membership_in_namespace("utxo", &[("b", witness.clone())])
+      .is_err());
+
+    let deleted = acc.delete_in_namespace("stxo", &[("b", witness)]).unwrap();
+    assert!(deleted == utxo.add_in_namespace("utxo", &["a"]));
+  }
+}
@@ -0,0 +1,232 @@
+//! Two-level accumulators, where the values of shard accumulators are themselves accumulated in a
+//! root accumulator.
+//!
+//! Sharding keeps each accumulator's exponent state small, while verifiers still track a single
+//! root. An element is proven a member with a `NestedMembershipProof`: a proof that the element is
+//! in its shard, and a proof that the shard's current value is in the root.
+//!
+//! Root elements are `(shard index, shard value)` pairs rather than bare shard values. Empty
+//! shards, and in general shards holding the same set, have equal values; hashed alone, they would
+//! map to the same prime, and deleting one from the root would need a witness that does not exist.
+//! Shard values are hashed as `GroupElem`s, so every platform computes the same root.
+use crate::accumulator::{AccError, Accumulator, ExponentState, MembershipProof};
+use crate::group::{ElemBytes, UnknownOrderGroup};
+use crate::group_elem::GroupElem;
+use crate::hash::{PrimeBits, PrimeParams};
+use std::hash::Hash;
+use std::slice;
+
+/// An element of the root accumulator: a shard's index and its current value.
+pub type ShardCommitment<G> = (u64, GroupElem<G>);
+
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+/// A proof that elements are in a shard of a `NestedAccumulator`, which is in turn in the root.
+pub struct NestedMembershipProof<G: ElemBytes + UnknownOrderGroup, T: Hash> {
+  shard: u64,
+  shard_acc: Accumulator<G, T>,
+  shard_proof: MembershipProof<G, T>,
+  root_proof: MembershipProof<G, ShardCommitment<G>>,
+}
+
+impl<G: ElemBytes + UnknownOrderGroup, T: Eq + Hash> NestedMembershipProof<G, T> {
+  /// Returns the index of the shard the elements are in.
+  pub fn shard(&self) -> u64 {
+    self.shard
+  }
+
+  /// Verifies that `elems` are in some shard whose value is in `root`. Fails if the shard or either
+  /// proof uses different parameters than `root`, as all accumulators of a `NestedAccumulator`
+  /// share them.
+  pub fn verify(&self, root: &Accumulator<G, ShardCommitment<G>>, elems: &[T]) -> bool {
+    let params = root.params();
+    if self.shard_acc.params() != params
+      || self.shard_proof.witness.0.params() != params
+      || self.root_proof.witness.0.params() != params
+    {
+      return false;
+    }
+    let commitment = (self.shard, self.shard_acc.to_group_elem());
+    root.verify_membership(&commitment, &self.root_proof)
+      && self
+        .shard_acc
+        .verify_membership_batch(elems, &self.shard_proof)
+  }
+}
+
+/// A fixed number of shard accumulators under a root accumulator, with the exponent state of each
+/// so that updates and proofs need no witnesses. See the module docs.
+pub struct NestedAccumulator<G: ElemBytes + UnknownOrderGroup, T: Hash> {
+  shards: Vec<(Accumulator<G, T>, ExponentState<G, T>)>,
+  root: Accumulator<G, ShardCommitment<G>>,
+  root_state: ExponentState<G, ShardCommitment<G>>,
+}
+
+impl<G: ElemBytes + UnknownOrderGroup, T: Eq + Hash> NestedAccumulator<G, T> {
+  /// Returns a nested accumulator with `n_shards` empty shards. Elements and shard values are
+  /// hashed to 256-bit primes.
+  pub fn new(n_shards: u64) -> Self {
+    Self::with_params(n_shards, PrimeBits::B256)
+  }
+
+  /// Returns a nested accumulator with `n_shards` empty shards, whose elements and shard values
+  /// are mapped to primes according to `params`.
+  pub fn with_params<P: Into<PrimeParams>>(n_shards: u64, params: P) -> Self {
+    let params = params.into();
+    let shards: Vec<(Accumulator<G, T>, _)> = (0..n_shards)
+      .map(|_| {
+        (
+          Accumulator::with_params(params),
+          ExponentState::with_params(params),
+        )
+      })
+      .collect();
+    let commitments = shards
+      .iter()
+      .enumerate()
+      .map(|(i, (acc, _))| (i as u64, acc.to_group_elem()))
+      .collect::<Vec<_>>();
+    let mut root_state = ExponentState::with_params(params);
+    root_state.add(&commitments);
+    Self {
+      shards,
+      root: Accumulator::with_params(params).add(&commitments),
+      root_state,
+    }
+  }
+
+  /// Returns the root accumulator, which verifiers track.
+  pub fn root(&self) -> &Accumulator<G, ShardCommitment<G>> {
+    &self.root
+  }
+
+  /// Returns the accumulator of shard `shard`, or `None` if there is no such shard.
+  pub fn shard(&self, shard: u64) -> Option<&Accumulator<G, T>> {
+    self.shards.get(shard as usize).map(|(acc, _)| acc)
+  }
+
+  /// Adds `elems` to shard `shard` and updates the root.
+  pub fn add(&mut self, shard: u64, elems: &[T]) -> Result<(), AccError> {
+    self.update_shard(shard, |acc, state| {
+      state.add(elems);
+      Ok(acc.add(elems))
+    })
+  }
+
+  /// Deletes `elems` from shard `shard` and updates the root.
+  pub fn delete(&mut self, shard: u64, elems: &[T]) -> Result<(), AccError> {
+    self.update_shard(shard, |acc, state| acc.delete_with_state(state, elems))
+  }
+
+  /// Proves that `elems` are in shard `shard`.
+  pub fn prove_membership(
+    &self,
+    shard: u64,
+    elems: &[T],
+  ) -> Result<NestedMembershipProof<G, T>, AccError> {
+    let (shard_acc, shard_state) = self
+      .shards
+      .get(shard as usize)
+      .ok_or(AccError::BadWitness)?;
+    let commitment = (shard, shard_acc.to_group_elem());
+    Ok(NestedMembershipProof {
+      shard,
+      shard_acc: shard_acc.clone(),
+      shard_proof: shard_acc.prove_membership_with_state(shard_state, elems)?,
+      root_proof: self
+        .root
+        .prove_membership_with_state(&self.root_state, &[commitment])?,
+    })
+  }
+
+  // Replaces shard `shard` with the result of `f` and swaps its commitment in the root. Leaves
+  // everything unchanged if `f` fails.
+  fn update_shard<F>(&mut self, shard: u64, f: F) -> Result<(), AccError>
+  where
+    F: FnOnce(Accumulator<G, T>, &mut ExponentState<G, T>) -> Result<Accumulator<G, T>, AccError>,
+  {
+    let (acc, state) = self
+      .shards
+      .get_mut(shard as usize)
+      .ok_or(AccError::BadWitness)?;
+    let mut new_state = state.clone();
+    let new_acc = f(acc.clone(), &mut new_state)?;
+    let old_commitment = (shard, acc.to_group_elem());
+    let new_commitment = (shard, new_acc.to_group_elem());
+    self.root = self
+      .root
+      .delete_with_state_ref(&mut self.root_state, &[old_commitment])?
+      .add(slice::from_ref(&new_commitment));
+    self.root_state.add(&[new_commitment]);
+    *acc = new_acc;
+    *state = new_state;
+    Ok(())
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::group::Rsa2048;
+
+  #[test]
+  fn test_nested_membership() {
+    let mut nested = NestedAccumulator::<Rsa2048, &'static str>::new(3);
+    nested.add(0, &["a", "b"]).unwrap();
+    nested.add(1, &["c"]).unwrap();
+    nested.add(2, &["d", "e"]).unwrap();
+    nested.delete(2, &["e"]).unwrap();
+
+    let proof = nested.prove_membership(0, &["a", "b"]).unwrap();
+    assert_eq!(proof.shard(), 0);
+    assert!(proof.verify(nested.root(), &["a", "b"]));
+    assert!(!proof.verify(nested.root(), &["a", "c"]));
+    let proof = nested.prove_membership(2, &["d"]).unwrap();
+    assert!(proof.verify(nested.root(), &["d"]));
+    assert!(nested.prove_membership(2, &["e"]).is_err());
+
+    // Proofs do not outlive changes to their shard.
+    let root = nested.root().clone();
+    nested.add(2, &["f"]).unwrap();
+    assert!(proof.verify(&root, &["d"]));
+    assert!(!proof.verify(nested.root(), &["d"]));
+  }
+
+  #[test]
+  fn test_nested_bad_update() {
+    let mut nested = NestedAccumulator::<Rsa2048, &'static str>::new(2);
+    nested.add(0, &["a"]).unwrap();
+    let root = nested.root().clone();
+    assert!(nested.delete(1, &["a"]).is_err());
+    assert!(nested.add(2, &["a"]).is_err());
+    assert!(*nested.root() == root);
+    assert!(*nested.shard(1).unwrap() == Accumulator::empty());
+  }
+
+  #[test]
+  fn test_nested_equal_shards() {
+    let mut nested = NestedAccumulator::<Rsa2048, &'static str>::new(2);
+    nested.add(0, &["a"]).unwrap();
+    nested.add(1, &["a"]).unwrap();
+    nested.delete(0, &["a"]).unwrap();
+    let proof = nested.prove_membership(1, &["a"]).unwrap();
+    assert!(proof.verify(nested.root(), &["a"]));
+  }
+
+  #[test]
+  fn test_nested_params_mismatch() {
+    // A root with 256-bit primes committing to a shard with 128-bit primes.
+    let (shard_acc, shard_proof) =
+      Accumulator::<Rsa2048, &'static str>::with_params(PrimeBits::B128).add_with_proof(&["a"]);
+    let commitment = (0, shard_acc.to_group_elem());
+    let (root, root_proof) =
+      Accumulator::<Rsa2048, ShardCommitment<Rsa2048>>::empty().add_with_proof(&[commitment]);
+    assert!(shard_acc.verify_membership(&"a", &shard_proof));
+    let proof = NestedMembershipProof {
+      shard: 0,
+      shard_acc,
+      shard_proof,
+      root_proof,
+    };
+    assert!(!proof.verify(&root, &["a"]));
+  }
+}
@@ -0,0 +1,178 @@
+//! Planning one update cycle of interleaved additions and deletions.
+//!
+//! Applications often collect changes as they happen, e.g. a credential issued and revoked within
+//! the same cycle. A `BatchPlanner` records them in order and nets them out per element, so such a
+//! pair costs nothing, and emits the rest as a single `AccumulatorUpdate` (which a
+//! `GovernedAccumulator`'s maintainers can sign as is). Applying the plan deletes before adding, as
+//! in BBF: the deletions then only need the exponent state of the old accumulator, and the
+//! accumulator between the two steps is the witness of both the deletion and the addition proof.
+use crate::accumulator::{AccError, Accumulator, ExponentState, MembershipProof};
+use crate::governed::AccumulatorUpdate;
+use crate::group::{ElemBytes, UnknownOrderGroup};
+use std::collections::HashMap;
+use std::hash::Hash;
+
+#[derive(Clone, Debug)]
+/// Collects the additions and deletions of one update cycle. See the module docs.
+pub struct BatchPlanner<T: Eq + Hash> {
+  epoch: u64,
+  // Each element with its net number of additions, in order of first occurrence.
+  counts: Vec<(T, i64)>,
+  index: HashMap<T, usize>,
+}
+
+#[derive(Clone, Debug)]
+/// The result of applying a `BatchPlanner`.
+pub struct PlannedUpdate<G: UnknownOrderGroup, T: Hash> {
+  /// The netted update that was applied.
+  pub update: AccumulatorUpdate<T>,
+  /// The accumulator after the update.
+  pub accumulator: Accumulator<G, T>,
+  /// A proof that the deleted elements were in the accumulator before the update.
+  pub deletion_proof: MembershipProof<G, T>,
+  /// A proof that the added elements are in the accumulator after the update.
+  pub addition_proof: MembershipProof<G, T>,
+}
+
+impl<T: Clone + Eq + Hash> BatchPlanner<T> {
+  /// Returns an empty planner for the update cycle `epoch`.
+  pub fn new(epoch: u64) -> Self {
+    Self {
+      epoch,
+      counts: Vec::new(),
+      index: HashMap::new(),
+    }
+  }
+
+  /// Records an addition of `elem`.
+  pub fn add(&mut self, elem: T) {
+    self.record(elem, 1);
+  }
+
+  /// Records a deletion of `elem`. A deletion cancels an earlier addition of the same element in
+  /// this cycle, and vice versa.
+  pub fn delete(&mut self, elem: T) {
+    self.record(elem, -1);
+  }
+
+  fn record(&mut self, elem: T, delta: i64) {
+    let counts = &mut self.counts;
+    let i = *self.index.entry(elem.clone()).or_insert_with(|| {
+      counts.push((elem, 0));
+      counts.len() - 1
+    });
+    self.counts[i].1 += delta;
+  }
+
+  /// Returns the netted update to `acc`: each element whose additions and deletions do not cancel
+  /// out, in order of first occurrence. Returns `DuplicateElement` if an element is left added or
+  /// deleted more than once, since a set accumulator holds each element at most once.
+  pub fn update<G: ElemBytes + UnknownOrderGroup>(
+    &self,
+    acc: &Accumulator<G, T>,
+  ) -> Result<AccumulatorUpdate<T>, AccError> {
+    let mut additions = Vec::new();
+    let mut deletions = Vec::new();
+    for (elem, count) in &self.counts {
+      match *count {
+        0 => {}
+        1 => additions.push(elem.clone()),
+        -1 => deletions.push(elem.clone()),
+        _ => return Err(AccError::DuplicateElement),
+      }
+    }
+    Ok(AccumulatorUpdate {
+      epoch: self.epoch,
+      base: acc.digest(),
+      additions,
+      deletions,
+    })
+  }
+
+  /// Applies the netted update to `acc`, whose exponent state is `state`: deletes, then adds, and
+  /// proves both steps. Updates `state` to match the new accumulator. Returns `BadWitness` if a
+  /// deleted element is not in `acc`, `DuplicateElement` if `update` does, and leaves `state`
+  /// unchanged on any error.
+  pub fn apply<G: ElemBytes + UnknownOrderGroup>(
+    &self,
+    acc: &Accumulator<G, T>,
+    state: &mut ExponentState<G, T>,
+  ) -> Result<PlannedUpdate<G, T>, AccError> {
+    let update = self.update(acc)?;
+    let deletion_proof = acc.prove_membership_with_state(state, &update.deletions)?;
+    let (accumulator, addition_proof) = deletion_proof
+      .witness
+      .0
+      .clone()
+      .add_with_proof(&update.additions);
+    state.delete(&update.deletions)?;
+    state.add(&update.additions);
+    Ok(PlannedUpdate {
+      update,
+      accumulator,
+      deletion_proof,
+      addition_proof,
+    })
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::group::Rsa2048;
+
+  #[test]
+  fn test_batch_planner() {
+    let mut state = ExponentState::<Rsa2048, &'static str>::empty();
+    state.add(&["a", "b", "c"]);
+    let acc = Accumulator::empty().add(&["a", "b", "c"]);
+
+    let mut planner = BatchPlanner::new(3);
+    planner.add("d");
+    planner.delete("a");
+    planner.add("e");
+    planner.delete("d");
+    planner.add("a");
+    planner.delete("b");
+    planner.delete("e");
+    planner.add("e");
+    let update = planner.update(&acc).unwrap();
+    assert_eq!(update.epoch, 3);
+    assert_eq!(update.base, acc.digest());
+    assert_eq!(update.additions, vec!["e"]);
+    assert_eq!(update.deletions, vec!["b"]);
+
+    let planned = planner.apply(&acc, &mut state).unwrap();
+    assert!(planned.accumulator == Accumulator::empty().add(&["a", "c", "e"]));
+    assert!(acc.verify_membership_batch(&["b"], &planned.deletion_proof));
+    assert!(planned
+      .accumulator
+      .verify_membership_batch(&["e"], &planned.addition_proof));
+    assert!(planned.deletion_proof.witness == planned.addition_proof.witness);
+    let mut expected = ExponentState::<Rsa2048, &'static str>::empty();
+    expected.add(&["a", "c", "e"]);
+    assert_eq!(state.product(), expected.product());
+
+    let mut bad = BatchPlanner::new(4);
+    bad.delete("b");
+    let product = state.product().clone();
+    assert!(bad.apply(&planned.accumulator, &mut state).is_err());
+    assert_eq!(*state.product(), product);
+
+    let mut twice = BatchPlanner::new(4);
+    twice.add("f");
+    twice.add("f");
+    assert!(matches!(
+      twice.update(&planned.accumulator),
+      Err(AccError::DuplicateElement)
+    ));
+    let mut twice = BatchPlanner::new(4);
+    twice.delete("c");
+    twice.delete("c");
+    assert!(matches!(
+      twice.apply(&planned.accumulator, &mut state),
+      Err(AccError::DuplicateElement)
+    ));
+    assert_eq!(*state.product(), product);
+  }
+}
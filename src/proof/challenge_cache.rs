@@ -0,0 +1,124 @@
+//! Verifier-side LRU cache of NI-PoE challenge primes, enabled with the `challenge_cache` feature.
+//!
+//! `Poe::verify` and `Poe::verify_batch` derive each challenge with `hash_to_prime`, which
+//! dominates verification for small exponents. Verifiers that check the same proofs repeatedly
+//! (e.g. once when a transaction enters the mempool and again when its block is validated) skip the
+//! search on a hit. Entries are keyed by the Blake2b digest of the challenge transcript, so a hit
+//! returns exactly the prime `hash_to_prime` would.
+//!
+//! The cache is global and shared by all threads. The lock is not held while a prime is computed.
+use crate::hash::{hash, hash_to_prime, Blake2b};
+use crate::integer::Integer;
+use std::collections::HashMap;
+use std::hash::Hash;
+use std::sync::Mutex;
+
+/// The number of challenges the cache holds unless changed with `set_capacity`.
+pub const DEFAULT_CAPACITY: usize = 1024;
+
+lazy_static! {
+  static ref CACHE: Mutex<ChallengeCache> = Mutex::new(ChallengeCache::new(DEFAULT_CAPACITY));
+}
+
+/// Empties the cache and sets the number of challenges it holds. A capacity of zero disables
+/// caching.
+pub fn set_capacity(capacity: usize) {
+  *CACHE.lock().unwrap() = ChallengeCache::new(capacity);
+}
+
+/// Empties the cache, keeping its capacity.
+pub fn clear() {
+  let mut cache = CACHE.lock().unwrap();
+  *cache = ChallengeCache::new(cache.capacity);
+}
+
+// Returns `hash_to_prime(transcript)`, from the cache if possible.
+pub(crate) fn challenge<T: Hash + ?Sized>(transcript: &T) -> Integer {
+  let key = hash(&Blake2b::default, transcript);
+  if let Some(l) = CACHE.lock().unwrap().get(&key) {
+    return l;
+  }
+  let l = hash_to_prime(transcript);
+  CACHE.lock().unwrap().insert(key, l.clone());
+  l
+}
+
+struct ChallengeCache {
+  capacity: usize,
+  tick: u64,
+  // Maps transcript digests to their challenge and the tick of its last use.
+  entries: HashMap<[u8; 32], (Integer, u64)>,
+}
+
+impl ChallengeCache {
+  fn new(capacity: usize) -> Self {
+    Self {
+      capacity,
+      tick: 0,
+      entries: HashMap::new(),
+    }
+  }
+
+  fn get(&mut self, key: &[u8; 32]) -> Option<Integer> {
+    self.tick += 1;
+    let tick = self.tick;
+    self.entries.get_mut(key).map(|(l, last_used)| {
+      *last_used = tick;
+      l.clone()
+    })
+  }
+
+  fn insert(&mut self, key: [u8; 32], l: Integer) {
+    if self.capacity == 0 {
+      return;
+    }
+    if self.entries.len() >= self.capacity && !self.entries.contains_key(&key) {
+      // Eviction scans every entry, which is cheap next to `hash_to_prime` at the small capacities
+      // the cache is meant for.
+      let oldest = self
+        .entries
+        .iter()
+        .min_by_key(|(_, (_, last_used))| *last_used)
+        .map(|(key, _)| *key);
+      if let Some(oldest) = oldest {
+        self.entries.remove(&oldest);
+      }
+    }
+    self.tick += 1;
+    self.entries.insert(key, (l, self.tick));
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn key(t: &str) -> [u8; 32] {
+    hash(&Blake2b::default, t)
+  }
+
+  #[test]
+  fn test_lru() {
+    let mut cache = ChallengeCache::new(2);
+    cache.insert(key("a"), hash_to_prime("a"));
+    cache.insert(key("b"), hash_to_prime("b"));
+    assert_eq!(cache.get(&key("a")), Some(hash_to_prime("a")));
+    cache.insert(key("c"), hash_to_prime("c"));
+    assert_eq!(cache.entries.len(), 2);
+    assert_eq!(cache.get(&key("b")), None);
+    assert!(cache.get(&key("a")).is_some() && cache.get(&key("c")).is_some());
+
+    let mut disabled = ChallengeCache::new(0);
+    disabled.insert(key("a"), hash_to_prime("a"));
+    assert_eq!(disabled.get(&key("a")), None);
+  }
+
+  #[test]
+  fn test_challenge() {
+    for t in &["x", "y", "x"] {
+      assert_eq!(challenge(t), hash_to_prime(t));
+    }
+    clear();
+    assert_eq!(challenge("x"), hash_to_prime("x"));
+  }
+}
@@ -4,9 +4,101 @@
 //! Use standalone with caution.
 //!
 //! Implementations are based on Section 3 of BBF.
+use crate::group::ElemBytes;
+
+#[cfg(feature = "challenge_cache")]
+pub mod challenge_cache;
 mod poe;
 pub use poe::Poe;
 mod pokcr;
 pub use pokcr::Pokcr;
 mod poke2;
-pub use poke2::Poke2;
+pub use poke2::{Poke2, Poke2Eq};
+
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+/// How thoroughly verifiers check a statement and proof before the verification equation.
+pub enum VerifyMode {
+  /// Only the checks each proof's verification equation relies on. Used by the plain `verify`
+  /// functions.
+  Standard,
+  /// Also rejects degenerate statements that honest protocols never produce, such as exponents
+  /// `<= 1` or equal to the challenge prime, composite roots, and elements breaking their group's
+  /// invariants. Costs a few extra group operations or primality tests per proof.
+  Strict,
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+/// The ways parsing a serialized proof can fail. Decoders never return a proof containing a value
+/// outside the group, so these are the only checks verifiers need on untrusted bytes.
+pub enum DecodeError {
+  /// The input ended in the middle of the proof.
+  Truncated,
+  /// The input continued past the end of the proof.
+  TrailingBytes,
+  /// A group element was not the canonical encoding of an element of the group.
+  BadElement,
+  /// A field holding prime parameters or a flag had an unknown value.
+  BadTag,
+  /// The parameter digest did not match the group decoded for and the prime parameters the proof
+  /// carries, e.g. because the proof was made on another network. See `proof_params_digest`.
+  ParameterMismatch,
+}
+
+// Reads the fields of an encoded proof in order. Each read fails rather than panicking on short
+// input, and `finish` rejects leftover bytes, so every accepted encoding is canonical.
+pub(crate) struct Decoder<'a> {
+  bytes: &'a [u8],
+}
+
+impl<'a> Decoder<'a> {
+  pub(crate) fn new(bytes: &'a [u8]) -> Self {
+    Self { bytes }
+  }
+
+  // Decodes all of `bytes` with `decode`.
+  pub(crate) fn decode_all<T, F>(bytes: &'a [u8], decode: F) -> Result<T, DecodeError>
+  where
+    F: FnOnce(&mut Self) -> Result<T, DecodeError>,
+  {
+    let mut decoder = Self::new(bytes);
+    let decoded = decode(&mut decoder)?;
+    decoder.finish()?;
+    Ok(decoded)
+  }
+
+  pub(crate) fn bytes(&mut self, n: usize) -> Result<&'a [u8], DecodeError> {
+    if self.bytes.len() < n {
+      return Err(DecodeError::Truncated);
+    }
+    let (head, tail) = self.bytes.split_at(n);
+    self.bytes = tail;
+    Ok(head)
+  }
+
+  pub(crate) fn byte(&mut self) -> Result<u8, DecodeError> {
+    Ok(self.bytes(1)?[0])
+  }
+
+  pub(crate) fn u64(&mut self) -> Result<u64, DecodeError> {
+    let mut buf = [0; 8];
+    buf.copy_from_slice(self.bytes(8)?);
+    Ok(u64::from_be_bytes(buf))
+  }
+
+  pub(crate) fn elem<G: ElemBytes>(&mut self) -> Result<G::Elem, DecodeError> {
+    G::elem_from_bytes(self.bytes(G::elem_bytes_len())?).ok_or(DecodeError::BadElement)
+  }
+
+  // Returns the number of bytes not yet read.
+  pub(crate) fn remaining(&self) -> usize {
+    self.bytes.len()
+  }
+
+  pub(crate) fn finish(self) -> Result<(), DecodeError> {
+    if self.bytes.is_empty() {
+      Ok(())
+    } else {
+      Err(DecodeError::TrailingBytes)
+    }
+  }
+}
@@ -1,11 +1,15 @@
 //! Non-Interactive Proofs of Exponentiation (NI-PoE). See BBF (pages 8 and 42) for details.
-use crate::group::Group;
-use crate::hash::hash_to_prime;
-use crate::util::int;
-use rug::Integer;
+use super::{DecodeError, Decoder, VerifyMode};
+use crate::group::{simultaneous_exp, ElemBytes, Group};
+use crate::hash::{blake2b, hash_to_prime_bits};
+use crate::integer::{Integer, IntegerOps};
+use crate::security::{Security128, SecurityLevel};
+use crate::util::{int, Redacted};
+use std::fmt;
+use std::hash::Hash;
 
 #[allow(non_snake_case)]
-#[derive(Debug, PartialEq, Eq, Hash, Clone)]
+#[derive(PartialEq, Eq, Hash, Clone)]
 /// Struct for NI-PoE.
 pub struct Poe<G: Group> {
   Q: G::Elem,
@@ -14,21 +18,177 @@ pub struct Poe<G: Group> {
 impl<G: Group> Poe<G> {
   /// Computes a proof that `base ^ exp` was performed to derive `result`.
   pub fn prove(base: &G::Elem, exp: &Integer, result: &G::Elem) -> Self {
-    let l = hash_to_prime(&(base, exp, result));
+    Self::prove_with_context(base, exp, result, &())
+  }
+
+  /// Like `prove`, but also hashes `context` (e.g. an epoch) into the challenge, so the proof only
+  /// verifies with `verify_with_context` and the same context. The empty context `()` hashes to
+  /// nothing, so proofs with it are exactly those of `prove`.
+  pub fn prove_with_context<C: Hash + ?Sized>(
+    base: &G::Elem,
+    exp: &Integer,
+    result: &G::Elem,
+    context: &C,
+  ) -> Self {
+    Self::prove_::<Security128, C>(base, exp, result, context)
+  }
+
+  /// Like `prove`, with challenges of the size `S` prescribes. `Security128` gives exactly the
+  /// proofs of `prove`.
+  pub fn prove_with_security<S: SecurityLevel>(
+    base: &G::Elem,
+    exp: &Integer,
+    result: &G::Elem,
+  ) -> Self {
+    Self::prove_::<S, ()>(base, exp, result, &())
+  }
+
+  fn prove_<S: SecurityLevel, C: Hash + ?Sized>(
+    base: &G::Elem,
+    exp: &Integer,
+    result: &G::Elem,
+    context: &C,
+  ) -> Self {
+    #[cfg(feature = "metrics")]
+    let _timer =
+      crate::metrics::Timer::start(crate::metrics::Operation::ProvePoe, exp.bit_length());
+    let l = hash_to_prime_bits(&(base, exp, result, context), S::CHALLENGE_BITS);
     let q = exp / l;
-    Self {
+    let proof = Self {
       Q: G::exp(&base, &q),
-    }
+    };
+    #[cfg(all(feature = "paranoid", debug_assertions))]
+    assert!(
+      Self::verify_::<S, C>(base, exp, result, &proof, VerifyMode::Standard, context),
+      "generated an invalid NI-PoE proof"
+    );
+    proof
   }
 
   /// Verifies that `base ^ exp = result` using the given proof to avoid computation.
   pub fn verify(base: &G::Elem, exp: &Integer, result: &G::Elem, proof: &Self) -> bool {
-    let l = hash_to_prime(&(base, exp, result));
+    Self::verify_with_mode(base, exp, result, proof, VerifyMode::Standard)
+  }
+
+  /// Like `verify`, but in `VerifyMode::Strict` also rejects exponents `<= 1` or equal to the
+  /// challenge prime, and elements breaking their group's invariants.
+  pub fn verify_with_mode(
+    base: &G::Elem,
+    exp: &Integer,
+    result: &G::Elem,
+    proof: &Self,
+    mode: VerifyMode,
+  ) -> bool {
+    Self::verify_::<Security128, ()>(base, exp, result, proof, mode, &())
+  }
+
+  /// Verifies a proof made with `prove_with_context` and the same `context`.
+  pub fn verify_with_context<C: Hash + ?Sized>(
+    base: &G::Elem,
+    exp: &Integer,
+    result: &G::Elem,
+    proof: &Self,
+    context: &C,
+  ) -> bool {
+    Self::verify_::<Security128, C>(base, exp, result, proof, VerifyMode::Standard, context)
+  }
+
+  /// Verifies a proof made with `prove_with_security` and the same `S`.
+  pub fn verify_with_security<S: SecurityLevel>(
+    base: &G::Elem,
+    exp: &Integer,
+    result: &G::Elem,
+    proof: &Self,
+  ) -> bool {
+    Self::verify_::<S, ()>(base, exp, result, proof, VerifyMode::Standard, &())
+  }
+
+  fn verify_<S: SecurityLevel, C: Hash + ?Sized>(
+    base: &G::Elem,
+    exp: &Integer,
+    result: &G::Elem,
+    proof: &Self,
+    mode: VerifyMode,
+    context: &C,
+  ) -> bool {
+    let l = challenge::<S, _>(&(base, exp, result, context));
+    if mode == VerifyMode::Strict
+      && (*exp <= 1 || *exp == l || ![base, result, &proof.Q].iter().all(|x| G::is_valid_elem(x)))
+    {
+      return false;
+    }
     let r = int(exp % &l);
     // w = Q^l * u^r
     let w = G::op(&G::exp(&proof.Q, &l), &G::exp(&base, &r));
     w == *result
   }
+
+  /// Verifies many `(base, exp, result, proof)` statements at once, returning true only if (with
+  /// overwhelming probability) all of them hold.
+  ///
+  /// The verification equations `Q^l * base^r = result` are raised to pseudorandom 128-bit
+  /// coefficients derived from all statements and multiplied together, so that both sides are
+  /// computed with a single `simultaneous_exp`. Soundness relies on the group having no known
+  /// elements of low order, like the other proofs in this module.
+  pub fn verify_batch(statements: &[(G::Elem, Integer, G::Elem, Self)]) -> bool {
+    let digest = blake2b(&statements);
+    let mut lhs_bases = Vec::with_capacity(2 * statements.len());
+    let mut lhs_exps = Vec::with_capacity(2 * statements.len());
+    let mut rhs_bases = Vec::with_capacity(statements.len());
+    let mut rhs_exps = Vec::with_capacity(statements.len());
+    for (i, (base, exp, result, proof)) in statements.iter().enumerate() {
+      let l = challenge::<Security128, _>(&(base, exp, result));
+      let r = int(exp % &l);
+      let coefficient = blake2b(&(&digest, i)).low_bits(128);
+      lhs_bases.push(proof.Q.clone());
+      lhs_exps.push(l * &coefficient);
+      lhs_bases.push(base.clone());
+      lhs_exps.push(r * &coefficient);
+      rhs_bases.push(result.clone());
+      rhs_exps.push(coefficient);
+    }
+    simultaneous_exp::<G>(&lhs_bases, &lhs_exps) == simultaneous_exp::<G>(&rhs_bases, &rhs_exps)
+  }
+}
+
+// Returns the challenge prime of a statement at level `S`. The cache, if it is enabled, only holds
+// 256-bit challenges.
+fn challenge<S: SecurityLevel, T: Hash + ?Sized>(transcript: &T) -> Integer {
+  #[cfg(feature = "challenge_cache")]
+  {
+    if S::CHALLENGE_BITS == crate::hash::PrimeBits::B256 {
+      return super::challenge_cache::challenge(transcript);
+    }
+  }
+  hash_to_prime_bits(transcript, S::CHALLENGE_BITS)
+}
+
+impl<G: Group> fmt::Debug for Poe<G> {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    f.debug_struct("Poe")
+      .field("Q", &Redacted(&self.Q))
+      .finish()
+  }
+}
+
+impl_encoding_traits!(Poe<G> where G: ElemBytes);
+
+impl<G: ElemBytes> Poe<G> {
+  /// Returns the canonical encoding of this proof: the encoded `Q`.
+  pub fn to_bytes(&self) -> Vec<u8> {
+    G::elem_to_bytes(&self.Q)
+  }
+
+  /// Parses the canonical encoding produced by `to_bytes`.
+  pub fn from_bytes(bytes: &[u8]) -> Result<Self, DecodeError> {
+    Decoder::decode_all(bytes, Self::decode)
+  }
+
+  pub(crate) fn decode(decoder: &mut Decoder) -> Result<Self, DecodeError> {
+    Ok(Self {
+      Q: decoder.elem::<G>()?,
+    })
+  }
 }
 
 #[cfg(test)]
@@ -64,4 +224,94 @@ mod tests {
         }
     );
   }
+
+  #[test]
+  fn test_poe_strict() {
+    let base = Rsa2048::unknown_order_elem();
+    let exp = int(1) << 300;
+    let result = Rsa2048::exp(&base, &exp);
+    let proof = Poe::<Rsa2048>::prove(&base, &exp, &result);
+    assert!(Poe::verify_with_mode(
+      &base,
+      &exp,
+      &result,
+      &proof,
+      VerifyMode::Strict
+    ));
+
+    // Trivial statements hold, but are rejected in strict mode.
+    for exp in &[int(0), int(1)] {
+      let result = Rsa2048::exp(&base, exp);
+      let proof = Poe::<Rsa2048>::prove(&base, exp, &result);
+      assert!(Poe::verify(&base, exp, &result, &proof));
+      assert!(!Poe::verify_with_mode(
+        &base,
+        exp,
+        &result,
+        &proof,
+        VerifyMode::Strict
+      ));
+    }
+  }
+
+  #[test]
+  fn test_poe_context() {
+    let base = Rsa2048::unknown_order_elem();
+    let exp = int(1) << 300;
+    let result = Rsa2048::exp(&base, &exp);
+    let proof = Poe::<Rsa2048>::prove_with_context(&base, &exp, &result, &("epoch", 1));
+    assert!(Poe::verify_with_context(
+      &base,
+      &exp,
+      &result,
+      &proof,
+      &("epoch", 1)
+    ));
+    assert!(!Poe::verify_with_context(
+      &base,
+      &exp,
+      &result,
+      &proof,
+      &("epoch", 2)
+    ));
+    assert!(!Poe::verify(&base, &exp, &result, &proof));
+  }
+
+  #[test]
+  fn test_poe_security() {
+    use crate::security::Security112;
+    let base = Rsa2048::unknown_order_elem();
+    let exp = int(1) << 300;
+    let result = Rsa2048::exp(&base, &exp);
+    let proof = Poe::<Rsa2048>::prove_with_security::<Security128>(&base, &exp, &result);
+    assert!(proof == Poe::prove(&base, &exp, &result));
+    let proof = Poe::<Rsa2048>::prove_with_security::<Security112>(&base, &exp, &result);
+    assert!(Poe::verify_with_security::<Security112>(
+      &base, &exp, &result, &proof
+    ));
+    assert!(!Poe::verify(&base, &exp, &result, &proof));
+  }
+
+  #[test]
+  fn test_poe_verify_batch() {
+    let base = Rsa2048::unknown_order_elem();
+    let statements = (1..5)
+      .map(|i| {
+        let exp = int(1) << (300 * i);
+        let result = Rsa2048::exp(&base, &exp);
+        let proof = Poe::<Rsa2048>::prove(&base, &exp, &result);
+        (base.clone(), exp, result, proof)
+      })
+      .collect::<Vec<_>>();
+    assert!(Poe::verify_batch(&statements));
+    assert!(Poe::<Rsa2048>::verify_batch(&[]));
+
+    let mut bad_statements = statements.clone();
+    bad_statements[2].1 += 1;
+    assert!(!Poe::verify_batch(&bad_statements));
+    let mut bad_statements = statements;
+    bad_statements.swap(0, 1);
+    bad_statements[0].3 = bad_statements[1].3.clone();
+    assert!(!Poe::verify_batch(&bad_statements));
+  }
 }
@@ -1,9 +1,13 @@
 //! Non-Interactive Proofs of Knowledge of Co-prime Roots (NI-PoKCR). See BBF (page 11) for details.
-use crate::group::{multi_exp, Group};
-use rug::Integer;
+use super::{DecodeError, Decoder, VerifyMode};
+use crate::group::{multi_exp, ElemBytes, Group};
+use crate::integer::{Integer, IntegerOps};
+use crate::security::{Security128, SecurityLevel};
+use crate::util::Redacted;
+use std::fmt;
 
 #[allow(non_snake_case)]
-#[derive(PartialEq, Eq, Hash, Clone, Debug)]
+#[derive(PartialEq, Eq, Hash, Clone)]
 /// Struct for NI-PoKCR.
 pub struct Pokcr<G: Group> {
   w: G::Elem,
@@ -12,6 +16,8 @@ pub struct Pokcr<G: Group> {
 impl<G: Group> Pokcr<G> {
   /// Generates an NI-PoKCR proof.
   pub fn prove(witnesses: &[G::Elem]) -> Self {
+    #[cfg(feature = "metrics")]
+    let _timer = crate::metrics::Timer::start(crate::metrics::Operation::ProvePokcr, 0);
     Self {
       w: witnesses.iter().fold(G::id(), |a, b| G::op(&a, b)),
     }
@@ -19,16 +25,82 @@ impl<G: Group> Pokcr<G> {
 
   /// Verifies an NI-PoKCR proof.
   pub fn verify(alphas: &[G::Elem], x: &[Integer], proof: &Self) -> bool {
+    Self::verify_with_mode(alphas, x, proof, VerifyMode::Standard)
+  }
+
+  /// Like `verify`, but in `VerifyMode::Strict` also requires one `x` per `alpha`, rejects `x`
+  /// values that are `<= 1` or composite, and elements breaking their group's invariants.
+  pub fn verify_with_mode(
+    alphas: &[G::Elem],
+    x: &[Integer],
+    proof: &Self,
+    mode: VerifyMode,
+  ) -> bool {
+    Self::verify_::<Security128>(alphas, x, proof, mode)
+  }
+
+  /// Like `verify_with_mode` in `VerifyMode::Strict`, with the number of Miller-Rabin rounds `S`
+  /// prescribes for checking that the `x` values are prime.
+  pub fn verify_with_security<S: SecurityLevel>(
+    alphas: &[G::Elem],
+    x: &[Integer],
+    proof: &Self,
+  ) -> bool {
+    Self::verify_::<S>(alphas, x, proof, VerifyMode::Strict)
+  }
+
+  fn verify_<S: SecurityLevel>(
+    alphas: &[G::Elem],
+    x: &[Integer],
+    proof: &Self,
+    mode: VerifyMode,
+  ) -> bool {
+    if mode == VerifyMode::Strict
+      && (alphas.len() != x.len()
+        || !x
+          .iter()
+          .all(|x| *x > 1 && x.is_probable_prime(S::MR_ROUNDS))
+        || !alphas.iter().chain(Some(&proof.w)).all(G::is_valid_elem))
+    {
+      return false;
+    }
     let y = multi_exp::<G>(alphas, x);
     let lhs = G::exp(&proof.w, &x.iter().product());
     lhs == y
   }
 }
 
+impl<G: Group> fmt::Debug for Pokcr<G> {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    f.debug_struct("Pokcr")
+      .field("w", &Redacted(&self.w))
+      .finish()
+  }
+}
+
+impl_encoding_traits!(Pokcr<G> where G: ElemBytes);
+
+impl<G: ElemBytes> Pokcr<G> {
+  /// Returns the canonical encoding of this proof: the encoded `w`.
+  pub fn to_bytes(&self) -> Vec<u8> {
+    G::elem_to_bytes(&self.w)
+  }
+
+  /// Parses the canonical encoding produced by `to_bytes`.
+  pub fn from_bytes(bytes: &[u8]) -> Result<Self, DecodeError> {
+    Decoder::decode_all(bytes, |decoder| {
+      Ok(Self {
+        w: decoder.elem::<G>()?,
+      })
+    })
+  }
+}
+
 #[cfg(test)]
 mod tests {
   use super::*;
   use crate::group::{ElemFrom, Rsa2048};
+  use crate::security::Security112;
   use crate::util::int;
 
   #[test]
@@ -39,5 +111,40 @@ mod tests {
     let proof = Pokcr::<Rsa2048>::prove(&witnesses);
     assert!(proof.w == Rsa2048::elem(6));
     assert!(Pokcr::verify(&alphas, &x, &proof));
+    assert!(Pokcr::verify_with_mode(
+      &alphas,
+      &x,
+      &proof,
+      VerifyMode::Strict
+    ));
+    assert!(Pokcr::verify_with_security::<Security112>(
+      &alphas, &x, &proof
+    ));
+  }
+
+  #[test]
+  fn test_pokcr_strict() {
+    // 2^4 = 16 holds, but 4 is not prime.
+    let proof = Pokcr::<Rsa2048>::prove(&[Rsa2048::elem(2)]);
+    let alphas = [Rsa2048::elem(16)];
+    assert!(Pokcr::verify(&alphas, &[int(4)], &proof));
+    assert!(!Pokcr::verify_with_mode(
+      &alphas,
+      &[int(4)],
+      &proof,
+      VerifyMode::Strict
+    ));
+    assert!(!Pokcr::verify_with_security::<Security112>(
+      &alphas,
+      &[int(4)],
+      &proof
+    ));
+    let proof = Pokcr::<Rsa2048>::prove(&[Rsa2048::elem(1)]);
+    assert!(!Pokcr::verify_with_mode(
+      &[Rsa2048::elem(1)],
+      &[int(1)],
+      &proof,
+      VerifyMode::Strict
+    ));
   }
 }
@@ -1,11 +1,23 @@
 //! Non-Interactive Proofs of Knowledge of Exponent (NI-PoKE2). See BBF (pages 10 and 42) for
 //! details.
-use crate::group::UnknownOrderGroup;
-use crate::hash::{blake2b, hash_to_prime};
-use rug::Integer;
+//!
+//! `Poke2Eq` runs NI-PoKE2 in two groups at once, with a shared challenge and a single residue `r`.
+//! Extracting from each group gives an exponent for each, and the shared `r` makes them agree
+//! modulo every challenge prime, so the prover knows one exponent that works in both.
+use super::{DecodeError, Decoder, VerifyMode};
+use crate::group::{simultaneous_exp, ElemBytes, UnknownOrderGroup};
+use crate::hash::{blake2b, hash_to_prime, hash_to_prime_bits};
+use crate::integer::{Integer, IntegerOps};
+use crate::security::{Security128, SecurityLevel};
+use crate::util::Redacted;
+use std::fmt;
+use std::hash::Hash;
+
+// `r` is reduced modulo a prime of at most 256 bits, so it always fits in this many bytes.
+const RESIDUE_BYTES: usize = 32;
 
 #[allow(non_snake_case)]
-#[derive(PartialEq, Eq, Hash, Clone, Debug)]
+#[derive(PartialEq, Eq, Hash, Clone)]
 /// Struct for NI-PoKE2.
 pub struct Poke2<G: UnknownOrderGroup> {
   z: G::Elem,
@@ -16,22 +28,112 @@ pub struct Poke2<G: UnknownOrderGroup> {
 impl<G: UnknownOrderGroup> Poke2<G> {
   /// Computes a proof that you know `exp` s.t. `base ^ exp = result`.
   pub fn prove(base: &G::Elem, exp: &Integer, result: &G::Elem) -> Self {
+    Self::prove_with_context(base, exp, result, &())
+  }
+
+  /// Like `prove`, but also hashes `context` (e.g. an epoch) into the challenges, so the proof
+  /// only verifies with `verify_with_context` and the same context. As for `Poe`, the empty
+  /// context `()` gives exactly the proofs of `prove`.
+  pub fn prove_with_context<C: Hash + ?Sized>(
+    base: &G::Elem,
+    exp: &Integer,
+    result: &G::Elem,
+    context: &C,
+  ) -> Self {
+    Self::prove_::<Security128, C>(base, exp, result, context)
+  }
+
+  /// Like `prove`, with a challenge prime of the size `S` prescribes. `Security128` gives exactly
+  /// the proofs of `prove`.
+  pub fn prove_with_security<S: SecurityLevel>(
+    base: &G::Elem,
+    exp: &Integer,
+    result: &G::Elem,
+  ) -> Self {
+    Self::prove_::<S, ()>(base, exp, result, &())
+  }
+
+  fn prove_<S: SecurityLevel, C: Hash + ?Sized>(
+    base: &G::Elem,
+    exp: &Integer,
+    result: &G::Elem,
+    context: &C,
+  ) -> Self {
+    #[cfg(feature = "metrics")]
+    let _timer =
+      crate::metrics::Timer::start(crate::metrics::Operation::ProvePoke2, exp.bit_length());
     let g = G::unknown_order_elem();
     let z = G::exp(&g, exp);
-    let l = hash_to_prime(&(base, result, &z));
-    let alpha = blake2b(&(base, result, &z, &l));
-    let (q, r) = <(Integer, Integer)>::from(exp.div_rem_euc_ref(&l));
+    let (l, alpha) = challenges::<G, S, C>(base, result, &z, context);
+    let (q, r) = exp.div_rem_euclid(&l);
     #[allow(non_snake_case)]
     let Q = G::exp(&G::op(&base, &G::exp(&g, &alpha)), &q);
-    Self { z, Q, r }
+    let proof = Self { z, Q, r };
+    #[cfg(all(feature = "paranoid", debug_assertions))]
+    assert!(
+      Self::verify_::<S, C>(base, result, &proof, VerifyMode::Standard, context),
+      "generated an invalid NI-PoKE2 proof"
+    );
+    proof
   }
 
   /// Verifies that the prover knows `exp` s.t. `base ^ exp = result`.
+  pub fn verify(base: &G::Elem, result: &G::Elem, proof: &Self) -> bool {
+    Self::verify_with_mode(base, result, proof, VerifyMode::Standard)
+  }
+
+  /// Like `verify`, but in `VerifyMode::Strict` also rejects elements breaking their group's
+  /// invariants, and commitments `z = g^exp` to exponents `0`, `1` or the challenge prime.
+  pub fn verify_with_mode(
+    base: &G::Elem,
+    result: &G::Elem,
+    proof: &Self,
+    mode: VerifyMode,
+  ) -> bool {
+    Self::verify_::<Security128, ()>(base, result, proof, mode, &())
+  }
+
+  /// Verifies a proof made with `prove_with_context` and the same `context`.
+  pub fn verify_with_context<C: Hash + ?Sized>(
+    base: &G::Elem,
+    result: &G::Elem,
+    proof: &Self,
+    context: &C,
+  ) -> bool {
+    Self::verify_::<Security128, C>(base, result, proof, VerifyMode::Standard, context)
+  }
+
+  /// Verifies a proof made with `prove_with_security` and the same `S`.
+  pub fn verify_with_security<S: SecurityLevel>(
+    base: &G::Elem,
+    result: &G::Elem,
+    proof: &Self,
+  ) -> bool {
+    Self::verify_::<S, ()>(base, result, proof, VerifyMode::Standard, &())
+  }
+
   #[allow(non_snake_case)]
-  pub fn verify(base: &G::Elem, result: &G::Elem, Self { z, Q, r }: &Self) -> bool {
+  fn verify_<S: SecurityLevel, C: Hash + ?Sized>(
+    base: &G::Elem,
+    result: &G::Elem,
+    Self { z, Q, r }: &Self,
+    mode: VerifyMode,
+    context: &C,
+  ) -> bool {
     let g = G::unknown_order_elem();
-    let l = hash_to_prime(&(base, result, &z));
-    let alpha = blake2b(&(base, result, &z, &l));
+    let (l, alpha) = challenges::<G, S, C>(base, result, z, context);
+    // Checked before exponentiating, so that an oversized `r` costs nothing to reject.
+    if *r >= l {
+      return false;
+    }
+    if mode == VerifyMode::Strict
+      && (![base, result, z, Q].iter().all(|x| G::is_valid_elem(x))
+        || *z == G::id()
+        || *z == g
+        || *z == G::exp(&g, &l))
+    {
+      return false;
+    }
     let lhs = G::op(
       &G::exp(Q, &l),
       &G::exp(&G::op(&base, &G::exp(&g, &alpha)), &r),
@@ -39,6 +141,226 @@ impl<G: UnknownOrderGroup> Poke2<G> {
     let rhs = G::op(result, &G::exp(&z, &alpha));
     lhs == rhs
   }
+
+  /// Verifies many `(base, result, proof)` statements at once, returning true only if (with
+  /// overwhelming probability) all of them hold.
+  ///
+  /// As in `Poe::verify_batch`, the verification equations `Q^l * (base * g^alpha)^r = result *
+  /// z^alpha` are raised to pseudorandom 128-bit coefficients derived from all statements and
+  /// multiplied together. The `g` terms of all statements share one base, so both sides are
+  /// computed with a single `simultaneous_exp` over `2k + 1` and `2k` bases.
+  pub fn verify_batch(statements: &[(G::Elem, G::Elem, Self)]) -> bool {
+    let digest = blake2b(&statements);
+    let mut g_exp = Integer::from(0);
+    let mut lhs_bases = Vec::with_capacity(2 * statements.len() + 1);
+    let mut lhs_exps = Vec::with_capacity(2 * statements.len() + 1);
+    let mut rhs_bases = Vec::with_capacity(2 * statements.len());
+    let mut rhs_exps = Vec::with_capacity(2 * statements.len());
+    for (i, (base, result, Self { z, Q, r })) in statements.iter().enumerate() {
+      let (l, alpha) = challenges::<G, Security128, ()>(base, result, z, &());
+      if *r < 0 || *r >= l {
+        return false;
+      }
+      let coefficient = blake2b(&(&digest, i)).low_bits(128);
+      let r_coefficient = Integer::from(r * &coefficient);
+      g_exp += Integer::from(&alpha * &r_coefficient);
+      lhs_bases.push(Q.clone());
+      lhs_exps.push(l * &coefficient);
+      lhs_bases.push(base.clone());
+      lhs_exps.push(r_coefficient);
+      rhs_bases.push(result.clone());
+      rhs_exps.push(coefficient.clone());
+      rhs_bases.push(z.clone());
+      rhs_exps.push(alpha * coefficient);
+    }
+    lhs_bases.push(G::unknown_order_elem());
+    lhs_exps.push(g_exp);
+    simultaneous_exp::<G>(&lhs_bases, &lhs_exps) == simultaneous_exp::<G>(&rhs_bases, &rhs_exps)
+  }
+}
+
+// Returns the challenge prime `l` and the exponent `alpha`. Contexts are hashed after the statement
+// and commitment, so the empty context leaves both as they were before contexts existed.
+fn challenges<G: UnknownOrderGroup, S: SecurityLevel, C: Hash + ?Sized>(
+  base: &G::Elem,
+  result: &G::Elem,
+  z: &G::Elem,
+  context: &C,
+) -> (Integer, Integer) {
+  let l = hash_to_prime_bits(&(base, result, z, context), S::CHALLENGE_BITS);
+  let alpha = blake2b(&(base, result, z, &l, context));
+  (l, alpha)
+}
+
+impl<G: UnknownOrderGroup> fmt::Debug for Poke2<G> {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    f.debug_struct("Poke2")
+      .field("z", &Redacted(&self.z))
+      .field("Q", &Redacted(&self.Q))
+      .field("r", &self.r)
+      .finish()
+  }
+}
+
+impl_encoding_traits!(Poke2<G> where G: ElemBytes + UnknownOrderGroup);
+
+impl<G: ElemBytes + UnknownOrderGroup> Poke2<G> {
+  /// Returns the canonical encoding of this proof: the encoded `z` and `Q`, followed by `r` as a
+  /// 32-byte big-endian integer.
+  pub fn to_bytes(&self) -> Vec<u8> {
+    let mut bytes = G::elem_to_bytes(&self.z);
+    bytes.extend(G::elem_to_bytes(&self.Q));
+    let digits = self.r.to_bytes_be();
+    bytes.resize(bytes.len() + RESIDUE_BYTES - digits.len(), 0);
+    bytes.extend(digits);
+    bytes
+  }
+
+  /// Parses the canonical encoding produced by `to_bytes`.
+  pub fn from_bytes(bytes: &[u8]) -> Result<Self, DecodeError> {
+    Decoder::decode_all(bytes, Self::decode)
+  }
+
+  #[allow(non_snake_case)]
+  pub(crate) fn decode(decoder: &mut Decoder) -> Result<Self, DecodeError> {
+    let z = decoder.elem::<G>()?;
+    let Q = decoder.elem::<G>()?;
+    let r = Integer::from_bytes_be(decoder.bytes(RESIDUE_BYTES)?);
+    Ok(Self { z, Q, r })
+  }
+}
+
+#[allow(non_snake_case)]
+#[derive(PartialEq, Eq, Hash, Clone)]
+/// Struct for NI-PoKE2 of one exponent in two groups.
+pub struct Poke2Eq<G: UnknownOrderGroup, H: UnknownOrderGroup> {
+  z_g: G::Elem,
+  Q_g: G::Elem,
+  z_h: H::Elem,
+  Q_h: H::Elem,
+  r: Integer,
+}
+
+impl<G: UnknownOrderGroup, H: UnknownOrderGroup> Poke2Eq<G, H> {
+  /// Computes a proof that you know `exp` s.t. `base_g ^ exp = result_g` in `G` and
+  /// `base_h ^ exp = result_h` in `H`.
+  #[allow(non_snake_case)]
+  pub fn prove(
+    base_g: &G::Elem,
+    base_h: &H::Elem,
+    exp: &Integer,
+    result_g: &G::Elem,
+    result_h: &H::Elem,
+  ) -> Self {
+    let g = G::unknown_order_elem();
+    let h = H::unknown_order_elem();
+    let z_g = G::exp(&g, exp);
+    let z_h = H::exp(&h, exp);
+    let l = hash_to_prime(&(base_g, result_g, &z_g, base_h, result_h, &z_h));
+    let alpha = blake2b(&(base_g, result_g, &z_g, base_h, result_h, &z_h, &l));
+    let (q, r) = exp.div_rem_euclid(&l);
+    let Q_g = G::exp(&G::op(base_g, &G::exp(&g, &alpha)), &q);
+    let Q_h = H::exp(&H::op(base_h, &H::exp(&h, &alpha)), &q);
+    let proof = Self {
+      z_g,
+      Q_g,
+      z_h,
+      Q_h,
+      r,
+    };
+    #[cfg(all(feature = "paranoid", debug_assertions))]
+    assert!(
+      Self::verify(base_g, base_h, result_g, result_h, &proof),
+      "generated an invalid NI-PoKE2 proof"
+    );
+    proof
+  }
+
+  /// Verifies that the prover knows `exp` s.t. `base_g ^ exp = result_g` in `G` and
+  /// `base_h ^ exp = result_h` in `H`.
+  #[allow(non_snake_case)]
+  pub fn verify(
+    base_g: &G::Elem,
+    base_h: &H::Elem,
+    result_g: &G::Elem,
+    result_h: &H::Elem,
+    Self {
+      z_g,
+      Q_g,
+      z_h,
+      Q_h,
+      r,
+    }: &Self,
+  ) -> bool {
+    let g = G::unknown_order_elem();
+    let h = H::unknown_order_elem();
+    let l = hash_to_prime(&(base_g, result_g, z_g, base_h, result_h, z_h));
+    if *r >= l {
+      return false;
+    }
+    let alpha = blake2b(&(base_g, result_g, z_g, base_h, result_h, z_h, &l));
+    let lhs_g = G::op(
+      &G::exp(Q_g, &l),
+      &G::exp(&G::op(base_g, &G::exp(&g, &alpha)), r),
+    );
+    let lhs_h = H::op(
+      &H::exp(Q_h, &l),
+      &H::exp(&H::op(base_h, &H::exp(&h, &alpha)), r),
+    );
+    lhs_g == G::op(result_g, &G::exp(z_g, &alpha)) && lhs_h == H::op(result_h, &H::exp(z_h, &alpha))
+  }
+}
+
+impl<G: UnknownOrderGroup, H: UnknownOrderGroup> fmt::Debug for Poke2Eq<G, H> {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    f.debug_struct("Poke2Eq")
+      .field("z_g", &Redacted(&self.z_g))
+      .field("Q_g", &Redacted(&self.Q_g))
+      .field("z_h", &Redacted(&self.z_h))
+      .field("Q_h", &Redacted(&self.Q_h))
+      .field("r", &self.r)
+      .finish()
+  }
+}
+
+impl_encoding_traits!(
+  Poke2Eq<G, H> where G: ElemBytes + UnknownOrderGroup, H: ElemBytes + UnknownOrderGroup
+);
+
+impl<G: ElemBytes + UnknownOrderGroup, H: ElemBytes + UnknownOrderGroup> Poke2Eq<G, H> {
+  /// Returns the canonical encoding of this proof: the encoded `z` and `Q` in `G`, then in `H`,
+  /// followed by `r` as a 32-byte big-endian integer.
+  pub fn to_bytes(&self) -> Vec<u8> {
+    let mut bytes = G::elem_to_bytes(&self.z_g);
+    bytes.extend(G::elem_to_bytes(&self.Q_g));
+    bytes.extend(H::elem_to_bytes(&self.z_h));
+    bytes.extend(H::elem_to_bytes(&self.Q_h));
+    let digits = self.r.to_bytes_be();
+    bytes.resize(bytes.len() + RESIDUE_BYTES - digits.len(), 0);
+    bytes.extend(digits);
+    bytes
+  }
+
+  /// Parses the canonical encoding produced by `to_bytes`.
+  pub fn from_bytes(bytes: &[u8]) -> Result<Self, DecodeError> {
+    Decoder::decode_all(bytes, Self::decode)
+  }
+
+  #[allow(non_snake_case)]
+  pub(crate) fn decode(decoder: &mut Decoder) -> Result<Self, DecodeError> {
+    let z_g = decoder.elem::<G>()?;
+    let Q_g = decoder.elem::<G>()?;
+    let z_h = decoder.elem::<H>()?;
+    let Q_h = decoder.elem::<H>()?;
+    let r = Integer::from_bytes_be(decoder.bytes(RESIDUE_BYTES)?);
+    Ok(Self {
+      z_g,
+      Q_g,
+      z_h,
+      Q_h,
+      r,
+    })
+  }
 }
 
 #[cfg(test)]
@@ -47,6 +369,69 @@ mod tests {
   use crate::group::{ElemFrom, Group, Rsa2048};
   use crate::util::int;
 
+  #[test]
+  fn test_poke2_strict() {
+    let base = Rsa2048::unknown_order_elem();
+    let exp = int(1) << 300;
+    let result = Rsa2048::exp(&base, &exp);
+    let proof = Poke2::<Rsa2048>::prove(&base, &exp, &result);
+    assert!(Poke2::verify_with_mode(
+      &base,
+      &result,
+      &proof,
+      VerifyMode::Strict
+    ));
+
+    // Trivial statements hold, but are rejected in strict mode.
+    for exp in &[int(0), int(1)] {
+      let result = Rsa2048::exp(&base, exp);
+      let proof = Poke2::<Rsa2048>::prove(&base, exp, &result);
+      assert!(Poke2::verify(&base, &result, &proof));
+      assert!(!Poke2::verify_with_mode(
+        &base,
+        &result,
+        &proof,
+        VerifyMode::Strict
+      ));
+    }
+  }
+
+  #[test]
+  fn test_poke2_context() {
+    let base = Rsa2048::unknown_order_elem();
+    let exp = int(1) << 300;
+    let result = Rsa2048::exp(&base, &exp);
+    let proof = Poke2::<Rsa2048>::prove_with_context(&base, &exp, &result, &("epoch", 1));
+    assert!(Poke2::verify_with_context(
+      &base,
+      &result,
+      &proof,
+      &("epoch", 1)
+    ));
+    assert!(!Poke2::verify_with_context(
+      &base,
+      &result,
+      &proof,
+      &("epoch", 2)
+    ));
+    assert!(!Poke2::verify(&base, &result, &proof));
+  }
+
+  #[test]
+  fn test_poke2_security() {
+    use crate::security::Security112;
+    let base = Rsa2048::unknown_order_elem();
+    let exp = int(1) << 300;
+    let result = Rsa2048::exp(&base, &exp);
+    let proof = Poke2::<Rsa2048>::prove_with_security::<Security128>(&base, &exp, &result);
+    assert!(proof == Poke2::prove(&base, &exp, &result));
+    let proof = Poke2::<Rsa2048>::prove_with_security::<Security112>(&base, &exp, &result);
+    assert!(Poke2::verify_with_security::<Security112>(
+      &base, &result, &proof
+    ));
+    assert!(!Poke2::verify(&base, &result, &proof));
+  }
+
   #[test]
   fn test_poke2() {
     // 2^20 = 1048576
@@ -82,6 +467,29 @@ mod tests {
     );
   }
 
+  #[test]
+  fn test_poke2_verify_batch() {
+    let base = Rsa2048::unknown_order_elem();
+    let statements = (1..5)
+      .map(|i| {
+        let exp = int(1) << (300 * i);
+        let result = Rsa2048::exp(&base, &exp);
+        let proof = Poke2::<Rsa2048>::prove(&base, &exp, &result);
+        (base.clone(), result, proof)
+      })
+      .collect::<Vec<_>>();
+    assert!(Poke2::verify_batch(&statements));
+    assert!(Poke2::<Rsa2048>::verify_batch(&[]));
+
+    let mut bad_statements = statements.clone();
+    bad_statements[2].1 = Rsa2048::op(&bad_statements[2].1, &base);
+    assert!(!Poke2::verify_batch(&bad_statements));
+    let mut bad_statements = statements;
+    bad_statements.swap(0, 1);
+    bad_statements[0].2 = bad_statements[1].2.clone();
+    assert!(!Poke2::verify_batch(&bad_statements));
+  }
+
   #[test]
   fn test_poke2_negative() {
     let base = Rsa2048::elem(2);
@@ -90,4 +498,49 @@ mod tests {
     let proof = Poke2::<Rsa2048>::prove(&base, &exp, &result);
     assert!(Poke2::verify(&base, &result, &proof));
   }
+
+  #[test]
+  fn test_poke2_bytes() {
+    let base = Rsa2048::unknown_order_elem();
+    let exp = int(1) << 300;
+    let result = Rsa2048::exp(&base, &exp);
+    let proof = Poke2::<Rsa2048>::prove(&base, &exp, &result);
+    let bytes = proof.to_bytes();
+    assert_eq!(bytes.len(), 2 * 256 + RESIDUE_BYTES);
+    assert!(Poke2::from_bytes(&bytes).unwrap() == proof);
+    assert_eq!(
+      Poke2::<Rsa2048>::from_bytes(&bytes[1..]),
+      Err(DecodeError::BadElement)
+    );
+
+    // A residue that is not reduced modulo the challenge prime is rejected, even though it
+    // satisfies the verification equation.
+    let l = hash_to_prime(&(&base, &result, &proof.z));
+    let alpha = blake2b(&(&base, &result, &proof.z, &l));
+    let shifted_base = Rsa2048::op(&base, &Rsa2048::exp(&base, &alpha));
+    let unreduced = Poke2 {
+      r: int(&proof.r + &l),
+      Q: Rsa2048::op(&proof.Q, &Rsa2048::inv(&shifted_base)),
+      ..proof
+    };
+    assert!(!Poke2::verify(&base, &result, &unreduced));
+  }
+
+  #[test]
+  fn test_poke2_eq() {
+    let g = Rsa2048::unknown_order_elem();
+    let h = Rsa2048::elem(3);
+    let exp = int(1) << 300;
+    let result_g = Rsa2048::exp(&g, &exp);
+    let result_h = Rsa2048::exp(&h, &exp);
+    let proof = Poke2Eq::<Rsa2048, Rsa2048>::prove(&g, &h, &exp, &result_g, &result_h);
+    assert!(Poke2Eq::verify(&g, &h, &result_g, &result_h, &proof));
+    let bytes = proof.to_bytes();
+    assert_eq!(bytes.len(), 4 * 256 + RESIDUE_BYTES);
+    assert!(Poke2Eq::from_bytes(&bytes).unwrap() == proof);
+
+    // Different exponents in the two groups.
+    let other_h = Rsa2048::exp(&h, &int(&exp + 1));
+    assert!(!Poke2Eq::verify(&g, &h, &result_g, &other_h, &proof));
+  }
 }
@@ -0,0 +1,192 @@
+//! Batched refreshing of membership witnesses, for wallets tracking many elements.
+//!
+//! Keeping a witness current means updating it after every change to the accumulator, which costs
+//! two exponentiations and an extended GCD per witness per change. A `WitnessRefresh` instead
+//! collects changes over a window and applies them to every tracked witness in one pass, so each
+//! witness pays for one update per window, and the changed elements are hashed to primes once per
+//! window instead of once per witness.
+use crate::accumulator::{AccError, Accumulator, Witness};
+use crate::group::UnknownOrderGroup;
+use std::hash::Hash;
+use std::slice;
+
+/// Tracks witnesses for a set of elements and keeps them up to date with accumulator changes.
+pub trait WitnessRefresh<G: UnknownOrderGroup, T: Hash> {
+  /// Records a change to the accumulator: `additions` and `deletions` took it to `acc`. Tracked
+  /// witnesses may not reflect the change until the next `refresh`.
+  fn record(
+    &mut self,
+    acc: &Accumulator<G, T>,
+    additions: &[T],
+    deletions: &[T],
+  ) -> Result<(), AccError>;
+
+  /// Brings every tracked witness up to date with all recorded changes.
+  fn refresh(&mut self) -> Result<(), AccError>;
+
+  /// Returns the witness for `t` as of the last refresh, if `t` is tracked.
+  fn witness(&self, t: &T) -> Option<&Witness<G, T>>;
+}
+
+/// The default `WitnessRefresh`, which refreshes once every `window` recorded changes.
+pub struct BatchedWitnessRefresh<G: UnknownOrderGroup, T: Hash> {
+  acc: Accumulator<G, T>,
+  tracked: Vec<(T, Witness<G, T>)>,
+  window: usize,
+  pending_changes: usize,
+  pending_additions: Vec<T>,
+  pending_deletions: Vec<T>,
+}
+
+impl<G: UnknownOrderGroup, T: Clone + Eq + Hash> BatchedWitnessRefresh<G, T> {
+  /// Returns a refresher tracking no elements, starting from `acc`, that refreshes every `window`
+  /// recorded changes. Panics if `window` is 0.
+  pub fn new(acc: Accumulator<G, T>, window: usize) -> Self {
+    assert!(window > 0, "window must be positive");
+    Self {
+      acc,
+      tracked: Vec::new(),
+      window,
+      pending_changes: 0,
+      pending_additions: Vec::new(),
+      pending_deletions: Vec::new(),
+    }
+  }
+
+  /// Returns the most recently recorded accumulator.
+  pub fn accumulator(&self) -> &Accumulator<G, T> {
+    &self.acc
+  }
+
+  /// Returns the tracked elements and their witnesses as of the last refresh.
+  pub fn tracked(&self) -> &[(T, Witness<G, T>)] {
+    &self.tracked
+  }
+
+  /// Returns the number of changes recorded since the last refresh.
+  pub fn pending(&self) -> usize {
+    self.pending_changes
+  }
+
+  /// Starts tracking `t`, given its `witness` w.r.t. the most recently recorded accumulator.
+  /// Refreshes first, so that all witnesses are w.r.t. the same accumulator. Returns `BadWitness`
+  /// if `witness` is not a witness for `t`, and `DuplicateElement` if `t` is already tracked.
+  pub fn track(&mut self, t: T, witness: Witness<G, T>) -> Result<(), AccError> {
+    if self.tracked.iter().any(|(u, _)| *u == t) {
+      return Err(AccError::DuplicateElement);
+    }
+    self.refresh()?;
+    if witness.0.params() != self.acc.params() || witness.0.add_ref(slice::from_ref(&t)) != self.acc
+    {
+      return Err(AccError::BadWitness);
+    }
+    self.tracked.push((t, witness));
+    Ok(())
+  }
+
+  /// Stops tracking `t` and returns its witness as of the last refresh, if it was tracked.
+  pub fn untrack(&mut self, t: &T) -> Option<Witness<G, T>> {
+    let i = self.tracked.iter().position(|(u, _)| u == t)?;
+    Some(self.tracked.remove(i).1)
+  }
+}
+
+impl<G: UnknownOrderGroup, T: Clone + Eq + Hash> WitnessRefresh<G, T>
+  for BatchedWitnessRefresh<G, T>
+{
+  /// Refreshes once `window` changes are pending.
+  fn record(
+    &mut self,
+    acc: &Accumulator<G, T>,
+    additions: &[T],
+    deletions: &[T],
+  ) -> Result<(), AccError> {
+    self.acc = acc.clone();
+    self.pending_additions.extend_from_slice(additions);
+    self.pending_deletions.extend_from_slice(deletions);
+    self.pending_changes += 1;
+    if self.pending_changes >= self.window {
+      self.refresh()?;
+    }
+    Ok(())
+  }
+
+  /// Tracked elements that were deleted stop being tracked. On error, tracked witnesses and
+  /// pending changes are left as they were.
+  fn refresh(&mut self) -> Result<(), AccError> {
+    if self.pending_changes == 0 {
+      return Ok(());
+    }
+    let deletions = &self.pending_deletions;
+    let tracked = self
+      .tracked
+      .iter()
+      .filter(|(t, _)| !deletions.contains(t))
+      .cloned()
+      .collect::<Vec<_>>();
+    let witnesses =
+      self
+        .acc
+        .update_membership_witnesses(&tracked, &self.pending_additions, deletions)?;
+    self.tracked = tracked
+      .into_iter()
+      .zip(witnesses)
+      .map(|((t, _), witness)| (t, witness))
+      .collect();
+    self.pending_changes = 0;
+    self.pending_additions.clear();
+    self.pending_deletions.clear();
+    Ok(())
+  }
+
+  fn witness(&self, t: &T) -> Option<&Witness<G, T>> {
+    self
+      .tracked
+      .iter()
+      .find(|(u, _)| u == t)
+      .map(|(_, witness)| witness)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::group::Rsa2048;
+
+  fn is_witness(
+    acc: &Accumulator<Rsa2048, &'static str>,
+    t: &'static str,
+    w: &Witness<Rsa2048, &'static str>,
+  ) -> bool {
+    w.0.clone().add(&[t]) == *acc
+  }
+
+  #[test]
+  fn test_batched_refresh() {
+    let acc = Accumulator::<Rsa2048, &'static str>::empty().add(&["a", "b", "c"]);
+    let mut refresh = BatchedWitnessRefresh::new(acc.clone(), 2);
+    let witness = |elems| Witness(Accumulator::empty().add(elems));
+    refresh.track("a", witness(&["b", "c"])).unwrap();
+    refresh.track("b", witness(&["a", "c"])).unwrap();
+    assert!(refresh.track("b", witness(&["a", "c"])).is_err());
+    assert!(refresh.track("d", Witness(acc.clone())).is_err());
+
+    let acc_1 = acc.clone().add(&["d"]);
+    refresh.record(&acc_1, &["d"], &[]).unwrap();
+    assert_eq!(refresh.pending(), 1);
+    assert!(is_witness(&acc, "a", refresh.witness(&"a").unwrap()));
+
+    let acc_2 = Accumulator::empty().add(&["a", "b", "d"]);
+    refresh.record(&acc_2, &[], &["c"]).unwrap();
+    assert_eq!(refresh.pending(), 0);
+    assert!(is_witness(&acc_2, "a", refresh.witness(&"a").unwrap()));
+    assert!(is_witness(&acc_2, "b", refresh.witness(&"b").unwrap()));
+
+    let acc_3 = Accumulator::empty().add(&["a", "d"]);
+    refresh.record(&acc_3, &[], &["b"]).unwrap();
+    refresh.refresh().unwrap();
+    assert!(refresh.witness(&"b").is_none());
+    assert!(is_witness(&acc_3, "a", &refresh.untrack(&"a").unwrap()));
+    assert!(refresh.tracked().is_empty());
+  }
+}
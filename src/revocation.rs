@@ -0,0 +1,324 @@
+//! A revocation registry for credentials, built on an accumulator of unrevoked credential IDs.
+//!
+//! The issuer runs a `RevocationRegistry`. Issuing a credential accumulates its ID and hands the
+//! holder a `Credential` containing a membership witness; revoking it removes the ID. Every
+//! issuance and revocation is logged as a `RegistryUpdate`, which the issuer publishes so that
+//! holders can bring their witnesses up to date without contacting the issuer. Verifiers only need
+//! the current accumulator. To push updates instead, register an `UpdateListener`.
+use crate::accumulator::{AccError, Accumulator, ExponentState, Witness};
+#[cfg(feature = "audit")]
+use crate::audit::AuditLog;
+use crate::collision::{CollisionPolicy, PrimeIndex};
+use crate::group::UnknownOrderGroup;
+use crate::hash::{PrimeBits, PrimeParams};
+use crate::listener::{UpdateListener, UpdateListeners};
+use std::collections::HashSet;
+use std::hash::Hash;
+use std::slice;
+
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+/// A change to a `RevocationRegistry`, to be published to credential holders.
+pub enum RegistryUpdate<T> {
+  /// A credential with this ID was issued.
+  Issued(T),
+  /// The credential with this ID was revoked.
+  Revoked(T),
+}
+
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+/// A credential held by its subject: its ID, and a witness that the ID is unrevoked as of `epoch`.
+pub struct Credential<G: UnknownOrderGroup, T: Hash> {
+  /// The credential ID.
+  pub id: T,
+  /// A witness for `id` w.r.t. the registry's accumulator at `epoch`.
+  pub witness: Witness<G, T>,
+  /// The number of registry updates the witness reflects.
+  pub epoch: usize,
+}
+
+impl<G: UnknownOrderGroup, T: Clone + Eq + Hash> Credential<G, T> {
+  /// Brings the witness up to date with `updates`, the registry updates published since this
+  /// credential's epoch, given `acc`, the registry's accumulator after them.
+  ///
+  /// Returns `BadWitnessUpdate` if the credential was revoked, in which case it is left unchanged.
+  pub fn update(
+    &mut self,
+    acc: &Accumulator<G, T>,
+    updates: &[RegistryUpdate<T>],
+  ) -> Result<(), AccError> {
+    let mut issued = vec![];
+    let mut revoked = vec![];
+    for update in updates {
+      match update {
+        RegistryUpdate::Issued(id) => issued.push(id.clone()),
+        RegistryUpdate::Revoked(id) => revoked.push(id.clone()),
+      }
+    }
+    self.witness = acc.update_membership_witness(
+      self.witness.clone(),
+      slice::from_ref(&self.id),
+      &issued,
+      &revoked,
+    )?;
+    self.epoch += updates.len();
+    Ok(())
+  }
+}
+
+/// The issuer's view of a revocation registry: the accumulator of unrevoked credential IDs, the
+/// full set of those IDs, and the log of updates.
+pub struct RevocationRegistry<G: UnknownOrderGroup, T: Hash> {
+  acc: Accumulator<G, T>,
+  state: ExponentState<G, T>,
+  ids: HashSet<T>,
+  log: Vec<RegistryUpdate<T>>,
+  prime_index: Option<PrimeIndex<T>>,
+  listeners: UpdateListeners<G>,
+  #[cfg(feature = "audit")]
+  audit_log: AuditLog<G>,
+}
+
+impl<G: UnknownOrderGroup, T: Clone + Eq + Hash> RevocationRegistry<G, T> {
+  /// Returns an empty registry. IDs are hashed to 256-bit primes.
+  pub fn new() -> Self {
+    Self::with_params(PrimeBits::B256)
+  }
+
+  /// Returns an empty registry whose IDs are mapped to primes according to `params`.
+  pub fn with_params<P: Into<PrimeParams>>(params: P) -> Self {
+    let params = params.into();
+    Self {
+      acc: Accumulator::with_params(params),
+      state: ExponentState::with_params(params),
+      ids: HashSet::new(),
+      log: vec![],
+      prime_index: None,
+      listeners: UpdateListeners::new(),
+      #[cfg(feature = "audit")]
+      audit_log: AuditLog::new(),
+    }
+  }
+
+  /// Returns the current accumulator, which verifiers check credentials against.
+  pub fn accumulator(&self) -> &Accumulator<G, T> {
+    &self.acc
+  }
+
+  /// Returns the audit log of every change to the accumulator, to be replayed from an empty
+  /// accumulator.
+  #[cfg(feature = "audit")]
+  pub fn audit_log(&self) -> &AuditLog<G> {
+    &self.audit_log
+  }
+
+  /// Starts checking issued IDs for prime collisions with the IDs already issued, and handling
+  /// them according to `policy`. Fails with `PrimeCollision` under `CollisionPolicy::Error` if
+  /// issued IDs already collide. See `PrimeIndex`.
+  pub fn set_collision_policy(&mut self, policy: CollisionPolicy) -> Result<(), AccError> {
+    let mut index = PrimeIndex::new(self.acc.params(), policy);
+    index.update(&self.ids.iter().cloned().collect::<Vec<_>>(), &[])?;
+    self.prime_index = Some(index);
+    Ok(())
+  }
+
+  /// Returns the prime index, if collisions are being checked.
+  pub fn prime_index(&self) -> Option<&PrimeIndex<T>> {
+    self.prime_index.as_ref()
+  }
+
+  /// Registers `listener` to be called after every issuance and revocation from now on.
+  pub fn add_listener<L: UpdateListener<G> + 'static>(&mut self, listener: L) {
+    self.listeners.push(listener);
+  }
+
+  /// Returns the number of updates made to the registry so far.
+  pub fn epoch(&self) -> usize {
+    self.log.len()
+  }
+
+  /// Returns the updates made since `epoch`, for holders of credentials from that epoch.
+  pub fn updates_since(&self, epoch: usize) -> &[RegistryUpdate<T>] {
+    &self.log[epoch.min(self.log.len())..]
+  }
+
+  /// Issues a credential for `id`. Returns `DuplicateElement` if `id` is already issued and
+  /// unrevoked, and `PrimeCollision` if it collides with another ID under the collision policy.
+  pub fn issue(&mut self, id: T) -> Result<Credential<G, T>, AccError> {
+    if self.ids.contains(&id) {
+      return Err(AccError::DuplicateElement);
+    }
+    if let Some(index) = &mut self.prime_index {
+      index.update(slice::from_ref(&id), &[])?;
+    }
+    // The accumulator before adding `id` is a witness for it.
+    let witness = Witness(self.acc.clone());
+    self.acc = self.acc.add_ref(slice::from_ref(&id));
+    #[cfg(feature = "audit")]
+    self
+      .audit_log
+      .record_add(&witness.0, slice::from_ref(&id), &self.acc);
+    self.state.add(slice::from_ref(&id));
+    self.ids.insert(id.clone());
+    self.log.push(RegistryUpdate::Issued(id.clone()));
+    self.listeners.notify(
+      self.acc.params(),
+      slice::from_ref(&id),
+      &[],
+      self.acc.value(),
+    );
+    Ok(Credential {
+      id,
+      witness,
+      epoch: self.epoch(),
+    })
+  }
+
+  /// Revokes the credential for `id`. Returns `BadWitness` if `id` is not issued and unrevoked.
+  pub fn revoke(&mut self, id: &T) -> Result<(), AccError> {
+    if !self.ids.contains(id) {
+      return Err(AccError::BadWitness);
+    }
+    let acc = self
+      .acc
+      .delete_with_state_ref(&mut self.state, slice::from_ref(id))?;
+    #[cfg(feature = "audit")]
+    self
+      .audit_log
+      .record_delete(&self.acc, slice::from_ref(id), &acc);
+    if let Some(index) = &mut self.prime_index {
+      index.update(&[], slice::from_ref(id))?;
+    }
+    self.acc = acc;
+    self.ids.remove(id);
+    self.log.push(RegistryUpdate::Revoked(id.clone()));
+    self.listeners.notify(
+      self.acc.params(),
+      &[],
+      slice::from_ref(id),
+      self.acc.value(),
+    );
+    Ok(())
+  }
+
+  /// Checks that `credential` is unrevoked, i.e. that its witness is valid for the current
+  /// accumulator. Credentials from earlier epochs must be updated first.
+  pub fn verify(&self, credential: &Credential<G, T>) -> bool {
+    let witness = &credential.witness.0;
+    witness.params() == self.acc.params()
+      && G::exp(
+        witness.value(),
+        &self.acc.params().hash_to_prime(&credential.id),
+      ) == *self.acc.value()
+  }
+}
+
+impl<G: UnknownOrderGroup, T: Clone + Eq + Hash> Default for RevocationRegistry<G, T> {
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::group::{Group, Rsa2048};
+  use crate::integer::Integer;
+  use std::sync::{Arc, Mutex};
+
+  #[test]
+  fn test_issue_revoke() {
+    let mut registry = RevocationRegistry::<Rsa2048, &'static str>::new();
+    let mut alice = registry.issue("alice").unwrap();
+    assert!(registry.verify(&alice));
+    let mut bob = registry.issue("bob").unwrap();
+    let carol = registry.issue("carol").unwrap();
+    assert!(registry.issue("bob").is_err());
+    assert!(!registry.verify(&alice));
+
+    registry.revoke(&"carol").unwrap();
+    assert!(registry.revoke(&"carol").is_err());
+    assert!(!registry.verify(&carol));
+
+    alice
+      .update(registry.accumulator(), registry.updates_since(alice.epoch))
+      .unwrap();
+    assert!(registry.verify(&alice));
+    assert_eq!(alice.epoch, registry.epoch());
+
+    registry.revoke(&"bob").unwrap();
+    assert!(bob
+      .update(registry.accumulator(), registry.updates_since(bob.epoch))
+      .is_err());
+    assert!(!registry.verify(&bob));
+  }
+
+  #[test]
+  fn test_update_reissued() {
+    let mut registry = RevocationRegistry::<Rsa2048, &'static str>::new();
+    let mut alice = registry.issue("alice").unwrap();
+    registry.issue("bob").unwrap();
+    registry.revoke(&"bob").unwrap();
+    registry.issue("bob").unwrap();
+    registry.issue("carol").unwrap();
+    alice
+      .update(registry.accumulator(), registry.updates_since(alice.epoch))
+      .unwrap();
+    assert!(registry.verify(&alice));
+  }
+
+  #[test]
+  fn test_update_listener() {
+    let events = Arc::new(Mutex::new(vec![]));
+    let mut registry = RevocationRegistry::<Rsa2048, &'static str>::new();
+    registry.issue("alice").unwrap();
+    let sink = Arc::clone(&events);
+    registry.add_listener(
+      move |added: &[Integer], deleted: &[Integer], root: &<Rsa2048 as Group>::Elem| {
+        sink
+          .lock()
+          .unwrap()
+          .push((added.to_vec(), deleted.to_vec(), root.clone()))
+      },
+    );
+    registry.issue("bob").unwrap();
+    let after_issue = registry.accumulator().clone();
+    registry.revoke(&"alice").unwrap();
+
+    let prime = |id| registry.accumulator().params().hash_to_prime(id);
+    let events = events.lock().unwrap();
+    assert_eq!(events.len(), 2);
+    assert_eq!(events[0].0, vec![prime(&"bob")]);
+    assert!(events[0].1.is_empty() && events[0].2 == *after_issue.value());
+    assert!(events[1].0.is_empty());
+    assert_eq!(events[1].1, vec![prime(&"alice")]);
+    assert!(events[1].2 == *registry.accumulator().value());
+  }
+
+  #[test]
+  fn test_collision_policy() {
+    let mut registry = RevocationRegistry::<Rsa2048, &'static str>::new();
+    assert!(registry.prime_index().is_none());
+    registry.issue("alice").unwrap();
+    registry
+      .set_collision_policy(CollisionPolicy::Error)
+      .unwrap();
+    registry.issue("bob").unwrap();
+    registry.revoke(&"alice").unwrap();
+    let index = registry.prime_index().unwrap();
+    assert_eq!(index.len(), 1);
+    assert_eq!(index.collisions(), 0);
+  }
+
+  #[cfg(feature = "audit")]
+  #[test]
+  fn test_audit_log() {
+    let mut registry = RevocationRegistry::<Rsa2048, &'static str>::new();
+    registry.issue("alice").unwrap();
+    registry.issue("bob").unwrap();
+    registry.revoke(&"alice").unwrap();
+    let log = registry.audit_log();
+    assert_eq!(log.records().len(), 3);
+    let genesis = Accumulator::<Rsa2048, &'static str>::empty();
+    assert!(log.replay(genesis.value()).unwrap() == *registry.accumulator().value());
+  }
+}
@@ -0,0 +1,120 @@
+//! Salted accumulation, for accumulators that should not reveal their elements.
+//!
+//! A public accumulator lets anyone test whether a guessed element is a member, given a membership
+//! proof for it. When elements are guessable (e.g. the identities on an allowlist), accumulate
+//! `Salted` elements instead: each is hashed to a prime together with a secret salt chosen by its
+//! holder, who keeps the salt alongside their witness. Membership can then only be proven or
+//! checked by someone the holder has shared the salt with.
+//!
+//! With the `zeroize` feature, salts are wiped from memory when dropped. Only the `Salt` holding
+//! them implements `Drop`, so `Salted` elements and `SaltedWitness`es can still be destructured.
+use crate::accumulator::{AccError, Accumulator, AccumulatorElement, MembershipProof, Witness};
+use crate::group::UnknownOrderGroup;
+#[cfg(feature = "zeroize")]
+use crate::util::zeroize_bytes;
+use std::hash::Hash;
+
+/// The size of salts in bytes.
+pub const SALT_BYTES: usize = 32;
+
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+/// A salt. Hashes as its bytes, and is wiped from memory when dropped with the `zeroize` feature.
+pub struct Salt(pub [u8; SALT_BYTES]);
+
+#[cfg(feature = "zeroize")]
+impl Drop for Salt {
+  fn drop(&mut self) {
+    zeroize_bytes(&mut self.0);
+  }
+}
+
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+/// An element blinded with a salt. Accumulated as `hash_to_prime((elem, salt))`.
+///
+/// Salts should be drawn uniformly at random and kept secret; anyone who knows an element's salt
+/// can test its membership.
+pub struct Salted<T> {
+  /// The underlying element.
+  pub elem: T,
+  /// The salt blinding `elem`.
+  pub salt: Salt,
+}
+
+impl<T> Salted<T> {
+  /// Blinds `elem` with `salt`.
+  pub fn new(elem: T, salt: [u8; SALT_BYTES]) -> Self {
+    Self {
+      elem,
+      salt: Salt(salt),
+    }
+  }
+}
+
+impl<T: AccumulatorElement> AccumulatorElement for Salted<T> {}
+
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+/// A witness for a salted element, carrying the salt needed to use it.
+pub struct SaltedWitness<G: UnknownOrderGroup, T: Hash> {
+  /// The salt the element was accumulated with.
+  pub salt: Salt,
+  /// The witness for the salted element.
+  pub witness: Witness<G, Salted<T>>,
+}
+
+impl<G: UnknownOrderGroup, T: Clone + Eq + Hash> Accumulator<G, Salted<T>> {
+  /// Proves that `elem`, salted with the salt in `witness`, is in the accumulator. The proof only
+  /// verifies given the same salt, so it reveals `elem` only to whoever the salt is shared with.
+  pub fn prove_salted_membership(
+    &self,
+    elem: &T,
+    witness: &SaltedWitness<G, T>,
+  ) -> Result<MembershipProof<G, Salted<T>>, AccError> {
+    let salted = Salted::new(elem.clone(), witness.salt.0);
+    self.prove_membership(&[(salted, witness.witness.clone())])
+  }
+
+  /// Verifies a proof made with `prove_salted_membership` for `elem` salted with `salt`.
+  pub fn verify_salted_membership(
+    &self,
+    elem: &T,
+    salt: &[u8; SALT_BYTES],
+    proof: &MembershipProof<G, Salted<T>>,
+  ) -> bool {
+    self.verify_membership(&Salted::new(elem.clone(), *salt), proof)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::group::Rsa2048;
+
+  #[test]
+  fn test_salted_membership() {
+    let a = Salted::new("a", [1; SALT_BYTES]);
+    let b = Salted::new("b", [2; SALT_BYTES]);
+    let acc = Accumulator::<Rsa2048, Salted<&'static str>>::empty().add(&[a.clone(), b.clone()]);
+    let witness = SaltedWitness {
+      salt: a.salt.clone(),
+      witness: Witness(Accumulator::empty().add(&[b])),
+    };
+    let proof = acc.prove_salted_membership(&"a", &witness).unwrap();
+    assert!(acc.verify_salted_membership(&"a", &a.salt.0, &proof));
+    assert!(!acc.verify_salted_membership(&"a", &[0; SALT_BYTES], &proof));
+    assert!(!acc.verify_salted_membership(&"b", &a.salt.0, &proof));
+  }
+
+  #[test]
+  fn test_salted_membership_bad_salt() {
+    let a = Salted::new("a", [1; SALT_BYTES]);
+    let acc = Accumulator::<Rsa2048, Salted<&'static str>>::empty().add(&[a]);
+    let witness = SaltedWitness {
+      salt: Salt([0; SALT_BYTES]),
+      witness: Witness(Accumulator::empty()),
+    };
+    assert!(acc.prove_salted_membership(&"a", &witness).is_err());
+    // Fields move out whether or not the salt is wiped on drop.
+    let SaltedWitness { salt, .. } = witness;
+    assert!(salt == Salt([0; SALT_BYTES]));
+  }
+}
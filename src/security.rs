@@ -0,0 +1,112 @@
+//! Security levels, as marker types fixing every size that determines soundness at once.
+//!
+//! Element primes need `2λ` bits for `λ`-bit collision resistance, NI-PoE and NI-PoKE2 challenge
+//! primes need `2λ` bits against grinding the Fiat-Shamir hash, and primality checks of prover
+//! supplied primes need `λ / 2` Miller-Rabin rounds for an error of at most `2^(-λ)`. Choosing
+//! these separately makes it easy to weaken one without noticing, e.g. by pairing 128-bit primes
+//! with a 128-bit level. A `SecurityLevel` fixes all three, and is passed where those sizes matter:
+//!
+//! - `Accumulator::with_params(Security112)` maps elements to primes of `S::PRIME_BITS` bits, and
+//!   `Accumulator::with_security::<Security112, _>(params)` rejects `params` with shorter primes.
+//! - `Poe`, `Poke2` and `Pokcr` have `prove_with_security` and `verify_with_security` variants.
+//!
+//! `Security128` is what the rest of the crate uses by default, so its proofs are exactly those of
+//! the plain `prove` functions. Accumulator proofs always use its challenges, which are never
+//! weaker than those of a lower level. The trait is sealed, since a level is only as strong as its
+//! weakest size. Note that the RSA-2048 group itself only offers about 112-bit security.
+use crate::hash::{PrimeBits, PrimeParams};
+use std::fmt::Debug;
+use std::hash::Hash;
+
+mod private {
+  pub trait Sealed {}
+}
+
+/// A security level. See the module docs.
+pub trait SecurityLevel: private::Sealed + Clone + Copy + Debug + Eq + Hash {
+  /// The security level in bits, `λ`.
+  const BITS: u32;
+  /// The size of the primes elements are mapped to.
+  const PRIME_BITS: PrimeBits;
+  /// The size of NI-PoE and NI-PoKE2 challenge primes.
+  const CHALLENGE_BITS: PrimeBits;
+  /// The number of Miller-Rabin rounds when checking that prover-supplied integers are prime.
+  const MR_ROUNDS: u32;
+}
+
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+/// 128-bit security, the default: 256-bit primes and challenges and 64 Miller-Rabin rounds.
+pub struct Security128;
+
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+/// 112-bit security: 224-bit primes and challenges and 56 Miller-Rabin rounds.
+pub struct Security112;
+
+macro_rules! security_level {
+  ($level:ident, $bits:expr, $prime_bits:ident) => {
+    impl private::Sealed for $level {}
+
+    impl SecurityLevel for $level {
+      const BITS: u32 = $bits;
+      const PRIME_BITS: PrimeBits = PrimeBits::$prime_bits;
+      const CHALLENGE_BITS: PrimeBits = PrimeBits::$prime_bits;
+      const MR_ROUNDS: u32 = $bits / 2;
+    }
+
+    impl From<$level> for PrimeParams {
+      fn from(_: $level) -> Self {
+        PrimeParams::from(<$level as SecurityLevel>::PRIME_BITS)
+      }
+    }
+  };
+}
+
+security_level!(Security128, 128, B256);
+security_level!(Security112, 112, B224);
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::accumulator::{AccError, Accumulator};
+  use crate::group::Rsa2048;
+
+  fn check_level<S: SecurityLevel>() {
+    assert_eq!(S::PRIME_BITS.bits(), 2 * S::BITS);
+    assert_eq!(S::CHALLENGE_BITS.bits(), 2 * S::BITS);
+    assert_eq!(2 * S::MR_ROUNDS, S::BITS);
+  }
+
+  #[test]
+  fn test_security_levels() {
+    check_level::<Security128>();
+    check_level::<Security112>();
+    assert_eq!(PrimeParams::from(Security128), PrimeBits::B256.into());
+    assert_eq!(PrimeParams::from(Security112), PrimeBits::B224.into());
+  }
+
+  #[test]
+  fn test_accumulator_security() {
+    let (acc, proof) =
+      Accumulator::<Rsa2048, &'static str>::with_params(Security112).add_with_proof(&["a", "b"]);
+    assert!(acc.prime_bits() == Security112::PRIME_BITS);
+    assert!(acc.verify_membership_batch(&["a", "b"], &proof));
+    assert!(Accumulator::<Rsa2048, &'static str>::with_params(Security128) == Accumulator::empty());
+
+    type Acc = Accumulator<Rsa2048, &'static str>;
+    assert!(matches!(
+      Acc::with_security::<Security112, _>(PrimeBits::B128),
+      Err(AccError::WeakParams)
+    ));
+    assert!(matches!(
+      Acc::with_security::<Security128, _>(Security112),
+      Err(AccError::WeakParams)
+    ));
+    assert!(
+      Acc::with_security::<Security112, _>(PrimeBits::B224)
+        .unwrap()
+        .prime_bits()
+        == PrimeBits::B224
+    );
+    assert!(Acc::with_security::<Security128, _>(PrimeBits::B384).is_ok());
+  }
+}
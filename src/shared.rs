@@ -0,0 +1,139 @@
+//! Accumulators shared between threads, e.g. by a service verifying proofs while an updater thread
+//! advances the state.
+//!
+//! A `SharedAccumulator` is an immutable snapshot behind an `Arc`, so cloning one for each request
+//! costs a reference count increment. Updates are copy-on-write: they compute the new accumulator
+//! and replace the `Arc` in the updated handle only, so clones taken earlier keep verifying against
+//! the state they were taken at. Publishing new snapshots to readers is left to the service, e.g.
+//! by swapping a `SharedAccumulator` behind a lock that is held only for the clone or the swap.
+use crate::accumulator::{AccError, Accumulator, MembershipProof, Witness};
+use crate::group::UnknownOrderGroup;
+use std::hash::Hash;
+use std::ops::Deref;
+use std::sync::Arc;
+
+#[derive(Debug)]
+/// A cheaply cloneable, immutable accumulator snapshot. See the module docs.
+///
+/// Dereferences to the accumulator, so verification methods can be called on it directly.
+pub struct SharedAccumulator<G: UnknownOrderGroup, T: Hash>(Arc<Accumulator<G, T>>);
+
+// Manual clone impl, since the derived one would require `G: Clone` and `T: Clone`.
+impl<G: UnknownOrderGroup, T: Hash> Clone for SharedAccumulator<G, T> {
+  fn clone(&self) -> Self {
+    Self(Arc::clone(&self.0))
+  }
+}
+
+impl<G: UnknownOrderGroup, T: Hash> Deref for SharedAccumulator<G, T> {
+  type Target = Accumulator<G, T>;
+
+  fn deref(&self) -> &Accumulator<G, T> {
+    &self.0
+  }
+}
+
+impl<G: UnknownOrderGroup, T: Hash> From<Accumulator<G, T>> for SharedAccumulator<G, T> {
+  fn from(acc: Accumulator<G, T>) -> Self {
+    Self(Arc::new(acc))
+  }
+}
+
+impl<G: UnknownOrderGroup, T: Eq + Hash> SharedAccumulator<G, T> {
+  /// Returns a shared snapshot of `acc`.
+  pub fn new(acc: Accumulator<G, T>) -> Self {
+    Self::from(acc)
+  }
+
+  /// Returns whether `a` and `b` are clones of the same snapshot.
+  pub fn ptr_eq(a: &Self, b: &Self) -> bool {
+    Arc::ptr_eq(&a.0, &b.0)
+  }
+
+  /// Returns the accumulator, cloning it only if other snapshots share it.
+  pub fn into_inner(self) -> Accumulator<G, T> {
+    Arc::try_unwrap(self.0).unwrap_or_else(|acc| (*acc).clone())
+  }
+
+  /// Adds `elems` to this handle's accumulator. Other clones are unaffected.
+  pub fn add(&mut self, elems: &[T]) {
+    self.0 = Arc::new(self.0.add_ref(elems));
+  }
+
+  /// Like `add`, but also returns a batch membership proof for the added elements.
+  pub fn add_with_proof(&mut self, elems: &[T]) -> MembershipProof<G, T> {
+    let (acc, proof) = (*self.0).clone().add_with_proof(elems);
+    self.0 = Arc::new(acc);
+    proof
+  }
+
+  /// Removes the elements in `elem_witnesses` from this handle's accumulator. Other clones are
+  /// unaffected, and this one is too if the deletion fails.
+  pub fn delete(&mut self, elem_witnesses: &[(T, Witness<G, T>)]) -> Result<(), AccError> {
+    self.0 = Arc::new(self.0.delete_ref(elem_witnesses)?);
+    Ok(())
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::group::Rsa2048;
+  use std::sync::Mutex;
+  use std::thread;
+
+  fn new_acc(elems: &[&'static str]) -> Accumulator<Rsa2048, &'static str> {
+    Accumulator::empty().add(elems)
+  }
+
+  #[test]
+  fn test_shared_accumulator() {
+    let mut shared = SharedAccumulator::new(new_acc(&["a", "b"]));
+    let snapshot = shared.clone();
+    assert!(SharedAccumulator::ptr_eq(&shared, &snapshot));
+
+    let proof = shared.add_with_proof(&["c"]);
+    assert!(!SharedAccumulator::ptr_eq(&shared, &snapshot));
+    assert!(shared.verify_membership(&"c", &proof));
+    assert!(!snapshot.verify_membership(&"c", &proof));
+    assert!(*snapshot == new_acc(&["a", "b"]));
+
+    assert!(shared.delete(&[("c", Witness(new_acc(&["b"])))]).is_err());
+    assert!(*shared == new_acc(&["a", "b", "c"]));
+    shared
+      .delete(&[("c", Witness(new_acc(&["a", "b"])))])
+      .unwrap();
+    assert!(shared.clone().into_inner() == new_acc(&["a", "b"]));
+  }
+
+  #[test]
+  fn test_shared_accumulator_threads() {
+    let current = Arc::new(Mutex::new(SharedAccumulator::new(new_acc(&["a"]))));
+    let proof = new_acc(&[]).add_with_proof(&["a"]).1;
+
+    let readers = (0..4)
+      .map(|_| {
+        let current = Arc::clone(&current);
+        let proof = proof.clone();
+        thread::spawn(move || {
+          // Readers see either state in full, and proofs keep verifying against the old one.
+          let snapshot = current.lock().unwrap().clone();
+          if *snapshot == new_acc(&["a"]) {
+            snapshot.verify_membership(&"a", &proof)
+          } else {
+            *snapshot == new_acc(&["a", "b"])
+          }
+        })
+      })
+      .collect::<Vec<_>>();
+
+    let mut updated = current.lock().unwrap().clone();
+    updated.add(&["b"]);
+    *current.lock().unwrap() = updated;
+
+    for reader in readers {
+      assert!(reader.join().unwrap());
+    }
+    assert!(**current.lock().unwrap() == new_acc(&["a", "b"]));
+  }
+}
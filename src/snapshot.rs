@@ -0,0 +1,204 @@
+//! Snapshots of an accumulator and its witnesses, for backing up and restoring nodes.
+//!
+//! A snapshot holds an accumulator, with its prime parameters, and optionally the witnesses an
+//! operator keeps for it, e.g. those from `ExponentState::compute_all_witnesses`. Witnesses are
+//! stored in the order given and restored in the same order; elements themselves are not stored,
+//! so operators keep track of which witness belongs to which element.
+//!
+//! `export_snapshot` ends the encoding with a Blake2b MAC keyed with an operator secret, and
+//! `import_snapshot` checks it before parsing anything else. A backup that was corrupted, or
+//! modified by anyone without the key, is rejected instead of restored.
+use crate::accumulator::{Accumulator, Witness};
+use crate::group::{ElemBytes, UnknownOrderGroup};
+use crate::proof::{DecodeError, Decoder};
+use blake2_rfc::blake2b::{Blake2b, Blake2bResult};
+use std::hash::Hash;
+use std::io::{self, Read, Write};
+
+/// The version of the snapshot encoding produced by `export_snapshot`.
+pub const SNAPSHOT_FORMAT_VERSION: u8 = 1;
+
+const MAC_BYTES: usize = 32;
+
+#[derive(Debug)]
+/// The ways exporting or importing a snapshot can fail.
+pub enum SnapshotError {
+  /// Reading or writing the snapshot failed.
+  Io(io::Error),
+  /// The MAC did not match the snapshot under the given key.
+  BadChecksum,
+  /// The snapshot had a valid MAC but was not a snapshot of this version and group.
+  BadEncoding,
+}
+
+impl From<io::Error> for SnapshotError {
+  fn from(e: io::Error) -> Self {
+    SnapshotError::Io(e)
+  }
+}
+
+#[derive(Debug, Eq, Hash, PartialEq)]
+/// An accumulator and, optionally, witnesses for it. See the module docs.
+pub struct Snapshot<G: UnknownOrderGroup, T: Hash> {
+  /// The accumulator.
+  pub acc: Accumulator<G, T>,
+  /// Witnesses w.r.t. `acc`, if any are attached.
+  pub witnesses: Option<Vec<Witness<G, T>>>,
+}
+
+// Manual clone impl for the same reason as `Accumulator`.
+impl<G: UnknownOrderGroup, T: Hash> Clone for Snapshot<G, T> {
+  fn clone(&self) -> Self {
+    Self {
+      acc: self.acc.clone(),
+      witnesses: self
+        .witnesses
+        .as_ref()
+        .map(|witnesses| witnesses.iter().map(|w| Witness(w.0.clone())).collect()),
+    }
+  }
+}
+
+impl<G: ElemBytes + UnknownOrderGroup, T: Eq + Hash> Snapshot<G, T> {
+  /// Returns a snapshot of `acc` without witnesses.
+  pub fn new(acc: Accumulator<G, T>) -> Self {
+    Self {
+      acc,
+      witnesses: None,
+    }
+  }
+
+  /// Attaches `witnesses` to the snapshot.
+  pub fn with_witnesses(self, witnesses: Vec<Witness<G, T>>) -> Self {
+    Self {
+      witnesses: Some(witnesses),
+      ..self
+    }
+  }
+
+  /// Writes the snapshot to `writer`: the format version, group ID and encoded accumulator, then a
+  /// flag byte that is 1 if witnesses are attached and 0 otherwise. Attached witnesses follow the
+  /// flag as a big-endian `u64` count and the encoded witnesses. Last comes a Blake2b MAC under
+  /// `key` of everything before it. Panics if `key` is longer than 64 bytes.
+  pub fn export_snapshot<W: Write>(&self, mut writer: W, key: &[u8]) -> Result<(), SnapshotError> {
+    let mut bytes = vec![SNAPSHOT_FORMAT_VERSION, G::GROUP_ID];
+    bytes.extend(self.acc.to_bytes());
+    match &self.witnesses {
+      None => bytes.push(0),
+      Some(witnesses) => {
+        bytes.push(1);
+        bytes.extend_from_slice(&(witnesses.len() as u64).to_be_bytes());
+        for witness in witnesses {
+          bytes.extend(witness.0.to_bytes());
+        }
+      }
+    }
+    bytes.extend_from_slice(mac(key, &bytes).as_bytes());
+    writer.write_all(&bytes)?;
+    Ok(writer.flush()?)
+  }
+
+  /// Reads `reader` to the end and parses the snapshot written by `export_snapshot` under `key`.
+  /// Fails with `BadChecksum` if the MAC does not match, before parsing anything else. Panics if
+  /// `key` is longer than 64 bytes.
+  pub fn import_snapshot<R: Read>(mut reader: R, key: &[u8]) -> Result<Self, SnapshotError> {
+    let mut bytes = Vec::new();
+    reader.read_to_end(&mut bytes)?;
+    if bytes.len() < MAC_BYTES {
+      return Err(SnapshotError::BadChecksum);
+    }
+    let (bytes, expected_mac) = bytes.split_at(bytes.len() - MAC_BYTES);
+    // `Blake2bResult` compares in constant time.
+    if mac(key, bytes) != *expected_mac {
+      return Err(SnapshotError::BadChecksum);
+    }
+    Decoder::decode_all(bytes, |decoder| {
+      if decoder.byte()? != SNAPSHOT_FORMAT_VERSION || decoder.byte()? != G::GROUP_ID {
+        return Err(DecodeError::BadTag);
+      }
+      let acc = Accumulator::decode(decoder)?;
+      let witnesses = match decoder.byte()? {
+        0 => None,
+        1 => {
+          let n_witnesses = decoder.u64()?;
+          // Check the count against the input before allocating for it.
+          if n_witnesses > (decoder.remaining() / (G::elem_bytes_len() + 2)) as u64 {
+            return Err(DecodeError::Truncated);
+          }
+          let witnesses = (0..n_witnesses)
+            .map(|_| Accumulator::decode(decoder).map(Witness))
+            .collect::<Result<Vec<_>, _>>()?;
+          if witnesses.iter().any(|w| w.0.params() != acc.params()) {
+            return Err(DecodeError::BadTag);
+          }
+          Some(witnesses)
+        }
+        _ => return Err(DecodeError::BadTag),
+      };
+      Ok(Self { acc, witnesses })
+    })
+    .map_err(|_| SnapshotError::BadEncoding)
+  }
+}
+
+fn mac(key: &[u8], bytes: &[u8]) -> Blake2bResult {
+  let mut hasher = Blake2b::with_key(MAC_BYTES, key);
+  hasher.update(bytes);
+  hasher.finalize()
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::accumulator::ExponentState;
+  use crate::group::Rsa2048;
+
+  const KEY: &[u8] = b"backup key";
+
+  fn export(snapshot: &Snapshot<Rsa2048, &'static str>) -> Vec<u8> {
+    let mut bytes = Vec::new();
+    snapshot.export_snapshot(&mut bytes, KEY).unwrap();
+    bytes
+  }
+
+  #[test]
+  fn test_snapshot() {
+    let elems = ["a", "b", "c"];
+    let mut state = ExponentState::empty();
+    state.add(&elems);
+    let acc = Accumulator::<Rsa2048, &'static str>::empty().add(&elems);
+    let witnesses = state.compute_all_witnesses(&elems).unwrap();
+
+    let snapshot = Snapshot::new(acc.clone());
+    assert!(Snapshot::import_snapshot(&export(&snapshot)[..], KEY).unwrap() == snapshot);
+    let snapshot = snapshot.with_witnesses(witnesses.clone());
+    let restored = Snapshot::import_snapshot(&export(&snapshot)[..], KEY).unwrap();
+    assert!(restored == snapshot);
+
+    let restored_witnesses = restored.witnesses.unwrap();
+    assert!(restored_witnesses == witnesses);
+    let proof = restored
+      .acc
+      .prove_membership(&[("b", restored_witnesses[1].clone())])
+      .unwrap();
+    assert!(acc.verify_membership(&"b", &proof));
+  }
+
+  #[test]
+  fn test_snapshot_tampered() {
+    let acc = Accumulator::<Rsa2048, &'static str>::empty().add(&["a"]);
+    let bytes = export(&Snapshot::new(acc));
+    let import = |bytes: &[u8], key| Snapshot::<Rsa2048, &'static str>::import_snapshot(bytes, key);
+    match import(&bytes, b"other key") {
+      Err(SnapshotError::BadChecksum) => (),
+      _ => panic!("expected BadChecksum"),
+    }
+    for i in &[0, 1, bytes.len() / 2, bytes.len() - 1] {
+      let mut tampered = bytes.clone();
+      tampered[*i] ^= 1;
+      assert!(import(&tampered, KEY).is_err());
+    }
+    assert!(import(&bytes[1..], KEY).is_err());
+    assert!(import(&[], KEY).is_err());
+  }
+}
@@ -0,0 +1,123 @@
+//! Split witnesses, for wallets that can only store a constant amount of witness data.
+//!
+//! A single witness `w` for all of a wallet's elements, with `w^x = acc` for the product `x` of
+//! their primes, takes constant space, but proving a subset of the elements needs `w^(x / x')`
+//! for the product `x'` of the subset, i.e. the whole, linear-size exponent `x`. `split_witness`
+//! splits such a witness into a `ClientWitness` (the witness and an NI-PoKE2 that the exponent
+//! known at the split takes it to the accumulator, so the client can check its witness is current
+//! without the exponent) and a `WitnessServer` (the elements). To prove membership, the client
+//! sends its witness to the server, which derives the subset's witness with the large
+//! exponentiation, and the client checks the result and computes the NI-PoE itself, which only
+//! costs exponentiations by the subset.
+use crate::accumulator::{AccError, Accumulator, MembershipProof, MembershipProofRef, Witness};
+use crate::group::UnknownOrderGroup;
+use crate::proof::{Poe, Poke2};
+use std::hash::Hash;
+
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+/// The constant-size, client-held part of a split witness. See the module docs.
+pub struct ClientWitness<G: UnknownOrderGroup, T: Hash> {
+  witness: Witness<G, T>,
+  proof: Poke2<G>,
+}
+
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+/// The server-held part of a split witness: the elements the witness is for. See the module docs.
+pub struct WitnessServer<T> {
+  elems: Vec<T>,
+}
+
+/// Splits `witness`, a witness for all of `elems` w.r.t. `acc`, into a client part and a server
+/// part. Returns `BadWitness` if it is not a witness for `elems`.
+pub fn split_witness<G: UnknownOrderGroup, T: Eq + Hash>(
+  acc: &Accumulator<G, T>,
+  witness: Witness<G, T>,
+  elems: Vec<T>,
+) -> Result<(ClientWitness<G, T>, WitnessServer<T>), AccError> {
+  let x = acc.params().prime_hash_product(&elems);
+  if witness.0.params() != acc.params() || G::exp(witness.0.value(), &x) != *acc.value() {
+    return Err(AccError::BadWitness);
+  }
+  let proof = Poke2::prove(witness.0.value(), &x, acc.value());
+  Ok((ClientWitness { witness, proof }, WitnessServer { elems }))
+}
+
+impl<G: UnknownOrderGroup, T: Eq + Hash> ClientWitness<G, T> {
+  /// Returns the witness, to send to the server with each request.
+  pub fn witness(&self) -> &Witness<G, T> {
+    &self.witness
+  }
+
+  /// Verifies that the exponent known at the split takes the witness to `acc`, i.e. that the
+  /// witness is current.
+  pub fn verify(&self, acc: &Accumulator<G, T>) -> bool {
+    self.witness.0.params() == acc.params()
+      && Poke2::verify(self.witness.0.value(), acc.value(), &self.proof)
+  }
+
+  /// Proves that `elems` are in `acc`, given `derived`, the witness for them the server derived
+  /// from this one. Returns `BadWitness` if `derived` is not a witness for `elems`.
+  pub fn prove_membership(
+    &self,
+    acc: &Accumulator<G, T>,
+    elems: &[T],
+    derived: &Witness<G, T>,
+  ) -> Result<MembershipProof<G, T>, AccError> {
+    let x = acc.params().prime_hash_product(elems);
+    if derived.0.params() != acc.params() || G::exp(derived.0.value(), &x) != *acc.value() {
+      return Err(AccError::BadWitness);
+    }
+    let proof = Poe::prove(derived.0.value(), &x, acc.value());
+    Ok(MembershipProofRef::from_parts(derived, &proof, None).into_owned())
+  }
+}
+
+impl<T: Clone + Eq + Hash> WitnessServer<T> {
+  /// Returns the elements the split witness is for.
+  pub fn elems(&self) -> &[T] {
+    &self.elems
+  }
+
+  /// Derives a witness for `elems`, which must be among this server's elements, from `witness`,
+  /// the client's witness for all of them. See `Witness::compute_subset_witness`.
+  pub fn derive<G: UnknownOrderGroup>(
+    &self,
+    witness: &Witness<G, T>,
+    elems: &[T],
+  ) -> Result<Witness<G, T>, AccError> {
+    witness.clone().compute_subset_witness(&self.elems, elems)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::group::Rsa2048;
+
+  fn new_acc(elems: &[&'static str]) -> Accumulator<Rsa2048, &'static str> {
+    Accumulator::empty().add(elems)
+  }
+
+  #[test]
+  fn test_split_witness() {
+    let acc = new_acc(&["a", "b", "c", "d"]);
+    let wallet = vec!["a", "b", "c"];
+    let (client, server) = split_witness(&acc, Witness(new_acc(&["d"])), wallet.clone()).unwrap();
+    assert!(client.verify(&acc));
+    assert!(!client.verify(&acc.clone().add(&["e"])));
+
+    let derived = server.derive(client.witness(), &["b"]).unwrap();
+    let proof = client.prove_membership(&acc, &["b"], &derived).unwrap();
+    assert!(acc.verify_membership(&"b", &proof));
+    assert!(client.prove_membership(&acc, &["a"], &derived).is_err());
+
+    let derived = server.derive(client.witness(), &["a", "c"]).unwrap();
+    let proof = client
+      .prove_membership(&acc, &["a", "c"], &derived)
+      .unwrap();
+    assert!(acc.verify_membership_batch(&["a", "c"], &proof));
+
+    assert!(server.derive(client.witness(), &["d"]).is_err());
+    assert!(split_witness(&acc, Witness(new_acc(&["c"])), wallet).is_err());
+  }
+}
@@ -0,0 +1,236 @@
+//! Versioned accumulator state, for deployments that outlive their parameters.
+//!
+//! An `AccumulatorState` records everything needed to interpret an accumulator value: the group
+//! it lives in, how elements are mapped to primes, and the version of the application's element
+//! encoding (i.e. its `Hash` impl for `T`). These are summarized in a parameter digest, which
+//! applications can attach to proofs so that a proof made under old parameters is never checked
+//! against a state with new ones. Serialized proofs already carry a digest of everything but the
+//! element encoding version; see `proof_params_digest`. Upgrading parameters goes through
+//! `migrate`, which rebuilds the accumulator from its elements instead of reinterpreting the old
+//! value.
+use crate::accumulator::{AccError, Accumulator};
+use crate::group::{ElemBytes, UnknownOrderGroup};
+use crate::hash::{Blake2b, GeneralHasher, PrimeParams};
+use std::hash::{Hash, Hasher};
+
+/// The version of the `AccumulatorState` encoding produced by `to_bytes`.
+pub const STATE_FORMAT_VERSION: u8 = 1;
+
+// Format version, group ID, element encoding version, and parameter digest.
+const HEADER_BYTES: usize = 35;
+
+#[derive(Debug, Eq, Hash, PartialEq)]
+/// An accumulator together with the parameters it was built under. See the module docs.
+pub struct AccumulatorState<G: ElemBytes + UnknownOrderGroup, T> {
+  elem_version: u8,
+  acc: Accumulator<G, T>,
+}
+
+// Manual clone impl for the same reason as `Accumulator`.
+impl<G: ElemBytes + UnknownOrderGroup, T: Hash> Clone for AccumulatorState<G, T> {
+  fn clone(&self) -> Self {
+    Self {
+      elem_version: self.elem_version,
+      acc: self.acc.clone(),
+    }
+  }
+}
+
+impl<G: ElemBytes + UnknownOrderGroup, T: Eq + Hash> AccumulatorState<G, T> {
+  /// Wraps `acc`, whose elements are encoded according to the application-defined
+  /// `elem_version`.
+  pub fn new(acc: Accumulator<G, T>, elem_version: u8) -> Self {
+    Self { elem_version, acc }
+  }
+
+  /// Returns the ID of the group the accumulator lives in.
+  pub fn group_id(&self) -> u8 {
+    G::GROUP_ID
+  }
+
+  /// Returns the parameters elements are mapped to primes with.
+  pub fn params(&self) -> PrimeParams {
+    self.acc.params()
+  }
+
+  /// Returns the version of the application's element encoding.
+  pub fn elem_version(&self) -> u8 {
+    self.elem_version
+  }
+
+  /// Returns a Blake2b digest of the group ID and instance bytes (see `ElemBytes::instance_bytes`),
+  /// prime parameters, and element encoding version.
+  /// Two states share a digest exactly when their accumulators can be compared.
+  pub fn params_digest(&self) -> [u8; 32] {
+    params_digest::<G>(self.params(), self.elem_version)
+  }
+
+  /// Returns a Blake2b digest of the parameter digest and the encoded accumulator, for anchoring
+  /// the state in block headers or other hash chains. Equal digests mean equal accumulator values
+  /// under equal parameters, barring a Blake2b collision, since both inputs have fixed lengths and
+  /// the accumulator encoding is canonical.
+  pub fn digest(&self) -> [u8; 32] {
+    digest::<G>(self.params(), self.elem_version, &self.acc.to_bytes())
+  }
+
+  /// Returns the wrapped accumulator.
+  pub fn accumulator(&self) -> &Accumulator<G, T> {
+    &self.acc
+  }
+
+  /// Unwraps the accumulator.
+  pub fn into_accumulator(self) -> Accumulator<G, T> {
+    self.acc
+  }
+
+  /// Rebuilds the accumulator under new `params` and `elem_version` from `elems`, the full set it
+  /// currently holds. Each element is converted to its new representation with `convert`, which
+  /// may simply clone it if only the prime parameters change.
+  ///
+  /// Returns `BadMigration` if `elems` do not reproduce the current accumulator, so a migration
+  /// can never silently drop or invent elements. Accumulators made with
+  /// `Accumulator::from_initial_exponent` are never reproduced from their elements alone, so they
+  /// cannot be migrated.
+  pub fn migrate<U: Eq + Hash, F: Fn(&T) -> U, P: Into<PrimeParams>>(
+    &self,
+    elems: &[T],
+    params: P,
+    elem_version: u8,
+    convert: F,
+  ) -> Result<AccumulatorState<G, U>, AccError> {
+    if Accumulator::with_params(self.params()).add(elems) != self.acc {
+      return Err(AccError::BadMigration);
+    }
+    let new_elems = elems.iter().map(convert).collect::<Vec<_>>();
+    Ok(AccumulatorState {
+      elem_version,
+      acc: Accumulator::with_params(params).add(&new_elems),
+    })
+  }
+
+  /// Returns the canonical encoding of this state: the format version, group ID, element encoding
+  /// version, and parameter digest, followed by the encoded accumulator.
+  pub fn to_bytes(&self) -> Vec<u8> {
+    let mut bytes = vec![STATE_FORMAT_VERSION, G::GROUP_ID, self.elem_version];
+    bytes.extend_from_slice(&self.params_digest());
+    bytes.extend(self.acc.to_bytes());
+    bytes
+  }
+
+  /// Parses the canonical encoding produced by `to_bytes`. Fails with `BadEncoding` on unknown
+  /// format versions, state from other groups, and digests that do not match the parameters.
+  pub fn from_bytes(bytes: &[u8]) -> Result<Self, AccError> {
+    if bytes.len() < HEADER_BYTES || bytes[0] != STATE_FORMAT_VERSION || bytes[1] != G::GROUP_ID {
+      return Err(AccError::BadEncoding);
+    }
+    let elem_version = bytes[2];
+    let acc = Accumulator::from_bytes(&bytes[HEADER_BYTES..])?;
+    let state = Self { elem_version, acc };
+    if state.params_digest()[..] != bytes[3..HEADER_BYTES] {
+      return Err(AccError::BadEncoding);
+    }
+    Ok(state)
+  }
+}
+
+/// The digest of an accumulator of group `G` with `params`, encoded as `acc_bytes`, in a state
+/// with `elem_version`. See `AccumulatorState::digest`.
+pub(crate) fn digest<G: ElemBytes>(
+  params: PrimeParams,
+  elem_version: u8,
+  acc_bytes: &[u8],
+) -> [u8; 32] {
+  let mut hasher = Blake2b::default();
+  hasher.write(&params_digest::<G>(params, elem_version));
+  hasher.write(acc_bytes);
+  hasher.finalize()
+}
+
+/// Returns the digest that serialized membership and nonmembership proofs of group `G` under
+/// `params` begin with: a Blake2b hash of the group ID, the group's encoded unknown-order element,
+/// which fixes its modulus or discriminant, and the encoded `params`. Decoding checks it against
+/// the group decoded for, and `Accumulator::verify_membership_checked` and
+/// `verify_nonmembership_checked` check the parameters it covers against the accumulator's.
+pub fn proof_params_digest<G: ElemBytes + UnknownOrderGroup>(params: PrimeParams) -> [u8; 32] {
+  let mut hasher = Blake2b::default();
+  // Keeps proof digests apart from state digests, which start with a format version.
+  hasher.write(b"proof params");
+  hasher.write(&[G::GROUP_ID]);
+  hasher.write(&G::elem_to_bytes(&G::unknown_order_elem()));
+  hasher.write(&params.to_bytes());
+  hasher.finalize()
+}
+
+fn params_digest<G: ElemBytes>(params: PrimeParams, elem_version: u8) -> [u8; 32] {
+  let mut hasher = Blake2b::default();
+  hasher.write(&[STATE_FORMAT_VERSION, G::GROUP_ID, elem_version]);
+  hasher.write(&params.to_bytes());
+  hasher.write(&G::instance_bytes());
+  hasher.finalize()
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::group::Rsa2048;
+  use crate::hash::PrimeBits;
+
+  fn new_state(elems: &[&'static str]) -> AccumulatorState<Rsa2048, &'static str> {
+    AccumulatorState::new(Accumulator::empty().add(elems), 0)
+  }
+
+  #[test]
+  fn test_to_bytes() {
+    let state = new_state(&["a", "b"]);
+    let bytes = state.to_bytes();
+    assert!(AccumulatorState::from_bytes(&bytes).unwrap() == state);
+
+    let mut bad_version = bytes.clone();
+    bad_version[0] = STATE_FORMAT_VERSION + 1;
+    assert!(AccumulatorState::<Rsa2048, &'static str>::from_bytes(&bad_version).is_err());
+    let mut bad_group = bytes.clone();
+    bad_group[1] = 0;
+    assert!(AccumulatorState::<Rsa2048, &'static str>::from_bytes(&bad_group).is_err());
+    let mut bad_digest = bytes.clone();
+    bad_digest[2] = 1;
+    assert!(AccumulatorState::<Rsa2048, &'static str>::from_bytes(&bad_digest).is_err());
+    assert!(AccumulatorState::<Rsa2048, &'static str>::from_bytes(&bytes[..HEADER_BYTES]).is_err());
+  }
+
+  #[test]
+  fn test_digest() {
+    let state = new_state(&["a", "b"]);
+    assert_eq!(state.digest(), new_state(&["b", "a"]).digest());
+    assert_eq!(state.digest(), state.accumulator().digest());
+    assert_ne!(state.digest(), new_state(&["a"]).digest());
+    let other_version = AccumulatorState::new(state.accumulator().clone(), 1);
+    assert_ne!(state.digest(), other_version.digest());
+    let empty = Accumulator::<Rsa2048, &'static str>::empty();
+    let other_params = Accumulator::<Rsa2048, &'static str>::with_params(PrimeBits::B128);
+    assert_ne!(empty.digest(), other_params.digest());
+  }
+
+  #[test]
+  fn test_migrate() {
+    let state = new_state(&["a", "b"]);
+    let migrated = state
+      .migrate(&["a", "b"], PrimeBits::B128, 1, |t| t.to_string())
+      .unwrap();
+    assert_eq!(migrated.params(), PrimeBits::B128.into());
+    assert_eq!(migrated.elem_version(), 1);
+    assert_ne!(migrated.params_digest(), state.params_digest());
+    assert!(
+      *migrated.accumulator()
+        == Accumulator::with_params(PrimeBits::B128).add(&["a".to_string(), "b".to_string()])
+    );
+  }
+
+  #[test]
+  fn test_migrate_missing_elems() {
+    let state = new_state(&["a", "b"]);
+    assert!(state.migrate(&["a"], PrimeBits::B128, 0, |t| *t).is_err());
+    assert!(state
+      .migrate(&["a", "b", "c"], PrimeBits::B128, 0, |t| *t)
+      .is_err());
+  }
+}
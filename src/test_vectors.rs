@@ -0,0 +1,265 @@
+//! Canonical input/output vectors, enabled with the `test_vectors` feature, for validating other
+//! implementations (e.g. in Go or JavaScript) against this crate. This crate's own tests check
+//! every vector, so they cannot drift from the implementation.
+//!
+//! Byte strings are lowercase hex, and integers are lowercase big-endian hex without leading zeros.
+//!
+//! Elements are byte strings, which this crate hashes through Rust's `Hash` impl for `[u8]`. On
+//! 64-bit little-endian platforms, the Blake2b-256 input for element `e` at nonce `n` is therefore
+//! `le64(len(e)) || e || le64(n)`. The digest is read as a little-endian integer, truncated to
+//! `bits` bits, and made odd; the prime is the candidate at the first nonce passing BPSW.
+
+#[derive(Clone, Copy, Debug)]
+/// An element, the size of the primes it is hashed to, and the resulting prime and nonce.
+pub struct HashToPrimeVector {
+  /// The element, as hex bytes.
+  pub elem: &'static str,
+  /// The maximum bit length of the prime.
+  pub bits: u32,
+  /// The nonce the prime was found at.
+  pub nonce: u64,
+  /// The prime, as hex.
+  pub prime: &'static str,
+}
+
+#[derive(Clone, Copy, Debug)]
+/// A step of an accumulator sequence: the elements deleted, then the elements added, and the
+/// resulting accumulator as encoded by `Accumulator::to_bytes`.
+pub struct AccumulatorStep {
+  /// The elements added, as hex bytes.
+  pub added: &'static [&'static str],
+  /// The elements deleted, as hex bytes.
+  pub deleted: &'static [&'static str],
+  /// The encoded accumulator after the step, as hex.
+  pub acc: &'static str,
+}
+
+#[derive(Clone, Copy, Debug)]
+/// A proof about some elements, as hex of its canonical encoding.
+pub struct ProofVector {
+  /// The elements the proof is about, as hex bytes.
+  pub elems: &'static [&'static str],
+  /// The encoded proof, as hex.
+  pub proof: &'static str,
+}
+
+/// Hashes to primes with Blake2b.
+pub const HASH_TO_PRIME: &[HashToPrimeVector] = &[
+  HashToPrimeVector {
+    elem: "",
+    bits: 128,
+    nonce: 30,
+    prime: "66601adcee9aca4b2f2a25396ab1ffeb",
+  },
+  HashToPrimeVector {
+    elem: "",
+    bits: 256,
+    nonce: 105,
+    prime: "a1a0634650194dbcf8eb06d3265acb986864a2309e277ddc6a00ec3d177e07e5",
+  },
+  HashToPrimeVector {
+    elem: "61",
+    bits: 128,
+    nonce: 20,
+    prime: "5053dcfdb4b00d435bea229fe4188d59",
+  },
+  HashToPrimeVector {
+    elem: "61",
+    bits: 256,
+    nonce: 155,
+    prime: "223904da132d7612794c789bfa6ff78334809f9b4a12969e493d23b2cb2e9913",
+  },
+  HashToPrimeVector {
+    elem: "616363756d756c61746f72",
+    bits: 128,
+    nonce: 25,
+    prime: "e9a7feb18aff5abf80af3dd04e6ee9b7",
+  },
+  HashToPrimeVector {
+    elem: "616363756d756c61746f72",
+    bits: 256,
+    nonce: 26,
+    prime: "fb218d93b803336843bc7df1eb23ac7ca1328e5b855828fd0d74eb43f38212ff",
+  },
+  HashToPrimeVector {
+    elem: "000102030405060708090a0b0c0d0e0f101112131415161718191a1b1c1d1e1f",
+    bits: 128,
+    nonce: 27,
+    prime: "b3fa4f0844904774bfb048acfc7cc8b1",
+  },
+  HashToPrimeVector {
+    elem: "000102030405060708090a0b0c0d0e0f101112131415161718191a1b1c1d1e1f",
+    bits: 256,
+    nonce: 23,
+    prime: "f9f6b74cdf2bda90ae8a1444081aface88441ac720a16c89bf6d9bc623b0d359",
+  },
+];
+
+/// Steps applied in order to an empty RSA-2048 accumulator with 256-bit Blake2b primes, starting
+/// with the empty accumulator itself.
+pub const RSA2048_ACCUMULATOR_STEPS: &[AccumulatorStep] = &[
+  AccumulatorStep {
+    added: &[],
+    deleted: &[],
+    acc:
+      "200000000000000000000000000000000000000000000000000000000000000000000000000000000000000000\
+      000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000\
+      000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000\
+      000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000\
+      000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000\
+      000000000000000000000000000000000000000000000000000000000000000002",
+  },
+  AccumulatorStep {
+    added: &["61", "62", "63"],
+    deleted: &[],
+    acc:
+      "20005ed09761f811367bc3226e7ab8bca06419efbfc9cc038966275e7f460eb4eb8bf91d37ed5a03027ffac578\
+      91c45b0b82011c0dad712cdf49050386a446f70a97dc29d5c27e7cb58df723fe17bf3d91e69530068fac4ada4a\
+      625ac387d58c3db2fc63b1067bc29d65b4a6dd3bd8fc80a087ac0ea211d59c7504af93e243a5d4ccc7bafd8fb0\
+      cc63b901a2a1b50a7432423ba58197e148f870a52d003a268b0a00e42530b70913b17fc73bf5bea31c3a493a5c\
+      a1551ab5906f27416cbb0bcdb16eb9e7006f5140208c96ac27264593fb896b7b6b8b4002ee8971da749513f28c\
+      e32ea8427a820835d6a7d1e83aa2db14b516c7c001d733168de37b339e5ee5955d",
+  },
+  AccumulatorStep {
+    added: &[],
+    deleted: &["62"],
+    acc:
+      "200012a15cc4b455843d217aef7aa6d2cdc00604f505bb49af6fb6c1711accbfaf4effbcdd0165dad6c7a653ec\
+      f1151fcf6875beddbfc7243dd1b2e744da71cd5c63ac92aea07c8268ccdc4e07a47fe47aa7f72304474f8b3a96\
+      d5a98d730c1ee1aac279f52a85fa0122283c66126083616c3d7a954427d0c1d6c9796c8be6daf94b6cbf3b2c61\
+      ba0c669f25a7efe66aa60f581c60504526ea32ad04c1da911662c8b43e8814a96c9dab4787fc5fe597c50cc3e4\
+      01c7300872d81d31099550a1c7b42fc1dafe7a902e24b40b43643b4f9f97e86be645d567217730028637d55ef3\
+      dd937f514e9ad4019c1cbc6d02f5f5682de023256f90d27a3bed7151e198cea282",
+  },
+  AccumulatorStep {
+    added: &["64"],
+    deleted: &[],
+    acc:
+      "2000331bca6ae3d73da6682ffb85929e30d888f3aa686ba84ca09436e19a570bec1e3f7d05522dea1916d814f6\
+      3961e81e149c05f6a8dc8eba5eb9426e9d976ff4b6d119b6727519c87059a29e0b94ae61a28e7b16c3830caf8c\
+      1dbd42367e4168033da8b2a16876f17dcdc4b63fbb015535a2f7da28efcbc082800dcc035dfa8bdaf4a9dc89f2\
+      93b06690dd5bfc9ab6062937fb72bee257bf1e25324a79cef4d29c83e362e3bfcc36639b4c843e2b3a5d6e2604\
+      f47a69a9bd5f3790b2472c34ddff967dbdbbcd10854422f7a99ed46b650e2f4f56f738b83675feba913fd9846d\
+      d1508f9f31ac9482afccda1d1dbc90a8365017d0aa9eca8095f2639b4bed35c672",
+  },
+];
+
+/// A membership proof for `a` w.r.t. the last accumulator of `RSA2048_ACCUMULATOR_STEPS`, as
+/// encoded by `MembershipProof::to_bytes`.
+pub const RSA2048_MEMBERSHIP_PROOF: ProofVector = ProofVector {
+  elems: &["61"],
+  proof: "b7e501311b8d20dfe1089358e39d7b5228f05b83edb77f2e077d306cbff1b96420000b3984e66cf56345443f\
+    c6b370272e6c0935201efc8aceb0780778cf24c074b5969a02f2ebd1c3f7fb74ad576a9691897be5f06313fba2\
+    096ab4ef2536122663926f6b0f37d70b7e5797f077189152a279d24102358739da480cf177c3e3f43631e5bb83\
+    fab33de708086b0209d46b0518076eec75dd44808574f36e4293aebe8b2d124cda40feaad4abf390dc7e029f71\
+    da26f4b625d5b7f4976247cafe6c7c274c342d40a8ddb9864625db26661688de48217abe5a5fd3107f9ca1e54f\
+    f12472fd946910286aab2bf1c7dbfe2e61726ed707d7731af1ba2cc953190dde8d5e49d76567a6d516c5987743\
+    8d15bc3a65ca012559dd7702e766e8cfd59a301ef3000000000000000000000000000000000000000000000000\
+    000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000\
+    000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000\
+    000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000\
+    000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000\
+    000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000\
+    0000000000000100",
+};
+
+/// A nonmembership proof for `b` w.r.t. the last accumulator of `RSA2048_ACCUMULATOR_STEPS`,
+/// as encoded by `NonmembershipProof::to_bytes`.
+pub const RSA2048_NONMEMBERSHIP_PROOF: ProofVector = ProofVector {
+  elems: &["62"],
+  proof: "b7e501311b8d20dfe1089358e39d7b5228f05b83edb77f2e077d306cbff1b964200034b0710e8719a5201a3c\
+    a95494d9af3b931b08c023250c76df09778fab8c1d08879a1983f77322cd10e091592681b88f92563c89e58a9b\
+    6ddad8f0165b1a0640eab851a776629113445554fb3f61d73cc9b3c96384f241bf05b5963a8ed76d0a2281c500\
+    3ba0ae3589a8a42ef2008589c5893cd7c065560435d753cdfa84f14bc3857e4c17009cbaaecb7607bdb5e17a1f\
+    2f9eafae86ef7aaec5db0e187f85a140dea3c2564f7b02a63c7d5f1773480f6e25ea0a3a245d1aeee1737f7198\
+    0163cae7c0bbd6829fa7bc78ef6ab7ebca6d6bcabbca076507e414747800ada462b5e0e1a572c2ff70f1958c4b\
+    3e2802db3041b37bdbdbb9e9c47031b1cd33976efe19b33a0febb8a4264bad34959136fc6cdc754067fc7e8688\
+    079d5d0abec6003ea544242bb320f29f9438f4699c5f23b5c9f4dccfcbca5c51db2c507f8e2e86ca0df7b509a3\
+    47c1a8355882fa9967fef44a5f65314a96c001f09487d1b10d17449c9d42f87d559c2a15d0ea6de23b6ecc9b32\
+    9f1e98fbb5d3e6a1cb4d11b227681ba19a57eefc23ccf3f1ea010acb0b66955ed91acbe9250243a115b492e182\
+    30851a9aec0b5f50dab1ea08c6769f38de3910a94a84e2d5641056c2b3b5927335e010275f39f394abcaf91076\
+    63ea5e8b8eaad11cd2e7e6f27b10f3e56c753268492f6fbc50e4855652d41f8bf0225db9afaf6d98cccff8bc7a\
+    a131fafa5b3f5a323720b7771b544720c9b7559945f4bd0402100681b08ba6166099068df27918f28aa59559f9\
+    2626841f0becbe9a86ab3c8ab311f23182a8a95a15f56c1864467e02ad4325a866c3a2430d0e66328a6809d3aa\
+    64b3a98a81263bd4f15c58c80bde61656ff2fec24ba365faeec7c5aa60b3e04a5e2030ae31727e69055e1bd4ad\
+    0b3a3dc0b9ffc876e55a8da5d8f4a608f9c12b182824256586fb498d3c2fc8d6d79299ca8775b832527aaa300e\
+    eb7f76de5a67a1170bce91a63427a6e97b7c6c87ce323e9d05e5230fef5faec17faae283546e06ca38ee452b0b\
+    0738b4b898df4473498bb5c933da99389e0998141e2bc728c1132d89dc71de6d4836fee92cf42e0e8225607382\
+    594bcb146b01a0f3573ea8fde957b0ab48592d386c35f5b75398c0403784342ccb65e4b2d818b71b90e23c69ca\
+    9ad4a7e2e92aac41eaaeee7862d853fcaa289186198b443c3e2bc9a13b53af67a9eacc64bbcf320d050d672b61\
+    7618361bbd4071b3ac1abfa63f541eb9f75fc48db011753a1720dbb6280a0a9f01392f16095bc70ba06e59da2c\
+    750f30db363c4f08baec6f32a7e498faa0ccf2d60096d45f7bbba42edf8126a9929ff6eba5f1f03ae682419e7f\
+    15eab4a69ccab7a080cf59d776167a047f6410a1cf44cf701e27ab33a7bd439744533e24b2271de039cfc774cb\
+    824668cf5acf491c045c773923ff2eef6f1eb8e732e80e125ec87486731ca3ec4ae39d643de939f941cec62120\
+    c214b6ce27f6c56a27470c52b3631f9f675ee4c5e813086890952434a54d9d8f095fc1d8d2372df59cc5e5b7bf\
+    d5e73fde03bea1a4903cae71244e645ae1a833fce026357b0f8682417efe4cd54dcbb397070bf2ba34046e7b4d\
+    cdf6f32ba8adc8fc67044e9420efc732288e4464713a90fcd3fe9140070b738564081fb76463375183faa67cd5\
+    b0669d54d6532bb9e2ed421836ca0566e93cc4e12047bff287eb045d17b0195dd98c6256098197d7c14dcc9003\
+    3686ffd5370c6cf0edbf6ba54b87be90068e8539c669d277e09d50ee4e35eb0c6d6707301f3c350c96a681c22a\
+    30bced6fd7c00454a3537b9379fb57df2743543ddfda4eead42ec2ada15614c2392b0e73fd8d4bc780a6000000\
+    000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000\
+    000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000\
+    000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000\
+    000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000\
+    000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000\
+    00000000000000000000000000000000000000000000000000000001",
+};
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::group::Rsa2048;
+  use crate::hash::{PrimeBits, PrimeParams};
+  use crate::integer::Integer;
+  use crate::{Accumulator, ExponentState, MembershipProof, NonmembershipProof};
+
+  fn from_hex(hex: &str) -> Vec<u8> {
+    (0..hex.len())
+      .step_by(2)
+      .map(|i| u8::from_str_radix(&hex[i..i + 2], 16).unwrap())
+      .collect()
+  }
+
+  fn elems(hexes: &[&str]) -> Vec<Vec<u8>> {
+    hexes.iter().map(|hex| from_hex(hex)).collect()
+  }
+
+  #[test]
+  fn test_hash_to_prime_vectors() {
+    for vector in HASH_TO_PRIME {
+      let params = PrimeParams::from(PrimeBits::from_bits(vector.bits).unwrap());
+      let (prime, nonce) = params.hash_to_prime_with_nonce(&from_hex(vector.elem)[..]);
+      assert_eq!(prime, Integer::from_str_radix(vector.prime, 16).unwrap());
+      assert_eq!(nonce, vector.nonce);
+    }
+  }
+
+  #[test]
+  fn test_accumulator_vectors() {
+    let mut acc = Accumulator::<Rsa2048, Vec<u8>>::empty();
+    let mut state = ExponentState::empty();
+    for step in RSA2048_ACCUMULATOR_STEPS {
+      acc = acc
+        .delete_with_state(&mut state, &elems(step.deleted))
+        .unwrap()
+        .add(&elems(step.added));
+      state.add(&elems(step.added));
+      assert_eq!(acc.to_bytes(), from_hex(step.acc));
+    }
+
+    let proof = acc
+      .prove_membership_with_state(&state, &elems(RSA2048_MEMBERSHIP_PROOF.elems))
+      .unwrap();
+    assert_eq!(proof.to_bytes(), from_hex(RSA2048_MEMBERSHIP_PROOF.proof));
+    let proof = MembershipProof::from_bytes(&from_hex(RSA2048_MEMBERSHIP_PROOF.proof)).unwrap();
+    assert!(acc.verify_membership_batch(&elems(RSA2048_MEMBERSHIP_PROOF.elems), &proof));
+
+    let proof =
+      NonmembershipProof::from_bytes(&from_hex(RSA2048_NONMEMBERSHIP_PROOF.proof)).unwrap();
+    assert!(acc.verify_nonmembership(&elems(RSA2048_NONMEMBERSHIP_PROOF.elems), &proof));
+    assert_eq!(
+      proof.to_bytes(),
+      from_hex(RSA2048_NONMEMBERSHIP_PROOF.proof)
+    );
+  }
+}
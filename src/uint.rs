@@ -1,10 +1,19 @@
-//! Zero-allocation U256 and U512 types built on GMP. We created this module specifically for our
-//! use case of implementing primality checking over 256-bit integers, but it may be worth
-//! polishing a bit for more general use.
+//! Zero-allocation U256, U384, U512, and U768 types built on GMP. We created this module
+//! specifically for our use case of implementing primality checking over 256- and 384-bit integers,
+//! but it may be worth polishing a bit for more general use.
 //!
 //! Obviously there are a lot of `unsafe` blocks to work with GMP. Take care when using this module
 //! because there may be bugs we did not catch.
 //!
+//! Values are stored as GMP limbs, which are 64 bits wide on most targets but 32 bits wide on
+//! some, e.g. armv7. Sizes in the public API are counted in 64-bit words regardless, and byte and
+//! word conversions are explicit about their byte order, so results do not depend on the target's
+//! limb size or endianness.
+//!
+//! Primality testing works modulo the candidate throughout, so `MontgomeryU256` and
+//! `MontgomeryU384` provide Montgomery arithmetic for it, which replaces each division in a modular
+//! multiplication with a cheaper reduction.
+//!
 //! TODO: Benchmark our U256 vs. 256-bit `rug::Integer` vs. Parity U256.
 #![allow(clippy::cast_sign_loss)]
 
@@ -13,9 +22,22 @@ use gmp_mpfr_sys::gmp::mpz_t;
 use rug::integer::Order;
 use rug::Integer;
 use std::cmp::{min, Ord, Ordering, PartialOrd};
-use std::convert::From;
-use std::mem::transmute;
+use std::convert::{From, TryFrom};
+use std::mem;
 use std::ops;
+use std::os::raw::{c_int, c_ulong};
+
+type Limb = gmp::limb_t;
+
+// Wide enough for the product of two limbs plus two more limbs, the inner step of Montgomery
+// multiplication. Limbs are at most 64 bits wide.
+type DoubleLimb = u128;
+
+const LIMB_BYTES: usize = mem::size_of::<Limb>();
+// The number of limbs in a 64-bit word.
+const WORD_LIMBS: usize = 8 / LIMB_BYTES;
+// The window width in bits of `pow` on Montgomery contexts. Must divide the limb size.
+const MONT_WINDOW: usize = 4;
 
 macro_rules! u_types {
   ($($t:ident,$size:expr),+) => {
@@ -25,38 +47,47 @@ macro_rules! u_types {
         // Field `size` also denotes the sign of the number, while `limbs` reflect only the
         // magnitude.
         // We keep size >= 0 except in very rare circumstances.
-        size: i64,
-        limbs: [u64; $size],
+        size: gmp::size_t,
+        limbs: [Limb; $size * WORD_LIMBS],
       }
 
       impl $t {
-        fn data(&self) -> *mut u64 {
-          &self.limbs as *const u64 as *mut u64
+        // The number of limbs, as opposed to `$size`, the number of 64-bit words.
+        const LIMBS: usize = $size * WORD_LIMBS;
+
+        fn data(&self) -> *mut Limb {
+          &self.limbs as *const Limb as *mut Limb
         }
 
+        // The number of limbs as an `mpn` operand size.
+        #[allow(clippy::cast_possible_wrap)]
+        fn n() -> gmp::size_t {
+          Self::LIMBS as gmp::size_t
+        }
+
+        #[allow(clippy::cast_possible_wrap)]
         fn normalize_size(&mut self) {
           self.size = 0;
-          for i in (0..$size).rev() {
+          for i in (0..Self::LIMBS).rev() {
             if self.limbs[i] != 0 {
-              self.size = (i + 1) as i64;
+              self.size = (i + 1) as gmp::size_t;
               break;
             }
           }
         }
 
-        // The cast from `i64` to `i32` is fine since |`size`| is <= 4 for `U256`, and <= 8 for
-        // `U512`.
-        #[allow(clippy::cast_possible_truncation)]
+        // The casts to `c_int` are fine since |`size`| is at most 24, for `U768` with 32-bit limbs.
+        #[allow(clippy::cast_possible_truncation, clippy::cast_possible_wrap)]
         fn as_mpz(&self) -> mpz_t {
           mpz_t {
-            size: self.size as i32,
+            size: self.size as c_int,
             d: self.data(),
-            alloc: $size,
+            alloc: Self::LIMBS as c_int,
           }
         }
 
         pub fn zero() -> Self {
-          Self { size: 0, limbs: [0; $size] }
+          Self { size: 0, limbs: [0; $size * WORD_LIMBS] }
         }
 
         pub fn is_zero(&self) -> bool {
@@ -64,7 +95,7 @@ macro_rules! u_types {
         }
 
         pub fn one() -> Self {
-          let mut limbs = [0; $size];
+          let mut limbs = [0; $size * WORD_LIMBS];
           limbs[0] = 1;
           Self { size: 1, limbs }
         }
@@ -82,7 +113,7 @@ macro_rules! u_types {
           let m = m.as_mpz();
           let exists = unsafe { gmp::mpz_invert(mut_ptr(&outmpz), mut_ptr(&s), mut_ptr(&m)) };
           if exists != 0 {
-            out.size = i64::from(outmpz.size);
+            out.size = gmp::size_t::from(outmpz.size);
             Some(out)
           }
           else {
@@ -98,7 +129,7 @@ macro_rules! u_types {
           let e = e.as_mpz();
           let m = m.as_mpz();
           unsafe { gmp::mpz_powm(mut_ptr(&outmpz), mut_ptr(&s), mut_ptr(&e), mut_ptr(&m)) };
-          out.size = i64::from(outmpz.size);
+          out.size = gmp::size_t::from(outmpz.size);
           out
         }
 
@@ -124,9 +155,26 @@ macro_rules! u_types {
         }
 
         pub fn is_divisible_u(&self, u: u64) -> bool {
-          let s = self.as_mpz();
-          let divisible = unsafe {gmp::mpz_divisible_ui_p(mut_ptr(&s), u)};
-          divisible != 0
+          // `c_ulong` is only 32 bits wide on some targets.
+          #[allow(clippy::unnecessary_fallible_conversions)]
+          match c_ulong::try_from(u) {
+            Ok(u) => {
+              let s = self.as_mpz();
+              let divisible = unsafe {gmp::mpz_divisible_ui_p(mut_ptr(&s), u)};
+              divisible != 0
+            }
+            Err(_) => (*self % &Self::from(u)).is_zero(),
+          }
+        }
+
+        /// Returns `self mod u`. Panics if `u == 0`.
+        pub fn rem_u(&self, u: u64) -> u64 {
+          // `Limb` is only 32 bits wide on some targets.
+          #[allow(clippy::unnecessary_fallible_conversions)]
+          match Limb::try_from(u) {
+            Ok(u) => u64::from(unsafe { gmp::mpn_mod_1(self.data(), self.size, u) }),
+            Err(_) => (*self % &Self::from(u)).to_words()[0],
+          }
         }
 
         /// Panics if `buf` is not large enough.
@@ -134,22 +182,51 @@ macro_rules! u_types {
           unsafe { gmp::mpn_get_str(mut_ptr(&buf[0]), 2, self.data(), self.size) }
         }
 
+        /// Big-endian `bytes`. Panics if `bytes` is empty or too long.
         pub fn from_be_bytes(bytes: &[u8]) -> Self {
-          let x = Self::zero();
+          let mut x = Self::zero();
           unsafe { gmp::mpn_set_str(x.data(), &bytes[0] as *const u8, bytes.len(), 256) };
+          x.normalize_size();
           x
         }
+
+        /// Returns the 64-bit words of this integer, least significant first.
+        pub fn to_words(&self) -> [u64; $size] {
+          let mut words = [0; $size];
+          for (word, limbs) in words.iter_mut().zip(self.limbs.chunks_exact(WORD_LIMBS)) {
+            for (j, &limb) in limbs.iter().enumerate() {
+              *word |= u64::from(limb) << (j * gmp::LIMB_BITS as usize);
+            }
+          }
+          words
+        }
+
+        /// Returns the little-endian bytes of this integer.
+        pub fn to_le_bytes(&self) -> [u8; $size * 8] {
+          let mut bytes = [0; $size * 8];
+          for (chunk, limb) in bytes.chunks_exact_mut(LIMB_BYTES).zip(&self.limbs) {
+            chunk.copy_from_slice(&limb.to_le_bytes());
+          }
+          bytes
+        }
       }
 
       impl PartialEq<u64> for $t {
         fn eq(&self, u: &u64) -> bool {
-          (*u == 0 && self.size == 0) || (self.size == 1 && self.limbs[0] == *u)
+          *self == Self::from(*u)
         }
       }
 
+      /// Least significant word first.
       impl From<[u64; $size]> for $t {
-        fn from(limbs: [u64; $size]) -> Self {
-          let mut x = Self { size: 0, limbs };
+        #[allow(clippy::cast_possible_truncation)]
+        fn from(words: [u64; $size]) -> Self {
+          let mut x = Self::zero();
+          for (limbs, word) in x.limbs.chunks_exact_mut(WORD_LIMBS).zip(&words) {
+            for (j, limb) in limbs.iter_mut().enumerate() {
+              *limb = (word >> (j * gmp::LIMB_BITS as usize)) as Limb;
+            }
+          }
           x.normalize_size();
           x
         }
@@ -157,54 +234,42 @@ macro_rules! u_types {
 
       impl From<u64> for $t {
         fn from(x: u64) -> Self {
-          let mut limbs = [0; $size];
-          limbs[0] = x;
-          Self::from(limbs)
+          let mut words = [0; $size];
+          words[0] = x;
+          Self::from(words)
         }
       }
 
       /// Lower-endian `bytes`.
       impl From<[u8; $size * 8]> for $t {
         fn from(bytes: [u8; $size * 8]) -> Self {
-          let chunks = unsafe { transmute::<[u8; $size * 8], [[u8; 8]; $size]>(bytes) };
-          let mut limbs = [0; $size];
-          for i in 0..$size {
-            limbs[i] = u64::from_le_bytes(chunks[i]);
-          }
-          Self::from(limbs)
+          Self::from(&bytes)
         }
       }
 
       /// Lower-endian `bytes`.
       impl From<&[u8; $size * 8]> for $t {
         fn from(bytes: &[u8; $size * 8]) -> Self {
-          let chunks = unsafe { transmute::<[u8; $size * 8], [[u8; 8]; $size]>(*bytes) };
-          let mut limbs = [0; $size];
-          for i in 0..$size {
-            limbs[i] = u64::from_le_bytes(chunks[i]);
+          let mut x = Self::zero();
+          for (limb, chunk) in x.limbs.iter_mut().zip(bytes.chunks_exact(LIMB_BYTES)) {
+            let mut buf = [0; LIMB_BYTES];
+            buf.copy_from_slice(chunk);
+            *limb = Limb::from_le_bytes(buf);
           }
-          Self::from(limbs)
+          x.normalize_size();
+          x
         }
       }
 
       impl PartialOrd for $t {
         fn partial_cmp(&self, x: &Self) -> Option<Ordering> {
-          let x = unsafe { gmp::mpn_cmp(self.data(), x.data(), $size) };
-          Some({
-            if x < 0 {
-              Ordering::Less
-            } else if x == 0 {
-              Ordering::Equal
-            } else {
-              Ordering::Greater
-            }
-          })
+          Some(self.cmp(x))
         }
       }
 
       impl Ord for $t {
         fn cmp(&self, x: &Self) -> Ordering {
-          let x = unsafe { gmp::mpn_cmp(self.data(), x.data(), $size) };
+          let x = unsafe { gmp::mpn_cmp(self.data(), x.data(), Self::n()) };
           if x < 0 {
             Ordering::Less
           } else if x == 0 {
@@ -218,9 +283,10 @@ macro_rules! u_types {
       impl ops::ShlAssign<u32> for $t {
         fn shl_assign(&mut self, mut x: u32) {
           while x != 0 {
-            let sz = min(gmp::LIMB_BITS as u32, x);
+            // `mpn_lshift` and `mpn_rshift` only shift by less than a limb.
+            let sz = min(gmp::LIMB_BITS as u32 - 1, x);
             x -= sz;
-            unsafe { gmp::mpn_lshift(self.data(), self.data(), $size, sz) };
+            unsafe { gmp::mpn_lshift(self.data(), self.data(), Self::n(), sz) };
           }
           self.normalize_size();
         }
@@ -238,9 +304,9 @@ macro_rules! u_types {
       impl ops::ShrAssign<u32> for $t {
         fn shr_assign(&mut self, mut x: u32) {
           while x != 0 {
-            let sz = min(gmp::LIMB_BITS as u32, x);
+            let sz = min(gmp::LIMB_BITS as u32 - 1, x);
             x -= sz;
-            unsafe { gmp::mpn_rshift(self.data(), self.data(), $size, sz) };
+            unsafe { gmp::mpn_rshift(self.data(), self.data(), Self::n(), sz) };
           }
           self.normalize_size();
         }
@@ -258,7 +324,7 @@ macro_rules! u_types {
       impl ops::AddAssign for $t {
         /// Panics if result overflows.
         fn add_assign(&mut self, x: Self) {
-          let carry = unsafe { gmp::mpn_add_n(self.data(), self.data(), x.data(), $size) };
+          let carry = unsafe { gmp::mpn_add_n(self.data(), self.data(), x.data(), Self::n()) };
           assert!(carry == 0);
           self.normalize_size();
         }
@@ -284,7 +350,7 @@ macro_rules! u_types {
       impl ops::SubAssign for $t {
         /// Panics if result is negative.
         fn sub_assign(&mut self, x: Self) {
-          let borrow = unsafe { gmp::mpn_sub_n(self.data(), self.data(), x.data(), $size) };
+          let borrow = unsafe { gmp::mpn_sub_n(self.data(), self.data(), x.data(), Self::n()) };
           assert!(borrow == 0);
           self.normalize_size();
         }
@@ -415,101 +481,459 @@ macro_rules! u_types {
   }
 }
 
-u_types!(U256, 4, U512, 8);
+u_types!(U256, 4, U384, 6, U512, 8, U768, 12);
 
-impl U512 {
-  /// Returns the lower half of this `U512` as a `U256`.
-  /// TODO: Make checked?
-  pub fn low_u256(self) -> U256 {
-    let mut x = unsafe { transmute::<Self, (U256, [u64; 4])>(self) }.0;
-    x.normalize_size();
-    x
-  }
-}
+/// Fixed-width integers with a double-width type for products, which is all that primality testing
+/// needs. Implemented by `U256` and `U384`, so that hashing to primes of up to 256 or 384 bits never
+/// allocates.
+pub trait Uint:
+  Copy
+  + Ord
+  + PartialEq<u64>
+  + From<u64>
+  + Into<Integer>
+  + ops::Add<Output = Self>
+  + ops::Add<u64, Output = Self>
+  + ops::Sub<Output = Self>
+  + ops::Sub<u64, Output = Self>
+  + ops::Shr<u32, Output = Self>
+  + ops::Mul<Output = <Self as Uint>::Wide>
+  + for<'a> ops::Rem<&'a Self, Output = Self>
+{
+  /// The type of products of two values.
+  type Wide: Copy
+    + Ord
+    + From<Self>
+    + ops::Add<Output = Self::Wide>
+    + ops::Sub<Output = Self::Wide>
+    + ops::Shl<u32, Output = Self::Wide>
+    + for<'a> ops::Rem<&'a Self, Output = Self>;
 
-impl From<&U256> for U512 {
-  fn from(x: &U256) -> Self {
-    let mut limbs = [0; 8];
-    limbs[..4].copy_from_slice(&x.limbs);
-    Self {
-      size: x.size,
-      limbs,
-    }
-  }
-}
+  /// The Montgomery arithmetic context for odd moduli of this width.
+  type Mont: Montgomery<Self>;
 
-impl From<U256> for U512 {
-  fn from(x: U256) -> Self {
-    Self::from(&x)
-  }
-}
+  /// The width in bits.
+  const BITS: usize;
 
-// This gets its own implementation for performance.
-impl ops::Rem<&U256> for U512 {
-  type Output = U256;
-  fn rem(self, x: &U256) -> U256 {
-    if x.size > self.size {
-      return self.low_u256();
-    }
-    let (y, mut rem) = (Self::zero(), U256::zero());
-    unsafe {
-      gmp::mpn_tdiv_qr(
-        y.data(),
-        rem.data(),
-        0,
-        self.data(),
-        self.size,
-        x.data(),
-        x.size,
-      )
-    };
-    rem.normalize_size();
-    rem
-  }
+  /// See the inherent method of the same name.
+  fn is_odd(&self) -> bool;
+  /// See the inherent method of the same name.
+  fn is_divisible_u(&self, u: u64) -> bool;
+  /// See the inherent method of the same name.
+  fn rem_u(&self, u: u64) -> u64;
+  /// See the inherent method of the same name.
+  fn is_perfect_square(&self) -> bool;
+  /// See the inherent method of the same name.
+  fn is_congruent(self, i: i32, m: &Self) -> bool;
+  /// See the inherent method of the same name.
+  fn jacobi(a: i32, b: &Self) -> i32;
+  /// Like the inherent method of the same name, but with Montgomery arithmetic for odd `m`.
+  fn pow_mod(self, e: Self, m: &Self) -> Self;
+  /// See the inherent method of the same name.
+  fn remove_factor(self, f: Self) -> (Self, u64);
+  /// See the inherent method of the same name.
+  fn write_binary(&self, buf: &mut [u8]) -> usize;
 }
 
-impl ops::Rem<U256> for U512 {
-  type Output = U256;
-  fn rem(self, x: U256) -> U256 {
-    #![allow(clippy::op_ref)]
-    self % &x
-  }
+/// Montgomery arithmetic modulo a fixed odd modulus, for fixed-width integers `U`. Implemented by
+/// `MontgomeryU256` and `MontgomeryU384`.
+pub trait Montgomery<U>: Clone {
+  /// See the inherent method of the same name.
+  fn new(n: U) -> Self;
+  /// See the inherent method of the same name.
+  fn to_mont(&self, x: U) -> U;
+  #[allow(clippy::wrong_self_convention)]
+  /// See the inherent method of the same name.
+  fn from_mont(&self, x: U) -> U;
+  /// See the inherent method of the same name.
+  fn add(&self, x: U, y: U) -> U;
+  /// See the inherent method of the same name.
+  fn sub(&self, x: U, y: U) -> U;
+  /// See the inherent method of the same name.
+  fn mul(&self, x: U, y: U) -> U;
+  /// See the inherent method of the same name.
+  fn square(&self, x: U) -> U;
+  /// See the inherent method of the same name.
+  fn pow(&self, x: U, e: &U) -> U;
+  /// See the inherent method of the same name.
+  fn pow2(&self, e: &U) -> U;
 }
 
-impl U256 {
-  /// Returns (result of removing all `f`s, number of `f`s removed)
-  pub fn remove_factor(self, f: Self) -> (Self, u64) {
-    // For some reason this needs extra scratch space.
-    let mut out = U512::zero();
-    let outmpz = out.as_mpz();
-    let s = self.as_mpz();
-    let f = f.as_mpz();
-    let c = unsafe { gmp::mpz_remove(mut_ptr(&outmpz), mut_ptr(&s), mut_ptr(&f)) };
-    out.size = i64::from(outmpz.size);
-    (out.low_u256(), c)
-  }
-}
+macro_rules! wide_types {
+  ($($t:ident,$wide:ident,$mont:ident,$size:expr,$low:ident),+) => {
+    $(
+      impl $wide {
+        /// Returns the lower half of this integer.
+        /// TODO: Make checked?
+        pub fn $low(self) -> $t {
+          let mut x = $t::zero();
+          x.limbs.copy_from_slice(&self.limbs[..$t::LIMBS]);
+          x.normalize_size();
+          x
+        }
+      }
 
-/// It turns out to be faster to provide multiplication as `U256 * U256 -> U512`, because it lets us
-/// use `mpn_mul_n` instead of `mpn_mul`.
-impl ops::Mul<&Self> for U256 {
-  type Output = U512;
-  fn mul(self, x: &Self) -> U512 {
-    let mut y = U512::zero();
-    unsafe { gmp::mpn_mul_n(y.data(), self.data(), x.data(), 4) };
-    y.normalize_size();
-    y
-  }
-}
+      impl From<&$t> for $wide {
+        fn from(x: &$t) -> Self {
+          let mut limbs = [0; 2 * $size * WORD_LIMBS];
+          limbs[..$t::LIMBS].copy_from_slice(&x.limbs);
+          Self {
+            size: x.size,
+            limbs,
+          }
+        }
+      }
+
+      impl From<$t> for $wide {
+        fn from(x: $t) -> Self {
+          Self::from(&x)
+        }
+      }
+
+      // This gets its own implementation for performance.
+      impl ops::Rem<&$t> for $wide {
+        type Output = $t;
+        fn rem(self, x: &$t) -> $t {
+          if x.size > self.size {
+            return self.$low();
+          }
+          let (y, mut rem) = (Self::zero(), $t::zero());
+          unsafe {
+            gmp::mpn_tdiv_qr(
+              y.data(),
+              rem.data(),
+              0,
+              self.data(),
+              self.size,
+              x.data(),
+              x.size,
+            )
+          };
+          rem.normalize_size();
+          rem
+        }
+      }
+
+      impl ops::Rem<$t> for $wide {
+        type Output = $t;
+        fn rem(self, x: $t) -> $t {
+          #![allow(clippy::op_ref)]
+          self % &x
+        }
+      }
+
+      impl $t {
+        /// Returns (result of removing all `f`s, number of `f`s removed)
+        pub fn remove_factor(self, f: Self) -> (Self, u64) {
+          // For some reason this needs extra scratch space.
+          let mut out = $wide::zero();
+          let outmpz = out.as_mpz();
+          let s = self.as_mpz();
+          let f = f.as_mpz();
+          let c = unsafe { gmp::mpz_remove(mut_ptr(&outmpz), mut_ptr(&s), mut_ptr(&f)) };
+          out.size = gmp::size_t::from(outmpz.size);
+          (out.$low(), c)
+        }
+      }
 
-impl ops::Mul for U256 {
-  type Output = U512;
-  fn mul(self, x: Self) -> U512 {
-    #![allow(clippy::op_ref)]
-    self * &x
+      /// It turns out to be faster to provide multiplication as a widening operation, because it
+      /// lets us use `mpn_mul_n` instead of `mpn_mul`.
+      impl ops::Mul<&Self> for $t {
+        type Output = $wide;
+        fn mul(self, x: &Self) -> $wide {
+          let mut y = $wide::zero();
+          unsafe { gmp::mpn_mul_n(y.data(), self.data(), x.data(), $t::n()) };
+          y.normalize_size();
+          y
+        }
+      }
+
+      impl ops::Mul for $t {
+        type Output = $wide;
+        fn mul(self, x: Self) -> $wide {
+          #![allow(clippy::op_ref)]
+          self * &x
+        }
+      }
+
+      impl Uint for $t {
+        type Wide = $wide;
+        type Mont = $mont;
+        const BITS: usize = 64 * $size;
+
+        fn is_odd(&self) -> bool {
+          $t::is_odd(self)
+        }
+        fn is_divisible_u(&self, u: u64) -> bool {
+          $t::is_divisible_u(self, u)
+        }
+        fn rem_u(&self, u: u64) -> u64 {
+          $t::rem_u(self, u)
+        }
+        fn is_perfect_square(&self) -> bool {
+          $t::is_perfect_square(self)
+        }
+        fn is_congruent(self, i: i32, m: &Self) -> bool {
+          $t::is_congruent(self, i, m)
+        }
+        fn jacobi(a: i32, b: &Self) -> i32 {
+          $t::jacobi(a, b)
+        }
+        fn pow_mod(self, e: Self, m: &Self) -> Self {
+          if m.is_odd() {
+            $mont::new(*m).pow_mod(self, &e)
+          } else {
+            $t::pow_mod(self, e, m)
+          }
+        }
+        fn remove_factor(self, f: Self) -> (Self, u64) {
+          $t::remove_factor(self, f)
+        }
+        fn write_binary(&self, buf: &mut [u8]) -> usize {
+          $t::write_binary(self, buf)
+        }
+      }
+
+      /// Montgomery arithmetic modulo a fixed odd modulus `n`, with `R = 2^BITS`. Multiplies with
+      /// inlined limb arithmetic, since `mpn` call overhead dominates at these sizes, and never
+      /// allocates. Values in Montgomery form are in `[0, n)`.
+      #[derive(Clone, Debug)]
+      pub struct $mont {
+        n: $t,
+        // `-n^(-1) mod 2^LIMB_BITS`.
+        n_inv: Limb,
+        // `R^2 mod n`, for converting into Montgomery form.
+        r2: $t,
+      }
+
+      impl $mont {
+        /// Panics if `n` is even.
+        pub fn new(n: $t) -> Self {
+          assert!(n.is_odd(), "Montgomery modulus must be odd");
+          // Each Newton iteration doubles the number of correct low bits of `n^(-1)`, starting
+          // from the 3 bits that `n` itself gets right.
+          let mut inv = n.limbs[0];
+          for _ in 0..5 {
+            inv = inv.wrapping_mul((2 as Limb).wrapping_sub(n.limbs[0].wrapping_mul(inv)));
+          }
+          let mut r = $wide::zero();
+          r.limbs[$t::LIMBS] = 1;
+          r.normalize_size();
+          let r = r % &n;
+          Self {
+            n,
+            n_inv: inv.wrapping_neg(),
+            r2: r * r % &n,
+          }
+        }
+
+        pub fn modulus(&self) -> &$t {
+          &self.n
+        }
+
+        /// Returns `x * R mod n`, the Montgomery form of `x`.
+        pub fn to_mont(&self, x: $t) -> $t {
+          self.mul(x, self.r2)
+        }
+
+        #[allow(clippy::wrong_self_convention)]
+        /// Returns `x / R mod n`, the value of `x` in Montgomery form.
+        pub fn from_mont(&self, x: $t) -> $t {
+          self.mul(x, $t::one())
+        }
+
+        /// Returns `x + y mod n`, for `x` and `y` in `[0, n)`.
+        pub fn add(&self, x: $t, y: $t) -> $t {
+          let mut sum = x;
+          let carry = unsafe { gmp::mpn_add_n(sum.data(), sum.data(), y.data(), $t::n()) };
+          if carry != 0 || sum >= self.n {
+            // The borrow cancels the carry.
+            unsafe { gmp::mpn_sub_n(sum.data(), sum.data(), self.n.data(), $t::n()) };
+          }
+          sum.normalize_size();
+          sum
+        }
+
+        /// Returns `x - y mod n`, for `x` and `y` in `[0, n)`.
+        pub fn sub(&self, x: $t, y: $t) -> $t {
+          let mut diff = x;
+          let borrow = unsafe { gmp::mpn_sub_n(diff.data(), diff.data(), y.data(), $t::n()) };
+          if borrow != 0 {
+            // The carry cancels the borrow.
+            unsafe { gmp::mpn_add_n(diff.data(), diff.data(), self.n.data(), $t::n()) };
+          }
+          diff.normalize_size();
+          diff
+        }
+
+        /// Returns `x * y / R mod n`, the Montgomery product. `x * y` must be less than `n * R`,
+        /// which holds if `x` or `y` is less than `n`.
+        pub fn mul(&self, x: $t, y: $t) -> $t {
+          Self::from_limbs(self.mul_limbs(&x.limbs, &y.limbs))
+        }
+
+        /// Like `mul(x, x)`.
+        pub fn square(&self, x: $t) -> $t {
+          self.mul(x, x)
+        }
+
+        /// Returns `x^e` for `x` in Montgomery form, also in Montgomery form. Exponentiates left to
+        /// right with a fixed window of `MONT_WINDOW` bits.
+        pub fn pow(&self, x: $t, e: &$t) -> $t {
+          // `powers[i]` is `x^i` in Montgomery form.
+          let mut powers = [[0; $t::LIMBS]; 1 << MONT_WINDOW];
+          powers[0] = self.to_mont($t::one()).limbs;
+          powers[1] = x.limbs;
+          for i in 2..powers.len() {
+            powers[i] = self.mul_limbs(&powers[i - 1], &powers[1]);
+          }
+          let limb_bits = gmp::LIMB_BITS as usize;
+          let mut acc = powers[0];
+          // Windows never straddle limbs, since `MONT_WINDOW` divides the limb size.
+          for bit in (0..e.size as usize * limb_bits).step_by(MONT_WINDOW).rev() {
+            for _ in 0..MONT_WINDOW {
+              acc = self.mul_limbs(&acc, &acc);
+            }
+            let digit = (e.limbs[bit / limb_bits] >> (bit % limb_bits)) as usize;
+            let digit = digit & ((1 << MONT_WINDOW) - 1);
+            if digit != 0 {
+              acc = self.mul_limbs(&acc, &powers[digit]);
+            }
+          }
+          Self::from_limbs(acc)
+        }
+
+        /// Returns `2^e` in Montgomery form. Doubling is a shift, so this takes a squaring per bit
+        /// of `e` and no multiplications.
+        pub fn pow2(&self, e: &$t) -> $t {
+          let mut acc = self.to_mont($t::one()).limbs;
+          let limb_bits = gmp::LIMB_BITS as usize;
+          for i in (0..e.size as usize * limb_bits).rev() {
+            acc = self.mul_limbs(&acc, &acc);
+            if (e.limbs[i / limb_bits] >> (i % limb_bits)) & 1 == 1 {
+              let mut carry = 0;
+              for limb in acc.iter_mut() {
+                let shifted = (*limb << 1) | carry;
+                carry = *limb >> (limb_bits - 1);
+                *limb = shifted;
+              }
+              acc = self.reduce_once(acc, carry);
+            }
+          }
+          Self::from_limbs(acc)
+        }
+
+        /// Returns `x^e mod n`.
+        pub fn pow_mod(&self, x: $t, e: &$t) -> $t {
+          self.from_mont(self.pow(self.to_mont(x), e))
+        }
+
+        fn from_limbs(limbs: [Limb; $t::LIMBS]) -> $t {
+          let mut x = $t::zero();
+          x.limbs = limbs;
+          x.normalize_size();
+          x
+        }
+
+        // Coarsely integrated operand scanning: adds `x * y_i` and then a multiple of `n` that
+        // clears the low limb, once per limb `y_i` of `y`, so the intermediate stays one limb
+        // wider than `n`, and ends below `2n`.
+        #[allow(clippy::cast_possible_truncation)]
+        fn mul_limbs(&self, x: &[Limb; $t::LIMBS], y: &[Limb; $t::LIMBS]) -> [Limb; $t::LIMBS] {
+          const L: usize = $t::LIMBS;
+          let n = &self.n.limbs;
+          let mut t = [0; $t::LIMBS + 2];
+          for &y_i in y.iter() {
+            let y_i = DoubleLimb::from(y_i);
+            let mut carry: DoubleLimb = 0;
+            for (t_j, &x_j) in t[..L].iter_mut().zip(x) {
+              let sum = DoubleLimb::from(*t_j) + DoubleLimb::from(x_j) * y_i + carry;
+              *t_j = sum as Limb;
+              carry = sum >> gmp::LIMB_BITS;
+            }
+            let sum = DoubleLimb::from(t[L]) + carry;
+            t[L] = sum as Limb;
+            t[L + 1] = (sum >> gmp::LIMB_BITS) as Limb;
+
+            let m = DoubleLimb::from(t[0].wrapping_mul(self.n_inv));
+            let mut carry = (DoubleLimb::from(t[0]) + m * DoubleLimb::from(n[0])) >> gmp::LIMB_BITS;
+            for j in 1..L {
+              let sum = DoubleLimb::from(t[j]) + m * DoubleLimb::from(n[j]) + carry;
+              t[j - 1] = sum as Limb;
+              carry = sum >> gmp::LIMB_BITS;
+            }
+            let sum = DoubleLimb::from(t[L]) + carry;
+            t[L - 1] = sum as Limb;
+            t[L] = t[L + 1] + (sum >> gmp::LIMB_BITS) as Limb;
+          }
+
+          let mut low = [0; $t::LIMBS];
+          low.copy_from_slice(&t[..L]);
+          self.reduce_once(low, t[L])
+        }
+
+        // Returns `t mod n` for `t = top * R + low < 2n`.
+        fn reduce_once(&self, mut low: [Limb; $t::LIMBS], top: Limb) -> [Limb; $t::LIMBS] {
+          if top != 0 || low.iter().rev().ge(self.n.limbs.iter().rev()) {
+            // The borrow cancels `top`.
+            let mut borrow = false;
+            for (low_j, &n_j) in low.iter_mut().zip(&self.n.limbs) {
+              let (diff, b1) = low_j.overflowing_sub(n_j);
+              let (diff, b2) = diff.overflowing_sub(Limb::from(borrow));
+              *low_j = diff;
+              borrow = b1 || b2;
+            }
+          }
+          low
+        }
+      }
+
+      impl Montgomery<$t> for $mont {
+        fn new(n: $t) -> Self {
+          $mont::new(n)
+        }
+        fn to_mont(&self, x: $t) -> $t {
+          $mont::to_mont(self, x)
+        }
+        fn from_mont(&self, x: $t) -> $t {
+          $mont::from_mont(self, x)
+        }
+        fn add(&self, x: $t, y: $t) -> $t {
+          $mont::add(self, x, y)
+        }
+        fn sub(&self, x: $t, y: $t) -> $t {
+          $mont::sub(self, x, y)
+        }
+        fn mul(&self, x: $t, y: $t) -> $t {
+          $mont::mul(self, x, y)
+        }
+        fn square(&self, x: $t) -> $t {
+          $mont::square(self, x)
+        }
+        fn pow(&self, x: $t, e: &$t) -> $t {
+          $mont::pow(self, x, e)
+        }
+        fn pow2(&self, e: &$t) -> $t {
+          $mont::pow2(self, e)
+        }
+      }
+    )+
   }
 }
 
+wide_types!(
+  U256,
+  U512,
+  MontgomeryU256,
+  4,
+  low_u256,
+  U384,
+  U768,
+  MontgomeryU384,
+  6,
+  low_u384
+);
+
 #[allow(unused_mut)]
 fn mut_ptr<T>(mut t: &T) -> *mut T {
   t as *const T as *mut T
@@ -522,6 +946,26 @@ where
   U256::from(t)
 }
 
+/// Returns whether `n` is prime, by the Baillie-PSW test `hash_to_prime` uses (see
+/// `hash::primality::is_prob_prime`). Unlike `rug::Integer::is_probably_prime`, it allocates
+/// nothing, which suits custom mappings of values to primes that test many candidates.
+///
+/// The answer is exact for `n < 2^64`. Above that, BPSW can in principle accept composites, but
+/// none is known despite extensive searches, including for inputs built to fool it. Unlike
+/// Miller-Rabin with `k` random bases, it has no proven bound such as `4^-k`, so checks of
+/// adversarial inputs that need one should add Miller-Rabin rounds (as
+/// `Pokcr::verify_with_security` does).
+pub fn is_prime_u256(n: &U256) -> bool {
+  crate::hash::primality::is_prob_prime(n)
+}
+
+pub fn u384<T>(t: T) -> U384
+where
+  U384: From<T>,
+{
+  U384::from(t)
+}
+
 pub fn u512<T>(t: T) -> U512
 where
   U512: From<T>,
@@ -529,8 +973,15 @@ where
   U512::from(t)
 }
 
-fn i32_to_mpz(i: i32, data: &mut u64) -> mpz_t {
-  *data = i.abs() as u64;
+pub fn u768<T>(t: T) -> U768
+where
+  U768: From<T>,
+{
+  U768::from(t)
+}
+
+fn i32_to_mpz(i: i32, data: &mut Limb) -> mpz_t {
+  *data = Limb::from(i.unsigned_abs());
   mpz_t {
     size: i.signum(),
     d: mut_ptr(&data),
@@ -601,4 +1052,100 @@ mod tests {
     assert!(u512(6) % u256(3) == u256(0));
     assert!(u512([1, 0, 1, 0, 0, 0, 0, 0]) % u256([0, 1, 0, 0]) == u256(1));
   }
+
+  #[test]
+  fn test_384() {
+    let x = u384([0, 0, 0, 0, 0, 1]);
+    assert!(x * x == u768([0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1, 0]));
+    assert!((x * u384(3)).low_u384() == u384([0, 0, 0, 0, 0, 3]));
+    assert!((x * x + u768(5)) % x == u384(5));
+    assert!((x * u384(12)).low_u384().remove_factor(u384(2)) == (u384(3), 322));
+    assert!(Integer::from(x) == Integer::from(1) << 320);
+  }
+
+  #[test]
+  fn test_montgomery() {
+    let p = u256([
+      0xffff_ffff_ffff_ffed,
+      0xffff_ffff_ffff_ffff,
+      0xffff_ffff_ffff_ffff,
+      0x7fff_ffff_ffff_ffff,
+    ]);
+    let mont = MontgomeryU256::new(p);
+    let x = u256([1, 2, 3, 4]);
+    assert!(mont.from_mont(mont.to_mont(x)) == x);
+    let xy = mont.from_mont(mont.mul(mont.to_mont(x), mont.to_mont(x)));
+    assert!(xy == x * x % p);
+    assert!(mont.square(mont.to_mont(x)) == mont.mul(mont.to_mont(x), mont.to_mont(x)));
+    let minus_one = mont.to_mont(p - 1);
+    assert!(mont.from_mont(mont.add(minus_one, mont.to_mont(x))) == x - 1);
+    assert!(mont.from_mont(mont.sub(mont.to_mont(x), minus_one)) == x + 1);
+    assert!(mont.from_mont(mont.pow2(&u256(300))) == u256(2).pow_mod(u256(300), &p));
+
+    // Against GMP, including all-ones operands, which need the final subtraction.
+    let cases = [
+      (u256(3), u256(4), u256(7)),
+      (u256(5), u256(0), u256(1)),
+      (u256(0), u256(0), u256(9)),
+      (
+        u256([7, 0, 0, 1]),
+        u256([9, 9, 9, 9]),
+        u256(0xffff_ffff_ffff_fff1),
+      ),
+      (u256([!0; 4]), p - 1, p),
+      (x, u256([!0; 4]), u256([!0; 4])),
+    ];
+    for &(x, e, m) in cases.iter() {
+      let expected = Integer::from(x)
+        .pow_mod(&Integer::from(e), &Integer::from(m))
+        .unwrap();
+      assert!(Integer::from(Uint::pow_mod(x, e, &m)) == expected);
+      assert!(Integer::from(MontgomeryU256::new(m).pow_mod(x, &e)) == expected);
+    }
+    let m = u384([1, 2, 3, 4, 5, 1 << 63]);
+    let (x, e) = (u384([6, 5, 4, 3, 2, 1]), u384([!0; 6]));
+    assert!(
+      Integer::from(Uint::pow_mod(x, e, &m))
+        == Integer::from(x)
+          .pow_mod(&Integer::from(e), &Integer::from(m))
+          .unwrap()
+    );
+    // Even moduli fall back to GMP.
+    assert!(Uint::pow_mod(u256(3), u256(5), &u256(100)) == 43);
+  }
+
+  #[test]
+  fn test_byte_order() {
+    let words = [0x0706_0504_0302_0100, 0x0f0e_0d0c_0b0a_0908, 0, 1 << 63];
+    let x = u256(words);
+    assert_eq!(x.to_words(), words);
+    let bytes = x.to_le_bytes();
+    assert_eq!(bytes[..16], (0..16).collect::<Vec<u8>>()[..]);
+    assert_eq!(bytes[31], 0x80);
+    assert!(u256(bytes) == x);
+    let mut be = bytes;
+    be.reverse();
+    assert!(U256::from_be_bytes(&be) == x);
+    assert!(Integer::from(x) == Integer::from_digits(&bytes, Order::Lsf));
+    assert!(x >> 248 == 0x80);
+    assert!(u256(0x1_0000_0001).is_divisible_u(0x1_0000_0001));
+    assert!(!u256(0x1_0000_0002).is_divisible_u(0x1_0000_0001));
+    assert_eq!(x.rem_u(1 << 63), 0x0706_0504_0302_0100);
+    assert_eq!(x.rem_u(0x1_0000_0001), 0x77f7_f7f9);
+    assert_eq!(x.rem_u(210), 98);
+  }
+
+  #[test]
+  fn test_is_prime_u256() {
+    let to_u256 = |n: &Integer| U256::from_be_bytes(&n.to_digits::<u8>(Order::Msf));
+    assert!(is_prime_u256(&to_u256(&((Integer::from(1) << 255) - 19))));
+    // A Carmichael number, which fools Fermat tests to every coprime base.
+    assert!(!is_prime_u256(&u256(561)));
+    let base = Integer::from(1) << 200;
+    for i in 0..200u32 {
+      let n = Integer::from(&base + i);
+      let expected = n.is_probably_prime(30) != rug::integer::IsPrime::No;
+      assert_eq!(is_prime_u256(&to_u256(&n)), expected);
+    }
+  }
 }
@@ -1,7 +1,8 @@
 //! Miscellaneous functions used throughout the library.
 use crate::group::Group;
-use crate::hash::hash_to_prime;
-use rug::Integer;
+use crate::hash::{hash, hash_to_prime, Blake2b};
+use crate::integer::{Integer, IntegerOps};
+use std::fmt;
 use std::hash::Hash;
 
 /// Pseudo-type-level programming.
@@ -22,9 +23,83 @@ where
   Integer::from(val)
 }
 
+/// Overwrites `bytes` with zeros in a way the compiler will not optimize away.
+#[cfg(feature = "zeroize")]
+pub fn zeroize_bytes(bytes: &mut [u8]) {
+  for byte in bytes.iter_mut() {
+    // Safe because `byte` is a valid, aligned reference.
+    unsafe { std::ptr::write_volatile(byte, 0) };
+  }
+  std::sync::atomic::compiler_fence(std::sync::atomic::Ordering::SeqCst);
+}
+
+/// Overwrites every limb allocated for `n`, then sets it to zero. The allocation is reused rather
+/// than freed, so no copy of the old value is left behind in freed memory.
+#[cfg(feature = "zeroize")]
+pub fn zeroize_integer(n: &mut Integer) {
+  use rug::Assign;
+  let bits = n.capacity() as u32;
+  if bits > 0 {
+    // Fits the allocation exactly, so GMP copies it over every limb instead of reallocating. The
+    // calls go through FFI and cannot be elided.
+    let ones = int(int(1) << bits) - 1;
+    n.assign(&ones);
+  }
+  n.assign(0);
+}
+
+/// An integer that is wiped from memory when dropped, with the `zeroize` feature. Only secret
+/// fields are wrapped, so that the structs holding them never implement `Drop` themselves and
+/// enabling the feature does not stop their other fields from being moved out.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub(crate) struct SecretInteger(pub(crate) Integer);
+
+#[cfg(feature = "zeroize")]
+impl Drop for SecretInteger {
+  fn drop(&mut self) {
+    zeroize_integer(&mut self.0);
+  }
+}
+
+/// Returns the lowercase hex encoding of `bytes`.
+pub(crate) fn to_hex(bytes: &[u8]) -> String {
+  bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+// Formats as a short Blake2b fingerprint of the wrapped value, for `Debug` impls of types holding
+// group elements, whose decimal expansions would run to hundreds of digits each.
+pub(crate) struct Redacted<'a, T: ?Sized>(pub &'a T);
+
+impl<'a, T: Hash + ?Sized> fmt::Debug for Redacted<'a, T> {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    write!(f, "<{}..>", to_hex(&hash(&Blake2b::default, self.0)[..8]))
+  }
+}
+
 /// Hashes its arguments to primes and takes their product.
 pub fn prime_hash_product<T: Hash>(ts: &[T]) -> Integer {
-  ts.iter().map(hash_to_prime).product()
+  product(ts.iter().map(hash_to_prime).collect())
+}
+
+/// Returns the product of `xs`, multiplying them pairwise up a balanced product tree.
+///
+/// The operands of each multiplication are about the same size, so multiplying `n` primes takes
+/// quasi-linear time in `n` with GMP's subquadratic multiplication, instead of the quadratic time
+/// of multiplying them one by one into an ever-growing product. Returns 1 for empty `xs`.
+pub fn product(xs: Vec<Integer>) -> Integer {
+  let mut level = xs;
+  while level.len() > 1 {
+    let mut next = Vec::with_capacity(level.len());
+    let mut nodes = level.into_iter();
+    while let Some(left) = nodes.next() {
+      next.push(match nodes.next() {
+        Some(right) => left * right,
+        None => left,
+      });
+    }
+    level = next;
+  }
+  level.pop().unwrap_or_else(|| int(1))
 }
 
 /// Computes the `(xy)`th root of `g` given the `x`th and `y`th roots of `g` and `(x, y)` coprime.
@@ -40,7 +115,18 @@ pub fn shamir_trick<G: Group>(
     return None;
   }
 
-  let (gcd, a, b) = <(Integer, Integer, Integer)>::from(x.gcd_cofactors_ref(&y));
+  shamir_combine::<G>(xth_root, yth_root, x, y)
+}
+
+// The Shamir trick without the check that both roots are of the same element.
+#[allow(clippy::similar_names)]
+fn shamir_combine<G: Group>(
+  xth_root: &G::Elem,
+  yth_root: &G::Elem,
+  x: &Integer,
+  y: &Integer,
+) -> Option<G::Elem> {
+  let (gcd, a, b) = x.xgcd(y);
 
   if gcd != int(1) {
     return None;
@@ -49,26 +135,183 @@ pub fn shamir_trick<G: Group>(
   Some(G::op(&G::exp(xth_root, &b), &G::exp(yth_root, &a)))
 }
 
+/// Computes the `(x_1 * ... * x_k)`th root of `g` given the `x_i`th root of `g` for each `x_i` in
+/// `exps`, which must be pairwise coprime. Returns `None` if the roots are not all of the same
+/// element, the exponents are not pairwise coprime, or the slices are empty or differ in length.
+///
+/// Merges roots pairwise in a balanced tree, checking each pair of subtree products for
+/// coprimality, which amounts to checking every pair of exponents. This takes `O(k log k)` group
+/// operations for `k` roots, against `O(k^2)` for folding `shamir_trick` over them.
+pub fn shamir_trick_n<G: Group>(roots: &[G::Elem], exps: &[Integer]) -> Option<G::Elem> {
+  if roots.is_empty() || roots.len() != exps.len() {
+    return None;
+  }
+  let g = G::exp(&roots[0], &exps[0]);
+  if roots
+    .iter()
+    .zip(exps)
+    .skip(1)
+    .any(|(root, x)| G::exp(root, x) != g)
+  {
+    return None;
+  }
+  let exp_roots = exps
+    .iter()
+    .cloned()
+    .zip(roots.iter().cloned())
+    .collect::<Vec<_>>();
+  shamir_combine_n::<G>(&exp_roots).map(|(_, root)| root)
+}
+
+// Like `shamir_trick_n` on already verified `(x_i, root)` pairs, also returning the product of the
+// `x_i`. Returns `None` only if the exponents are not pairwise coprime.
+pub(crate) fn shamir_combine_n<G: Group>(
+  exp_roots: &[(Integer, G::Elem)],
+) -> Option<(Integer, G::Elem)> {
+  divide_and_conquer(
+    |(x, xth_root), (y, yth_root)| {
+      let root = shamir_combine::<G>(xth_root, yth_root, x, y).ok_or(())?;
+      Ok::<_, ()>((int(x * y), root))
+    },
+    exp_roots[0].clone(),
+    &exp_roots[1..],
+  )
+  .ok()
+}
+
+/// Computes `g^(x / x_i)` for every `x_i` in `xs`, where `x` is the product of `xs`: the `x_i`th
+/// root of `g^x`. This is RootFactor from BBF (page 18).
+///
+/// Takes `O(n log n)` exponentiations by single elements of `xs`, where `n = xs.len()`, instead of
+/// the `O(n^2)` of computing each product separately. Returns an empty vector for empty `xs`.
+pub fn root_factor<G: Group>(g: &G::Elem, xs: &[Integer]) -> Vec<G::Elem> {
+  if xs.len() <= 1 {
+    return xs.iter().map(|_| g.clone()).collect();
+  }
+  let half_n = xs.len() / 2;
+  let g_l = xs[..half_n]
+    .iter()
+    .fold(g.clone(), |acc, x| G::exp(&acc, x));
+  let g_r = xs[half_n..]
+    .iter()
+    .fold(g.clone(), |acc, x| G::exp(&acc, x));
+  let mut roots = root_factor::<G>(&g_r, &xs[..half_n]);
+  roots.append(&mut root_factor::<G>(&g_l, &xs[half_n..]));
+  roots
+}
+
+/// Computes `(x / x_i) mod m` for every `x_i` in `xs`, where `x` is the product of `xs`.
+///
+/// Builds a product tree of `xs` reduced modulo `m`, then descends it as a remainder tree: the root
+/// has cofactor 1, and each child's cofactor is its parent's times its sibling's product, modulo
+/// `m`. All operands stay below `m^2`, so this takes `O(n)` multiplications of `m`-sized integers
+/// instead of computing `n` quotients of `x`. Returns an empty vector for empty `xs`.
+pub fn cofactors_mod(xs: &[Integer], m: &Integer) -> Vec<Integer> {
+  if xs.is_empty() {
+    return Vec::new();
+  }
+  let mut levels = vec![xs.iter().map(|x| int(x % m)).collect::<Vec<_>>()];
+  while levels[levels.len() - 1].len() > 1 {
+    let next = levels[levels.len() - 1]
+      .chunks(2)
+      .map(|pair| pair.iter().product::<Integer>() % m)
+      .collect();
+    levels.push(next);
+  }
+  let mut cofactors = vec![int(1) % m];
+  for level in levels.iter().rev().skip(1) {
+    cofactors = (0..level.len())
+      .map(|i| match level.get(i ^ 1) {
+        Some(sibling) => int(&cofactors[i / 2] * sibling) % m,
+        None => cofactors[i / 2].clone(),
+      })
+      .collect();
+  }
+  cofactors
+}
+
 /// Solves a linear congruence of form `ax = b mod m` for the set of solutions `x`. Solution sets
 /// are characterized by integers `mu` and `v` s.t. `x = mu + vn` and `n` is any integer.
+///
+/// Returns `None` if there is no solution, i.e. `gcd(a, m)` does not divide `b`, or if `m` is not
+/// positive. Use `linear_congruence_solutions` to enumerate the solutions mod `m`.
 pub fn solve_linear_congruence(
   a: &Integer,
   b: &Integer,
   m: &Integer,
 ) -> Option<(Integer, Integer)> {
+  linear_congruence_solutions(a, b, m).map(|solutions| (solutions.mu, solutions.v))
+}
+
+/// Solves `ax = b mod m` like `solve_linear_congruence`, returning the solution set as an iterator
+/// over its `g = gcd(a, m)` elements mod `m`, in increasing order. `g` can be as large as `m`, so
+/// take only as many as needed.
+///
+/// ```
+/// use accumulator::util::{int, linear_congruence_solutions};
+/// let solutions = linear_congruence_solutions(&int(6), &int(4), &int(10)).unwrap();
+/// assert_eq!(solutions.v, int(5));
+/// assert_eq!(solutions.collect::<Vec<_>>(), vec![int(4), int(9)]);
+/// ```
+pub fn linear_congruence_solutions(
+  a: &Integer,
+  b: &Integer,
+  m: &Integer,
+) -> Option<LinearCongruenceSolutions> {
+  if *m <= 0 {
+    return None;
+  }
   // g = gcd(a, m) => da + em = g
-  let (g, d, _) = <(Integer, Integer, Integer)>::from(a.gcd_cofactors_ref(m));
+  let (g, d, _) = a.xgcd(m);
 
   // q = floor_div(b, g)
   // r = b % g
-  let (q, r) = <(Integer, Integer)>::from(b.div_rem_floor_ref(&g));
+  let (q, r) = b.div_rem_euclid(&g);
   if r != 0 {
     return None;
   }
 
   let mu = (q * d) % m;
-  let v = m / g;
-  Some((mu, v))
+  let v = int(m / g);
+  let (_, next) = mu.div_rem_euclid(&v);
+  Some(LinearCongruenceSolutions {
+    mu,
+    v,
+    m: m.clone(),
+    next,
+  })
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+/// The solutions of a linear congruence `ax = b mod m`, as returned by
+/// `linear_congruence_solutions`. Iterating yields each solution in `[0, m)`.
+pub struct LinearCongruenceSolutions {
+  /// A solution, not necessarily in `[0, m)`.
+  pub mu: Integer,
+  /// The distance between consecutive solutions, `m / gcd(a, m)`.
+  pub v: Integer,
+  m: Integer,
+  next: Integer,
+}
+
+impl LinearCongruenceSolutions {
+  /// Returns the number of solutions mod `m`, i.e. `gcd(a, m)`.
+  pub fn count_mod_m(&self) -> Integer {
+    int(&self.m / &self.v)
+  }
+}
+
+impl Iterator for LinearCongruenceSolutions {
+  type Item = Integer;
+
+  fn next(&mut self) -> Option<Integer> {
+    if self.next >= self.m {
+      return None;
+    }
+    let solution = self.next.clone();
+    self.next += &self.v;
+    Some(solution)
+  }
 }
 
 /// Folds over `xs` but in a divide-and-conquer fashion: Instead of `F(F(F(F(acc, a), b), c), d))`
@@ -107,6 +350,21 @@ mod tests {
   use crate::group::{Group, Rsa2048, UnknownOrderGroup};
   use crate::util::int;
 
+  #[cfg(feature = "zeroize")]
+  #[test]
+  fn test_zeroize() {
+    let mut n = int(int(12345) << 1000);
+    let capacity = n.capacity();
+    zeroize_integer(&mut n);
+    assert_eq!(n, 0);
+    // The original allocation was overwritten in place, not replaced.
+    assert_eq!(n.capacity(), capacity);
+
+    let mut bytes = [0xab; 32];
+    zeroize_bytes(&mut bytes);
+    assert_eq!(bytes, [0; 32]);
+  }
+
   #[derive(Debug)]
   enum Never {}
 
@@ -154,6 +412,22 @@ mod tests {
     );
   }
 
+  #[test]
+  fn test_linear_congruence_solutions() {
+    // `12x = 8 mod 20` has `gcd(12, 20) = 4` solutions mod 20.
+    let solutions = linear_congruence_solutions(&int(12), &int(8), &int(20)).unwrap();
+    assert_eq!(solutions.count_mod_m(), 4);
+    let all = solutions.collect::<Vec<_>>();
+    assert_eq!(all, vec![int(4), int(9), int(14), int(19)]);
+    assert!(all.iter().all(|x| int(int(x * 12) - 8).is_divisible_u(20)));
+
+    let all = linear_congruence_solutions(&int(0), &int(6), &int(3))
+      .unwrap()
+      .collect::<Vec<_>>();
+    assert_eq!(all, vec![int(0), int(1), int(2)]);
+    assert!(linear_congruence_solutions(&int(3), &int(2), &int(0)).is_none());
+  }
+
   #[test]
   fn test_linear_congruence_solver_no_solution() {
     // Let `g = gcd(a, m)`. If `b` is not divisible by `g`, there are no solutions. If `b` is
@@ -184,6 +458,75 @@ mod tests {
     assert!(shamir_trick::<Rsa2048>(&xth_root, &yth_root, x, y) == None);
   }
 
+  #[test]
+  fn test_shamir_trick_n() {
+    let g = Rsa2048::unknown_order_elem();
+    let exps = [13, 17, 19, 23].iter().map(|&x| int(x)).collect::<Vec<_>>();
+    let product = int(13 * 17 * 19 * 23);
+    let h = Rsa2048::exp(&g, &product);
+    let roots = exps
+      .iter()
+      .map(|x| Rsa2048::exp(&g, &int(&product / x)))
+      .collect::<Vec<_>>();
+    assert!(shamir_trick_n::<Rsa2048>(&roots, &exps) == Some(g.clone()));
+    assert!(shamir_trick_n::<Rsa2048>(&roots[..1], &exps[..1]) == Some(roots[0].clone()));
+    assert!(shamir_trick_n::<Rsa2048>(std::slice::from_ref(&h), &[int(1)]) == Some(h.clone()));
+    assert!(shamir_trick_n::<Rsa2048>(&roots, &exps[..3]).is_none());
+    assert!(shamir_trick_n::<Rsa2048>(&[], &[]).is_none());
+
+    // Pairwise coprimality fails only between the first and last roots.
+    let (x, y, z) = (int(13 * 17), int(19), int(23 * 13));
+    let roots = [&x, &y, &z]
+      .iter()
+      .map(|&e| Rsa2048::exp(&g, &int(int(&x * &y) * &z / e)))
+      .collect::<Vec<_>>();
+    assert!(shamir_trick_n::<Rsa2048>(&roots, &[x, y, z]).is_none());
+    let mut roots = roots;
+    roots[1] = Rsa2048::op(&roots[1], &g);
+    assert!(shamir_trick_n::<Rsa2048>(&roots[..2], &[int(13 * 17), int(19)]).is_none());
+  }
+
+  #[test]
+  fn test_root_factor() {
+    let g = Rsa2048::unknown_order_elem();
+    let xs = [3, 5, 7, 11, 13]
+      .iter()
+      .map(|&x| int(x))
+      .collect::<Vec<_>>();
+    let x: Integer = xs.iter().product();
+    let roots = root_factor::<Rsa2048>(&g, &xs);
+    assert_eq!(roots.len(), xs.len());
+    for (root, x_i) in roots.iter().zip(xs.iter()) {
+      assert!(*root == Rsa2048::exp(&g, &int(&x / x_i)));
+    }
+    assert!(root_factor::<Rsa2048>(&g, &[]).is_empty());
+  }
+
+  #[test]
+  fn test_product() {
+    let xs = (1..=20).map(int).collect::<Vec<_>>();
+    assert_eq!(product(xs.clone()), xs.iter().product::<Integer>());
+    assert_eq!(product(vec![int(7)]), int(7));
+    assert_eq!(product(vec![]), int(1));
+  }
+
+  #[test]
+  fn test_cofactors_mod() {
+    let xs = [3, 5, 7, 11, 13, 17, 19]
+      .iter()
+      .map(|&x| int(x))
+      .collect::<Vec<_>>();
+    let x: Integer = xs.iter().product();
+    let m = int(1_000_003);
+    let cofactors = cofactors_mod(&xs, &m);
+    assert_eq!(cofactors.len(), xs.len());
+    for (cofactor, x_i) in cofactors.iter().zip(xs.iter()) {
+      assert_eq!(*cofactor, int(&x / x_i) % &m);
+    }
+    assert_eq!(cofactors_mod(&xs[..1], &m), vec![int(1)]);
+    assert!(cofactors_mod(&[], &m).is_empty());
+  }
+
   #[test]
   fn test_merge_product() {
     let ints = vec![int(3), int(5), int(7), int(9), int(11)];
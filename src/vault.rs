@@ -0,0 +1,346 @@
+//! Encrypted witness backups, for custodians keeping witnesses for many holders in cold storage.
+//!
+//! A `WitnessVault` holds the accumulator some witnesses are for, and each witness with a label
+//! chosen by the custodian (e.g. an account ID or the element's encoding), since witnesses do not
+//! say which element they belong to. After restoring a vault, the custodian catches its witnesses
+//! up to the current accumulator with `update_membership_witness`, starting from the stored one.
+//!
+//! `export_vault` encrypts the witnesses with an AEAD supplied by the caller through the `Aead`
+//! trait (e.g. ChaCha20-Poly1305 or AES-256-GCM from a crate of their choice), in records of up to
+//! `WITNESSES_PER_RECORD` witnesses, so that millions of witnesses never need to be decrypted at
+//! once. The header, holding the format version, group ID, vault ID and accumulator, is stored in
+//! the clear but authenticated with every record. Each record is encrypted under the nonce made of
+//! the vault ID and the record's index, and the last record says that it is the last, so records
+//! cannot be reordered, dropped, or moved between vaults without `import_vault` noticing.
+use crate::accumulator::{Accumulator, Witness};
+use crate::group::{ElemBytes, UnknownOrderGroup};
+use crate::proof::{DecodeError, Decoder};
+use std::convert::TryFrom;
+use std::hash::Hash;
+use std::io::{self, Read, Write};
+
+/// The version of the vault encoding produced by `export_vault`.
+pub const VAULT_FORMAT_VERSION: u8 = 1;
+
+/// The maximum number of witnesses encrypted together in one record.
+pub const WITNESSES_PER_RECORD: usize = 1024;
+
+/// The length of AEAD nonces: an 8-byte vault ID followed by a 4-byte big-endian record index.
+pub const NONCE_BYTES: usize = 12;
+
+/// An authenticated encryption scheme with associated data, keyed by the caller.
+pub trait Aead {
+  /// Encrypts and authenticates `plaintext`, and authenticates `aad`, under `nonce`.
+  fn seal(&self, nonce: &[u8; NONCE_BYTES], aad: &[u8], plaintext: &[u8]) -> Vec<u8>;
+  /// Decrypts `ciphertext` sealed with the same `nonce` and `aad`, or returns `None` if it does
+  /// not authenticate.
+  fn open(&self, nonce: &[u8; NONCE_BYTES], aad: &[u8], ciphertext: &[u8]) -> Option<Vec<u8>>;
+}
+
+#[derive(Debug)]
+/// The ways exporting or importing a vault can fail.
+pub enum VaultError {
+  /// Reading or writing the vault failed.
+  Io(io::Error),
+  /// A record did not authenticate: the key is wrong, or the vault was modified, reordered or
+  /// truncated.
+  Unauthenticated,
+  /// The vault authenticated but was not a vault of this version and group.
+  BadEncoding,
+  /// A label is longer than 65535 bytes, so the vault cannot be exported.
+  LabelTooLong,
+}
+
+impl From<io::Error> for VaultError {
+  fn from(e: io::Error) -> Self {
+    VaultError::Io(e)
+  }
+}
+
+impl From<DecodeError> for VaultError {
+  fn from(_: DecodeError) -> Self {
+    VaultError::BadEncoding
+  }
+}
+
+#[derive(Debug, Eq, Hash, PartialEq)]
+/// An accumulator and labeled witnesses for it. See the module docs.
+pub struct WitnessVault<G: UnknownOrderGroup, T: Hash> {
+  /// The accumulator the witnesses are for.
+  pub acc: Accumulator<G, T>,
+  /// The witnesses, each with its label, in the order they were pushed.
+  pub entries: Vec<(Vec<u8>, Witness<G, T>)>,
+}
+
+impl<G: ElemBytes + UnknownOrderGroup, T: Eq + Hash> WitnessVault<G, T> {
+  /// Returns an empty vault for witnesses w.r.t. `acc`.
+  pub fn new(acc: Accumulator<G, T>) -> Self {
+    Self {
+      acc,
+      entries: Vec::new(),
+    }
+  }
+
+  /// Adds `witness` under `label`. Panics if `label` is longer than 65535 bytes.
+  pub fn push(&mut self, label: Vec<u8>, witness: Witness<G, T>) {
+    assert!(label.len() <= usize::from(u16::MAX), "label too long");
+    self.entries.push((label, witness));
+  }
+
+  /// Writes the vault to `writer`, encrypted with `aead`. `vault_id` must never be used twice
+  /// with the same key, or nonces repeat; drawing it at random for each export is enough for up
+  /// to about `2^32` exports per key.
+  ///
+  /// The header is the format version, group ID, `vault_id` and encoded accumulator. Each record
+  /// follows as a big-endian `u32` length and the sealed plaintext: a byte that is 1 for the last
+  /// record and 0 otherwise, then each witness as a big-endian `u16` label length, the label and
+  /// the encoded witness. Fails with `LabelTooLong`, before writing anything, if a label was
+  /// pushed onto `entries` directly and is longer than 65535 bytes.
+  pub fn export_vault<W: Write, A: Aead>(
+    &self,
+    mut writer: W,
+    aead: &A,
+    vault_id: [u8; 8],
+  ) -> Result<(), VaultError> {
+    if self
+      .entries
+      .iter()
+      .any(|(label, _)| u16::try_from(label.len()).is_err())
+    {
+      return Err(VaultError::LabelTooLong);
+    }
+    let header = self.header(vault_id);
+    writer.write_all(&header)?;
+    let mut records = self.entries.chunks(WITNESSES_PER_RECORD).peekable();
+    let mut index = 0;
+    // An empty vault still gets one (empty) last record.
+    loop {
+      let chunk = records.next().unwrap_or(&[]);
+      let last = records.peek().is_none();
+      let mut plaintext = vec![last as u8];
+      for (label, witness) in chunk {
+        let label_len = u16::try_from(label.len()).expect("label lengths checked above");
+        plaintext.extend_from_slice(&label_len.to_be_bytes());
+        plaintext.extend_from_slice(label);
+        plaintext.extend(witness.0.to_bytes());
+      }
+      let ciphertext = aead.seal(&nonce(vault_id, index), &header, &plaintext);
+      let len = u32::try_from(ciphertext.len()).expect("record too long");
+      writer.write_all(&len.to_be_bytes())?;
+      writer.write_all(&ciphertext)?;
+      if last {
+        break;
+      }
+      index += 1;
+    }
+    Ok(writer.flush()?)
+  }
+
+  /// Reads a vault written by `export_vault` from `reader`, decrypting it with `aead`. Fails with
+  /// `Unauthenticated` if any record fails to open or the last record is missing, and with
+  /// `BadEncoding` on a malformed header, or if anything follows the last record.
+  pub fn import_vault<R: Read, A: Aead>(mut reader: R, aead: &A) -> Result<Self, VaultError> {
+    let mut header = vec![0; 2 + 8 + 2 + G::elem_bytes_len()];
+    reader.read_exact(&mut header)?;
+    let (vault_id, acc) = Decoder::decode_all(&header, |decoder| {
+      if decoder.byte()? != VAULT_FORMAT_VERSION || decoder.byte()? != G::GROUP_ID {
+        return Err(DecodeError::BadTag);
+      }
+      let vault_id = *array_ref![decoder.bytes(8)?, 0, 8];
+      Ok((vault_id, Accumulator::<G, T>::decode(decoder)?))
+    })?;
+    let mut vault = Self::new(acc);
+    let mut index: u64 = 0;
+    loop {
+      let mut len = [0; 4];
+      if reader.read(&mut len[..1])? == 0 {
+        return Err(VaultError::Unauthenticated);
+      }
+      reader.read_exact(&mut len[1..])?;
+      let len = u64::from(u32::from_be_bytes(len));
+      let mut ciphertext = Vec::new();
+      // Grows with the data actually read, rather than with the claimed length.
+      if (&mut reader).take(len).read_to_end(&mut ciphertext)? as u64 != len {
+        return Err(VaultError::Unauthenticated);
+      }
+      let index_u32 = u32::try_from(index).map_err(|_| VaultError::BadEncoding)?;
+      let plaintext = aead
+        .open(&nonce(vault_id, index_u32), &header, &ciphertext)
+        .ok_or(VaultError::Unauthenticated)?;
+      let last = Decoder::decode_all(&plaintext, |decoder| {
+        let last = match decoder.byte()? {
+          0 => false,
+          1 => true,
+          _ => return Err(DecodeError::BadTag),
+        };
+        while decoder.remaining() > 0 {
+          let label_len = decoder.bytes(2)?;
+          let label_len = usize::from(u16::from_be_bytes([label_len[0], label_len[1]]));
+          let label = decoder.bytes(label_len)?.to_vec();
+          let witness = Accumulator::decode(decoder)?;
+          if witness.params() != vault.acc.params() {
+            return Err(DecodeError::BadTag);
+          }
+          vault.entries.push((label, Witness(witness)));
+        }
+        Ok(last)
+      })?;
+      if last {
+        break;
+      }
+      index += 1;
+    }
+    if reader.read(&mut [0])? != 0 {
+      return Err(VaultError::BadEncoding);
+    }
+    Ok(vault)
+  }
+
+  fn header(&self, vault_id: [u8; 8]) -> Vec<u8> {
+    let mut header = vec![VAULT_FORMAT_VERSION, G::GROUP_ID];
+    header.extend_from_slice(&vault_id);
+    header.extend(self.acc.to_bytes());
+    header
+  }
+}
+
+fn nonce(vault_id: [u8; 8], index: u32) -> [u8; NONCE_BYTES] {
+  let mut nonce = [0; NONCE_BYTES];
+  nonce[..8].copy_from_slice(&vault_id);
+  nonce[8..].copy_from_slice(&index.to_be_bytes());
+  nonce
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::group::Rsa2048;
+  use blake2_rfc::blake2b::Blake2b;
+
+  // An insecure stand-in for a real AEAD: a Blake2b keystream and a keyed Blake2b tag.
+  struct ToyAead(&'static [u8]);
+
+  impl ToyAead {
+    fn keystream(&self, nonce: &[u8; NONCE_BYTES], len: usize) -> Vec<u8> {
+      let mut stream = Vec::new();
+      for block in 0..=(len / 64) as u64 {
+        let mut hasher = Blake2b::with_key(64, self.0);
+        hasher.update(nonce);
+        hasher.update(&block.to_be_bytes());
+        stream.extend_from_slice(hasher.finalize().as_bytes());
+      }
+      stream.truncate(len);
+      stream
+    }
+
+    fn tag(&self, nonce: &[u8; NONCE_BYTES], aad: &[u8], ciphertext: &[u8]) -> Vec<u8> {
+      let mut hasher = Blake2b::with_key(32, self.0);
+      hasher.update(&[0xff]);
+      hasher.update(nonce);
+      hasher.update(&(aad.len() as u64).to_be_bytes());
+      hasher.update(aad);
+      hasher.update(ciphertext);
+      hasher.finalize().as_bytes().to_vec()
+    }
+  }
+
+  impl Aead for ToyAead {
+    fn seal(&self, nonce: &[u8; NONCE_BYTES], aad: &[u8], plaintext: &[u8]) -> Vec<u8> {
+      let stream = self.keystream(nonce, plaintext.len());
+      let mut ciphertext = plaintext
+        .iter()
+        .zip(stream)
+        .map(|(p, k)| p ^ k)
+        .collect::<Vec<_>>();
+      let tag = self.tag(nonce, aad, &ciphertext);
+      ciphertext.extend(tag);
+      ciphertext
+    }
+
+    fn open(&self, nonce: &[u8; NONCE_BYTES], aad: &[u8], ciphertext: &[u8]) -> Option<Vec<u8>> {
+      if ciphertext.len() < 32 {
+        return None;
+      }
+      let (ciphertext, tag) = ciphertext.split_at(ciphertext.len() - 32);
+      if self.tag(nonce, aad, ciphertext) != tag {
+        return None;
+      }
+      let stream = self.keystream(nonce, ciphertext.len());
+      Some(ciphertext.iter().zip(stream).map(|(c, k)| c ^ k).collect())
+    }
+  }
+
+  const AEAD: ToyAead = ToyAead(b"vault key");
+
+  fn export(vault: &WitnessVault<Rsa2048, &'static str>) -> Vec<u8> {
+    let mut bytes = Vec::new();
+    vault.export_vault(&mut bytes, &AEAD, [7; 8]).unwrap();
+    bytes
+  }
+
+  fn import(
+    bytes: &[u8],
+    aead: &ToyAead,
+  ) -> Result<WitnessVault<Rsa2048, &'static str>, VaultError> {
+    WitnessVault::import_vault(bytes, aead)
+  }
+
+  #[test]
+  fn test_witness_vault() {
+    let acc = Accumulator::<Rsa2048, &'static str>::empty().add(&["a", "b"]);
+    let mut vault = WitnessVault::new(acc.clone());
+    assert!(import(&export(&vault), &AEAD).unwrap() == vault);
+    // Enough witnesses for two records.
+    let witness = Witness(Accumulator::empty().add(&["b"]));
+    for i in 0..=WITNESSES_PER_RECORD {
+      vault.push(format!("holder {}", i).into_bytes(), witness.clone());
+    }
+    let restored = import(&export(&vault), &AEAD).unwrap();
+    assert!(restored == vault);
+    let (label, witness) = restored.entries[WITNESSES_PER_RECORD].clone();
+    assert_eq!(
+      label,
+      format!("holder {}", WITNESSES_PER_RECORD).into_bytes()
+    );
+    let proof = restored.acc.prove_membership(&[("a", witness)]).unwrap();
+    assert!(acc.verify_membership(&"a", &proof));
+  }
+
+  #[test]
+  fn test_witness_vault_tampered() {
+    let mut vault = WitnessVault::new(Accumulator::<Rsa2048, &'static str>::empty().add(&["a"]));
+    for _ in 0..=WITNESSES_PER_RECORD {
+      vault.push(b"holder".to_vec(), Witness(Accumulator::empty()));
+    }
+    let bytes = export(&vault);
+    match import(&bytes, &ToyAead(b"other key")) {
+      Err(VaultError::Unauthenticated) => (),
+      _ => panic!("expected Unauthenticated"),
+    }
+    for i in &[0, 2, 20, bytes.len() / 2, bytes.len() - 1] {
+      let mut tampered = bytes.clone();
+      tampered[*i] ^= 1;
+      assert!(import(&tampered, &AEAD).is_err());
+    }
+    // Dropping the last record, or appending to it, is detected.
+    let header_len = 2 + 8 + 2 + Rsa2048::elem_bytes_len();
+    let first_len = u32::from_be_bytes(*array_ref![bytes, header_len, 4]) as usize;
+    assert!(import(&bytes[..header_len + 4 + first_len], &AEAD).is_err());
+    let mut extended = bytes.clone();
+    extended.push(0);
+    assert!(import(&extended, &AEAD).is_err());
+  }
+
+  #[test]
+  fn test_witness_vault_label_too_long() {
+    let mut vault = WitnessVault::new(Accumulator::<Rsa2048, &'static str>::empty());
+    let label = vec![0; usize::from(u16::MAX) + 1];
+    vault.entries.push((label, Witness(Accumulator::empty())));
+    let mut bytes = Vec::new();
+    match vault.export_vault(&mut bytes, &AEAD, [7; 8]) {
+      Err(VaultError::LabelTooLong) => (),
+      _ => panic!("expected LabelTooLong"),
+    }
+    assert!(bytes.is_empty());
+  }
+}
@@ -0,0 +1,103 @@
+//! Wesolowski verifiable delay functions over the crate's groups. See Wesolowski 2018
+//! [\[Link\]](https://eprint.iacr.org/2018/623.pdf).
+//!
+//! The VDF maps an input `x` to `y = x^(2^t)`, which takes `t` sequential squarings in a group of
+//! unknown order. Its proof is an NI-PoE for that statement, computed alongside the squarings
+//! instead of from the exponent `2^t`, which would take `t` bits to store. Verification costs two
+//! exponentiations by about 256 bits, whatever `t` is.
+//!
+//! Inputs should be derived from a seed with `UnknownOrderGroup::elem_from_hash`, and class groups
+//! are the usual choice of group, since RSA groups need a trusted setup.
+use crate::group::UnknownOrderGroup;
+use crate::hash::hash_to_prime;
+use crate::integer::{Integer, IntegerOps};
+use crate::util::int;
+
+// Separates VDF challenges from those of other proofs.
+const DOMAIN: &str = "accumulator vdf";
+
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+/// A proof that a VDF output is correct.
+pub struct VdfProof<G: UnknownOrderGroup> {
+  pi: G::Elem,
+}
+
+/// Returns `x^(2^t)`, computed with `t` squarings.
+pub fn evaluate<G: UnknownOrderGroup>(x: &G::Elem, t: u64) -> G::Elem {
+  let mut y = x.clone();
+  for _ in 0..t {
+    G::square_assign(&mut y);
+  }
+  y
+}
+
+/// Returns `x^(2^t)` and a proof of it. Takes `2t` squarings, half of them for the proof.
+pub fn evaluate_with_proof<G: UnknownOrderGroup>(x: &G::Elem, t: u64) -> (G::Elem, VdfProof<G>) {
+  let y = evaluate::<G>(x, t);
+  let proof = VdfProof::prove(x, t, &y);
+  (y, proof)
+}
+
+impl<G: UnknownOrderGroup> VdfProof<G> {
+  /// Proves that `y = x^(2^t)`, which must hold.
+  ///
+  /// The proof is `x^q` for `q = floor(2^t / l)`, where `l` is the challenge prime. The bits of `q`
+  /// are found by long division of `2^t` by `l`, most significant first, while `x^q` is built up by
+  /// square-and-multiply, so that neither `2^t` nor `q` is ever held.
+  pub fn prove(x: &G::Elem, t: u64, y: &G::Elem) -> Self {
+    let l = challenge::<G>(x, t, y);
+    let mut pi = G::id();
+    let mut r = int(1);
+    for _ in 0..t {
+      G::square_assign(&mut pi);
+      r <<= 1;
+      if r >= l {
+        r -= &l;
+        G::op_assign(&mut pi, x);
+      }
+    }
+    Self { pi }
+  }
+
+  /// Verifies that `y = x^(2^t)`.
+  pub fn verify(x: &G::Elem, t: u64, y: &G::Elem, proof: &Self) -> bool {
+    let l = challenge::<G>(x, t, y);
+    let r = int(2).mod_pow(&Integer::from(t), &l).unwrap();
+    // y = pi^l * x^r
+    G::op(&G::exp(&proof.pi, &l), &G::exp(x, &r)) == *y
+  }
+}
+
+fn challenge<G: UnknownOrderGroup>(x: &G::Elem, t: u64, y: &G::Elem) -> Integer {
+  hash_to_prime(&(DOMAIN, x, t, y))
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::group::{Group, Rsa2048};
+
+  #[test]
+  fn test_vdf() {
+    let x = Rsa2048::elem_from_hash(&"seed");
+    let t = 1000;
+    let (y, proof) = evaluate_with_proof::<Rsa2048>(&x, t);
+    assert!(y == Rsa2048::exp(&x, &(int(1) << t as u32)));
+    assert!(VdfProof::verify(&x, t, &y, &proof));
+
+    assert!(!VdfProof::verify(&x, t + 1, &y, &proof));
+    assert!(!VdfProof::verify(&x, t, &Rsa2048::op(&y, &x), &proof));
+    let other = Rsa2048::elem_from_hash(&"other seed");
+    assert!(!VdfProof::verify(&other, t, &y, &proof));
+    let (_, other_proof) = evaluate_with_proof::<Rsa2048>(&other, t);
+    assert!(!VdfProof::verify(&x, t, &y, &other_proof));
+  }
+
+  #[test]
+  fn test_vdf_zero_steps() {
+    let x = Rsa2048::elem_from_hash(&"seed");
+    let (y, proof) = evaluate_with_proof::<Rsa2048>(&x, 0);
+    assert!(y == x);
+    assert!(VdfProof::verify(&x, 0, &y, &proof));
+  }
+}
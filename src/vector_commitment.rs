@@ -1,8 +1,15 @@
 //! Vector commitment library, built on a generic group interface. **Very much a WIP.**
-use super::accumulator::{Accumulator, MembershipProof, NonmembershipProof, Witness};
+use super::accumulator::{
+  Accumulator, AccumulatorElement, MembershipProof, NonmembershipProof, Witness,
+};
 use crate::group::UnknownOrderGroup;
-use rug::Integer;
+use crate::hash::blake2b;
+use crate::integer::{Integer, IntegerOps};
+use crate::proof::{Poe, Pokcr, Poke2};
+use crate::util::int;
 use std::collections::HashSet;
+use std::marker::PhantomData;
+use std::ops::Range;
 
 #[derive(Debug)]
 /// The different types of vector commitment errors.
@@ -16,9 +23,19 @@ pub enum VCError {
 }
 
 #[derive(Clone, Debug, Eq, Hash, PartialEq)]
-/// A vector commitment, wrapping an underlying accumulator. The accumulator contains indices of an
-/// abstract vector where the corresponding bit is True.
-pub struct VectorCommitment<G: UnknownOrderGroup>(Accumulator<G, Integer>);
+/// A vector commitment, wrapping an underlying accumulator.
+///
+/// By default (`V = Bit`), the accumulator contains indices of an abstract vector where the
+/// corresponding bit is True. For any other value type `V`, it contains a hash of each (index,
+/// value) pair instead, which commits to a set of such pairs rather than a vector: nothing in the
+/// commitment keeps an index from holding two values, and an opening shows that a pair is in the
+/// set, not that its value is the only one at its index. Position binding therefore relies on
+/// whoever updates the commitment, as described for `update_entries`.
+pub struct VectorCommitment<G: UnknownOrderGroup, V = Bit>(Accumulator<G, Integer>, PhantomData<V>);
+
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+/// Marker for vector commitments to bits, the default value type of `VectorCommitment`.
+pub enum Bit {}
 
 #[derive(Clone, Debug, Eq, Hash, PartialEq)]
 /// A vector commitment proof.
@@ -27,6 +44,45 @@ pub struct VectorProof<G: UnknownOrderGroup> {
   nonmembership_proof: NonmembershipProof<G, Integer>,
 }
 
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+/// A proof of openings of several vector commitments at once (e.g. one per shard). Following BBF,
+/// the witnesses for all openings are aggregated with PoKCR, so the proof carries one element for
+/// all set bits and one for all unset bits, plus a PoKE2 per commitment.
+pub struct AggregateVectorProof<G: UnknownOrderGroup> {
+  membership_proof: Pokcr<G>,
+  nonmembership_proof: Pokcr<G>,
+  v: Vec<G::Elem>,
+  poke2_proofs: Vec<Poke2<G>>,
+}
+
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+/// A proof of the bits at a contiguous range of positions. See `VectorCommitment::open_range`.
+///
+/// `w` is a witness for the set bits in the range and `(d, v)` an LLX nonmembership witness for the
+/// unset ones. The two resulting proofs of exponentiation are checked in a single batched equation
+/// (see `Poe::verify_batch`), plus a PoKE2 that `v` is a power of the commitment.
+pub struct VectorRangeProof<G: UnknownOrderGroup> {
+  w: G::Elem,
+  d: G::Elem,
+  v: G::Elem,
+  poke2_proof: Poke2<G>,
+  membership_proof: Poe<G>,
+  nonmembership_proof: Poe<G>,
+}
+
+#[derive(Clone, Debug)]
+/// An opening of a single vector commitment, for use with `VectorCommitment::open_aggregate`.
+pub struct VectorOpening<'a, G: UnknownOrderGroup> {
+  /// The VC being opened.
+  pub vc: &'a VectorCommitment<G>,
+  /// All indices that are set (True) in `vc`.
+  pub vc_acc_set: &'a [Integer],
+  /// Indices you want to prove are unset (False).
+  pub zero_bits: &'a [Integer],
+  /// Indices you want to prove are set (True) and their witnesses.
+  pub one_bit_witnesses: &'a [(Integer, Witness<G, Integer>)],
+}
+
 fn group_elems_by_bit(bits: &[(bool, Integer)]) -> Result<(Vec<Integer>, Vec<Integer>), VCError> {
   let mut elems_with_one = vec![];
   let mut elems_with_zero = vec![];
@@ -44,12 +100,14 @@ fn group_elems_by_bit(bits: &[(bool, Integer)]) -> Result<(Vec<Integer>, Vec<Int
   Ok((elems_with_zero, elems_with_one))
 }
 
-impl<G: UnknownOrderGroup> VectorCommitment<G> {
+impl<G: UnknownOrderGroup, V> VectorCommitment<G, V> {
   /// Initializes a new vector commitment (VC).
   pub fn empty() -> Self {
-    Self(Accumulator::<G, Integer>::empty())
+    Self(Accumulator::<G, Integer>::empty(), PhantomData)
   }
+}
 
+impl<G: UnknownOrderGroup> VectorCommitment<G> {
   /// Updates a VC with a list of values and indices.
   ///
   /// # Arguments
@@ -69,7 +127,7 @@ impl<G: UnknownOrderGroup> VectorCommitment<G> {
       .prove_nonmembership(vc_acc_set, &elems_with_zero)
       .map_err(|_| VCError::UnexpectedState)?;
     Ok((
-      Self(new_acc),
+      Self(new_acc, PhantomData),
       VectorProof {
         membership_proof,
         nonmembership_proof,
@@ -130,8 +188,545 @@ impl<G: UnknownOrderGroup> VectorCommitment<G> {
       .verify_nonmembership(&elems_with_zero, nonmembership_proof);
     verified_membership && verified_nonmembership
   }
+
+  /// Opens several VCs at once, producing a single proof for all openings. The same index may not
+  /// be opened in more than one VC, since PoKCR needs the hashed indices of different VCs to be
+  /// coprime (this is the case for e.g. shards covering disjoint index ranges).
+  pub fn open_aggregate(openings: &[VectorOpening<G>]) -> Result<AggregateVectorProof<G>, VCError> {
+    let mut seen_indices = HashSet::new();
+    for opening in openings {
+      let indices = opening
+        .zero_bits
+        .iter()
+        .chain(opening.one_bit_witnesses.iter().map(|(i, _)| i));
+      for i in indices {
+        if !seen_indices.insert(i) {
+          return Err(VCError::ConflictingIndices);
+        }
+      }
+    }
+
+    let g = G::unknown_order_elem();
+    let mut membership_witnesses = Vec::with_capacity(openings.len());
+    let mut nonmembership_witnesses = Vec::with_capacity(openings.len());
+    let mut v = Vec::with_capacity(openings.len());
+    let mut poke2_proofs = Vec::with_capacity(openings.len());
+    for opening in openings {
+      let acc = &opening.vc.0;
+
+      // Deleting every set bit from the VC yields a single witness for all of them.
+      let witness = acc
+        .clone()
+        .delete(opening.one_bit_witnesses)
+        .map_err(|_| VCError::InvalidOpen)?;
+      membership_witnesses.push(witness.value().clone());
+
+      // LLX nonmembership: `d^x * v = g` for `d = g^a`, `v = acc^b`, and `ax + bs = 1`.
+      let x = acc.params().prime_hash_product(opening.zero_bits);
+      let s = acc.params().prime_hash_product(opening.vc_acc_set);
+      let (gcd, a, b) = x.xgcd(&s);
+      if gcd != int(1) {
+        return Err(VCError::InvalidOpen);
+      }
+      let v_j = G::exp(acc.value(), &b);
+      poke2_proofs.push(Poke2::prove(acc.value(), &b, &v_j));
+      nonmembership_witnesses.push(G::exp(&g, &a));
+      v.push(v_j);
+    }
+
+    Ok(AggregateVectorProof {
+      membership_proof: Pokcr::prove(&membership_witnesses),
+      nonmembership_proof: Pokcr::prove(&nonmembership_witnesses),
+      v,
+      poke2_proofs,
+    })
+  }
+
+  /// Verifies an aggregate opening of `vcs`, where `bits[j]` are the tuples (truth value, bit
+  /// index) opened in `vcs[j]`.
+  pub fn verify_aggregate(
+    vcs: &[&Self],
+    bits: &[&[(bool, Integer)]],
+    proof: &AggregateVectorProof<G>,
+  ) -> bool {
+    if vcs.is_empty()
+      || vcs.len() != bits.len()
+      || vcs.len() != proof.v.len()
+      || vcs.len() != proof.poke2_proofs.len()
+    {
+      return false;
+    }
+    let mut seen_indices = HashSet::new();
+    if !bits
+      .iter()
+      .flat_map(|b| b.iter())
+      .all(|(_, i)| seen_indices.insert(i))
+    {
+      return false;
+    }
+
+    let g = G::unknown_order_elem();
+    let mut one_alphas = Vec::with_capacity(vcs.len());
+    let mut one_x = Vec::with_capacity(vcs.len());
+    let mut zero_alphas = Vec::with_capacity(vcs.len());
+    let mut zero_x = Vec::with_capacity(vcs.len());
+    for ((vc, bits), (v_j, poke2_proof)) in vcs
+      .iter()
+      .zip(bits.iter())
+      .zip(proof.v.iter().zip(proof.poke2_proofs.iter()))
+    {
+      let acc = &vc.0;
+      if !Poke2::verify(acc.value(), v_j, poke2_proof) {
+        return false;
+      }
+      let (elems_with_zero, elems_with_one) = match group_elems_by_bit(bits) {
+        Ok(elems) => elems,
+        Err(_) => return false,
+      };
+      one_alphas.push(acc.value().clone());
+      one_x.push(acc.params().prime_hash_product(&elems_with_one));
+      zero_alphas.push(G::op(&g, &G::inv(v_j)));
+      zero_x.push(acc.params().prime_hash_product(&elems_with_zero));
+    }
+    Pokcr::verify(&one_alphas, &one_x, &proof.membership_proof)
+      && Pokcr::verify(&zero_alphas, &zero_x, &proof.nonmembership_proof)
+  }
+}
+
+impl<G: UnknownOrderGroup> VectorCommitment<G> {
+  /// Opens the bits at every position in `range` with a single proof.
+  ///
+  /// # Arguments
+  /// * `vc_acc_set` - All indices that are set (True).
+  /// * `range` - The positions to open.
+  pub fn open_range(
+    vc: &Self,
+    vc_acc_set: &[Integer],
+    range: Range<u64>,
+  ) -> Result<VectorRangeProof<G>, VCError> {
+    let acc = &vc.0;
+    let params = acc.params();
+    let set = vc_acc_set.iter().collect::<HashSet<_>>();
+    let (ones, zeros): (Vec<_>, Vec<_>) = range.map(int).partition(|i| set.contains(i));
+    let g = G::unknown_order_elem();
+    let s = params.prime_hash_product(vc_acc_set);
+    let x_one = params.prime_hash_product(&ones);
+    let x_zero = params.prime_hash_product(&zeros);
+
+    let (rest, remainder) = s.div_rem_euclid(&x_one);
+    let w = G::exp(&g, &rest);
+    if remainder != 0 || G::exp(&w, &x_one) != *acc.value() {
+      return Err(VCError::InvalidOpen);
+    }
+
+    // LLX nonmembership: `d^x * v = g` for `d = g^a`, `v = acc^b`, and `ax + bs = 1`.
+    let (gcd, a, b) = x_zero.xgcd(&s);
+    if gcd != int(1) {
+      return Err(VCError::InvalidOpen);
+    }
+    let d = G::exp(&g, &a);
+    let v = G::exp(acc.value(), &b);
+    let gv_inv = G::op(&g, &G::inv(&v));
+    Ok(VectorRangeProof {
+      poke2_proof: Poke2::prove(acc.value(), &b, &v),
+      membership_proof: Poe::prove(&w, &x_one, acc.value()),
+      nonmembership_proof: Poe::prove(&d, &x_zero, &gv_inv),
+      w,
+      d,
+      v,
+    })
+  }
+
+  /// Verifies that the bits at positions `start..start + bits.len()` are `bits`. Returns false if
+  /// the end of that range does not fit in a `u64`.
+  pub fn verify_range(vc: &Self, start: u64, bits: &[bool], proof: &VectorRangeProof<G>) -> bool {
+    if start.checked_add(bits.len() as u64).is_none() {
+      return false;
+    }
+    let acc = &vc.0;
+    let params = acc.params();
+    let (ones, zeros): (Vec<_>, Vec<_>) = bits.iter().zip(start..).partition(|(bit, _)| **bit);
+    let x_one = ones
+      .iter()
+      .map(|(_, i)| params.hash_to_prime(&int(*i)))
+      .product();
+    let x_zero = zeros
+      .iter()
+      .map(|(_, i)| params.hash_to_prime(&int(*i)))
+      .product();
+    let g = G::unknown_order_elem();
+    let gv_inv = G::op(&g, &G::inv(&proof.v));
+    Poke2::verify(acc.value(), &proof.v, &proof.poke2_proof)
+      && Poe::verify_batch(&[
+        (
+          proof.w.clone(),
+          x_one,
+          acc.value().clone(),
+          proof.membership_proof.clone(),
+        ),
+        (
+          proof.d.clone(),
+          x_zero,
+          gv_inv,
+          proof.nonmembership_proof.clone(),
+        ),
+      ])
+  }
+}
+
+impl<G: UnknownOrderGroup, V: AccumulatorElement> VectorCommitment<G, V> {
+  /// Returns the accumulated element for `value` at `index`. Compute witnesses over these.
+  pub fn entry(index: &Integer, value: &V) -> Integer {
+    blake2b(&(index, value))
+  }
+
+  /// Updates a VC by removing entries (tuples (index, value, witness)) and adding new ones (tuples
+  /// (index, value)), returning a proof for the added entries. Returns `ConflictingIndices` if two
+  /// additions share an index.
+  ///
+  /// The commitment does not record which indices hold values, so an addition at an index that
+  /// already holds one is not detected, and leaves both values provably at that index. Callers
+  /// must track occupied indices themselves, and change a value by removing the old entry in the
+  /// same update.
+  ///
+  /// Uses a move instead of a `&self` reference to prevent accidental use of the old VC state.
+  pub fn update_entries(
+    vc: Self,
+    removals: &[(Integer, V, Witness<G, Integer>)],
+    additions: &[(Integer, V)],
+  ) -> Result<(Self, MembershipProof<G, Integer>), VCError> {
+    let mut seen_indices = HashSet::new();
+    if !additions.iter().all(|(i, _)| seen_indices.insert(i)) {
+      return Err(VCError::ConflictingIndices);
+    }
+    let removals = removals
+      .iter()
+      .map(|(i, value, witness)| (Self::entry(i, value), witness.clone()))
+      .collect::<Vec<_>>();
+    let acc = vc
+      .0
+      .delete(&removals)
+      .map_err(|_| VCError::UnexpectedState)?;
+    let (new_acc, proof) = acc.add_with_proof(&Self::entries(additions));
+    Ok((Self(new_acc, PhantomData), proof))
+  }
+
+  /// Opens a VC at the given entries (tuples (index, value, witness)).
+  pub fn open_entries(
+    vc: &Self,
+    entry_witnesses: &[(Integer, V, Witness<G, Integer>)],
+  ) -> Result<MembershipProof<G, Integer>, VCError> {
+    let elem_witnesses = entry_witnesses
+      .iter()
+      .map(|(i, value, witness)| (Self::entry(i, value), witness.clone()))
+      .collect::<Vec<_>>();
+    vc.0
+      .prove_membership(&elem_witnesses)
+      .map_err(|_| VCError::InvalidOpen)
+  }
+
+  /// Verifies that the VC holds the claimed `entries` (tuples (index, value)).
+  pub fn verify_entries(
+    vc: &Self,
+    entries: &[(Integer, V)],
+    proof: &MembershipProof<G, Integer>,
+  ) -> bool {
+    vc.0.verify_membership_batch(&Self::entries(entries), proof)
+  }
+
+  fn entries(entries: &[(Integer, V)]) -> Vec<Integer> {
+    entries
+      .iter()
+      .map(|(i, value)| Self::entry(i, value))
+      .collect()
+  }
+
+  /// Returns the accumulated element for `value` in field `field` of the record at `index`.
+  /// Compute witnesses over these.
+  ///
+  /// A VC should hold either records or plain entries, since an entry whose value hashes like a
+  /// (field, value) pair is indistinguishable from that field.
+  pub fn field_entry(index: &Integer, field: u32, value: &V) -> Integer {
+    blake2b(&(index, field, value))
+  }
+
+  /// Adds `records` (tuples (index, fields)) to a VC, each field committed separately under its
+  /// position in the record, so that fields can be opened and changed individually. Returns a proof
+  /// for all added fields. The indices must not already hold records.
+  ///
+  /// Uses a move instead of a `&self` reference to prevent accidental use of the old VC state.
+  pub fn add_records(
+    vc: Self,
+    records: &[(Integer, Vec<V>)],
+  ) -> Result<(Self, MembershipProof<G, Integer>), VCError> {
+    let mut seen_indices = HashSet::new();
+    if !records.iter().all(|(i, _)| seen_indices.insert(i)) {
+      return Err(VCError::ConflictingIndices);
+    }
+    let additions = records
+      .iter()
+      .flat_map(|(i, fields)| {
+        (0..)
+          .zip(fields)
+          .map(move |(field, value)| (i.clone(), field, value.clone()))
+      })
+      .collect::<Vec<_>>();
+    Self::update_fields(vc, &[], &additions)
+  }
+
+  /// Updates a VC by removing record fields (tuples (index, field, value, witness)) and adding new
+  /// ones (tuples (index, field, value)), returning a proof for the added fields. As with
+  /// `update_entries`, change a field by removing its old value in the same update.
+  ///
+  /// Uses a move instead of a `&self` reference to prevent accidental use of the old VC state.
+  pub fn update_fields(
+    vc: Self,
+    removals: &[(Integer, u32, V, Witness<G, Integer>)],
+    additions: &[(Integer, u32, V)],
+  ) -> Result<(Self, MembershipProof<G, Integer>), VCError> {
+    let mut seen_fields = HashSet::new();
+    if !additions
+      .iter()
+      .all(|(i, field, _)| seen_fields.insert((i, field)))
+    {
+      return Err(VCError::ConflictingIndices);
+    }
+    let removals = Self::field_witnesses(removals);
+    let acc = vc
+      .0
+      .delete(&removals)
+      .map_err(|_| VCError::UnexpectedState)?;
+    let (new_acc, proof) = acc.add_with_proof(&Self::field_entries(additions));
+    Ok((Self(new_acc, PhantomData), proof))
+  }
+
+  /// Opens a VC at the given record fields (tuples (index, field, value, witness)), revealing
+  /// nothing about the other fields of their records.
+  pub fn open_fields(
+    vc: &Self,
+    field_witnesses: &[(Integer, u32, V, Witness<G, Integer>)],
+  ) -> Result<MembershipProof<G, Integer>, VCError> {
+    vc.0
+      .prove_membership(&Self::field_witnesses(field_witnesses))
+      .map_err(|_| VCError::InvalidOpen)
+  }
+
+  /// Verifies that the VC holds the claimed record `fields` (tuples (index, field, value)).
+  pub fn verify_fields(
+    vc: &Self,
+    fields: &[(Integer, u32, V)],
+    proof: &MembershipProof<G, Integer>,
+  ) -> bool {
+    vc.0
+      .verify_membership_batch(&Self::field_entries(fields), proof)
+  }
+
+  fn field_entries(fields: &[(Integer, u32, V)]) -> Vec<Integer> {
+    fields
+      .iter()
+      .map(|(i, field, value)| Self::field_entry(i, *field, value))
+      .collect()
+  }
+
+  fn field_witnesses(
+    field_witnesses: &[(Integer, u32, V, Witness<G, Integer>)],
+  ) -> Vec<(Integer, Witness<G, Integer>)> {
+    field_witnesses
+      .iter()
+      .map(|(i, field, value, witness)| (Self::field_entry(i, *field, value), witness.clone()))
+      .collect()
+  }
 }
 
 // TODO: Write tests.
 #[cfg(test)]
-mod tests {}
+mod tests {
+  use super::*;
+  use crate::group::Rsa2048;
+
+  fn new_vc(set: &[Integer]) -> VectorCommitment<Rsa2048> {
+    VectorCommitment(Accumulator::empty().add(set), PhantomData)
+  }
+
+  #[test]
+  fn test_typed_values() {
+    let entries = [(int(0), "foo".to_string()), (int(1), "bar".to_string())];
+    let (vc, proof) =
+      VectorCommitment::<Rsa2048, String>::update_entries(VectorCommitment::empty(), &[], &entries)
+        .unwrap();
+    assert!(VectorCommitment::verify_entries(&vc, &entries, &proof));
+    assert!(!VectorCommitment::verify_entries(
+      &vc,
+      &[(int(0), "bar".to_string()), (int(1), "foo".to_string())],
+      &proof
+    ));
+
+    // Change the value at index 1 and open it.
+    let foo = [VectorCommitment::<Rsa2048, String>::entry(
+      &entries[0].0,
+      &entries[0].1,
+    )];
+    let bar_witness = Witness(Accumulator::empty().add(&foo));
+    let baz = [(int(1), "baz".to_string())];
+    let (vc, _) =
+      VectorCommitment::update_entries(vc, &[(int(1), "bar".to_string(), bar_witness)], &baz)
+        .unwrap();
+    let baz_witness = Witness(Accumulator::empty().add(&foo));
+    let proof =
+      VectorCommitment::open_entries(&vc, &[(int(1), "baz".to_string(), baz_witness)]).unwrap();
+    assert!(VectorCommitment::verify_entries(&vc, &baz, &proof));
+    assert!(!VectorCommitment::verify_entries(
+      &vc,
+      &entries[1..],
+      &proof
+    ));
+  }
+
+  #[test]
+  fn test_typed_values_occupied_index() {
+    type StringVc = VectorCommitment<Rsa2048, String>;
+    let foo = [(int(0), "foo".to_string())];
+    let bar = [(int(0), "bar".to_string())];
+    assert!(matches!(
+      StringVc::update_entries(StringVc::empty(), &[], &[foo[0].clone(), bar[0].clone()]),
+      Err(VCError::ConflictingIndices)
+    ));
+
+    // Across updates the commitment cannot tell that index 0 is taken, so both values open.
+    let (vc, _) = StringVc::update_entries(StringVc::empty(), &[], &foo).unwrap();
+    let (vc, proof) = StringVc::update_entries(vc, &[], &bar).unwrap();
+    assert!(StringVc::verify_entries(&vc, &bar, &proof));
+    let foo_witness = Witness(Accumulator::empty().add(&[StringVc::entry(&bar[0].0, &bar[0].1)]));
+    let proof = StringVc::open_entries(&vc, &[(int(0), "foo".to_string(), foo_witness)]).unwrap();
+    assert!(StringVc::verify_entries(&vc, &foo, &proof));
+  }
+
+  #[test]
+  fn test_records() {
+    type AccountVc = VectorCommitment<Rsa2048, u64>;
+    // Records are (balance, nonce).
+    let records = [(int(0), vec![100, 1]), (int(1), vec![50, 7])];
+    let (vc, proof) = AccountVc::add_records(AccountVc::empty(), &records).unwrap();
+    let fields = [
+      (int(0), 0, 100),
+      (int(0), 1, 1),
+      (int(1), 0, 50),
+      (int(1), 1, 7),
+    ];
+    assert!(AccountVc::verify_fields(&vc, &fields, &proof));
+
+    // Open only the balance at index 1.
+    let others = [
+      AccountVc::field_entry(&int(0), 0, &100),
+      AccountVc::field_entry(&int(0), 1, &1),
+      AccountVc::field_entry(&int(1), 1, &7),
+    ];
+    let balance_witness = Witness(Accumulator::empty().add(&others));
+    let proof = AccountVc::open_fields(&vc, &[(int(1), 0, 50, balance_witness.clone())]).unwrap();
+    assert!(AccountVc::verify_fields(&vc, &[(int(1), 0, 50)], &proof));
+    assert!(!AccountVc::verify_fields(&vc, &[(int(1), 0, 51)], &proof));
+    assert!(!AccountVc::verify_fields(&vc, &[(int(1), 1, 50)], &proof));
+    assert!(!AccountVc::verify_fields(&vc, &[(int(0), 0, 50)], &proof));
+    assert!(AccountVc::open_fields(&vc, &[(int(1), 0, 49, balance_witness.clone())]).is_err());
+
+    // Change the balance at index 1.
+    let (vc, proof) =
+      AccountVc::update_fields(vc, &[(int(1), 0, 50, balance_witness)], &[(int(1), 0, 40)])
+        .unwrap();
+    assert!(AccountVc::verify_fields(&vc, &[(int(1), 0, 40)], &proof));
+    let (expected, _) = AccountVc::add_records(
+      AccountVc::empty(),
+      &[(int(0), vec![100, 1]), (int(1), vec![40, 7])],
+    )
+    .unwrap();
+    assert!(vc == expected);
+
+    match AccountVc::add_records(vc, &[(int(2), vec![1]), (int(2), vec![2])]) {
+      Err(VCError::ConflictingIndices) => (),
+      _ => panic!("expected ConflictingIndices"),
+    }
+  }
+
+  #[test]
+  fn test_open_aggregate() {
+    let shard_1_set = [int(0), int(2)];
+    let shard_2_set = [int(5)];
+    let shard_1 = new_vc(&shard_1_set);
+    let shard_2 = new_vc(&shard_2_set);
+    let shard_1_witnesses = [(int(0), Witness(Accumulator::empty().add(&[int(2)])))];
+    let proof = VectorCommitment::open_aggregate(&[
+      VectorOpening {
+        vc: &shard_1,
+        vc_acc_set: &shard_1_set,
+        zero_bits: &[int(1)],
+        one_bit_witnesses: &shard_1_witnesses,
+      },
+      VectorOpening {
+        vc: &shard_2,
+        vc_acc_set: &shard_2_set,
+        zero_bits: &[int(4), int(6)],
+        one_bit_witnesses: &[],
+      },
+    ])
+    .unwrap();
+
+    let shard_1_bits = [(true, int(0)), (false, int(1))];
+    let shard_2_bits = [(false, int(4)), (false, int(6))];
+    assert!(VectorCommitment::verify_aggregate(
+      &[&shard_1, &shard_2],
+      &[&shard_1_bits, &shard_2_bits],
+      &proof
+    ));
+    assert!(!VectorCommitment::verify_aggregate(
+      &[&shard_2, &shard_1],
+      &[&shard_1_bits, &shard_2_bits],
+      &proof
+    ));
+    let wrong_bits = [(false, int(0)), (false, int(1))];
+    assert!(!VectorCommitment::verify_aggregate(
+      &[&shard_1, &shard_2],
+      &[&wrong_bits, &shard_2_bits],
+      &proof
+    ));
+  }
+
+  #[test]
+  fn test_open_range() {
+    let set = [int(1), int(2), int(5), int(9)];
+    let vc = new_vc(&set);
+    let proof = VectorCommitment::open_range(&vc, &set, 0..6).unwrap();
+    let bits = [false, true, true, false, false, true];
+    assert!(VectorCommitment::verify_range(&vc, 0, &bits, &proof));
+    assert!(!VectorCommitment::verify_range(&vc, 1, &bits[1..], &proof));
+    let mut wrong_bits = bits;
+    wrong_bits[3] = true;
+    assert!(!VectorCommitment::verify_range(&vc, 0, &wrong_bits, &proof));
+
+    let proof = VectorCommitment::open_range(&vc, &set, 6..9).unwrap();
+    assert!(VectorCommitment::verify_range(&vc, 6, &[false; 3], &proof));
+    let proof = VectorCommitment::open_range(&vc, &set, 9..9).unwrap();
+    assert!(VectorCommitment::verify_range(&vc, 9, &[], &proof));
+    assert!(VectorCommitment::open_range(&vc, &set[1..], 0..4).is_err());
+    assert!(!VectorCommitment::verify_range(
+      &vc,
+      u64::MAX,
+      &[false],
+      &proof
+    ));
+  }
+
+  #[test]
+  fn test_open_aggregate_shared_index() {
+    let vc = new_vc(&[int(1)]);
+    let opening = VectorOpening {
+      vc: &vc,
+      vc_acc_set: &[int(1)],
+      zero_bits: &[int(0)],
+      one_bit_witnesses: &[],
+    };
+    match VectorCommitment::open_aggregate(&[opening.clone(), opening]) {
+      Err(VCError::ConflictingIndices) => (),
+      _ => panic!("expected ConflictingIndices"),
+    }
+  }
+}
@@ -0,0 +1,140 @@
+//! Verify-only handles on accumulators, for request handlers that only check proofs.
+//!
+//! An `AccumulatorView` borrows an accumulator's value and copies its parameters, and offers only
+//! verification. It has no element type parameter, so one view checks proofs for any element type
+//! under the same parameters, and it is `Copy` whatever the group, so it can be passed by value
+//! into per-request contexts. Services that must hold the value beyond a borrow take views of a
+//! `SharedAccumulator` kept alive for the request.
+use crate::accumulator::{
+  verify_membership_at, verify_nonmembership_at, Accumulator, MembershipProof, MembershipProofRef,
+  NonmembershipProof,
+};
+use crate::group::UnknownOrderGroup;
+use crate::hash::PrimeParams;
+use crate::util::Redacted;
+use std::fmt;
+use std::hash::Hash;
+
+/// A verify-only view of an accumulator. See the module docs.
+pub struct AccumulatorView<'a, G: UnknownOrderGroup> {
+  params: PrimeParams,
+  value: &'a G::Elem,
+}
+
+// Manual impls, since the derived ones would require `G` itself to implement each trait. Debug
+// output shows only a fingerprint of the value, as for `Accumulator`.
+impl<'a, G: UnknownOrderGroup> Clone for AccumulatorView<'a, G> {
+  fn clone(&self) -> Self {
+    *self
+  }
+}
+
+impl<'a, G: UnknownOrderGroup> Copy for AccumulatorView<'a, G> {}
+
+impl<'a, G: UnknownOrderGroup> PartialEq for AccumulatorView<'a, G> {
+  fn eq(&self, other: &Self) -> bool {
+    self.params == other.params && self.value == other.value
+  }
+}
+
+impl<'a, G: UnknownOrderGroup> Eq for AccumulatorView<'a, G> {}
+
+impl<'a, G: UnknownOrderGroup> fmt::Debug for AccumulatorView<'a, G> {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    f.debug_struct("AccumulatorView")
+      .field("params", &self.params)
+      .field("value", &Redacted(self.value))
+      .finish()
+  }
+}
+
+impl<'a, G: UnknownOrderGroup> AccumulatorView<'a, G> {
+  /// Returns a view of the accumulator with value `value` under `params`.
+  pub fn new<P: Into<PrimeParams>>(params: P, value: &'a G::Elem) -> Self {
+    Self {
+      params: params.into(),
+      value,
+    }
+  }
+
+  /// Returns the prime parameters of the viewed accumulator.
+  pub fn params(&self) -> PrimeParams {
+    self.params
+  }
+
+  /// Returns the value of the viewed accumulator.
+  pub fn value(&self) -> &'a G::Elem {
+    self.value
+  }
+
+  /// Like `Accumulator::verify_membership`.
+  pub fn verify_membership<T: Eq + Hash>(&self, t: &T, proof: &MembershipProof<G, T>) -> bool {
+    self.verify_membership_batch(std::slice::from_ref(t), proof)
+  }
+
+  /// Like `Accumulator::verify_membership_batch`.
+  pub fn verify_membership_batch<T: Eq + Hash>(
+    &self,
+    elems: &[T],
+    proof: &MembershipProof<G, T>,
+  ) -> bool {
+    self.verify_membership_ref(elems, proof.borrowed())
+  }
+
+  /// Like `Accumulator::verify_membership_ref`.
+  pub fn verify_membership_ref<T: Eq + Hash>(
+    &self,
+    elems: &[T],
+    proof: MembershipProofRef<'_, G, T>,
+  ) -> bool {
+    verify_membership_at(self.params, self.value, elems, proof, &())
+  }
+
+  /// Like `Accumulator::verify_nonmembership`.
+  pub fn verify_nonmembership<T: Eq + Hash>(
+    &self,
+    elems: &[T],
+    proof: &NonmembershipProof<G, T>,
+  ) -> bool {
+    verify_nonmembership_at(self.params, self.value, elems, proof, &())
+  }
+}
+
+impl<G: UnknownOrderGroup, T: Eq + Hash> Accumulator<G, T> {
+  /// Returns a verify-only view of this accumulator.
+  pub fn view(&self) -> AccumulatorView<'_, G> {
+    AccumulatorView::new(self.params(), self.value())
+  }
+}
+
+impl<'a, G: UnknownOrderGroup, T: Eq + Hash> From<&'a Accumulator<G, T>>
+  for AccumulatorView<'a, G>
+{
+  fn from(acc: &'a Accumulator<G, T>) -> Self {
+    acc.view()
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::group::Rsa2048;
+
+  #[test]
+  fn test_accumulator_view() {
+    let (acc, proof) = Accumulator::<Rsa2048, &'static str>::empty().add_with_proof(&["a", "b"]);
+    let (ints, int_proof) = Accumulator::<Rsa2048, u64>::empty().add_with_proof(&[1, 2]);
+    let view = acc.view();
+    let copy = view;
+    assert!(view == copy && view == AccumulatorView::from(&acc));
+    assert!(view.verify_membership_batch(&["a", "b"], &proof));
+    assert!(!view.verify_membership(&"c", &proof));
+    assert!(ints.view().verify_membership_batch(&[1, 2], &int_proof));
+    assert!(!view.verify_membership_batch(&[1, 2], &int_proof));
+
+    let nonmembership = acc.prove_nonmembership(&["a", "b"], &["c"]).unwrap();
+    assert!(copy.verify_nonmembership(&["c"], &nonmembership));
+    assert!(!copy.verify_nonmembership(&["a"], &nonmembership));
+    assert!(std::mem::size_of::<AccumulatorView<Rsa2048>>() <= 3 * std::mem::size_of::<usize>());
+  }
+}
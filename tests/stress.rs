@@ -1,27 +1,29 @@
 use accumulator::group::Rsa2048;
 use accumulator::Accumulator;
-use rand::Rng;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
 
 /// Adds 10,000 random primes to accumulator (unverified), then tests 100 more random additions
 /// (with verification) and 100 random elements are verified to be nonmembers.
 ///
-/// Takes about 5 minutes.
+/// Elements come from a fixed seed, so failures reproduce from run to run.
 ///
-/// TODO: Use a counter instead of random bits.
+/// Takes about 5 minutes.
 #[test]
 #[ignore]
 fn stress_test() {
   let mut acc_set = Vec::new();
   let mut acc = Accumulator::<Rsa2048, [u8; 32]>::empty();
+  let mut rng = StdRng::seed_from_u64(0);
   for _ in 0..100 {
-    let random_elem = rand::thread_rng().gen::<[u8; 32]>();
+    let random_elem = rng.gen::<[u8; 32]>();
     acc_set.push(random_elem);
   }
   println!("Starting add");
   acc = acc.clone().add(&acc_set);
   println!("{}", acc_set.len());
   for _ in 0..100 {
-    let new_elem = rand::thread_rng().gen::<[u8; 32]>();
+    let new_elem = rng.gen::<[u8; 32]>();
     assert!(!acc_set.contains(&new_elem));
     let (new_acc, add_proof) = acc.clone().add_with_proof(&[new_elem]);
     assert!(new_acc.verify_membership(&new_elem, &add_proof));